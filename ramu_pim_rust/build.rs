@@ -0,0 +1,28 @@
+//! generate `ramu_pim_capi.h`, the C header of the `extern "C"` functions in `src/capi.rs`
+
+const HEADER: &str = "\
+// the C ABI of the Rust memory, the same as the one of ramulator_wrapper.h, so its callers can
+// link ramu_pim_rust instead of the C++ ramulator
+//
+// generated from src/capi.rs by build.rs, do not edit";
+
+fn main() {
+    println!("cargo:rerun-if-changed=src/capi.rs");
+    println!("cargo:rerun-if-changed=build.rs");
+    let config = cbindgen::Config {
+        language: cbindgen::Language::C,
+        header: Some(HEADER.to_string()),
+        include_guard: Some("RAMU_PIM_CAPI_H".to_string()),
+        cpp_compat: true,
+        no_includes: true,
+        sys_includes: vec!["stdbool.h".to_string(), "stdint.h".to_string()],
+        documentation_style: cbindgen::DocumentationStyle::C99,
+        ..Default::default()
+    };
+    cbindgen::Builder::new()
+        .with_src("src/capi.rs")
+        .with_config(config)
+        .generate()
+        .expect("failed to generate the C header")
+        .write_to_file("ramu_pim_capi.h");
+}
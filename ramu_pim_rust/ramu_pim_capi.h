@@ -0,0 +1,72 @@
+// the C ABI of the Rust memory, the same as the one of ramulator_wrapper.h, so its callers can
+// link ramu_pim_rust instead of the C++ ramulator
+//
+// generated from src/capi.rs by build.rs, do not edit
+
+#ifndef RAMU_PIM_CAPI_H
+#define RAMU_PIM_CAPI_H
+
+#include <stdbool.h>
+#include <stdint.h>
+
+// returned by `ramulator_get` and `ramulator_pop` when no finished read is waiting
+#define RAMULATOR_NO_READ UINT64_MAX
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// build a memory from the config file `config_name`, the stats are written to `stats_name` when
+// the memory is deleted, return null if the config is invalid
+//
+// # Safety
+// `config_name` and `stats_name` are null or point to nul-terminated strings
+void *get_ramulator(const void *config_name, const void *stats_name);
+
+// drain the requests in flight, write the stats and free the memory
+//
+// # Safety
+// `ramu` comes from `get_ramulator` and is not used after
+void delete_ramulator(void *ramu);
+
+// queue a request, it is sent to the memory by the next cycles
+//
+// # Safety
+// `ramu` comes from `get_ramulator`
+void ramulator_send(void *ramu, unsigned long long addr, bool is_write);
+
+// return if the channel of `addr` takes another request
+//
+// # Safety
+// `ramu` comes from `get_ramulator`
+bool ramulator_available(void *ramu, unsigned long long addr);
+
+// advance the memory by a cycle
+//
+// # Safety
+// `ramu` comes from `get_ramulator`
+void ramulator_cycle(void *ramu);
+
+// return if a finished read is waiting to be popped
+//
+// # Safety
+// `ramu` comes from `get_ramulator`
+bool ramulator_ret_available(void *ramu);
+
+// the address of the oldest finished read, `RAMULATOR_NO_READ` if there is none
+//
+// # Safety
+// `ramu` comes from `get_ramulator`
+unsigned long long ramulator_get(const void *ramu);
+
+// remove the oldest finished read and return its address, `RAMULATOR_NO_READ` if there is none
+//
+// # Safety
+// `ramu` comes from `get_ramulator`
+unsigned long long ramulator_pop(void *ramu);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* RAMU_PIM_CAPI_H */
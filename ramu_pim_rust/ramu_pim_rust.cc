@@ -1,17 +1,94 @@
 #include <array>
+#include <cstddef>
 #include <cstdint>
+#include <exception>
+#include <new>
 #include <string>
+#include <type_traits>
+#include <utility>
 
 namespace rust {
 inline namespace cxxbridge1 {
 // #include "rust/cxx.h"
 
+struct unsafe_bitcopy_t;
+
 namespace {
 template <typename T>
 class impl;
 } // namespace
 
-class String;
+#ifndef CXXBRIDGE1_RUST_STRING
+#define CXXBRIDGE1_RUST_STRING
+// https://cxx.rs/binding/string.html
+class String final {
+public:
+  String() noexcept;
+  String(const String &) noexcept;
+  String(String &&) noexcept;
+  ~String() noexcept;
+
+  String(const std::string &);
+  String(const char *);
+  String(const char *, std::size_t);
+  String(const char16_t *);
+  String(const char16_t *, std::size_t);
+
+  // Replace invalid Unicode data with the replacement character (U+FFFD).
+  static String lossy(const std::string &) noexcept;
+  static String lossy(const char *) noexcept;
+  static String lossy(const char *, std::size_t) noexcept;
+  static String lossy(const char16_t *) noexcept;
+  static String lossy(const char16_t *, std::size_t) noexcept;
+
+  String &operator=(const String &) &noexcept;
+  String &operator=(String &&) &noexcept;
+
+  explicit operator std::string() const;
+
+  // Note: no null terminator.
+  const char *data() const noexcept;
+  std::size_t size() const noexcept;
+  std::size_t length() const noexcept;
+  bool empty() const noexcept;
+
+  const char *c_str() noexcept;
+
+  std::size_t capacity() const noexcept;
+  void reserve(size_t new_cap) noexcept;
+
+  using iterator = char *;
+  iterator begin() noexcept;
+  iterator end() noexcept;
+
+  using const_iterator = const char *;
+  const_iterator begin() const noexcept;
+  const_iterator end() const noexcept;
+  const_iterator cbegin() const noexcept;
+  const_iterator cend() const noexcept;
+
+  bool operator==(const String &) const noexcept;
+  bool operator!=(const String &) const noexcept;
+  bool operator<(const String &) const noexcept;
+  bool operator<=(const String &) const noexcept;
+  bool operator>(const String &) const noexcept;
+  bool operator>=(const String &) const noexcept;
+
+  void swap(String &) noexcept;
+
+  // Internal API only intended for the cxxbridge code generator.
+  String(unsafe_bitcopy_t, const String &) noexcept;
+
+private:
+  struct lossy_t;
+  String(lossy_t, const char *, std::size_t) noexcept;
+  String(lossy_t, const char16_t *, std::size_t) noexcept;
+  friend void swap(String &lhs, String &rhs) noexcept { lhs.swap(rhs); }
+
+  // Size and alignment statically verified by rust_string.rs.
+  std::array<std::uintptr_t, 3> repr;
+};
+#endif // CXXBRIDGE1_RUST_STRING
 
 #ifndef CXXBRIDGE1_RUST_STR
 #define CXXBRIDGE1_RUST_STR
@@ -59,9 +136,319 @@ private:
   std::array<std::uintptr_t, 2> repr;
 };
 #endif // CXXBRIDGE1_RUST_STR
+
+#ifndef CXXBRIDGE1_RUST_BOX
+#define CXXBRIDGE1_RUST_BOX
+// https://cxx.rs/binding/box.html
+template <typename T>
+class Box final {
+public:
+  using element_type = T;
+  using const_pointer =
+      typename std::add_pointer<typename std::add_const<T>::type>::type;
+  using pointer = typename std::add_pointer<T>::type;
+
+  Box() = delete;
+  Box(Box &&) noexcept;
+  ~Box() noexcept;
+
+  explicit Box(const T &);
+  explicit Box(T &&);
+
+  Box &operator=(Box &&) &noexcept;
+
+  const T *operator->() const noexcept;
+  const T &operator*() const noexcept;
+  T *operator->() noexcept;
+  T &operator*() noexcept;
+
+  template <typename... Fields>
+  static Box in_place(Fields &&...);
+
+  void swap(Box &) noexcept;
+
+  // Important: requires that `raw` came from an into_raw call. Do not pass a
+  // pointer from `new` or any other source.
+  static Box from_raw(T *) noexcept;
+
+  T *into_raw() noexcept;
+
+  /* Deprecated */ using value_type = element_type;
+
+private:
+  class uninit;
+  class allocation;
+  Box(uninit) noexcept;
+  void drop() noexcept;
+
+  friend void swap(Box &lhs, Box &rhs) noexcept { lhs.swap(rhs); }
+
+  T *ptr;
+};
+template <typename T>
+class Box<T>::uninit {};
+
+template <typename T>
+class Box<T>::allocation {
+  static T *alloc() noexcept;
+  static void dealloc(T *) noexcept;
+
+public:
+  allocation() noexcept : ptr(alloc()) {}
+  ~allocation() noexcept {
+    if (this->ptr) {
+      dealloc(this->ptr);
+    }
+  }
+  T *ptr;
+};
+
+template <typename T>
+Box<T>::Box(Box &&other) noexcept : ptr(other.ptr) {
+  other.ptr = nullptr;
+}
+
+template <typename T>
+Box<T>::Box(const T &val) {
+  allocation alloc;
+  ::new (alloc.ptr) T(val);
+  this->ptr = alloc.ptr;
+  alloc.ptr = nullptr;
+}
+
+template <typename T>
+Box<T>::Box(T &&val) {
+  allocation alloc;
+  ::new (alloc.ptr) T(std::move(val));
+  this->ptr = alloc.ptr;
+  alloc.ptr = nullptr;
+}
+
+template <typename T>
+Box<T>::~Box() noexcept {
+  if (this->ptr) {
+    this->drop();
+  }
+}
+
+template <typename T>
+Box<T> &Box<T>::operator=(Box &&other) &noexcept {
+  if (this->ptr) {
+    this->drop();
+  }
+  this->ptr = other.ptr;
+  other.ptr = nullptr;
+  return *this;
+}
+
+template <typename T>
+const T *Box<T>::operator->() const noexcept {
+  return this->ptr;
+}
+
+template <typename T>
+const T &Box<T>::operator*() const noexcept {
+  return *this->ptr;
+}
+
+template <typename T>
+T *Box<T>::operator->() noexcept {
+  return this->ptr;
+}
+
+template <typename T>
+T &Box<T>::operator*() noexcept {
+  return *this->ptr;
+}
+
+template <typename T>
+template <typename... Fields>
+Box<T> Box<T>::in_place(Fields &&...fields) {
+  allocation alloc;
+  auto ptr = alloc.ptr;
+  ::new (ptr) T{std::forward<Fields>(fields)...};
+  alloc.ptr = nullptr;
+  return from_raw(ptr);
+}
+
+template <typename T>
+void Box<T>::swap(Box &rhs) noexcept {
+  using std::swap;
+  swap(this->ptr, rhs.ptr);
+}
+
+template <typename T>
+Box<T> Box<T>::from_raw(T *raw) noexcept {
+  Box box = uninit{};
+  box.ptr = raw;
+  return box;
+}
+
+template <typename T>
+T *Box<T>::into_raw() noexcept {
+  T *raw = this->ptr;
+  this->ptr = nullptr;
+  return raw;
+}
+
+template <typename T>
+Box<T>::Box(uninit) noexcept {}
+#endif // CXXBRIDGE1_RUST_BOX
+
+#ifndef CXXBRIDGE1_RUST_ERROR
+#define CXXBRIDGE1_RUST_ERROR
+// https://cxx.rs/binding/result.html
+class Error final : public std::exception {
+public:
+  Error(const Error &);
+  Error(Error &&) noexcept;
+  ~Error() noexcept override;
+
+  Error &operator=(const Error &) &;
+  Error &operator=(Error &&) &noexcept;
+
+  const char *what() const noexcept override;
+
+private:
+  Error() noexcept = default;
+  friend impl<Error>;
+  const char *msg;
+  std::size_t len;
+};
+#endif // CXXBRIDGE1_RUST_ERROR
+
+#ifndef CXXBRIDGE1_RUST_OPAQUE
+#define CXXBRIDGE1_RUST_OPAQUE
+// Base class of generated opaque Rust types.
+class Opaque {
+public:
+  Opaque() = delete;
+  Opaque(const Opaque &) = delete;
+  ~Opaque() = delete;
+};
+#endif // CXXBRIDGE1_RUST_OPAQUE
+
+#ifndef CXXBRIDGE1_IS_COMPLETE
+#define CXXBRIDGE1_IS_COMPLETE
+namespace detail {
+namespace {
+template <typename T, typename = std::size_t>
+struct is_complete : std::false_type {};
+template <typename T>
+struct is_complete<T, decltype(sizeof(T))> : std::true_type {};
+} // namespace
+} // namespace detail
+#endif // CXXBRIDGE1_IS_COMPLETE
+
+#ifndef CXXBRIDGE1_LAYOUT
+#define CXXBRIDGE1_LAYOUT
+class layout {
+  template <typename T>
+  friend std::size_t size_of();
+  template <typename T>
+  friend std::size_t align_of();
+  template <typename T>
+  static typename std::enable_if<std::is_base_of<Opaque, T>::value,
+                                 std::size_t>::type
+  do_size_of() {
+    return T::layout::size();
+  }
+  template <typename T>
+  static typename std::enable_if<!std::is_base_of<Opaque, T>::value,
+                                 std::size_t>::type
+  do_size_of() {
+    return sizeof(T);
+  }
+  template <typename T>
+  static
+      typename std::enable_if<detail::is_complete<T>::value, std::size_t>::type
+      size_of() {
+    return do_size_of<T>();
+  }
+  template <typename T>
+  static typename std::enable_if<std::is_base_of<Opaque, T>::value,
+                                 std::size_t>::type
+  do_align_of() {
+    return T::layout::align();
+  }
+  template <typename T>
+  static typename std::enable_if<!std::is_base_of<Opaque, T>::value,
+                                 std::size_t>::type
+  do_align_of() {
+    return alignof(T);
+  }
+  template <typename T>
+  static
+      typename std::enable_if<detail::is_complete<T>::value, std::size_t>::type
+      align_of() {
+    return do_align_of<T>();
+  }
+};
+
+template <typename T>
+std::size_t size_of() {
+  return layout::size_of<T>();
+}
+
+template <typename T>
+std::size_t align_of() {
+  return layout::align_of<T>();
+}
+#endif // CXXBRIDGE1_LAYOUT
+
+#ifndef CXXBRIDGE1_RUST_MAYBEUNINIT
+#define CXXBRIDGE1_RUST_MAYBEUNINIT
+template <typename T>
+union MaybeUninit {
+  T value;
+  MaybeUninit() {}
+  ~MaybeUninit() {}
+};
+#endif // CXXBRIDGE1_RUST_MAYBEUNINIT
+
+namespace repr {
+struct PtrLen final {
+  void *ptr;
+  ::std::size_t len;
+};
+} // namespace repr
+
+namespace {
+template <>
+class impl<Error> final {
+public:
+  static Error error(repr::PtrLen repr) noexcept {
+    Error error;
+    error.msg = static_cast<char const *>(repr.ptr);
+    error.len = repr.len;
+    return error;
+  }
+};
+} // namespace
 } // namespace cxxbridge1
 } // namespace rust
 
+struct RustMemory;
+
+#ifndef CXXBRIDGE1_STRUCT_RustMemory
+#define CXXBRIDGE1_STRUCT_RustMemory
+struct RustMemory final : public ::rust::Opaque {
+  bool try_send(::std::uint64_t addr, bool is_write) noexcept;
+  void tick() noexcept;
+  bool try_recv(::std::uint64_t &addr) noexcept;
+  ::std::size_t pending_requests() const noexcept;
+  ::rust::String finish() noexcept;
+  ~RustMemory() = delete;
+
+private:
+  friend ::rust::layout;
+  struct layout {
+    static ::std::size_t size() noexcept;
+    static ::std::size_t align() noexcept;
+  };
+};
+#endif // CXXBRIDGE1_STRUCT_RustMemory
+
 extern "C" {
 void cxxbridge1$init_logger() noexcept;
 
@@ -76,6 +463,22 @@ void cxxbridge1$rdebug_with_target(::rust::Str target, ::rust::Str msg) noexcept
 void cxxbridge1$rinfo_with_target(::rust::Str target, ::rust::Str msg) noexcept;
 
 void cxxbridge1$rerror_with_target(::rust::Str target, ::rust::Str msg) noexcept;
+::std::size_t cxxbridge1$RustMemory$operator$sizeof() noexcept;
+::std::size_t cxxbridge1$RustMemory$operator$alignof() noexcept;
+
+::rust::repr::PtrLen cxxbridge1$memory_from_file(::rust::Str path, ::rust::Box<::RustMemory> *return$) noexcept;
+
+::rust::repr::PtrLen cxxbridge1$memory_from_str(::rust::Str content, ::rust::Box<::RustMemory> *return$) noexcept;
+
+bool cxxbridge1$RustMemory$try_send(::RustMemory &self, ::std::uint64_t addr, bool is_write) noexcept;
+
+void cxxbridge1$RustMemory$tick(::RustMemory &self) noexcept;
+
+bool cxxbridge1$RustMemory$try_recv(::RustMemory &self, ::std::uint64_t &addr) noexcept;
+
+::std::size_t cxxbridge1$RustMemory$pending_requests(const ::RustMemory &self) noexcept;
+
+void cxxbridge1$RustMemory$finish(::RustMemory &self, ::rust::String *return$) noexcept;
 } // extern "C"
 
 void init_logger() noexcept {
@@ -105,3 +508,74 @@ void rinfo_with_target(::rust::Str target, ::rust::Str msg) noexcept {
 void rerror_with_target(::rust::Str target, ::rust::Str msg) noexcept {
   cxxbridge1$rerror_with_target(target, msg);
 }
+
+::std::size_t RustMemory::layout::size() noexcept {
+  return cxxbridge1$RustMemory$operator$sizeof();
+}
+
+::std::size_t RustMemory::layout::align() noexcept {
+  return cxxbridge1$RustMemory$operator$alignof();
+}
+
+::rust::Box<::RustMemory> memory_from_file(::rust::Str path) {
+  ::rust::MaybeUninit<::rust::Box<::RustMemory>> return$;
+  ::rust::repr::PtrLen error$ = cxxbridge1$memory_from_file(path, &return$.value);
+  if (error$.ptr) {
+    throw ::rust::impl<::rust::Error>::error(error$);
+  }
+  return ::std::move(return$.value);
+}
+
+::rust::Box<::RustMemory> memory_from_str(::rust::Str content) {
+  ::rust::MaybeUninit<::rust::Box<::RustMemory>> return$;
+  ::rust::repr::PtrLen error$ = cxxbridge1$memory_from_str(content, &return$.value);
+  if (error$.ptr) {
+    throw ::rust::impl<::rust::Error>::error(error$);
+  }
+  return ::std::move(return$.value);
+}
+
+bool RustMemory::try_send(::std::uint64_t addr, bool is_write) noexcept {
+  return cxxbridge1$RustMemory$try_send(*this, addr, is_write);
+}
+
+void RustMemory::tick() noexcept {
+  cxxbridge1$RustMemory$tick(*this);
+}
+
+bool RustMemory::try_recv(::std::uint64_t &addr) noexcept {
+  return cxxbridge1$RustMemory$try_recv(*this, addr);
+}
+
+::std::size_t RustMemory::pending_requests() const noexcept {
+  return cxxbridge1$RustMemory$pending_requests(*this);
+}
+
+::rust::String RustMemory::finish() noexcept {
+  ::rust::MaybeUninit<::rust::String> return$;
+  cxxbridge1$RustMemory$finish(*this, &return$.value);
+  return ::std::move(return$.value);
+}
+
+extern "C" {
+::RustMemory *cxxbridge1$box$RustMemory$alloc() noexcept;
+void cxxbridge1$box$RustMemory$dealloc(::RustMemory *) noexcept;
+void cxxbridge1$box$RustMemory$drop(::rust::Box<::RustMemory> *ptr) noexcept;
+} // extern "C"
+
+namespace rust {
+inline namespace cxxbridge1 {
+template <>
+::RustMemory *Box<::RustMemory>::allocation::alloc() noexcept {
+  return cxxbridge1$box$RustMemory$alloc();
+}
+template <>
+void Box<::RustMemory>::allocation::dealloc(::RustMemory *ptr) noexcept {
+  cxxbridge1$box$RustMemory$dealloc(ptr);
+}
+template <>
+void Box<::RustMemory>::drop() noexcept {
+  cxxbridge1$box$RustMemory$drop(this);
+}
+} // namespace cxxbridge1
+} // namespace rust
@@ -1,9 +1,49 @@
+#include <cassert>
+#include <cstdint>
 #include <iostream>
 #include <ramu_pim_rust.h>
-int main() {
+#include <set>
+#include <string>
+int main(int argc, char **argv) {
 
   init_logger();
   rinfo("Hello, world!");
   rdebug("Hello, world!");
   rinfo_with_target("ramu_pim_rust", "Hello, world!");
-}
\ No newline at end of file
+
+  // the memory is built from a config file when one is given, the same as get_ramulator
+  auto memory = argc > 1 ? memory_from_file(argv[1])
+                         : memory_from_str("standard = DDR4\nchannels = 2\nranks = 1");
+  std::set<uint64_t> reads;
+  for (uint64_t i = 0; i < 256; i++) {
+    uint64_t addr = i * 64;
+    bool is_write = i % 4 == 0;
+    // a rejected request is sent again after a cycle, like ramulator_available
+    while (!memory->try_send(addr, is_write)) {
+      memory->tick();
+    }
+    if (!is_write) {
+      reads.insert(addr);
+    }
+  }
+  uint64_t cycles = 0;
+  uint64_t addr = 0;
+  while (memory->pending_requests() > 0) {
+    memory->tick();
+    cycles++;
+    while (memory->try_recv(addr)) {
+      assert(reads.count(addr) == 1);
+      reads.erase(addr);
+    }
+  }
+  assert(reads.empty());
+  std::cout << "finished in " << cycles << " cycles" << std::endl;
+  std::cout << std::string(memory->finish());
+
+  try {
+    memory_from_str("standard = DDR9");
+    assert(false);
+  } catch (const rust::Error &e) {
+    std::cout << "invalid config: " << e.what() << std::endl;
+  }
+}
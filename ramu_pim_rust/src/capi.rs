@@ -0,0 +1,235 @@
+use std::{
+    collections::VecDeque,
+    ffi::{c_ulonglong, c_void, CStr},
+    panic::AssertUnwindSafe,
+    path::PathBuf,
+};
+
+use crate::{config::Config, rust_memory::RustMemory};
+
+/// the requests a channel holds before `ramulator_available` reports it full
+const IN_QUEUE_SIZE: usize = 512;
+/// returned by `ramulator_get` and `ramulator_pop` when no finished read is waiting
+pub const RAMULATOR_NO_READ: u64 = u64::MAX;
+
+/// the memory behind the `void *` handle of the C ABI, the same as the one of
+/// `ramulator_wrapper.h` so its callers can link this crate instead, the declarations are
+/// generated into `ramu_pim_capi.h` by `build.rs`. the requests are queued per channel like in
+/// `ramulator_wrapper` so a full channel does not block the others
+struct CMemory {
+    memory: RustMemory,
+    /// the address and the is_write of the requests sent but not accepted by the memory yet
+    in_queue: Vec<VecDeque<(u64, bool)>>,
+    /// the addresses of the finished reads
+    out_queue: VecDeque<u64>,
+    stats_path: Option<PathBuf>,
+}
+impl CMemory {
+    fn cycle(&mut self) {
+        for queue in self.in_queue.iter_mut() {
+            if let Some(&(addr, is_write)) = queue.front() {
+                if self.memory.try_send(addr, is_write) {
+                    queue.pop_front();
+                }
+            }
+        }
+        self.memory.tick();
+        let mut addr = 0;
+        while self.memory.try_recv(&mut addr) {
+            self.out_queue.push_back(addr);
+        }
+    }
+}
+
+/// run an entry point, a panic can not unwind into the C caller so it is logged and `default`
+/// is returned instead
+fn catch<R>(name: &str, default: R, f: impl FnOnce() -> R) -> R {
+    std::panic::catch_unwind(AssertUnwindSafe(f)).unwrap_or_else(|_| {
+        tracing::error!("{name} panicked");
+        default
+    })
+}
+
+unsafe fn to_str<'a>(s: *const c_void) -> Option<&'a str> {
+    if s.is_null() {
+        None
+    } else {
+        CStr::from_ptr(s as *const _).to_str().ok()
+    }
+}
+
+/// build a memory from the config file `config_name`, the stats are written to `stats_name` when
+/// the memory is deleted, return null if the config is invalid
+///
+/// # Safety
+/// `config_name` and `stats_name` are null or point to nul-terminated strings
+#[no_mangle]
+pub unsafe extern "C" fn get_ramulator(
+    config_name: *const c_void,
+    stats_name: *const c_void,
+) -> *mut c_void {
+    catch("get_ramulator", std::ptr::null_mut(), || {
+        let Some(config_name) = to_str(config_name) else {
+            tracing::error!("the config path is not a valid string");
+            return std::ptr::null_mut();
+        };
+        let memory =
+            match Config::from_file(config_name).and_then(|config| RustMemory::new(&config)) {
+                Ok(memory) => memory,
+                Err(e) => {
+                    tracing::error!("{config_name}: {e}");
+                    return std::ptr::null_mut();
+                }
+            };
+        let memory = Box::new(CMemory {
+            in_queue: vec![VecDeque::new(); memory.get_channel_num()],
+            memory,
+            out_queue: VecDeque::new(),
+            stats_path: to_str(stats_name).map(PathBuf::from),
+        });
+        Box::into_raw(memory) as *mut c_void
+    })
+}
+
+/// drain the requests in flight, write the stats and free the memory
+///
+/// # Safety
+/// `ramu` comes from `get_ramulator` and is not used after
+#[no_mangle]
+pub unsafe extern "C" fn delete_ramulator(ramu: *mut c_void) {
+    if ramu.is_null() {
+        return;
+    }
+    let mut memory = Box::from_raw(ramu as *mut CMemory);
+    catch("delete_ramulator", (), || {
+        while memory.in_queue.iter().any(|queue| !queue.is_empty()) {
+            memory.cycle();
+        }
+        let stats = memory.memory.finish();
+        if let Some(path) = &memory.stats_path {
+            if let Err(e) = std::fs::write(path, stats) {
+                tracing::error!("failed to write the stats to {}: {e}", path.display());
+            }
+        }
+    });
+}
+
+/// queue a request, it is sent to the memory by the next cycles
+///
+/// # Safety
+/// `ramu` comes from `get_ramulator`
+#[no_mangle]
+pub unsafe extern "C" fn ramulator_send(ramu: *mut c_void, addr: c_ulonglong, is_write: bool) {
+    let memory = &mut *(ramu as *mut CMemory);
+    catch("ramulator_send", (), || {
+        let channel = memory.memory.get_channel_id(addr);
+        memory.in_queue[channel].push_back((addr, is_write));
+    });
+}
+
+/// return if the channel of `addr` takes another request
+///
+/// # Safety
+/// `ramu` comes from `get_ramulator`
+#[no_mangle]
+pub unsafe extern "C" fn ramulator_available(ramu: *mut c_void, addr: c_ulonglong) -> bool {
+    let memory = &*(ramu as *const CMemory);
+    catch("ramulator_available", false, || {
+        memory.in_queue[memory.memory.get_channel_id(addr)].len() <= IN_QUEUE_SIZE
+    })
+}
+
+/// advance the memory by a cycle
+///
+/// # Safety
+/// `ramu` comes from `get_ramulator`
+#[no_mangle]
+pub unsafe extern "C" fn ramulator_cycle(ramu: *mut c_void) {
+    let memory = &mut *(ramu as *mut CMemory);
+    catch("ramulator_cycle", (), || memory.cycle());
+}
+
+/// return if a finished read is waiting to be popped
+///
+/// # Safety
+/// `ramu` comes from `get_ramulator`
+#[no_mangle]
+pub unsafe extern "C" fn ramulator_ret_available(ramu: *mut c_void) -> bool {
+    !(*(ramu as *const CMemory)).out_queue.is_empty()
+}
+
+/// the address of the oldest finished read, `RAMULATOR_NO_READ` if there is none
+///
+/// # Safety
+/// `ramu` comes from `get_ramulator`
+#[no_mangle]
+pub unsafe extern "C" fn ramulator_get(ramu: *const c_void) -> c_ulonglong {
+    let memory = &*(ramu as *const CMemory);
+    memory
+        .out_queue
+        .front()
+        .copied()
+        .unwrap_or(RAMULATOR_NO_READ)
+}
+
+/// remove the oldest finished read and return its address, `RAMULATOR_NO_READ` if there is none
+///
+/// # Safety
+/// `ramu` comes from `get_ramulator`
+#[no_mangle]
+pub unsafe extern "C" fn ramulator_pop(ramu: *mut c_void) -> c_ulonglong {
+    let memory = &mut *(ramu as *mut CMemory);
+    memory.out_queue.pop_front().unwrap_or(RAMULATOR_NO_READ)
+}
+
+#[cfg(test)]
+mod test {
+    use std::ffi::{c_void, CString};
+
+    use super::{
+        catch, delete_ramulator, get_ramulator, ramulator_available, ramulator_cycle,
+        ramulator_get, ramulator_pop, ramulator_ret_available, ramulator_send, RAMULATOR_NO_READ,
+    };
+
+    #[test]
+    fn test_c_api() {
+        let config = CString::new(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/../ramulator/configs/HBM-config.cfg"
+        ))
+        .unwrap();
+        let stats_path = std::env::temp_dir().join("ramu_pim_capi_test.stats");
+        let stats = CString::new(stats_path.to_str().unwrap()).unwrap();
+        unsafe {
+            let missing = CString::new("no/such/config.cfg").unwrap();
+            assert!(get_ramulator(missing.as_ptr() as *const c_void, std::ptr::null()).is_null());
+
+            let ramu = get_ramulator(config.as_ptr() as *const c_void, stats.as_ptr() as _);
+            assert!(!ramu.is_null());
+            // nothing has finished yet
+            assert_eq!(ramulator_get(ramu), RAMULATOR_NO_READ);
+            assert_eq!(ramulator_pop(ramu), RAMULATOR_NO_READ);
+            for i in 0..64 {
+                assert!(ramulator_available(ramu, i * 64));
+                ramulator_send(ramu, i * 64, i % 2 == 1);
+            }
+            let mut reads = vec![];
+            for _ in 0..10_000 {
+                ramulator_cycle(ramu);
+                while ramulator_ret_available(ramu) {
+                    let addr = ramulator_get(ramu);
+                    assert_eq!(ramulator_pop(ramu), addr);
+                    reads.push(addr);
+                }
+            }
+            reads.sort();
+            assert_eq!(reads, (0..32).map(|i| i * 128).collect::<Vec<_>>());
+            delete_ramulator(ramu);
+        }
+        let stats = std::fs::read_to_string(&stats_path).unwrap();
+        assert!(stats.contains("read_requests"));
+        std::fs::remove_file(stats_path).unwrap();
+        // a panic stops at the entry point
+        assert_eq!(catch("test", 42, || panic!("unwinding into C")), 42);
+    }
+}
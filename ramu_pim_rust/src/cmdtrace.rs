@@ -0,0 +1,166 @@
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+};
+
+use crate::{command::Command, config::Config, dram::DramSpec, memory::Level};
+
+/// the commands issued by a channel, in the format of ramulator so the traces can be diffed
+/// against it and fed to DRAMPower
+///
+/// each rank has a file of `<clk>,<cmd>,<bank>` lines, the bank is flattened over the bank
+/// groups and omitted for the commands to all the banks, e.g. PREA and REF
+#[derive(Default)]
+pub struct CmdTrace {
+    /// the files of the ranks, a file is dropped after an io error
+    files: Vec<Option<BufWriter<File>>>,
+    print: bool,
+}
+impl CmdTrace {
+    pub fn new(config: &Config, channel_id: u64, ranks: usize) -> Self {
+        let mut files = vec![];
+        if config.record_cmd_trace {
+            files = (0..ranks)
+                .map(|rank| {
+                    let path = format!(
+                        "{}chan-{channel_id}-rank-{rank}.cmdtrace",
+                        config.cmd_trace_prefix
+                    );
+                    File::create(&path)
+                        .map(BufWriter::new)
+                        .map_err(|e| tracing::error!("failed to create {path}: {e}"))
+                        .ok()
+                })
+                .collect();
+        }
+        Self {
+            files,
+            print: config.print_cmd_trace,
+        }
+    }
+    pub fn record<T>(&mut self, spec: &T, cmd: Command, addr_vec: &[u64], clk: u64)
+    where
+        T: DramSpec,
+    {
+        if self.print {
+            println!("{}", format_cmd(cmd, addr_vec, clk));
+        }
+        if self.files.is_empty() {
+            return;
+        }
+        let rank = addr_vec[spec.get_level_index(spec.get_rank_level())];
+        let line = match get_bank_id(spec, addr_vec) {
+            Some(bank) => format!("{clk},{cmd:?},{bank}"),
+            None => format!("{clk},{cmd:?}"),
+        };
+        for (i, file) in self.files.iter_mut().enumerate() {
+            if rank != u64::MAX && rank != i as u64 {
+                continue;
+            }
+            if let Some(writer) = file {
+                if let Err(e) = writeln!(writer, "{line}") {
+                    tracing::error!(rank = i, "failed to record the command trace: {e}");
+                    *file = None;
+                }
+            }
+        }
+    }
+}
+
+/// the line printed by `print_cmd_trace`, the wildcard addresses are printed as -1 like ramulator
+fn format_cmd(cmd: Command, addr_vec: &[u64], clk: u64) -> String {
+    let mut line = format!("{:>5} {clk:>10}:", format!("{cmd:?}"));
+    for &addr in addr_vec {
+        line += &format!(" {:>5}", addr as i64);
+    }
+    line
+}
+/// the bank within its rank, the levels between the rank and the row are flattened
+fn get_bank_id<T>(spec: &T, addr_vec: &[u64]) -> Option<u64>
+where
+    T: DramSpec,
+{
+    let rank_index = spec.get_level_index(spec.get_rank_level());
+    let row_index = spec.get_level_index(Level::Row);
+    let child_size = spec.get_child_size();
+    (rank_index + 1..row_index).try_fold(0, |bank, level| {
+        let addr = addr_vec[level];
+        (addr != u64::MAX).then_some(bank * child_size[level] as u64 + addr)
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use super::format_cmd;
+    use crate::{
+        command::Command,
+        config::Config,
+        controller::Controller,
+        ddr4::DDR4,
+        dram::{Dram, DramSpec},
+        memory::Level,
+        request::{ReqType, Request},
+    };
+
+    #[test]
+    fn test_cmd_trace() {
+        let dir = std::env::temp_dir().join(format!("ramu_pim_cmd_trace_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let config = Config {
+            ranks: 2,
+            record_cmd_trace: true,
+            cmd_trace_prefix: format!("{}/", dir.display()),
+            ..Default::default()
+        };
+        let ddr4 = Arc::new(DDR4::new(&config));
+        let channel = Dram::new(ddr4.clone(), Level::Channel, ddr4.get_child_size());
+        let mut controller = Controller::new(&config, channel);
+        // a miss and a hit in bank 0, a conflict in bank 5 of rank 1 and a write miss
+        let reqs = [
+            (vec![0, 0, 0, 0, 0, 0], ReqType::Read),
+            (vec![0, 0, 0, 0, 0, 1], ReqType::Read),
+            (vec![0, 1, 1, 1, 0, 0], ReqType::Read),
+            (vec![0, 1, 1, 1, 1, 0], ReqType::Write),
+        ];
+        for (addr_vec, req_type) in reqs {
+            controller
+                .try_enqueue(Request::with_addr_vec(addr_vec, req_type))
+                .unwrap();
+        }
+        let mut clk = 0;
+        while controller.pending_requests() > 0 {
+            clk += 1;
+            controller.tick(clk);
+        }
+        // a command to all the banks of rank 1 has no bank
+        let refresh = [0, 1, u64::MAX, u64::MAX, u64::MAX, u64::MAX];
+        controller
+            .cmd_trace
+            .record(&*ddr4, Command::REF, &refresh, clk);
+        // the files are flushed when the controller is dropped
+        drop(controller);
+        let read = |rank: usize| {
+            std::fs::read_to_string(dir.join(format!("chan-0-rank-{rank}.cmdtrace"))).unwrap()
+        };
+        assert_eq!(read(0), "1,ACT,0\n23,RD,0\n33,RD,0\n");
+        assert_eq!(
+            read(1),
+            format!("34,ACT,5\n56,RD,5\n90,PRE,5\n112,ACT,5\n134,WR,5\n{clk},REF\n")
+        );
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_format_cmd() {
+        assert_eq!(
+            format_cmd(
+                Command::PREA,
+                &[0, 1, u64::MAX, u64::MAX, u64::MAX, u64::MAX],
+                42
+            ),
+            " PREA         42:     0     1    -1    -1    -1    -1"
+        );
+    }
+}
@@ -1,3 +1,5 @@
+use std::str::FromStr;
+
 use enum_as_inner::EnumAsInner;
 use num_enum::{IntoPrimitive, TryFromPrimitive};
 
@@ -5,6 +7,7 @@ use num_enum::{IntoPrimitive, TryFromPrimitive};
     Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, IntoPrimitive, TryFromPrimitive, EnumAsInner,
 )]
 #[repr(u8)]
+#[allow(clippy::upper_case_acronyms)]
 pub enum Command {
     ACT = 0,
     PRE,
@@ -14,9 +17,38 @@ pub enum Command {
     RDA,
     WRA,
     REF,
+    /// refresh a bank instead of the whole rank, a single bank in HBM, the bank of the same index
+    /// in every bank group in DDR5 (REFsb)
+    REFSB,
+    /// refresh a single bank, the per-bank refresh of LPDDR4
+    REFPB,
     PDE,
     PDX,
     SRE,
     SRX,
+    /// multiply-accumulate a column of the opened row into the PIM unit of the bank
+    MAC,
+    /// load a column of the opened row into the PIM register of the bank
+    LDR,
+    /// store the PIM register of the bank into a column of the opened row
+    STR,
+    /// write the global buffer of a rank, which is broadcast to the PIM units of all its banks
+    WRGB,
+    /// activate the same row in all the targeted banks, the all-bank mode of the PIM standards
+    ACTAB,
+    /// precharge all the targeted banks
+    PREAB,
+    /// multiply-accumulate in the PIM units of all the targeted banks
+    MACAB,
     Max,
 }
+impl FromStr for Command {
+    type Err = ();
+    /// parse the name of a command, e.g. `ACT`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        (0..Command::Max as u8)
+            .map(|cmd| Command::try_from(cmd).unwrap())
+            .find(|cmd| format!("{cmd:?}") == s)
+            .ok_or(())
+    }
+}
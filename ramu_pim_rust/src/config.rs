@@ -1,24 +1,456 @@
+use std::{
+    fmt::Display,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
 use crate::{
+    command::Command,
+    ddr3::{self, DDR3Org},
     ddr4::{DDR4Org, Speed},
+    ddr5::{self, DDR5Org},
+    gddr5::{self, GDDR5Org},
+    gddr6::{self, GDDR6Org},
+    hbm::{self, HBMOrg},
+    lpddr4::{self, LPDDR4Org},
     memory::MappingType,
+    pimmode::PimDrainPolicy,
+    rowpolicy::RowPolicyType,
+    scheduler::SchedulerType,
 };
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[allow(clippy::upper_case_acronyms)]
+pub enum Standard {
+    DDR3,
+    DDR4,
+    DDR5,
+    GDDR5,
+    GDDR6,
+    HBM,
+    HBM2,
+    LPDDR4,
+}
+impl FromStr for Standard {
+    type Err = ();
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "DDR3" => Ok(Standard::DDR3),
+            "DDR4" => Ok(Standard::DDR4),
+            "DDR5" => Ok(Standard::DDR5),
+            "GDDR5" => Ok(Standard::GDDR5),
+            "GDDR6" => Ok(Standard::GDDR6),
+            "HBM" => Ok(Standard::HBM),
+            "HBM2" => Ok(Standard::HBM2),
+            "LPDDR4" => Ok(Standard::LPDDR4),
+            _ => Err(()),
+        }
+    }
+}
+
+#[derive(Debug)]
 pub struct Config {
+    pub standard: Standard,
     pub channels: usize,
     pub ranks: usize,
     pub ddr4_org: DDR4Org,
     pub ddr4_speed: Speed,
+    pub ddr3_org: DDR3Org,
+    pub ddr3_speed: ddr3::Speed,
+    pub hbm_org: HBMOrg,
+    pub hbm_speed: hbm::Speed,
+    /// split every HBM2 channel into 2 pseudo channels, modeled as the ranks of the channel
+    pub pseudo_channel: bool,
+    pub lpddr4_org: LPDDR4Org,
+    pub lpddr4_speed: lpddr4::Speed,
+    pub ddr5_org: DDR5Org,
+    pub ddr5_speed: ddr5::Speed,
+    pub gddr5_org: GDDR5Org,
+    pub gddr5_speed: gddr5::Speed,
+    /// the org of a GDDR6 device, `channels` counts the devices and each has 2 channels
+    pub gddr6_org: GDDR6Org,
+    pub gddr6_speed: gddr6::Speed,
+    /// refresh the banks one by one instead of all the banks of a rank at once, with REFPB of
+    /// LPDDR4 or REFSB of HBM, the REFSB of DDR5 refreshes a bank in every bank group
+    pub per_bank_refresh: bool,
+    /// the registers of a PIM unit
+    pub pim_registers: usize,
+    /// the fp16 lanes of the ALU of a PIM unit
+    pub pim_simd_width: u64,
+    /// the cycles from an op entering the ALU of a PIM unit to its result
+    pub pim_alu_latency: u64,
+    /// the cycles between two ops entering the ALU of a PIM unit
+    pub pim_alu_interval: u64,
+    /// the banks sharing a PIM unit, 1 or 2
+    pub pim_banks_per_unit: usize,
+    /// switch the device between the host and the PIM modes instead of serving the PIM requests
+    /// along the reads and writes
+    pub pim_mode_switch: bool,
+    pub pim_drain_policy: PimDrainPolicy,
+    /// the waiting PIM requests that take the device from the host with the Batch policy
+    pub pim_batch_size: usize,
+    /// the ACT and PRE issued to a reserved row of every rank to enter the PIM mode
+    pub pim_entry_sequence: Vec<Command>,
+    pub pim_exit_sequence: Vec<Command>,
+    /// the cycles after the sequence before the device is in the PIM mode
+    pub pim_entry_latency: u64,
+    pub pim_exit_latency: u64,
     pub mapping_type: MappingType,
+    /// a ramulator `.map` file that replaces `mapping_type`, `defaultmapping` means none like in
+    /// ramulator
+    pub mapping_file: Option<PathBuf>,
+    pub scheduler_type: SchedulerType,
+    /// the max row hits of a bank before FRFCFS_Cap stops prioritizing them
+    pub scheduler_cap: u64,
+    pub row_policy: RowPolicyType,
+    /// the idle cycles before a row is closed by the Timeout row policy
+    pub row_policy_timeout: u64,
+    /// write the issued commands to a file per rank, named `<prefix>chan-<c>-rank-<r>.cmdtrace`
+    pub record_cmd_trace: bool,
+    /// print the issued commands with their full address to stdout
+    pub print_cmd_trace: bool,
+    pub cmd_trace_prefix: String,
 }
 
 impl Default for Config {
     fn default() -> Self {
         Self {
+            standard: Standard::DDR4,
             channels: 1,
             ranks: 1,
             mapping_type: MappingType::RoBaRaCoCh,
+            mapping_file: None,
             ddr4_org: DDR4Org::DDR4_2Gb_x4,
             ddr4_speed: Speed::DDR4_3200,
+            ddr3_org: DDR3Org::DDR3_2Gb_x8,
+            ddr3_speed: ddr3::Speed::DDR3_1600K,
+            hbm_org: HBMOrg::HBM_4Gb,
+            hbm_speed: hbm::Speed::HBM_1Gbps,
+            pseudo_channel: false,
+            lpddr4_org: LPDDR4Org::LPDDR4_8Gb_x16,
+            lpddr4_speed: lpddr4::Speed::LPDDR4_2400,
+            ddr5_org: DDR5Org::DDR5_16Gb_x8,
+            ddr5_speed: ddr5::Speed::DDR5_4800AN,
+            gddr5_org: GDDR5Org::GDDR5_8Gb_x16,
+            gddr5_speed: gddr5::Speed::GDDR5_6000,
+            gddr6_org: GDDR6Org::GDDR6_16Gb,
+            gddr6_speed: gddr6::Speed::GDDR6_14000,
+            per_bank_refresh: false,
+            pim_registers: 8,
+            pim_simd_width: 16,
+            pim_alu_latency: 4,
+            pim_alu_interval: 1,
+            pim_banks_per_unit: 1,
+            pim_mode_switch: false,
+            pim_drain_policy: PimDrainPolicy::Drain,
+            pim_batch_size: 16,
+            pim_entry_sequence: vec![Command::ACT, Command::PRE, Command::ACT, Command::PRE],
+            pim_exit_sequence: vec![Command::ACT, Command::PRE],
+            pim_entry_latency: 8,
+            pim_exit_latency: 8,
+            scheduler_type: SchedulerType::FCFS,
+            scheduler_cap: 16,
+            row_policy: RowPolicyType::Opened,
+            row_policy_timeout: 50,
+            record_cmd_trace: false,
+            print_cmd_trace: false,
+            cmd_trace_prefix: "cmd-trace-".to_string(),
+        }
+    }
+}
+
+/// the keys of ramulator's config files that only matter to the cpu side of the simulator
+const IGNORED_KEYS: &[&str] = &[
+    "cpu_tick",
+    "mem_tick",
+    "early_exit",
+    "expected_limit_insts",
+    "warmup_insts",
+    "cache",
+    "translation",
+    "subarrays",
+    "trace_type",
+];
+
+#[derive(Debug)]
+pub enum ConfigError {
+    Io(std::io::Error),
+    /// a line that is not a comment nor a `key = value` pair
+    Syntax {
+        line: usize,
+        content: String,
+    },
+    UnknownKey {
+        line: usize,
+        key: String,
+    },
+    InvalidValue {
+        line: usize,
+        key: String,
+        value: String,
+    },
+    /// a valid value the memory cannot be built with, e.g. an org and a speed of different
+    /// generations
+    Unsupported {
+        key: String,
+        reason: String,
+    },
+}
+impl Display for ConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConfigError::Io(e) => write!(f, "failed to read the config file: {e}"),
+            ConfigError::Syntax { line, content } => {
+                write!(f, "line {line}: expect `key = value`, found `{content}`")
+            }
+            ConfigError::UnknownKey { line, key } => write!(f, "line {line}: unknown key `{key}`"),
+            ConfigError::InvalidValue { line, key, value } => {
+                write!(f, "line {line}: invalid value `{value}` for key `{key}`")
+            }
+            ConfigError::Unsupported { key, reason } => write!(f, "unsupported `{key}`: {reason}"),
+        }
+    }
+}
+impl std::error::Error for ConfigError {}
+impl From<std::io::Error> for ConfigError {
+    fn from(e: std::io::Error) -> Self {
+        ConfigError::Io(e)
+    }
+}
+
+impl Config {
+    /// read a ramulator config file, the keys not in the file keep their default values
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, ConfigError> {
+        Self::parse(&std::fs::read_to_string(path)?)
+    }
+    /// parse the content of a ramulator config file
+    pub fn parse(content: &str) -> Result<Self, ConfigError> {
+        let mut config = Config::default();
+        // the org and speed names depend on the standard, which may come later in the file
+        let mut org_speed = vec![];
+        for (i, line) in content.lines().enumerate() {
+            let line_no = i + 1;
+            let tokens = line
+                .split([' ', '\t', '='])
+                .filter(|token| !token.is_empty())
+                .collect::<Vec<_>>();
+            match tokens[..] {
+                [] => {}
+                [first, ..] if first.starts_with('#') => {}
+                [key @ ("org" | "speed"), value] => org_speed.push((line_no, key, value)),
+                [key, value] => config.set(line_no, key, value)?,
+                _ => {
+                    return Err(ConfigError::Syntax {
+                        line: line_no,
+                        content: line.trim().to_string(),
+                    })
+                }
+            }
+        }
+        for (line_no, key, value) in org_speed {
+            config.set(line_no, key, value)?;
+        }
+        Ok(config)
+    }
+    fn set(&mut self, line: usize, key: &str, value: &str) -> Result<(), ConfigError> {
+        let invalid_value = || ConfigError::InvalidValue {
+            line,
+            key: key.to_string(),
+            value: value.to_string(),
+        };
+        let parse_switch = || match value {
+            "on" => Ok(true),
+            "off" => Ok(false),
+            _ => Err(invalid_value()),
+        };
+        // the counts the memory divides by or builds its hierarchy from
+        let parse_count = || {
+            parse_value(value)
+                .filter(|&count: &usize| count > 0)
+                .ok_or_else(invalid_value)
+        };
+        // the transition opens and closes the reserved row of a bank, nothing else
+        let parse_sequence = || {
+            parse_list(value)
+                .filter(|cmds: &Vec<Command>| {
+                    cmds.iter()
+                        .all(|cmd| matches!(cmd, Command::ACT | Command::PRE))
+                })
+                .ok_or_else(invalid_value)
+        };
+        match key {
+            "standard" => self.standard = parse_value(value).ok_or_else(invalid_value)?,
+            "channels" => self.channels = parse_count()?,
+            "ranks" => self.ranks = parse_count()?,
+            "org" => match self.standard {
+                Standard::DDR3 => self.ddr3_org = parse_value(value).ok_or_else(invalid_value)?,
+                Standard::DDR4 => self.ddr4_org = parse_value(value).ok_or_else(invalid_value)?,
+                Standard::DDR5 => self.ddr5_org = parse_value(value).ok_or_else(invalid_value)?,
+                Standard::GDDR5 => self.gddr5_org = parse_value(value).ok_or_else(invalid_value)?,
+                Standard::GDDR6 => self.gddr6_org = parse_value(value).ok_or_else(invalid_value)?,
+                Standard::HBM | Standard::HBM2 => {
+                    self.hbm_org = parse_value(value).ok_or_else(invalid_value)?
+                }
+                Standard::LPDDR4 => {
+                    self.lpddr4_org = parse_value(value).ok_or_else(invalid_value)?
+                }
+            },
+            "speed" => match self.standard {
+                Standard::DDR3 => self.ddr3_speed = parse_value(value).ok_or_else(invalid_value)?,
+                Standard::DDR4 => self.ddr4_speed = parse_value(value).ok_or_else(invalid_value)?,
+                Standard::DDR5 => self.ddr5_speed = parse_value(value).ok_or_else(invalid_value)?,
+                Standard::GDDR5 => {
+                    self.gddr5_speed = parse_value(value).ok_or_else(invalid_value)?
+                }
+                Standard::GDDR6 => {
+                    self.gddr6_speed = parse_value(value).ok_or_else(invalid_value)?
+                }
+                Standard::HBM | Standard::HBM2 => {
+                    self.hbm_speed = parse_value(value).ok_or_else(invalid_value)?
+                }
+                Standard::LPDDR4 => {
+                    self.lpddr4_speed = parse_value(value).ok_or_else(invalid_value)?
+                }
+            },
+            "addr_mapping" => self.mapping_type = parse_value(value).ok_or_else(invalid_value)?,
+            "mapping_file" => {
+                self.mapping_file = (value != "defaultmapping").then(|| PathBuf::from(value))
+            }
+            "pseudo_channel" => self.pseudo_channel = parse_switch()?,
+            "per_bank_refresh" => self.per_bank_refresh = parse_switch()?,
+            "pim_registers" => self.pim_registers = parse_count()?,
+            "pim_simd_width" => self.pim_simd_width = parse_count()? as u64,
+            "pim_alu_latency" => {
+                self.pim_alu_latency = parse_value(value).ok_or_else(invalid_value)?
+            }
+            "pim_alu_interval" => self.pim_alu_interval = parse_count()? as u64,
+            // a unit serves a bank or a bank pair
+            "pim_banks_per_unit" => {
+                self.pim_banks_per_unit = parse_value(value)
+                    .filter(|banks| matches!(banks, 1 | 2))
+                    .ok_or_else(invalid_value)?
+            }
+            "pim_mode_switch" => self.pim_mode_switch = parse_switch()?,
+            "pim_drain_policy" => {
+                self.pim_drain_policy = parse_value(value).ok_or_else(invalid_value)?
+            }
+            "pim_batch_size" => {
+                self.pim_batch_size = parse_value(value).ok_or_else(invalid_value)?
+            }
+            "pim_entry_sequence" => self.pim_entry_sequence = parse_sequence()?,
+            "pim_exit_sequence" => self.pim_exit_sequence = parse_sequence()?,
+            "pim_entry_latency" => {
+                self.pim_entry_latency = parse_value(value).ok_or_else(invalid_value)?
+            }
+            "pim_exit_latency" => {
+                self.pim_exit_latency = parse_value(value).ok_or_else(invalid_value)?
+            }
+            "scheduler_type" => {
+                self.scheduler_type = parse_value(value).ok_or_else(invalid_value)?
+            }
+            "scheduler_cap" => self.scheduler_cap = parse_value(value).ok_or_else(invalid_value)?,
+            "row_policy" => self.row_policy = parse_value(value).ok_or_else(invalid_value)?,
+            "row_policy_timeout" => {
+                self.row_policy_timeout = parse_value(value).ok_or_else(invalid_value)?
+            }
+            "record_cmd_trace" => self.record_cmd_trace = parse_switch()?,
+            "print_cmd_trace" => self.print_cmd_trace = parse_switch()?,
+            "cmd_trace_prefix" => self.cmd_trace_prefix = value.to_string(),
+            _ if IGNORED_KEYS.contains(&key) => {}
+            _ => {
+                return Err(ConfigError::UnknownKey {
+                    line,
+                    key: key.to_string(),
+                })
+            }
+        }
+        Ok(())
+    }
+}
+fn parse_value<T: FromStr>(value: &str) -> Option<T> {
+    value.parse().ok()
+}
+/// parse a comma separated list, e.g. `ACT,PRE`
+fn parse_list<T: FromStr>(value: &str) -> Option<Vec<T>> {
+    value.split(',').map(parse_value).collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Config, ConfigError, Standard};
+    use crate::{
+        ddr4::{DDR4Org, Speed},
+        memory::MappingType,
+        rowpolicy::RowPolicyType,
+        scheduler::SchedulerType,
+    };
+
+    #[test]
+    fn test_parse_config() {
+        let config =
+            Config::parse(include_str!("../../ramulator/configs/DDR4-config.cfg")).unwrap();
+        assert_eq!(config.standard, Standard::DDR4);
+        assert_eq!(config.channels, 1);
+        assert_eq!(config.ranks, 1);
+        assert_eq!(config.ddr4_speed, Speed::DDR4_2400R);
+        assert_eq!(config.ddr4_org, DDR4Org::DDR4_4Gb_x8);
+        assert!(!config.record_cmd_trace);
+        assert_eq!(config.cmd_trace_prefix, "cmd-trace-");
+
+        let config = Config::parse("channels=2\n\taddr_mapping = RoCoBaRaCh\n# ranks = 4").unwrap();
+        assert_eq!(config.channels, 2);
+        assert_eq!(config.ranks, 1);
+        assert_eq!(config.mapping_type, MappingType::RoCoBaRaCh);
+
+        let config = Config::parse("pim_simd_width = 8\npim_banks_per_unit = 2").unwrap();
+        assert_eq!(config.pim_simd_width, 8);
+        assert_eq!(config.pim_banks_per_unit, 2);
+        assert_eq!(config.pim_registers, 8);
+
+        let config = Config::parse("scheduler_type = FRFCFS_Cap\nscheduler_cap = 4").unwrap();
+        assert_eq!(config.scheduler_type, SchedulerType::FRFCFS_Cap);
+        assert_eq!(config.scheduler_cap, 4);
+
+        let config = Config::parse("row_policy = Timeout\nrow_policy_timeout = 120").unwrap();
+        assert_eq!(config.row_policy, RowPolicyType::Timeout);
+        assert_eq!(config.row_policy_timeout, 120);
+
+        let config = Config::parse("record_cmd_trace = on\ncmd_trace_prefix = out/ddr4-").unwrap();
+        assert!(config.record_cmd_trace);
+        assert_eq!(config.cmd_trace_prefix, "out/ddr4-");
+    }
+
+    #[test]
+    fn test_parse_config_error() {
+        let err = Config::parse("channels = 1\nrows = 2").unwrap_err();
+        assert!(matches!(err, ConfigError::UnknownKey { line: 2, ref key } if key == "rows"));
+        let err = Config::parse("speed = DDR4_9999").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "line 1: invalid value `DDR4_9999` for key `speed`"
+        );
+        let err = Config::parse("record_cmd_trace = yes").unwrap_err();
+        assert!(matches!(err, ConfigError::InvalidValue { line: 1, .. }));
+        for content in [
+            "channels = 0",
+            "ranks = 0",
+            "pim_registers = 0",
+            "pim_banks_per_unit = 3",
+            "scheduler_type = FRFCFS_Fast",
+            "scheduler_cap = -1",
+            "row_policy = Lazy",
+            "row_policy_timeout = 1.5",
+            "pim_entry_sequence = ACT,RD,PRE",
+            "pim_exit_sequence = PREA",
+        ] {
+            let err = Config::parse(content).unwrap_err();
+            assert!(matches!(err, ConfigError::InvalidValue { line: 1, .. }));
         }
+        let err = Config::parse("channels = 1 2").unwrap_err();
+        assert!(matches!(err, ConfigError::Syntax { line: 1, .. }));
+        assert!(matches!(
+            Config::from_file("no_such.cfg"),
+            Err(ConfigError::Io(_))
+        ));
     }
 }
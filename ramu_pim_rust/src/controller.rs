@@ -1,14 +1,19 @@
 use std::collections::VecDeque;
 
 use crate::{
+    cmdtrace::CmdTrace,
     command::Command,
     config::Config,
     dram::{Dram, DramSpec},
+    memory::Level,
+    pim::PimUnitConfig,
+    pimmode::{PimMode, Transition},
     refresh::Refresh,
     request::{ReqType, Request},
-    rowpolicy::RowPolicy,
+    rowpolicy::{RowPolicy, RowPolicyType},
     rowtable::RowTable,
     scheduler::Scheduler,
+    stats::{Histogram, StatList},
 };
 pub struct Queue {
     pub queue: VecDeque<Request>,
@@ -22,11 +27,13 @@ impl Default for Queue {
         }
     }
 }
+#[derive(Clone, Copy)]
 enum QueueType {
     Read,
     Write,
     Act,
     Other,
+    Pim,
 }
 impl Queue {
     pub fn new(max: usize) -> Self {
@@ -42,82 +49,221 @@ impl Queue {
         self.queue.len() >= self.max
     }
 }
+/// the counters of a channel, dumped by `Controller::collect_stats`
+#[derive(Default)]
+pub struct ControllerStats {
+    pub row_hits: u64,
+    pub row_misses: u64,
+    pub row_conflicts: u64,
+    pub read_row_hits: u64,
+    pub read_row_misses: u64,
+    pub read_row_conflicts: u64,
+    pub write_row_hits: u64,
+    pub write_row_misses: u64,
+    pub write_row_conflicts: u64,
+    pub pim_row_hits: u64,
+    pub pim_row_misses: u64,
+    pub pim_row_conflicts: u64,
+    /// the rows closed without any column access
+    pub useless_activates: u64,
+    pub read_transaction_bytes: u64,
+    pub write_transaction_bytes: u64,
+    /// the bytes accessed by the PIM units or broadcast to them
+    pub pim_transaction_bytes: u64,
+    /// the finished PIM requests
+    pub pim_requests: u64,
+    /// the cycles from the arrival of the PIM requests to their results in the processing units
+    pub pim_latency_sum: u64,
+    /// the cycle the last PIM request finished, the end of the PIM kernel
+    pub pim_finish_clk: u64,
+    /// the finished read requests
+    pub read_requests: u64,
+    pub read_latency_sum: u64,
+    pub read_latency_hist: Histogram,
+    /// the sums of the queue lengths over all cycles
+    pub req_queue_length_sum: u64,
+    pub read_req_queue_length_sum: u64,
+    pub write_req_queue_length_sum: u64,
+    /// the switches into the PIM mode
+    pub pim_mode_switches: u64,
+    pub pim_mode_cycles: u64,
+    /// the cycles switching between the host and the PIM modes
+    pub pim_transition_cycles: u64,
+    /// the cycles the reads and writes wait for the PIM mode or a transition
+    pub host_blocked_cycles: u64,
+}
 pub enum RunningMode {
     Reading,
     Writing,
+    /// the device is in the PIM mode, only the PIM requests and the refreshes are served
+    Pim,
 }
-pub struct Controller<'a, T> {
-    pub channel: Dram<'a, T>,
+pub struct Controller<T> {
+    pub clk: u64,
+    pub channel: Dram<T>,
     pub scheduler: Scheduler,
     pub row_policy: RowPolicy,
     pub row_table: RowTable,
     pub refresh: Refresh,
+    pub pim_mode: PimMode,
     pub read_queue: Queue,
     pub write_queue: Queue,
     pub act_queue: Queue,
     pub other_queue: Queue,
+    /// the PIM requests, served before the reads and writes
+    pub pim_queue: Queue,
     pub pending_queue: VecDeque<Request>,
+    /// the PIM requests waiting for their processing units, in the order of their finish times
+    pub pim_pending_queue: VecDeque<Request>,
     pub finished_queue: VecDeque<Request>,
     pub running_mode: RunningMode,
     pub wr_hight_watermark: f32,
     pub wr_low_watermark: f32,
+    pub stats: ControllerStats,
+    pub cmd_trace: CmdTrace,
 }
 
-impl<'a, T> Controller<'a, T>
+impl<T> Controller<T>
 where
     T: DramSpec,
 {
-    pub fn new(config: &Config, dram: Dram<'a, T>) -> Self {
+    pub fn new(config: &Config, mut dram: Dram<T>) -> Self {
+        let spec = dram.spec.clone();
+        dram.attach_pim_units(&PimUnitConfig::new(
+            config,
+            spec.get_transaction_bytes(),
+            spec.get_read_latency(),
+        ));
+        let refresh = Refresh::new(config, &dram);
+        let row_table = RowTable::new(&*spec);
+        let cmd_trace = CmdTrace::new(config, dram.id, dram.children.len());
         Self {
+            clk: 0,
             channel: dram,
-            scheduler: Default::default(),
-            row_policy: Default::default(),
-            row_table: Default::default(),
-            refresh: Default::default(),
+            scheduler: Scheduler::new(config),
+            row_policy: RowPolicy::new(config),
+            row_table,
+            refresh,
+            pim_mode: PimMode::new(config),
             read_queue: Default::default(),
             write_queue: Default::default(),
             act_queue: Default::default(),
             other_queue: Default::default(),
+            pim_queue: Default::default(),
             pending_queue: Default::default(),
+            pim_pending_queue: Default::default(),
             finished_queue: Default::default(),
             running_mode: RunningMode::Reading,
             wr_hight_watermark: 0.8,
             wr_low_watermark: 0.2,
+            stats: Default::default(),
+            cmd_trace,
         }
     }
-    pub fn finish(read_req: u64, dram_cycles: u64) {
-        todo!("implement me")
+    /// finalize the stats of the channel at the end of the simulation, the requests still in the
+    /// queues are reported as unfinished
+    pub fn finish(&self, stats: &mut StatList) {
+        let unfinished = self.pending_requests();
+        if unfinished > 0 {
+            tracing::warn!(
+                channel = self.channel.id,
+                unfinished,
+                "simulation finished with requests in the queues"
+            );
+        }
+        self.collect_stats(stats);
+        stats.scalar(
+            format!("unfinished_requests_{}", self.channel.id),
+            "Number of requests not finished at the end of the simulation in this channel",
+            0,
+            unfinished as f64,
+        );
     }
-    pub fn try_enqueue(&mut self, req: Request) -> Result<(), Request> {
+    /// the requests in the queues or waiting for their data
+    pub fn pending_requests(&self) -> usize {
+        self.read_queue.size()
+            + self.write_queue.size()
+            + self.act_queue.size()
+            + self.other_queue.size()
+            + self.pim_queue.size()
+            + self.pending_queue.len()
+            + self.pim_pending_queue.len()
+    }
+    pub fn try_enqueue(&mut self, mut req: Request) -> Result<(), Request> {
         let queue = match req.req_type {
             ReqType::Read => &mut self.read_queue,
             ReqType::Write => &mut self.write_queue,
+            ReqType::Refresh => &mut self.other_queue,
+            ReqType::RefreshBank => {
+                // there is no command to serve them
+                if !T::supports_per_bank_refresh() {
+                    return Err(req);
+                }
+                &mut self.other_queue
+            }
+            ReqType::PimMac
+            | ReqType::PimMacAllBank
+            | ReqType::PimLoad
+            | ReqType::PimStore
+            | ReqType::PimBroadcast => {
+                // they would never be served without the PIM commands
+                if !T::supports_pim() {
+                    return Err(req);
+                }
+                &mut self.pim_queue
+            }
         };
         if queue.full() {
             return Err(req);
         } else {
+            req.arrival_time = self.clk;
             queue.queue.push_back(req);
         }
 
         Ok(())
     }
     pub fn tick(&mut self, clk: u64) {
+        self.clk = clk;
+        let read_queue_length = (self.read_queue.size() + self.pending_queue.len()) as u64;
+        let write_queue_length = self.write_queue.size() as u64;
+        self.stats.req_queue_length_sum += read_queue_length + write_queue_length;
+        self.stats.read_req_queue_length_sum += read_queue_length;
+        self.stats.write_req_queue_length_sum += write_queue_length;
         // serve pending requests
         if let Some(req) = self.pending_queue.pop_front() {
             if req.finish_time <= clk {
+                let latency = req.finish_time - req.arrival_time;
+                self.stats.read_requests += 1;
+                self.stats.read_latency_sum += latency;
+                self.stats.read_latency_hist.add(latency);
                 self.finished_queue.push_back(req);
             } else {
                 self.pending_queue.push_front(req);
             }
         }
+        while self
+            .pim_pending_queue
+            .front()
+            .is_some_and(|req| req.finish_time <= clk)
+        {
+            let req = self.pim_pending_queue.pop_front().unwrap();
+            self.stats.pim_requests += 1;
+            self.stats.pim_latency_sum += req.finish_time - req.arrival_time;
+            self.stats.pim_finish_clk = req.finish_time;
+            self.finished_queue.push_back(req);
+        }
         // serve refresh
-        self.refresh.tick(clk);
+        self.refresh.tick(clk, &mut self.other_queue);
+        if self.pim_mode.enabled && self.tick_pim_mode(clk) {
+            return;
+        }
 
         // serve read/write queue
         match self.running_mode {
             RunningMode::Reading => {
                 if self.write_queue.size()
                     > (self.wr_hight_watermark * self.write_queue.max as f32) as usize
+                    || self.read_queue.size() == 0
                 {
                     self.running_mode = RunningMode::Writing;
                 }
@@ -130,49 +276,70 @@ where
                     self.running_mode = RunningMode::Reading;
                 }
             }
+            RunningMode::Pim => {}
         }
         // find the best command to schedule
-        if let Some((index, req)) = self.scheduler.get_best_req(&self.act_queue, &self.channel) {
+        if let Some((index, req)) =
+            self.scheduler
+                .get_best_req(&self.act_queue, &self.channel, &self.row_table, clk)
+        {
             let cmd = self.get_first_cmd(req);
             let is_last = cmd == T::get_first_cmd(&req.req_type);
             if self.is_ready_cmd(&cmd, &req.addr_vec, clk) {
-                self.issue_cmd(cmd, &req.addr_vec.clone(), clk);
+                let addr_vec = req.addr_vec.clone();
+                self.update_first_cmd_stats(index, QueueType::Act);
+                self.issue_cmd(cmd, &addr_vec, clk);
                 self.handle_after_issue(index, &cmd, is_last, QueueType::Act, clk);
                 return;
             }
         }
         // not find the act queue req
         let (queue, queue_type) = self.get_best_queue();
-        if let Some((index, req)) = self.scheduler.get_best_req(queue, &self.channel) {
+        if let Some((index, req)) =
+            self.scheduler
+                .get_best_req(queue, &self.channel, &self.row_table, clk)
+        {
             let cmd = self.get_first_cmd(req);
             let is_last = cmd == T::get_first_cmd(&req.req_type);
             if self.is_ready_cmd(&cmd, &req.addr_vec, clk) {
                 // pop the request from the queue
-                self.issue_cmd(cmd, &req.addr_vec.clone(), clk);
+                let addr_vec = req.addr_vec.clone();
+                self.update_first_cmd_stats(index, queue_type);
+                self.issue_cmd(cmd, &addr_vec, clk);
                 self.handle_after_issue(index, &cmd, is_last, queue_type, clk);
                 return;
             }
         }
-
-        // find read and write queue
+        // no request can be scheduled, try to close a row speculatively
+        if let Some(victim) = self
+            .row_policy
+            .get_victim(&self.channel, &self.row_table, clk)
+        {
+            self.issue_cmd(Command::PRE, &victim, clk);
+        }
     }
-    pub fn is_ready_req(&self, cmd: &Request) -> bool {
-        todo!("implement me")
+    pub fn is_ready_req(&self, req: &Request) -> bool {
+        let cmd = self.get_first_cmd(req);
+        self.is_ready_cmd(&cmd, &req.addr_vec, self.clk)
     }
     pub fn is_ready_cmd(&self, cmd: &Command, addr_vec: &[u64], clk: u64) -> bool {
         self.channel.check(cmd, addr_vec, clk)
     }
+    /// the request hits the opened row
     pub fn is_row_hit_req(&self, req: &Request) -> bool {
-        todo!("implement me")
+        // cmd must be decided by the request type, not the first cmd
+        self.is_row_hit_cmd(&T::get_first_cmd(&req.req_type), &req.addr_vec)
     }
-    pub fn is_row_hit_cmd(&self, cmd: &Command, addr_vec: &[u64]) -> bool {
-        todo!("implement me")
+    pub fn is_row_hit_cmd(&self, _cmd: &Command, addr_vec: &[u64]) -> bool {
+        let row = addr_vec[self.channel.spec.get_level_index(Level::Row)];
+        self.row_table.get_open_row(addr_vec) == Some(row)
     }
+    /// the bank of the request has an opened row, a conflict if it is not a hit
     pub fn is_row_open_req(&self, req: &Request) -> bool {
-        todo!("implement me")
+        self.is_row_open_cmd(&T::get_first_cmd(&req.req_type), &req.addr_vec)
     }
-    pub fn is_row_open_cmd(&self, cmd: &Command, addr_vec: &[u64]) -> bool {
-        todo!("implement me")
+    pub fn is_row_open_cmd(&self, _cmd: &Command, addr_vec: &[u64]) -> bool {
+        self.row_table.get_open_row(addr_vec).is_some()
     }
     pub fn is_active(&self) -> bool {
         todo!("implement me")
@@ -180,13 +347,286 @@ where
     pub fn is_refreshing(&self) -> bool {
         todo!("implement me")
     }
+    /// dump the counters of the channel, its ranks and its banks into `stats`
+    pub fn collect_stats(&self, stats: &mut StatList) {
+        let id = self.channel.id;
+        let s = &self.stats;
+        let rows = [
+            (s.row_hits, "row_hits", "Number of row hits"),
+            (s.row_misses, "row_misses", "Number of row misses"),
+            (s.row_conflicts, "row_conflicts", "Number of row conflicts"),
+            (
+                s.read_row_hits,
+                "read_row_hits",
+                "Number of row hits for read requests",
+            ),
+            (
+                s.read_row_misses,
+                "read_row_misses",
+                "Number of row misses for read requests",
+            ),
+            (
+                s.read_row_conflicts,
+                "read_row_conflicts",
+                "Number of row conflicts for read requests",
+            ),
+            (
+                s.write_row_hits,
+                "write_row_hits",
+                "Number of row hits for write requests",
+            ),
+            (
+                s.write_row_misses,
+                "write_row_misses",
+                "Number of row misses for write requests",
+            ),
+            (
+                s.write_row_conflicts,
+                "write_row_conflicts",
+                "Number of row conflicts for write requests",
+            ),
+            (
+                s.pim_row_hits,
+                "pim_row_hits",
+                "Number of row hits for PIM requests",
+            ),
+            (
+                s.pim_row_misses,
+                "pim_row_misses",
+                "Number of row misses for PIM requests",
+            ),
+            (
+                s.pim_row_conflicts,
+                "pim_row_conflicts",
+                "Number of row conflicts for PIM requests",
+            ),
+        ];
+        for (value, name, desc) in rows {
+            let desc = format!("{desc} per channel");
+            stats.scalar(format!("{name}_channel_{id}_core"), &desc, 0, value as f64);
+        }
+        stats.scalar(
+            format!("useless_activates_{id}_core"),
+            "Number of useless activations. E.g, ACT -> PRE w/o RD or WR",
+            0,
+            s.useless_activates as f64,
+        );
+        stats.scalar(
+            format!("read_transaction_bytes_{id}"),
+            "The total byte of read transaction per channel",
+            0,
+            s.read_transaction_bytes as f64,
+        );
+        stats.scalar(
+            format!("write_transaction_bytes_{id}"),
+            "The total byte of write transaction per channel",
+            0,
+            s.write_transaction_bytes as f64,
+        );
+        stats.scalar(
+            format!("pim_transaction_bytes_{id}"),
+            "The total byte accessed by the PIM units per channel",
+            0,
+            s.pim_transaction_bytes as f64,
+        );
+        stats.scalar(
+            format!("pim_requests_{id}"),
+            "Number of finished PIM requests per channel",
+            0,
+            s.pim_requests as f64,
+        );
+        let pim_mode = [
+            (
+                s.pim_mode_switches,
+                "pim_mode_switches",
+                "Number of switches into the PIM mode",
+            ),
+            (
+                s.pim_mode_cycles,
+                "pim_mode_cycles",
+                "The cycles in the PIM mode",
+            ),
+            (
+                s.pim_transition_cycles,
+                "pim_transition_cycles",
+                "The cycles switching between the host and the PIM modes",
+            ),
+            (
+                s.host_blocked_cycles,
+                "host_blocked_cycles",
+                "The cycles the reads and writes wait for the PIM mode",
+            ),
+        ];
+        for (value, name, desc) in pim_mode {
+            stats.scalar(
+                format!("{name}_{id}"),
+                &format!("{desc} in this channel"),
+                0,
+                value as f64,
+            );
+        }
+        stats.scalar(
+            format!("pim_latency_avg_{id}"),
+            "The average cycles from the arrival of a PIM request to its result in the processing units in this channel",
+            6,
+            average(s.pim_latency_sum, s.pim_requests),
+        );
+        stats.scalar(
+            format!("pim_finish_cycles_{id}"),
+            "The cycle the last PIM request finished in this channel, including the DRAM timing and the compute of the processing units",
+            0,
+            s.pim_finish_clk as f64,
+        );
+        let units = self
+            .channel
+            .get_nodes(Level::Bank)
+            .into_iter()
+            .filter_map(|bank| bank.pim_unit.as_ref())
+            .collect::<Vec<_>>();
+        stats.scalar(
+            format!("pim_alu_ops_{id}"),
+            "Number of ALU ops of the processing units in this channel",
+            0,
+            units.iter().map(|unit| unit.ops).sum::<u64>() as f64,
+        );
+        let busy_cycles = units.iter().map(|unit| unit.busy_cycles).sum::<u64>();
+        stats.scalar(
+            format!("pim_alu_busy_cycles_{id}"),
+            "The cycles the ALUs of the processing units are taking ops, summed over the units in this channel",
+            0,
+            busy_cycles as f64,
+        );
+        stats.scalar(
+            format!("pim_alu_utilization_{id}"),
+            "The fraction of the cycles the ALUs of the processing units are taking ops in this channel",
+            6,
+            average(busy_cycles, units.len() as u64 * self.clk),
+        );
+        stats.scalar(
+            format!("read_latency_sum_{id}"),
+            "The memory latency cycles (in memory time domain) sum for all read requests in this channel",
+            0,
+            s.read_latency_sum as f64,
+        );
+        stats.scalar(
+            format!("read_latency_avg_{id}"),
+            "The average memory latency cycles (in memory time domain) per request for all read requests in this channel",
+            6,
+            average(s.read_latency_sum, s.read_requests),
+        );
+        stats.histogram(
+            format!("read_latency_hist_{id}"),
+            "The distribution of the read latency cycles in this channel",
+            s.read_latency_hist.clone(),
+        );
+        let queues = [
+            (
+                s.req_queue_length_sum,
+                "req_queue_length",
+                "read/write queue length",
+            ),
+            (
+                s.read_req_queue_length_sum,
+                "read_req_queue_length",
+                "read queue length",
+            ),
+            (
+                s.write_req_queue_length_sum,
+                "write_req_queue_length",
+                "write queue length",
+            ),
+        ];
+        for (sum, name, desc) in queues {
+            stats.scalar(
+                format!("{name}_sum_{id}"),
+                &format!("Sum of {desc} per memory cycle per channel"),
+                0,
+                sum as f64,
+            );
+            stats.scalar(
+                format!("{name}_avg_{id}"),
+                &format!("Average of {desc} per memory cycle per channel"),
+                6,
+                average(sum, self.clk),
+            );
+        }
+        for cmd in (0..Command::Max as u8).map(|cmd| Command::try_from(cmd).unwrap()) {
+            stats.scalar(
+                format!("{cmd:?}_commands_channel_{id}"),
+                &format!("Number of {cmd:?} commands issued in this channel"),
+                0,
+                self.channel.cmd_counts[cmd as usize] as f64,
+            );
+        }
+        for rank in &self.channel.children {
+            let rank_id = rank.id;
+            stats.scalar(
+                format!("refreshes_channel_{id}_rank_{rank_id}"),
+                "Number of refresh commands issued to this rank",
+                0,
+                rank.cmd_counts[Command::REF as usize] as f64,
+            );
+            let activations = rank
+                .get_nodes(Level::Bank)
+                .iter()
+                .map(|bank| bank.cmd_counts[Command::ACT as usize] as f64)
+                .collect();
+            stats.vector(
+                format!("activations_channel_{id}_rank_{rank_id}"),
+                "Number of activations of each bank in this rank",
+                activations,
+            );
+        }
+    }
 
     fn get_first_cmd(&self, req: &Request) -> Command {
         let frist_cmd = self.channel.get_first_cmd(&req.req_type);
-        return self.channel.decode(&frist_cmd, &req.addr_vec);
+        self.channel.decode(&frist_cmd, &req.addr_vec)
+    }
+    /// upgrade the access to the last pending request of a row to an auto precharge command
+    fn cmd_issue_autoprecharge(&self, cmd: Command, addr_vec: &[u64]) -> Command {
+        if self.row_policy.policy_type != RowPolicyType::ClosedAP {
+            return cmd;
+        }
+        let auto_precharge_cmd = match cmd {
+            Command::RD => Command::RDA,
+            Command::WR => Command::WRA,
+            _ => return cmd,
+        };
+        let row_index = self.channel.spec.get_level_index(Level::Row);
+        let row = &addr_vec[..=row_index];
+        let count_row_hits = |queue: &Queue| {
+            queue
+                .queue
+                .iter()
+                .filter(|req| req.addr_vec.get(..=row_index) == Some(row))
+                .count()
+        };
+        let queue = match self.running_mode {
+            RunningMode::Reading => &self.read_queue,
+            RunningMode::Writing => &self.write_queue,
+            RunningMode::Pim => &self.pim_queue,
+        };
+        let mut row_hits = count_row_hits(queue);
+        if row_hits == 0 {
+            row_hits = count_row_hits(&self.act_queue);
+        }
+        if row_hits <= 1 {
+            auto_precharge_cmd
+        } else {
+            cmd
+        }
     }
     fn issue_cmd(&mut self, cmd: Command, addr_vec: &[u64], clk: u64) {
+        let cmd = self.cmd_issue_autoprecharge(cmd, addr_vec);
+        if cmd == Command::PRE && self.row_table.get_hits(addr_vec, true) == 0 {
+            self.stats.useless_activates += 1;
+        }
         self.channel.update(&cmd, addr_vec, clk);
+        self.row_table
+            .update(&*self.channel.spec, &cmd, addr_vec, clk);
+        self.cmd_trace
+            .record(&*self.channel.spec, cmd, addr_vec, clk);
     }
     fn handle_after_issue(
         &mut self,
@@ -198,13 +638,11 @@ where
     ) {
         // check if the request is finished
         if is_last {
-            let queue = match queue_type {
-                QueueType::Read => &mut self.read_queue,
-                QueueType::Write => &mut self.write_queue,
-                QueueType::Act => &mut self.act_queue,
-                QueueType::Other => &mut self.other_queue,
-            };
-            let mut req = queue.queue.remove(cmd_index).unwrap();
+            let mut req = self
+                .get_queue_mut(queue_type)
+                .queue
+                .remove(cmd_index)
+                .unwrap();
             match req.req_type {
                 ReqType::Read => {
                     req.finish_time = clk + self.channel.spec.get_read_latency();
@@ -213,37 +651,349 @@ where
                 ReqType::Write => {
                     self.finished_queue.push_back(req);
                 }
+                ReqType::PimMac
+                | ReqType::PimMacAllBank
+                | ReqType::PimLoad
+                | ReqType::PimStore
+                | ReqType::PimBroadcast => {
+                    // the request finishes when its result is in the processing units
+                    req.finish_time = clk.max(self.channel.get_pim_ready_clk(&req.addr_vec));
+                    let index = self
+                        .pim_pending_queue
+                        .partition_point(|pending| pending.finish_time <= req.finish_time);
+                    self.pim_pending_queue.insert(index, req);
+                }
+                ReqType::Refresh | ReqType::RefreshBank => {}
+            }
+        } else if cmd.is_act() {
+            let req = self
+                .get_queue_mut(queue_type)
+                .queue
+                .remove(cmd_index)
+                .unwrap();
+            self.act_queue.queue.push_back(req);
+        }
+    }
+    /// record the row buffer outcome and the bytes of a request when its first command is issued
+    fn update_first_cmd_stats(&mut self, cmd_index: usize, queue_type: QueueType) {
+        let req = &self.get_queue(queue_type).queue[cmd_index];
+        if !req.is_first_command {
+            return;
+        }
+        let req_type = req.req_type;
+        let is_row_hit = self.is_row_hit_req(req);
+        let is_row_open = self.is_row_open_req(req);
+        let transaction_bytes = self.channel.spec.get_transaction_bytes();
+        self.get_queue_mut(queue_type).queue[cmd_index].is_first_command = false;
+
+        let stats = &mut self.stats;
+        let (row_hits, row_misses, row_conflicts, bytes) = match req_type {
+            ReqType::Read => (
+                &mut stats.read_row_hits,
+                &mut stats.read_row_misses,
+                &mut stats.read_row_conflicts,
+                &mut stats.read_transaction_bytes,
+            ),
+            ReqType::Write => (
+                &mut stats.write_row_hits,
+                &mut stats.write_row_misses,
+                &mut stats.write_row_conflicts,
+                &mut stats.write_transaction_bytes,
+            ),
+            ReqType::PimBroadcast => {
+                // the operand goes to the global buffer, no row is accessed
+                stats.pim_transaction_bytes += transaction_bytes;
+                return;
             }
+            ReqType::PimMac | ReqType::PimMacAllBank | ReqType::PimLoad | ReqType::PimStore => (
+                &mut stats.pim_row_hits,
+                &mut stats.pim_row_misses,
+                &mut stats.pim_row_conflicts,
+                &mut stats.pim_transaction_bytes,
+            ),
+            ReqType::Refresh | ReqType::RefreshBank => return,
+        };
+        *bytes += transaction_bytes;
+        if is_row_hit {
+            *row_hits += 1;
+            stats.row_hits += 1;
+        } else if is_row_open {
+            *row_conflicts += 1;
+            stats.row_conflicts += 1;
         } else {
-            if cmd.is_act() {
-                let queue = match queue_type {
-                    QueueType::Read => &mut self.read_queue,
-                    QueueType::Write => &mut self.write_queue,
-                    QueueType::Act => &mut self.act_queue,
-                    QueueType::Other => &mut self.other_queue,
+            *row_misses += 1;
+            stats.row_misses += 1;
+        }
+    }
+    fn get_queue(&self, queue_type: QueueType) -> &Queue {
+        match queue_type {
+            QueueType::Read => &self.read_queue,
+            QueueType::Write => &self.write_queue,
+            QueueType::Act => &self.act_queue,
+            QueueType::Other => &self.other_queue,
+            QueueType::Pim => &self.pim_queue,
+        }
+    }
+    fn get_queue_mut(&mut self, queue_type: QueueType) -> &mut Queue {
+        match queue_type {
+            QueueType::Read => &mut self.read_queue,
+            QueueType::Write => &mut self.write_queue,
+            QueueType::Act => &mut self.act_queue,
+            QueueType::Other => &mut self.other_queue,
+            QueueType::Pim => &mut self.pim_queue,
+        }
+    }
+
+    /// switch between the host and the PIM modes, return if the cycle is taken by a transition.
+    /// the mode is entered when `PimMode::should_enter` favors the PIM queue over the host
+    /// requests and left once the PIM queue is empty
+    fn tick_pim_mode(&mut self, clk: u64) -> bool {
+        let host_waiting = self.read_queue.size() + self.write_queue.size();
+        let in_pim = matches!(self.running_mode, RunningMode::Pim);
+        if in_pim {
+            self.stats.pim_mode_cycles += 1;
+        }
+        if host_waiting > 0 && (in_pim || self.pim_mode.transition.is_some()) {
+            self.stats.host_blocked_cycles += 1;
+        }
+        if self.pim_mode.transition.is_none() {
+            // the requests with opened rows are served in the current mode
+            if self.act_queue.size() > 0 {
+                return false;
+            }
+            let entering = !in_pim;
+            let switch = if entering {
+                self.pim_mode
+                    .should_enter(self.pim_queue.size(), host_waiting)
+            } else {
+                self.pim_queue.size() == 0
+            };
+            if !switch {
+                return false;
+            }
+            if entering {
+                self.stats.pim_mode_switches += 1;
+            }
+            self.pim_mode.transition = Some(Transition {
+                entering,
+                issued: 0,
+                done_clk: None,
+            });
+        }
+        self.stats.pim_transition_cycles += 1;
+        self.tick_transition(clk);
+        true
+    }
+    /// close all the rows, then issue the sequence to a reserved row of every rank
+    fn tick_transition(&mut self, clk: u64) {
+        let transition = self.pim_mode.transition.as_ref().unwrap();
+        let entering = transition.entering;
+        if let Some(done_clk) = transition.done_clk {
+            if clk >= done_clk {
+                self.running_mode = if entering {
+                    RunningMode::Pim
+                } else {
+                    RunningMode::Reading
                 };
-                let req = queue.queue.remove(cmd_index).unwrap();
-                self.act_queue.queue.push_back(req);
+                self.pim_mode.transition = None;
+            }
+            return;
+        }
+        // a shared handle, the channel is updated along the way
+        let spec = self.channel.spec.clone();
+        let rank_index = spec.get_level_index(spec.get_rank_level());
+        let row_index = spec.get_level_index(Level::Row);
+        let mut addr_vec = vec![u64::MAX; spec.get_levels().len()];
+        addr_vec[0] = self.channel.id;
+        if let Some(bank) = self.row_table.table.keys().next() {
+            addr_vec[rank_index] = bank[rank_index];
+            if self.is_ready_cmd(&Command::PREA, &addr_vec, clk) {
+                self.channel.update(&Command::PREA, &addr_vec, clk);
+                self.row_table
+                    .update(&*spec, &Command::PREA, &addr_vec, clk);
+                self.cmd_trace.record(&*spec, Command::PREA, &addr_vec, clk);
             }
+            return;
+        }
+        let sequence = self.pim_mode.get_sequence(entering);
+        let ranks = self.channel.children.len();
+        let total = sequence.len() * ranks;
+        let mut issued = transition.issued;
+        if issued < total {
+            let cmd = sequence[issued / ranks];
+            addr_vec[rank_index + 1..].fill(0);
+            addr_vec[rank_index] = (issued % ranks) as u64;
+            addr_vec[row_index] = spec.get_child_size()[row_index] as u64 - 1;
+            if !self.is_ready_cmd(&cmd, &addr_vec, clk) {
+                return;
+            }
+            self.channel.update(&cmd, &addr_vec, clk);
+            self.row_table.update(&*spec, &cmd, &addr_vec, clk);
+            self.cmd_trace.record(&*spec, cmd, &addr_vec, clk);
+            issued += 1;
+        }
+        let latency = self.pim_mode.get_latency(entering);
+        let transition = self.pim_mode.transition.as_mut().unwrap();
+        transition.issued = issued;
+        if issued == total {
+            transition.done_clk = Some(clk + latency);
         }
     }
-
+    /// the refreshes go first, then the PIM requests, which hold the banks like the PIM mode of
+    /// HBM-PIM, then the reads or the writes
     fn get_best_queue(&self) -> (&Queue, QueueType) {
-        match self.other_queue.size() {
-            0 => match self.running_mode {
-                RunningMode::Reading => (&self.read_queue, QueueType::Read),
-                RunningMode::Writing => (&self.write_queue, QueueType::Write),
-            },
-            _ => (&self.other_queue, QueueType::Other),
-        }
-    }
-    fn get_best_queue_mut(&mut self) -> &mut Queue {
-        match self.other_queue.size() {
-            0 => match self.running_mode {
-                RunningMode::Reading => &mut self.read_queue,
-                RunningMode::Writing => &mut self.write_queue,
-            },
-            _ => &mut self.other_queue,
+        if self.other_queue.size() > 0 {
+            return (&self.other_queue, QueueType::Other);
         }
+        // the PIM requests wait for the PIM mode when the modes are switched
+        if self.pim_queue.size() > 0 && !self.pim_mode.enabled {
+            return (&self.pim_queue, QueueType::Pim);
+        }
+        match self.running_mode {
+            RunningMode::Reading => (&self.read_queue, QueueType::Read),
+            RunningMode::Writing => (&self.write_queue, QueueType::Write),
+            RunningMode::Pim => (&self.pim_queue, QueueType::Pim),
+        }
+    }
+}
+
+fn average(sum: u64, count: u64) -> f64 {
+    if count == 0 {
+        0.0
+    } else {
+        sum as f64 / count as f64
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use super::Controller;
+    use crate::{
+        command::Command,
+        config::Config,
+        ddr4::DDR4,
+        dram::{Dram, DramSpec},
+        memory::Level,
+        request::{ReqType, Request},
+        stats::{StatList, StatValue},
+    };
+
+    #[test]
+    fn test_controller_stats() {
+        let config = Config::default();
+        let ddr4 = Arc::new(DDR4::new(&config));
+        let channel = Dram::new(ddr4.clone(), Level::Channel, ddr4.get_child_size());
+        let mut controller = Controller::new(&config, channel);
+        let reqs = [
+            // miss, hit, conflict in bank 0 and a miss in bank 1
+            (vec![0, 0, 0, 0, 0, 0], ReqType::Read),
+            (vec![0, 0, 0, 0, 0, 1], ReqType::Read),
+            (vec![0, 0, 0, 0, 1, 0], ReqType::Read),
+            (vec![0, 0, 0, 1, 0, 0], ReqType::Write),
+        ];
+        for (addr_vec, req_type) in reqs {
+            controller
+                .try_enqueue(Request::with_addr_vec(addr_vec, req_type))
+                .unwrap();
+        }
+        let mut finished = 0;
+        let mut clk = 0;
+        while finished < 4 {
+            clk += 1;
+            controller.tick(clk);
+            finished += controller.finished_queue.drain(..).count();
+        }
+        let mut stats = StatList::default();
+        controller.collect_stats(&mut stats);
+        let get = |name: &str| stats.get_scalar(name).unwrap();
+        assert_eq!(get("row_hits_channel_0_core"), 1.0);
+        assert_eq!(get("row_misses_channel_0_core"), 2.0);
+        assert_eq!(get("row_conflicts_channel_0_core"), 1.0);
+        assert_eq!(get("read_row_hits_channel_0_core"), 1.0);
+        assert_eq!(get("read_row_misses_channel_0_core"), 1.0);
+        assert_eq!(get("read_row_conflicts_channel_0_core"), 1.0);
+        assert_eq!(get("write_row_misses_channel_0_core"), 1.0);
+        assert_eq!(get("useless_activates_0_core"), 0.0);
+        let bytes = ddr4.get_transaction_bytes() as f64;
+        assert_eq!(get("read_transaction_bytes_0"), 3.0 * bytes);
+        assert_eq!(get("write_transaction_bytes_0"), bytes);
+        assert!(get("read_latency_avg_0") >= ddr4.get_read_latency() as f64);
+        assert!(get("read_req_queue_length_avg_0") > 0.0);
+        assert_eq!(get("ACT_commands_channel_0"), 3.0);
+        assert_eq!(get("PRE_commands_channel_0"), 1.0);
+        assert_eq!(get("RD_commands_channel_0"), 3.0);
+        assert_eq!(get("WR_commands_channel_0"), 1.0);
+        match stats.get("activations_channel_0_rank_0").unwrap() {
+            StatValue::Vector(activations) => {
+                assert_eq!(activations.len(), 16);
+                assert_eq!(activations[..2], [2.0, 1.0]);
+            }
+            _ => panic!("activations should be a vector"),
+        }
+        match stats.get("read_latency_hist_0").unwrap() {
+            StatValue::Histogram(histogram) => assert_eq!(histogram.samples, 3),
+            _ => panic!("read latency should be a histogram"),
+        }
+        let bank = &controller.channel.children[0].children[0].children[0];
+        assert_eq!(bank.cmd_counts[Command::RD as usize], 3);
+    }
+
+    #[test]
+    fn test_pim_requests() {
+        let config = Config::default();
+        let ddr4 = Arc::new(DDR4::new(&config));
+        let channel = Dram::new(ddr4.clone(), Level::Channel, ddr4.get_child_size());
+        let mut controller = Controller::new(&config, channel);
+        // broadcast the operand, then load, accumulate twice and store back in bank 0
+        let reqs = [
+            ReqType::PimBroadcast,
+            ReqType::PimLoad,
+            ReqType::PimMac,
+            ReqType::PimMac,
+            ReqType::PimStore,
+        ];
+        for (column, req_type) in reqs.into_iter().enumerate() {
+            let addr_vec = vec![0, 0, 0, 0, 0, column as u64];
+            controller
+                .try_enqueue(Request::with_addr_vec(addr_vec, req_type))
+                .unwrap();
+        }
+        assert_eq!(controller.pim_queue.size(), 5);
+        assert_eq!(
+            controller.read_queue.size() + controller.write_queue.size(),
+            0
+        );
+        let mut finished = 0;
+        let mut clk = 0;
+        while finished < 5 {
+            clk += 1;
+            controller.tick(clk);
+            finished += controller.finished_queue.drain(..).count();
+        }
+        let bank = &controller.channel.children[0].children[0].children[0];
+        assert_eq!(bank.cmd_counts[Command::ACT as usize], 1);
+        assert_eq!(bank.cmd_counts[Command::LDR as usize], 1);
+        assert_eq!(bank.cmd_counts[Command::MAC as usize], 2);
+        assert_eq!(bank.cmd_counts[Command::STR as usize], 1);
+        assert_eq!(
+            controller.channel.children[0].cmd_counts[Command::WRGB as usize],
+            1
+        );
+        // the MAC waits for the operand in the global buffer
+        let wrgb_clk = controller.channel.children[0].prev[Command::WRGB as usize][0];
+        let mac_clk = bank.prev[Command::MAC as usize][0];
+        assert!(mac_clk > wrgb_clk);
+
+        let mut stats = StatList::default();
+        controller.collect_stats(&mut stats);
+        let get = |name: &str| stats.get_scalar(name).unwrap();
+        assert_eq!(get("pim_requests_0"), 5.0);
+        assert_eq!(get("pim_row_misses_channel_0_core"), 1.0);
+        assert_eq!(get("pim_row_hits_channel_0_core"), 3.0);
+        assert_eq!(get("read_row_hits_channel_0_core"), 0.0);
+        let bytes = ddr4.get_transaction_bytes() as f64;
+        assert_eq!(get("pim_transaction_bytes_0"), 5.0 * bytes);
     }
 }
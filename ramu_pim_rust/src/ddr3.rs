@@ -0,0 +1,1490 @@
+use std::str::FromStr;
+
+use crate::{
+    command::Command,
+    config::{Config, ConfigError},
+    dram::{self, log2, Dram, DramSpec, State, TimeEntry, TimingTable},
+    memory::Level,
+    request::ReqType,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[allow(non_camel_case_types)]
+pub enum DDR3Org {
+    DDR3_512Mb_x4,
+    DDR3_512Mb_x8,
+    DDR3_512Mb_x16,
+    DDR3_1Gb_x4,
+    DDR3_1Gb_x8,
+    DDR3_1Gb_x16,
+    DDR3_2Gb_x4,
+    DDR3_2Gb_x8,
+    DDR3_2Gb_x16,
+    DDR3_4Gb_x4,
+    DDR3_4Gb_x8,
+    DDR3_4Gb_x16,
+    DDR3_8Gb_x4,
+    DDR3_8Gb_x8,
+    DDR3_8Gb_x16,
+}
+impl FromStr for DDR3Org {
+    type Err = ();
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "DDR3_512Mb_x4" => DDR3Org::DDR3_512Mb_x4,
+            "DDR3_512Mb_x8" => DDR3Org::DDR3_512Mb_x8,
+            "DDR3_512Mb_x16" => DDR3Org::DDR3_512Mb_x16,
+            "DDR3_1Gb_x4" => DDR3Org::DDR3_1Gb_x4,
+            "DDR3_1Gb_x8" => DDR3Org::DDR3_1Gb_x8,
+            "DDR3_1Gb_x16" => DDR3Org::DDR3_1Gb_x16,
+            "DDR3_2Gb_x4" => DDR3Org::DDR3_2Gb_x4,
+            "DDR3_2Gb_x8" => DDR3Org::DDR3_2Gb_x8,
+            "DDR3_2Gb_x16" => DDR3Org::DDR3_2Gb_x16,
+            "DDR3_4Gb_x4" => DDR3Org::DDR3_4Gb_x4,
+            "DDR3_4Gb_x8" => DDR3Org::DDR3_4Gb_x8,
+            "DDR3_4Gb_x16" => DDR3Org::DDR3_4Gb_x16,
+            "DDR3_8Gb_x4" => DDR3Org::DDR3_8Gb_x4,
+            "DDR3_8Gb_x8" => DDR3Org::DDR3_8Gb_x8,
+            "DDR3_8Gb_x16" => DDR3Org::DDR3_8Gb_x16,
+            _ => return Err(()),
+        })
+    }
+}
+
+/// the density(in Mb) and data width of a chip, and the number of its rows and columns
+struct OrgEntry {
+    size: usize,
+    dq: usize,
+    count: [usize; 2],
+}
+#[allow(non_snake_case, dead_code)]
+pub struct SpeedEntry {
+    rate: u64,
+    freq: f64,
+    tCK: f64,
+    nBL: u64,
+    nCCD: u64,
+    nRTRS: u64,
+    nCL: u64,
+    nRCD: u64,
+    nRP: u64,
+    nCWL: u64,
+    nRAS: u64,
+    nRC: u64,
+    nRTP: u64,
+    nWTR: u64,
+    nWR: u64,
+    nRRD: u64,
+    nFAW: u64,
+    nRFC: u64,
+    nREFI: u64,
+    nPD: u64,
+    nXP: u64,
+    nXPDLL: u64,
+    nCKESR: u64,
+    nXS: u64,
+    nXSDLL: u64,
+}
+
+/// 8n prefetch DDR
+const PREFETCH_SIZE: u64 = 8;
+const CHANNEL_WIDTH: u64 = 64;
+/// DDR3 has no bank groups, the banks are the children of the ranks
+const LEVELS: [Level; 5] = [
+    Level::Channel,
+    Level::Rank,
+    Level::Bank,
+    Level::Row,
+    Level::Column,
+];
+const BANKS: usize = 8;
+pub struct DDR3 {
+    child_size: Vec<usize>,
+    addr_bits: Vec<usize>,
+    speed_entry: SpeedEntry,
+    timing: TimingTable,
+    read_latency: u64,
+}
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[allow(non_camel_case_types)]
+pub enum Speed {
+    DDR3_800D,
+    DDR3_800E,
+    DDR3_1066E,
+    DDR3_1066F,
+    DDR3_1066G,
+    DDR3_1333G,
+    DDR3_1333H,
+    DDR3_1600H,
+    DDR3_1600J,
+    DDR3_1600K,
+    DDR3_1866K,
+    DDR3_1866L,
+    DDR3_2133L,
+    DDR3_2133M,
+}
+impl FromStr for Speed {
+    type Err = ();
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "DDR3_800D" => Speed::DDR3_800D,
+            "DDR3_800E" => Speed::DDR3_800E,
+            "DDR3_1066E" => Speed::DDR3_1066E,
+            "DDR3_1066F" => Speed::DDR3_1066F,
+            "DDR3_1066G" => Speed::DDR3_1066G,
+            "DDR3_1333G" => Speed::DDR3_1333G,
+            "DDR3_1333H" => Speed::DDR3_1333H,
+            "DDR3_1600H" => Speed::DDR3_1600H,
+            "DDR3_1600J" => Speed::DDR3_1600J,
+            "DDR3_1600K" => Speed::DDR3_1600K,
+            "DDR3_1866K" => Speed::DDR3_1866K,
+            "DDR3_1866L" => Speed::DDR3_1866L,
+            "DDR3_2133L" => Speed::DDR3_2133L,
+            "DDR3_2133M" => Speed::DDR3_2133M,
+            _ => return Err(()),
+        })
+    }
+}
+impl DDR3 {
+    pub fn new(config: &Config) -> Self {
+        let org = Self::get_org(&config.ddr3_org);
+        let child_size = vec![
+            config.channels,
+            config.ranks,
+            BANKS,
+            org.count[0],
+            org.count[1],
+        ];
+        let addr_bits = child_size.iter().map(|x| log2(*x)).collect::<Vec<usize>>();
+        let mut speed_entry = Self::get_speed(&config.ddr3_speed);
+        Self::init_speed(&mut speed_entry, &org);
+        let mut timing = TimingTable::new(&LEVELS);
+        Self::init_timing(&mut timing, &speed_entry);
+        let read_latency = speed_entry.nCL + speed_entry.nBL;
+        Self {
+            child_size,
+            addr_bits,
+            speed_entry,
+            timing,
+            read_latency,
+        }
+    }
+    fn get_org(org: &DDR3Org) -> OrgEntry {
+        match org {
+            DDR3Org::DDR3_512Mb_x4 => OrgEntry {
+                size: 512,
+                dq: 4,
+                count: [1 << 13, 1 << 11],
+            },
+            DDR3Org::DDR3_512Mb_x8 => OrgEntry {
+                size: 512,
+                dq: 8,
+                count: [1 << 13, 1 << 10],
+            },
+            DDR3Org::DDR3_512Mb_x16 => OrgEntry {
+                size: 512,
+                dq: 16,
+                count: [1 << 12, 1 << 10],
+            },
+            DDR3Org::DDR3_1Gb_x4 => OrgEntry {
+                size: 1 << 10,
+                dq: 4,
+                count: [1 << 14, 1 << 11],
+            },
+            DDR3Org::DDR3_1Gb_x8 => OrgEntry {
+                size: 1 << 10,
+                dq: 8,
+                count: [1 << 14, 1 << 10],
+            },
+            DDR3Org::DDR3_1Gb_x16 => OrgEntry {
+                size: 1 << 10,
+                dq: 16,
+                count: [1 << 13, 1 << 10],
+            },
+            DDR3Org::DDR3_2Gb_x4 => OrgEntry {
+                size: 2 << 10,
+                dq: 4,
+                count: [1 << 15, 1 << 11],
+            },
+            DDR3Org::DDR3_2Gb_x8 => OrgEntry {
+                size: 2 << 10,
+                dq: 8,
+                count: [1 << 15, 1 << 10],
+            },
+            DDR3Org::DDR3_2Gb_x16 => OrgEntry {
+                size: 2 << 10,
+                dq: 16,
+                count: [1 << 14, 1 << 10],
+            },
+            DDR3Org::DDR3_4Gb_x4 => OrgEntry {
+                size: 4 << 10,
+                dq: 4,
+                count: [1 << 16, 1 << 11],
+            },
+            DDR3Org::DDR3_4Gb_x8 => OrgEntry {
+                size: 4 << 10,
+                dq: 8,
+                count: [1 << 16, 1 << 10],
+            },
+            DDR3Org::DDR3_4Gb_x16 => OrgEntry {
+                size: 4 << 10,
+                dq: 16,
+                count: [1 << 15, 1 << 10],
+            },
+            DDR3Org::DDR3_8Gb_x4 => OrgEntry {
+                size: 8 << 10,
+                dq: 4,
+                count: [1 << 16, 1 << 12],
+            },
+            DDR3Org::DDR3_8Gb_x8 => OrgEntry {
+                size: 8 << 10,
+                dq: 8,
+                count: [1 << 16, 1 << 11],
+            },
+            DDR3Org::DDR3_8Gb_x16 => OrgEntry {
+                size: 8 << 10,
+                dq: 16,
+                count: [1 << 16, 1 << 10],
+            },
+        }
+    }
+    /// fill the page size and density dependent timings
+    fn init_speed(s: &mut SpeedEntry, org: &OrgEntry) {
+        // nRRD, nFAW
+        let page = (org.dq * org.count[1]) >> 13;
+        let (rrd, faw) = match s.rate {
+            800 => ([4, 4], [16, 20]),
+            1066 => ([4, 6], [20, 27]),
+            1333 => ([4, 5], [20, 30]),
+            1600 => ([5, 6], [24, 32]),
+            1866 => ([5, 6], [26, 33]),
+            2133 => ([5, 6], [27, 34]),
+            _ => unreachable!("invalid speed rate"),
+        };
+        let page = if page == 1 { 0 } else { 1 };
+        s.nRRD = rrd[page];
+        s.nFAW = faw[page];
+
+        // nRFC, nXS
+        const RFC_TABLE: [[u64; 5]; 6] = [
+            [36, 44, 64, 104, 140],
+            [48, 59, 86, 139, 187],
+            [60, 74, 107, 174, 234],
+            [72, 88, 128, 208, 280],
+            [84, 103, 150, 243, 327],
+            [96, 118, 171, 278, 374],
+        ];
+        const XS_TABLE: [[u64; 5]; 6] = [
+            [40, 48, 68, 108, 144],
+            [54, 64, 91, 144, 192],
+            [67, 80, 114, 180, 240],
+            [80, 96, 136, 216, 288],
+            [94, 112, 159, 252, 336],
+            [107, 128, 182, 288, 384],
+        ];
+        let speed = match s.rate {
+            800 => 0,
+            1066 => 1,
+            1333 => 2,
+            1600 => 3,
+            1866 => 4,
+            2133 => 5,
+            _ => unreachable!("invalid speed rate"),
+        };
+        let density = match org.size {
+            512 => 0,
+            1024 => 1,
+            2048 => 2,
+            4096 => 3,
+            _ => 4,
+        };
+        s.nRFC = RFC_TABLE[speed][density];
+        s.nXS = XS_TABLE[speed][density];
+    }
+    fn init_timing(timing: &mut TimingTable, s: &SpeedEntry) {
+        /*** Channel ***/
+        let t = timing.get_level_mut(Level::Channel);
+
+        // CAS <-> CAS
+        t[Command::RD as usize].push(TimeEntry {
+            cmd: Command::RD,
+            dist: 1,
+            val: s.nBL,
+            sibling: false,
+        });
+        t[Command::RD as usize].push(TimeEntry {
+            cmd: Command::RDA,
+            dist: 1,
+            val: s.nBL,
+            sibling: false,
+        });
+        t[Command::RDA as usize].push(TimeEntry {
+            cmd: Command::RD,
+            dist: 1,
+            val: s.nBL,
+            sibling: false,
+        });
+        t[Command::RDA as usize].push(TimeEntry {
+            cmd: Command::RDA,
+            dist: 1,
+            val: s.nBL,
+            sibling: false,
+        });
+        t[Command::WR as usize].push(TimeEntry {
+            cmd: Command::WR,
+            dist: 1,
+            val: s.nBL,
+            sibling: false,
+        });
+        t[Command::WR as usize].push(TimeEntry {
+            cmd: Command::WRA,
+            dist: 1,
+            val: s.nBL,
+            sibling: false,
+        });
+        t[Command::WRA as usize].push(TimeEntry {
+            cmd: Command::WR,
+            dist: 1,
+            val: s.nBL,
+            sibling: false,
+        });
+        t[Command::WRA as usize].push(TimeEntry {
+            cmd: Command::WRA,
+            dist: 1,
+            val: s.nBL,
+            sibling: false,
+        });
+
+        /*** Rank ***/
+        let t = timing.get_level_mut(Level::Rank);
+
+        // CAS <-> CAS
+        t[Command::RD as usize].push(TimeEntry {
+            cmd: Command::RD,
+            dist: 1,
+            val: s.nCCD,
+            sibling: false,
+        });
+        t[Command::RD as usize].push(TimeEntry {
+            cmd: Command::RDA,
+            dist: 1,
+            val: s.nCCD,
+            sibling: false,
+        });
+        t[Command::RDA as usize].push(TimeEntry {
+            cmd: Command::RD,
+            dist: 1,
+            val: s.nCCD,
+            sibling: false,
+        });
+        t[Command::RDA as usize].push(TimeEntry {
+            cmd: Command::RDA,
+            dist: 1,
+            val: s.nCCD,
+            sibling: false,
+        });
+        t[Command::WR as usize].push(TimeEntry {
+            cmd: Command::WR,
+            dist: 1,
+            val: s.nCCD,
+            sibling: false,
+        });
+        t[Command::WR as usize].push(TimeEntry {
+            cmd: Command::WRA,
+            dist: 1,
+            val: s.nCCD,
+            sibling: false,
+        });
+        t[Command::WRA as usize].push(TimeEntry {
+            cmd: Command::WR,
+            dist: 1,
+            val: s.nCCD,
+            sibling: false,
+        });
+        t[Command::WRA as usize].push(TimeEntry {
+            cmd: Command::WRA,
+            dist: 1,
+            val: s.nCCD,
+            sibling: false,
+        });
+        t[Command::RD as usize].push(TimeEntry {
+            cmd: Command::WR,
+            dist: 1,
+            val: s.nCL + s.nCCD + 2 - s.nCWL,
+            sibling: false,
+        });
+        t[Command::RD as usize].push(TimeEntry {
+            cmd: Command::WRA,
+            dist: 1,
+            val: s.nCL + s.nCCD + 2 - s.nCWL,
+            sibling: false,
+        });
+        t[Command::RDA as usize].push(TimeEntry {
+            cmd: Command::WR,
+            dist: 1,
+            val: s.nCL + s.nCCD + 2 - s.nCWL,
+            sibling: false,
+        });
+        t[Command::RDA as usize].push(TimeEntry {
+            cmd: Command::WRA,
+            dist: 1,
+            val: s.nCL + s.nCCD + 2 - s.nCWL,
+            sibling: false,
+        });
+        t[Command::WR as usize].push(TimeEntry {
+            cmd: Command::RD,
+            dist: 1,
+            val: s.nCWL + s.nBL + s.nWTR,
+            sibling: false,
+        });
+        t[Command::WR as usize].push(TimeEntry {
+            cmd: Command::RDA,
+            dist: 1,
+            val: s.nCWL + s.nBL + s.nWTR,
+            sibling: false,
+        });
+        t[Command::WRA as usize].push(TimeEntry {
+            cmd: Command::RD,
+            dist: 1,
+            val: s.nCWL + s.nBL + s.nWTR,
+            sibling: false,
+        });
+        t[Command::WRA as usize].push(TimeEntry {
+            cmd: Command::RDA,
+            dist: 1,
+            val: s.nCWL + s.nBL + s.nWTR,
+            sibling: false,
+        });
+
+        // CAS <-> CAS (between sibling ranks)
+        t[Command::RD as usize].push(TimeEntry {
+            cmd: Command::RD,
+            dist: 1,
+            val: s.nBL + s.nRTRS,
+            sibling: true,
+        });
+        t[Command::RD as usize].push(TimeEntry {
+            cmd: Command::RDA,
+            dist: 1,
+            val: s.nBL + s.nRTRS,
+            sibling: true,
+        });
+        t[Command::RDA as usize].push(TimeEntry {
+            cmd: Command::RD,
+            dist: 1,
+            val: s.nBL + s.nRTRS,
+            sibling: true,
+        });
+        t[Command::RDA as usize].push(TimeEntry {
+            cmd: Command::RDA,
+            dist: 1,
+            val: s.nBL + s.nRTRS,
+            sibling: true,
+        });
+        t[Command::RD as usize].push(TimeEntry {
+            cmd: Command::WR,
+            dist: 1,
+            val: s.nBL + s.nRTRS,
+            sibling: true,
+        });
+        t[Command::RD as usize].push(TimeEntry {
+            cmd: Command::WRA,
+            dist: 1,
+            val: s.nBL + s.nRTRS,
+            sibling: true,
+        });
+        t[Command::RDA as usize].push(TimeEntry {
+            cmd: Command::WR,
+            dist: 1,
+            val: s.nBL + s.nRTRS,
+            sibling: true,
+        });
+        t[Command::RDA as usize].push(TimeEntry {
+            cmd: Command::WRA,
+            dist: 1,
+            val: s.nBL + s.nRTRS,
+            sibling: true,
+        });
+        t[Command::RD as usize].push(TimeEntry {
+            cmd: Command::WR,
+            dist: 1,
+            val: s.nCL + s.nBL + s.nRTRS - s.nCWL,
+            sibling: true,
+        });
+        t[Command::RD as usize].push(TimeEntry {
+            cmd: Command::WRA,
+            dist: 1,
+            val: s.nCL + s.nBL + s.nRTRS - s.nCWL,
+            sibling: true,
+        });
+        t[Command::RDA as usize].push(TimeEntry {
+            cmd: Command::WR,
+            dist: 1,
+            val: s.nCL + s.nBL + s.nRTRS - s.nCWL,
+            sibling: true,
+        });
+        t[Command::RDA as usize].push(TimeEntry {
+            cmd: Command::WRA,
+            dist: 1,
+            val: s.nCL + s.nBL + s.nRTRS - s.nCWL,
+            sibling: true,
+        });
+        t[Command::WR as usize].push(TimeEntry {
+            cmd: Command::RD,
+            dist: 1,
+            val: s.nCWL + s.nBL + s.nRTRS - s.nCL,
+            sibling: true,
+        });
+        t[Command::WR as usize].push(TimeEntry {
+            cmd: Command::RDA,
+            dist: 1,
+            val: s.nCWL + s.nBL + s.nRTRS - s.nCL,
+            sibling: true,
+        });
+        t[Command::WRA as usize].push(TimeEntry {
+            cmd: Command::RD,
+            dist: 1,
+            val: s.nCWL + s.nBL + s.nRTRS - s.nCL,
+            sibling: true,
+        });
+        t[Command::WRA as usize].push(TimeEntry {
+            cmd: Command::RDA,
+            dist: 1,
+            val: s.nCWL + s.nBL + s.nRTRS - s.nCL,
+            sibling: true,
+        });
+
+        t[Command::RD as usize].push(TimeEntry {
+            cmd: Command::PREA,
+            dist: 1,
+            val: s.nRTP,
+            sibling: false,
+        });
+        t[Command::WR as usize].push(TimeEntry {
+            cmd: Command::PREA,
+            dist: 1,
+            val: s.nCWL + s.nBL + s.nWR,
+            sibling: false,
+        });
+
+        // CAS <-> PD
+        t[Command::RD as usize].push(TimeEntry {
+            cmd: Command::PDE,
+            dist: 1,
+            val: s.nCL + s.nBL + 1,
+            sibling: false,
+        });
+        t[Command::RDA as usize].push(TimeEntry {
+            cmd: Command::PDE,
+            dist: 1,
+            val: s.nCL + s.nBL + 1,
+            sibling: false,
+        });
+        t[Command::WR as usize].push(TimeEntry {
+            cmd: Command::PDE,
+            dist: 1,
+            val: s.nCWL + s.nBL + s.nWR,
+            sibling: false,
+        });
+        t[Command::WRA as usize].push(TimeEntry {
+            cmd: Command::PDE,
+            dist: 1,
+            val: s.nCWL + s.nBL + s.nWR + 1,
+            sibling: false,
+        }); // +1 for pre
+        t[Command::PDX as usize].push(TimeEntry {
+            cmd: Command::RD,
+            dist: 1,
+            val: s.nXP,
+            sibling: false,
+        });
+        t[Command::PDX as usize].push(TimeEntry {
+            cmd: Command::RDA,
+            dist: 1,
+            val: s.nXP,
+            sibling: false,
+        });
+        t[Command::PDX as usize].push(TimeEntry {
+            cmd: Command::WR,
+            dist: 1,
+            val: s.nXP,
+            sibling: false,
+        });
+        t[Command::PDX as usize].push(TimeEntry {
+            cmd: Command::WRA,
+            dist: 1,
+            val: s.nXP,
+            sibling: false,
+        });
+
+        // CAS <-> SR: none (all banks have to be precharged)
+
+        // RAS <-> RAS
+        t[Command::ACT as usize].push(TimeEntry {
+            cmd: Command::ACT,
+            dist: 1,
+            val: s.nRRD,
+            sibling: false,
+        });
+        t[Command::ACT as usize].push(TimeEntry {
+            cmd: Command::ACT,
+            dist: 4,
+            val: s.nFAW,
+            sibling: false,
+        });
+        t[Command::ACT as usize].push(TimeEntry {
+            cmd: Command::PREA,
+            dist: 1,
+            val: s.nRAS,
+            sibling: false,
+        });
+        t[Command::PREA as usize].push(TimeEntry {
+            cmd: Command::ACT,
+            dist: 1,
+            val: s.nRP,
+            sibling: false,
+        });
+
+        // RAS <-> REF
+        t[Command::ACT as usize].push(TimeEntry {
+            cmd: Command::REF,
+            dist: 1,
+            val: s.nRC,
+            sibling: false,
+        });
+        t[Command::PRE as usize].push(TimeEntry {
+            cmd: Command::REF,
+            dist: 1,
+            val: s.nRP,
+            sibling: false,
+        });
+        t[Command::PREA as usize].push(TimeEntry {
+            cmd: Command::REF,
+            dist: 1,
+            val: s.nRP,
+            sibling: false,
+        });
+        t[Command::RDA as usize].push(TimeEntry {
+            cmd: Command::REF,
+            dist: 1,
+            val: s.nRTP + s.nRP,
+            sibling: false,
+        });
+        t[Command::WRA as usize].push(TimeEntry {
+            cmd: Command::REF,
+            dist: 1,
+            val: s.nCWL + s.nBL + s.nWR + s.nRP,
+            sibling: false,
+        });
+        t[Command::REF as usize].push(TimeEntry {
+            cmd: Command::ACT,
+            dist: 1,
+            val: s.nRFC,
+            sibling: false,
+        });
+
+        // RAS <-> PD
+        t[Command::ACT as usize].push(TimeEntry {
+            cmd: Command::PDE,
+            dist: 1,
+            val: 1,
+            sibling: false,
+        });
+        t[Command::PDX as usize].push(TimeEntry {
+            cmd: Command::ACT,
+            dist: 1,
+            val: s.nXP,
+            sibling: false,
+        });
+        t[Command::PDX as usize].push(TimeEntry {
+            cmd: Command::PRE,
+            dist: 1,
+            val: s.nXP,
+            sibling: false,
+        });
+        t[Command::PDX as usize].push(TimeEntry {
+            cmd: Command::PREA,
+            dist: 1,
+            val: s.nXP,
+            sibling: false,
+        });
+
+        // RAS <-> SR
+        t[Command::PRE as usize].push(TimeEntry {
+            cmd: Command::SRE,
+            dist: 1,
+            val: s.nRP,
+            sibling: false,
+        });
+        t[Command::PREA as usize].push(TimeEntry {
+            cmd: Command::SRE,
+            dist: 1,
+            val: s.nRP,
+            sibling: false,
+        });
+        t[Command::SRX as usize].push(TimeEntry {
+            cmd: Command::ACT,
+            dist: 1,
+            val: s.nXS,
+            sibling: false,
+        });
+
+        // REF <-> REF
+        t[Command::REF as usize].push(TimeEntry {
+            cmd: Command::REF,
+            dist: 1,
+            val: s.nRFC,
+            sibling: false,
+        });
+
+        // REF <-> PD
+        t[Command::REF as usize].push(TimeEntry {
+            cmd: Command::PDE,
+            dist: 1,
+            val: 1,
+            sibling: false,
+        });
+        t[Command::PDX as usize].push(TimeEntry {
+            cmd: Command::REF,
+            dist: 1,
+            val: s.nXP,
+            sibling: false,
+        });
+
+        // REF <-> SR
+        t[Command::SRX as usize].push(TimeEntry {
+            cmd: Command::REF,
+            dist: 1,
+            val: s.nXS,
+            sibling: false,
+        });
+
+        // PD <-> PD
+        t[Command::PDE as usize].push(TimeEntry {
+            cmd: Command::PDX,
+            dist: 1,
+            val: s.nPD,
+            sibling: false,
+        });
+        t[Command::PDX as usize].push(TimeEntry {
+            cmd: Command::PDE,
+            dist: 1,
+            val: s.nXP,
+            sibling: false,
+        });
+
+        // PD <-> SR
+        t[Command::PDX as usize].push(TimeEntry {
+            cmd: Command::SRE,
+            dist: 1,
+            val: s.nXP,
+            sibling: false,
+        });
+        t[Command::SRX as usize].push(TimeEntry {
+            cmd: Command::PDE,
+            dist: 1,
+            val: s.nXS,
+            sibling: false,
+        });
+
+        // SR <-> SR
+        t[Command::SRE as usize].push(TimeEntry {
+            cmd: Command::SRX,
+            dist: 1,
+            val: s.nCKESR,
+            sibling: false,
+        });
+        t[Command::SRX as usize].push(TimeEntry {
+            cmd: Command::SRE,
+            dist: 1,
+            val: s.nXS,
+            sibling: false,
+        });
+
+        /*** Bank ***/
+        let t = timing.get_level_mut(Level::Bank);
+
+        // CAS <-> RAS
+        t[Command::ACT as usize].push(TimeEntry {
+            cmd: Command::RD,
+            dist: 1,
+            val: s.nRCD,
+            sibling: false,
+        });
+        t[Command::ACT as usize].push(TimeEntry {
+            cmd: Command::RDA,
+            dist: 1,
+            val: s.nRCD,
+            sibling: false,
+        });
+        t[Command::ACT as usize].push(TimeEntry {
+            cmd: Command::WR,
+            dist: 1,
+            val: s.nRCD,
+            sibling: false,
+        });
+        t[Command::ACT as usize].push(TimeEntry {
+            cmd: Command::WRA,
+            dist: 1,
+            val: s.nRCD,
+            sibling: false,
+        });
+
+        t[Command::RD as usize].push(TimeEntry {
+            cmd: Command::PRE,
+            dist: 1,
+            val: s.nRTP,
+            sibling: false,
+        });
+        t[Command::WR as usize].push(TimeEntry {
+            cmd: Command::PRE,
+            dist: 1,
+            val: s.nCWL + s.nBL + s.nWR,
+            sibling: false,
+        });
+
+        t[Command::RDA as usize].push(TimeEntry {
+            cmd: Command::ACT,
+            dist: 1,
+            val: s.nRTP + s.nRP,
+            sibling: false,
+        });
+        t[Command::WRA as usize].push(TimeEntry {
+            cmd: Command::ACT,
+            dist: 1,
+            val: s.nCWL + s.nBL + s.nWR + s.nRP,
+            sibling: false,
+        });
+
+        // RAS <-> RAS
+        t[Command::ACT as usize].push(TimeEntry {
+            cmd: Command::ACT,
+            dist: 1,
+            val: s.nRC,
+            sibling: false,
+        });
+        t[Command::ACT as usize].push(TimeEntry {
+            cmd: Command::PRE,
+            dist: 1,
+            val: s.nRAS,
+            sibling: false,
+        });
+        t[Command::PRE as usize].push(TimeEntry {
+            cmd: Command::ACT,
+            dist: 1,
+            val: s.nRP,
+            sibling: false,
+        });
+    }
+
+    pub fn get_speed(speed: &Speed) -> SpeedEntry {
+        match speed {
+            Speed::DDR3_800D => SpeedEntry {
+                rate: 800,
+                freq: (400.0 / 3.0) * 3.0,
+                tCK: (3.0 / 0.4) / 3.0,
+                nBL: 4,
+                nCCD: 4,
+                nRTRS: 2,
+                nCL: 5,
+                nRCD: 5,
+                nRP: 5,
+                nCWL: 5,
+                nRAS: 15,
+                nRC: 20,
+                nRTP: 4,
+                nWTR: 4,
+                nWR: 6,
+                nRRD: 0,
+                nFAW: 0,
+                nRFC: 0,
+                nREFI: 3120,
+                nPD: 3,
+                nXP: 3,
+                nXPDLL: 10,
+                nCKESR: 4,
+                nXS: 0,
+                nXSDLL: 512,
+            },
+            Speed::DDR3_800E => SpeedEntry {
+                rate: 800,
+                freq: (400.0 / 3.0) * 3.0,
+                tCK: (3.0 / 0.4) / 3.0,
+                nBL: 4,
+                nCCD: 4,
+                nRTRS: 2,
+                nCL: 6,
+                nRCD: 6,
+                nRP: 6,
+                nCWL: 5,
+                nRAS: 15,
+                nRC: 21,
+                nRTP: 4,
+                nWTR: 4,
+                nWR: 6,
+                nRRD: 0,
+                nFAW: 0,
+                nRFC: 0,
+                nREFI: 3120,
+                nPD: 3,
+                nXP: 3,
+                nXPDLL: 10,
+                nCKESR: 4,
+                nXS: 0,
+                nXSDLL: 512,
+            },
+            Speed::DDR3_1066E => SpeedEntry {
+                rate: 1066,
+                freq: (400.0 / 3.0) * 4.0,
+                tCK: (3.0 / 0.4) / 4.0,
+                nBL: 4,
+                nCCD: 4,
+                nRTRS: 2,
+                nCL: 6,
+                nRCD: 6,
+                nRP: 6,
+                nCWL: 6,
+                nRAS: 20,
+                nRC: 26,
+                nRTP: 4,
+                nWTR: 4,
+                nWR: 8,
+                nRRD: 0,
+                nFAW: 0,
+                nRFC: 0,
+                nREFI: 4160,
+                nPD: 3,
+                nXP: 4,
+                nXPDLL: 13,
+                nCKESR: 4,
+                nXS: 0,
+                nXSDLL: 512,
+            },
+            Speed::DDR3_1066F => SpeedEntry {
+                rate: 1066,
+                freq: (400.0 / 3.0) * 4.0,
+                tCK: (3.0 / 0.4) / 4.0,
+                nBL: 4,
+                nCCD: 4,
+                nRTRS: 2,
+                nCL: 7,
+                nRCD: 7,
+                nRP: 7,
+                nCWL: 6,
+                nRAS: 20,
+                nRC: 27,
+                nRTP: 4,
+                nWTR: 4,
+                nWR: 8,
+                nRRD: 0,
+                nFAW: 0,
+                nRFC: 0,
+                nREFI: 4160,
+                nPD: 3,
+                nXP: 4,
+                nXPDLL: 13,
+                nCKESR: 4,
+                nXS: 0,
+                nXSDLL: 512,
+            },
+            Speed::DDR3_1066G => SpeedEntry {
+                rate: 1066,
+                freq: (400.0 / 3.0) * 4.0,
+                tCK: (3.0 / 0.4) / 4.0,
+                nBL: 4,
+                nCCD: 4,
+                nRTRS: 2,
+                nCL: 8,
+                nRCD: 8,
+                nRP: 8,
+                nCWL: 6,
+                nRAS: 20,
+                nRC: 28,
+                nRTP: 4,
+                nWTR: 4,
+                nWR: 8,
+                nRRD: 0,
+                nFAW: 0,
+                nRFC: 0,
+                nREFI: 4160,
+                nPD: 3,
+                nXP: 4,
+                nXPDLL: 13,
+                nCKESR: 4,
+                nXS: 0,
+                nXSDLL: 512,
+            },
+            Speed::DDR3_1333G => SpeedEntry {
+                rate: 1333,
+                freq: (400.0 / 3.0) * 5.0,
+                tCK: (3.0 / 0.4) / 5.0,
+                nBL: 4,
+                nCCD: 4,
+                nRTRS: 2,
+                nCL: 8,
+                nRCD: 8,
+                nRP: 8,
+                nCWL: 7,
+                nRAS: 24,
+                nRC: 32,
+                nRTP: 5,
+                nWTR: 5,
+                nWR: 10,
+                nRRD: 0,
+                nFAW: 0,
+                nRFC: 0,
+                nREFI: 5200,
+                nPD: 4,
+                nXP: 4,
+                nXPDLL: 16,
+                nCKESR: 5,
+                nXS: 0,
+                nXSDLL: 512,
+            },
+            Speed::DDR3_1333H => SpeedEntry {
+                rate: 1333,
+                freq: (400.0 / 3.0) * 5.0,
+                tCK: (3.0 / 0.4) / 5.0,
+                nBL: 4,
+                nCCD: 4,
+                nRTRS: 2,
+                nCL: 9,
+                nRCD: 9,
+                nRP: 9,
+                nCWL: 7,
+                nRAS: 24,
+                nRC: 33,
+                nRTP: 5,
+                nWTR: 5,
+                nWR: 10,
+                nRRD: 0,
+                nFAW: 0,
+                nRFC: 0,
+                nREFI: 5200,
+                nPD: 4,
+                nXP: 4,
+                nXPDLL: 16,
+                nCKESR: 5,
+                nXS: 0,
+                nXSDLL: 512,
+            },
+            Speed::DDR3_1600H => SpeedEntry {
+                rate: 1600,
+                freq: (400.0 / 3.0) * 6.0,
+                tCK: (3.0 / 0.4) / 6.0,
+                nBL: 4,
+                nCCD: 4,
+                nRTRS: 2,
+                nCL: 9,
+                nRCD: 9,
+                nRP: 9,
+                nCWL: 8,
+                nRAS: 28,
+                nRC: 37,
+                nRTP: 6,
+                nWTR: 6,
+                nWR: 12,
+                nRRD: 0,
+                nFAW: 0,
+                nRFC: 0,
+                nREFI: 6240,
+                nPD: 4,
+                nXP: 5,
+                nXPDLL: 20,
+                nCKESR: 5,
+                nXS: 0,
+                nXSDLL: 512,
+            },
+            Speed::DDR3_1600J => SpeedEntry {
+                rate: 1600,
+                freq: (400.0 / 3.0) * 6.0,
+                tCK: (3.0 / 0.4) / 6.0,
+                nBL: 4,
+                nCCD: 4,
+                nRTRS: 2,
+                nCL: 10,
+                nRCD: 10,
+                nRP: 10,
+                nCWL: 8,
+                nRAS: 28,
+                nRC: 38,
+                nRTP: 6,
+                nWTR: 6,
+                nWR: 12,
+                nRRD: 0,
+                nFAW: 0,
+                nRFC: 0,
+                nREFI: 6240,
+                nPD: 4,
+                nXP: 5,
+                nXPDLL: 20,
+                nCKESR: 5,
+                nXS: 0,
+                nXSDLL: 512,
+            },
+            Speed::DDR3_1600K => SpeedEntry {
+                rate: 1600,
+                freq: (400.0 / 3.0) * 6.0,
+                tCK: (3.0 / 0.4) / 6.0,
+                nBL: 4,
+                nCCD: 4,
+                nRTRS: 2,
+                nCL: 11,
+                nRCD: 11,
+                nRP: 11,
+                nCWL: 8,
+                nRAS: 28,
+                nRC: 39,
+                nRTP: 6,
+                nWTR: 6,
+                nWR: 12,
+                nRRD: 0,
+                nFAW: 0,
+                nRFC: 0,
+                nREFI: 6240,
+                nPD: 4,
+                nXP: 5,
+                nXPDLL: 20,
+                nCKESR: 5,
+                nXS: 0,
+                nXSDLL: 512,
+            },
+            Speed::DDR3_1866K => SpeedEntry {
+                rate: 1866,
+                freq: (400.0 / 3.0) * 7.0,
+                tCK: (3.0 / 0.4) / 7.0,
+                nBL: 4,
+                nCCD: 4,
+                nRTRS: 2,
+                nCL: 11,
+                nRCD: 11,
+                nRP: 11,
+                nCWL: 9,
+                nRAS: 32,
+                nRC: 43,
+                nRTP: 7,
+                nWTR: 7,
+                nWR: 14,
+                nRRD: 0,
+                nFAW: 0,
+                nRFC: 0,
+                nREFI: 7280,
+                nPD: 5,
+                nXP: 6,
+                nXPDLL: 23,
+                nCKESR: 6,
+                nXS: 0,
+                nXSDLL: 512,
+            },
+            Speed::DDR3_1866L => SpeedEntry {
+                rate: 1866,
+                freq: (400.0 / 3.0) * 7.0,
+                tCK: (3.0 / 0.4) / 7.0,
+                nBL: 4,
+                nCCD: 4,
+                nRTRS: 2,
+                nCL: 12,
+                nRCD: 12,
+                nRP: 12,
+                nCWL: 9,
+                nRAS: 32,
+                nRC: 44,
+                nRTP: 7,
+                nWTR: 7,
+                nWR: 14,
+                nRRD: 0,
+                nFAW: 0,
+                nRFC: 0,
+                nREFI: 7280,
+                nPD: 5,
+                nXP: 6,
+                nXPDLL: 23,
+                nCKESR: 6,
+                nXS: 0,
+                nXSDLL: 512,
+            },
+            Speed::DDR3_2133L => SpeedEntry {
+                rate: 2133,
+                freq: (400.0 / 3.0) * 8.0,
+                tCK: (3.0 / 0.4) / 8.0,
+                nBL: 4,
+                nCCD: 4,
+                nRTRS: 2,
+                nCL: 12,
+                nRCD: 12,
+                nRP: 12,
+                nCWL: 10,
+                nRAS: 36,
+                nRC: 48,
+                nRTP: 8,
+                nWTR: 8,
+                nWR: 16,
+                nRRD: 0,
+                nFAW: 0,
+                nRFC: 0,
+                nREFI: 8320,
+                nPD: 6,
+                nXP: 7,
+                nXPDLL: 26,
+                nCKESR: 7,
+                nXS: 0,
+                nXSDLL: 512,
+            },
+            Speed::DDR3_2133M => SpeedEntry {
+                rate: 2133,
+                freq: (400.0 / 3.0) * 8.0,
+                tCK: (3.0 / 0.4) / 8.0,
+                nBL: 4,
+                nCCD: 4,
+                nRTRS: 2,
+                nCL: 13,
+                nRCD: 13,
+                nRP: 13,
+                nCWL: 10,
+                nRAS: 36,
+                nRC: 49,
+                nRTP: 8,
+                nWTR: 8,
+                nWR: 16,
+                nRRD: 0,
+                nFAW: 0,
+                nRFC: 0,
+                nREFI: 8320,
+                nPD: 6,
+                nXP: 7,
+                nXPDLL: 26,
+                nCKESR: 7,
+                nXS: 0,
+                nXSDLL: 512,
+            },
+        }
+    }
+}
+impl DramSpec for DDR3 {
+    fn from_config(config: &Config) -> Result<Self, ConfigError> {
+        Ok(Self::new(config))
+    }
+    fn supports_per_bank_refresh() -> bool {
+        false
+    }
+    fn supports_pim() -> bool {
+        false
+    }
+    fn get_first_cmd(req_type: &ReqType) -> Command {
+        match req_type {
+            ReqType::Read => Command::RD,
+            ReqType::Write => Command::WR,
+            ReqType::Refresh => Command::REF,
+            ReqType::RefreshBank => unreachable!("DDR3 has no per-bank refresh"),
+            ReqType::PimMac
+            | ReqType::PimMacAllBank
+            | ReqType::PimLoad
+            | ReqType::PimStore
+            | ReqType::PimBroadcast => {
+                unreachable!("DDR3 has no PIM commands")
+            }
+        }
+    }
+
+    fn get_pre_cmd(dram: &Dram<Self>, cmd: &Command, child_id: u64) -> Option<Command> {
+        match (&dram.level, cmd) {
+            (Level::Rank, Command::RD) | (Level::Rank, Command::WR) => match dram.state {
+                dram::State::PowerUp => None,
+                dram::State::ActPowerDown => Some(Command::PDX),
+                dram::State::PrePowerDown => Some(Command::PDX),
+                dram::State::SelfRefresh => Some(Command::SRX),
+                _ => unreachable!("invalid dram state"),
+            },
+            (Level::Rank, Command::REF) => {
+                if dram
+                    .children
+                    .iter()
+                    .any(|bank| !matches!(bank.state, dram::State::Closed))
+                {
+                    Some(Command::PREA)
+                } else {
+                    Some(Command::REF)
+                }
+            }
+            (Level::Rank, Command::PDE) => match dram.state {
+                dram::State::PowerUp | dram::State::ActPowerDown | dram::State::PrePowerDown => {
+                    Some(Command::PDE)
+                }
+                dram::State::SelfRefresh => Some(Command::SRX),
+                _ => unreachable!("invalid dram state"),
+            },
+            (Level::Rank, Command::SRE) => match dram.state {
+                dram::State::PowerUp | dram::State::SelfRefresh => Some(Command::SRE),
+                dram::State::ActPowerDown | dram::State::PrePowerDown => Some(Command::PDX),
+                _ => unreachable!("invalid dram state"),
+            },
+
+            (Level::Bank, Command::RD) | (Level::Bank, Command::WR) => match dram.state {
+                dram::State::Closed => Some(Command::ACT),
+                dram::State::Opened(row_id) => {
+                    if row_id == child_id {
+                        Some(*cmd)
+                    } else {
+                        Some(Command::PRE)
+                    }
+                }
+                _ => unreachable!("invalid dram state"),
+            },
+            _ => None,
+        }
+    }
+
+    fn get_start_state(level: &Level) -> dram::State {
+        match level {
+            Level::Rank => dram::State::PowerUp,
+            Level::Bank => dram::State::Closed,
+            Level::Row => dram::State::Closed,
+            _ => dram::State::NoUse,
+        }
+    }
+
+    fn get_levels(&self) -> &[Level] {
+        &LEVELS
+    }
+
+    fn get_addr_bits(&self) -> &[usize] {
+        &self.addr_bits
+    }
+
+    fn get_child_size(&self) -> &[usize] {
+        &self.child_size
+    }
+
+    fn get_scope(&self, cmd: &Command) -> Level {
+        match cmd {
+            Command::ACT | Command::ACTAB => Level::Row,
+            Command::PRE | Command::PREAB | Command::REFSB | Command::REFPB => Level::Bank,
+            Command::RD
+            | Command::WR
+            | Command::RDA
+            | Command::WRA
+            | Command::MAC
+            | Command::LDR
+            | Command::STR
+            | Command::MACAB => Level::Column,
+            Command::PREA
+            | Command::REF
+            | Command::PDE
+            | Command::PDX
+            | Command::SRE
+            | Command::SRX
+            | Command::WRGB => Level::Rank,
+            Command::Max => unreachable!(),
+        }
+    }
+
+    fn update_state(&self, dram: &mut Dram<Self>, cmd: &Command, child_id: u64) {
+        let banks_closed =
+            |dram: &Dram<Self>| dram.children.iter().all(|bank| bank.state == State::Closed);
+        match (dram.level, cmd) {
+            (Level::Bank, Command::ACT) => {
+                dram.state = State::Opened(child_id);
+            }
+            (Level::Bank, Command::PRE | Command::RDA | Command::WRA) => {
+                dram.state = State::Closed;
+            }
+            (Level::Rank, Command::PREA) => {
+                dram.children.iter_mut().for_each(|bank| {
+                    bank.state = State::Closed;
+                });
+            }
+            (Level::Rank, Command::PDE) => {
+                dram.state = if banks_closed(dram) {
+                    State::PrePowerDown
+                } else {
+                    State::ActPowerDown
+                };
+            }
+            (Level::Rank, Command::PDX | Command::SRX) => {
+                dram.state = State::PowerUp;
+            }
+            (Level::Rank, Command::SRE) => {
+                dram.state = State::SelfRefresh;
+            }
+            _ => {}
+        }
+    }
+    fn get_timming(&self, depth: usize, cmd: &Command) -> &[TimeEntry] {
+        self.timing.get(depth, cmd)
+    }
+
+    fn get_read_latency(&self) -> u64 {
+        self.read_latency
+    }
+
+    fn get_refresh_interval(&self) -> u64 {
+        self.speed_entry.nREFI
+    }
+
+    fn get_clk_ns(&self) -> f64 {
+        self.speed_entry.tCK
+    }
+
+    fn get_transaction_bytes(&self) -> u64 {
+        PREFETCH_SIZE * CHANNEL_WIDTH / 8
+    }
+
+    fn get_burst_cycles(&self) -> u64 {
+        self.speed_entry.nBL
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use super::{DDR3Org, Speed, DDR3};
+    use crate::{
+        command::Command,
+        config::{Config, Standard},
+        controller::Controller,
+        dram::{Dram, DramSpec},
+        memory::Level,
+        request::{ReqType, Request},
+    };
+
+    #[test]
+    fn test_ddr3() {
+        let config =
+            Config::parse(include_str!("../../ramulator/configs/DDR3-config.cfg")).unwrap();
+        assert_eq!(config.standard, Standard::DDR3);
+        assert_eq!(config.ddr3_org, DDR3Org::DDR3_2Gb_x8);
+        assert_eq!(config.ddr3_speed, Speed::DDR3_1600K);
+        let ddr3 = Arc::new(DDR3::new(&config));
+        assert_eq!(ddr3.get_child_size(), [1, 1, 8, 1 << 15, 1 << 10]);
+        assert_eq!(ddr3.get_addr_bits(), [0, 0, 3, 15, 10]);
+        assert_eq!(ddr3.get_read_latency(), 11 + 4);
+        assert_eq!(ddr3.get_refresh_interval(), 6240);
+        let n_rfc = ddr3
+            .get_timming(ddr3.get_level_index(Level::Rank), &Command::REF)
+            .iter()
+            .find(|t| t.cmd == Command::ACT)
+            .unwrap()
+            .val;
+        assert_eq!(n_rfc, 128);
+
+        // a row hit, then a row conflict in the same bank
+        let channel = Dram::new(ddr3.clone(), Level::Channel, ddr3.get_child_size());
+        let mut controller = Controller::new(&config, channel);
+        for row in [0, 0, 1] {
+            let req = Request::with_addr_vec(vec![0, 0, 5, row, 0], ReqType::Read);
+            controller.try_enqueue(req).unwrap();
+        }
+        let mut finished = 0;
+        let mut clk = 0;
+        while finished < 3 {
+            clk += 1;
+            controller.tick(clk);
+            finished += controller.finished_queue.drain(..).count();
+        }
+        assert_eq!(controller.stats.row_hits, 1);
+        assert_eq!(controller.stats.row_conflicts, 1);
+        let bank = &controller.channel.children[0].children[5];
+        assert_eq!(bank.cmd_counts[Command::ACT as usize], 2);
+    }
+}
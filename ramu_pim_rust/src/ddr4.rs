@@ -1,18 +1,14 @@
+use std::str::FromStr;
+
 use crate::{
     command::Command,
-    config::Config,
-    dram::{self, Dram, DramSpec, State, TimeEntry},
+    config::{Config, ConfigError},
+    dram::{self, log2, Dram, DramSpec, State, TimeEntry, TimingTable},
     memory::Level,
     request::ReqType,
 };
-fn log2(mut x: usize) -> usize {
-    let mut i = 0;
-    while x > 1 {
-        x = x >> 1;
-        i += 1;
-    }
-    i
-}
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[allow(non_camel_case_types)]
 pub enum DDR4Org {
     DDR4_2Gb_x4,
     DDR4_2Gb_x8,
@@ -25,7 +21,29 @@ pub enum DDR4Org {
     DDR4_8Gb_x16,
     MAX,
 }
+impl FromStr for DDR4Org {
+    type Err = ();
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "DDR4_2Gb_x4" => DDR4Org::DDR4_2Gb_x4,
+            "DDR4_2Gb_x8" => DDR4Org::DDR4_2Gb_x8,
+            "DDR4_2Gb_x16" => DDR4Org::DDR4_2Gb_x16,
+            "DDR4_4Gb_x4" => DDR4Org::DDR4_4Gb_x4,
+            "DDR4_4Gb_x8" => DDR4Org::DDR4_4Gb_x8,
+            "DDR4_4Gb_x16" => DDR4Org::DDR4_4Gb_x16,
+            "DDR4_8Gb_x4" => DDR4Org::DDR4_8Gb_x4,
+            "DDR4_8Gb_x8" => DDR4Org::DDR4_8Gb_x8,
+            "DDR4_8Gb_x16" => DDR4Org::DDR4_8Gb_x16,
+            _ => return Err(()),
+        })
+    }
+}
 
+/// the density(in Mb) and data width of a chip
+struct OrgEntry {
+    size: usize,
+    dq: usize,
+}
 #[allow(non_snake_case, dead_code)]
 pub struct SpeedEntry {
     rate: u64,
@@ -57,12 +75,26 @@ pub struct SpeedEntry {
     nXS: u64,
     nXSDLL: u64,
 }
+const LEVELS: [Level; 6] = [
+    Level::Channel,
+    Level::Rank,
+    Level::BankGroup,
+    Level::Bank,
+    Level::Row,
+    Level::Column,
+];
+/// 8n prefetch DDR
+const PREFETCH_SIZE: u64 = 8;
+const CHANNEL_WIDTH: u64 = 64;
 pub struct DDR4 {
     child_size: Vec<usize>,
     addr_bits: Vec<usize>,
-    timing: Vec<Vec<Vec<TimeEntry>>>,
+    speed_entry: SpeedEntry,
+    timing: TimingTable,
     read_latency: u64,
 }
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[allow(non_camel_case_types)]
 pub enum Speed {
     DDR4_1600K,
     DDR4_1600L,
@@ -74,6 +106,23 @@ pub enum Speed {
     DDR4_2400U,
     DDR4_3200,
 }
+impl FromStr for Speed {
+    type Err = ();
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "DDR4_1600K" => Speed::DDR4_1600K,
+            "DDR4_1600L" => Speed::DDR4_1600L,
+            "DDR4_1866M" => Speed::DDR4_1866M,
+            "DDR4_1866N" => Speed::DDR4_1866N,
+            "DDR4_2133P" => Speed::DDR4_2133P,
+            "DDR4_2133R" => Speed::DDR4_2133R,
+            "DDR4_2400R" => Speed::DDR4_2400R,
+            "DDR4_2400U" => Speed::DDR4_2400U,
+            "DDR4_3200" => Speed::DDR4_3200,
+            _ => return Err(()),
+        })
+    }
+}
 impl DDR4 {
     pub fn new(config: &Config) -> Self {
         let channels = config.channels;
@@ -91,20 +140,110 @@ impl DDR4 {
             DDR4Org::MAX => unreachable!(),
         };
         let addr_bits = child_size.iter().map(|x| log2(*x)).collect::<Vec<usize>>();
-        let speed_entry = Self::get_speed(&config.ddr4_speed);
-        let mut timing = vec![vec![vec![]; Command::Max as usize]; Level::Max as usize];
+        let mut speed_entry = Self::get_speed(&config.ddr4_speed);
+        Self::init_speed(&mut speed_entry, &Self::get_org(&config.ddr4_org));
+        let mut timing = TimingTable::new(&LEVELS);
         Self::init_timing(&mut timing, &speed_entry);
+        dram::add_all_bank_timing(&mut timing);
         let read_latency = speed_entry.nCL + speed_entry.nBL;
         Self {
             child_size,
             addr_bits,
+            speed_entry,
             timing,
             read_latency,
         }
     }
-    fn init_timing(timing: &mut Vec<Vec<Vec<TimeEntry>>>, s: &SpeedEntry) {
+    fn get_org(org: &DDR4Org) -> OrgEntry {
+        match org {
+            DDR4Org::DDR4_2Gb_x4 => OrgEntry {
+                size: 2 << 10,
+                dq: 4,
+            },
+            DDR4Org::DDR4_2Gb_x8 => OrgEntry {
+                size: 2 << 10,
+                dq: 8,
+            },
+            DDR4Org::DDR4_2Gb_x16 => OrgEntry {
+                size: 2 << 10,
+                dq: 16,
+            },
+            DDR4Org::DDR4_4Gb_x4 => OrgEntry {
+                size: 4 << 10,
+                dq: 4,
+            },
+            DDR4Org::DDR4_4Gb_x8 => OrgEntry {
+                size: 4 << 10,
+                dq: 8,
+            },
+            DDR4Org::DDR4_4Gb_x16 => OrgEntry {
+                size: 4 << 10,
+                dq: 16,
+            },
+            DDR4Org::DDR4_8Gb_x4 => OrgEntry {
+                size: 8 << 10,
+                dq: 4,
+            },
+            DDR4Org::DDR4_8Gb_x8 => OrgEntry {
+                size: 8 << 10,
+                dq: 8,
+            },
+            DDR4Org::DDR4_8Gb_x16 => OrgEntry {
+                size: 8 << 10,
+                dq: 16,
+            },
+            DDR4Org::MAX => unreachable!(),
+        }
+    }
+    /// fill the density and speed dependent timings, only the 1X refresh mode is supported
+    fn init_speed(s: &mut SpeedEntry, org: &OrgEntry) {
+        const RRDS_TABLE: [[u64; 5]; 2] = [[4, 4, 4, 4, 4], [5, 5, 6, 7, 9]];
+        const RRDL_TABLE: [[u64; 5]; 2] = [[5, 5, 6, 6, 8], [6, 6, 7, 8, 11]];
+        const FAW_TABLE: [[u64; 5]; 3] = [
+            [16, 16, 16, 16, 16],
+            [20, 22, 23, 26, 34],
+            [28, 28, 32, 36, 48],
+        ];
+        const RFC_TABLE: [[u64; 5]; 3] = [
+            [128, 150, 171, 192, 256],
+            [208, 243, 278, 312, 416],
+            [280, 327, 374, 420, 560],
+        ];
+        const REFI_TABLE: [u64; 5] = [6240, 7280, 8320, 9360, 12480];
+        const XS_TABLE: [[u64; 5]; 3] = [
+            [136, 159, 182, 204, 272],
+            [216, 252, 288, 324, 432],
+            [288, 336, 384, 432, 576],
+        ];
+        let speed = match s.rate {
+            1600 => 0,
+            1866 => 1,
+            2133 => 2,
+            2400 => 3,
+            3200 => 4,
+            _ => unreachable!("invalid speed rate"),
+        };
+        let density = match org.size >> 10 {
+            2 => 0,
+            4 => 1,
+            8 => 2,
+            _ => unreachable!("invalid density"),
+        };
+        let dq = match org.dq {
+            4 => 0,
+            8 => 1,
+            _ => 2,
+        };
+        s.nRRDS = RRDS_TABLE[if org.dq == 16 { 1 } else { 0 }][speed];
+        s.nRRDL = RRDL_TABLE[if org.dq == 16 { 1 } else { 0 }][speed];
+        s.nFAW = FAW_TABLE[dq][speed];
+        s.nRFC = RFC_TABLE[density][speed];
+        s.nREFI = REFI_TABLE[speed];
+        s.nXS = XS_TABLE[density][speed];
+    }
+    fn init_timing(timing: &mut TimingTable, s: &SpeedEntry) {
         /*** Channel ***/
-        let t = &mut timing[Level::Channel as usize];
+        let t = timing.get_level_mut(Level::Channel);
 
         // CAS <-> CAS
         t[Command::RD as usize].push(TimeEntry {
@@ -157,7 +296,7 @@ impl DDR4 {
         });
 
         /*** Rank ***/
-        let t = &mut timing[Level::Rank as usize];
+        let t = timing.get_level_mut(Level::Rank);
 
         // CAS <-> CAS
         t[Command::RD as usize].push(TimeEntry {
@@ -603,7 +742,7 @@ impl DDR4 {
         });
 
         /*** Bank Group ***/
-        let t = &mut timing[Level::BankGroup as usize];
+        let t = timing.get_level_mut(Level::BankGroup);
         // CAS <-> CAS
         t[Command::RD as usize].push(TimeEntry {
             cmd: Command::RD,
@@ -687,7 +826,7 @@ impl DDR4 {
         });
 
         /*** Bank ***/
-        let t = &mut timing[Level::Bank as usize];
+        let t = timing.get_level_mut(Level::Bank);
 
         // CAS <-> RAS
         t[Command::ACT as usize].push(TimeEntry {
@@ -760,6 +899,222 @@ impl DDR4 {
             val: s.nRP,
             sibling: false,
         });
+
+        /*** PIM ***/
+        // the PIM commands access the banks like RD and WR, their data never leaves the chip
+        let t = timing.get_level_mut(Level::Rank);
+
+        // PIM <-> PIM
+        t[Command::MAC as usize].push(TimeEntry {
+            cmd: Command::MAC,
+            dist: 1,
+            val: s.nCCDS,
+            sibling: false,
+        });
+        t[Command::MAC as usize].push(TimeEntry {
+            cmd: Command::LDR,
+            dist: 1,
+            val: s.nCCDS,
+            sibling: false,
+        });
+        t[Command::MAC as usize].push(TimeEntry {
+            cmd: Command::STR,
+            dist: 1,
+            val: s.nCCDS,
+            sibling: false,
+        });
+        t[Command::LDR as usize].push(TimeEntry {
+            cmd: Command::MAC,
+            dist: 1,
+            val: s.nCCDS,
+            sibling: false,
+        });
+        t[Command::LDR as usize].push(TimeEntry {
+            cmd: Command::LDR,
+            dist: 1,
+            val: s.nCCDS,
+            sibling: false,
+        });
+        t[Command::LDR as usize].push(TimeEntry {
+            cmd: Command::STR,
+            dist: 1,
+            val: s.nCCDS,
+            sibling: false,
+        });
+        t[Command::STR as usize].push(TimeEntry {
+            cmd: Command::MAC,
+            dist: 1,
+            val: s.nCCDS,
+            sibling: false,
+        });
+        t[Command::STR as usize].push(TimeEntry {
+            cmd: Command::LDR,
+            dist: 1,
+            val: s.nCCDS,
+            sibling: false,
+        });
+        t[Command::STR as usize].push(TimeEntry {
+            cmd: Command::STR,
+            dist: 1,
+            val: s.nCCDS,
+            sibling: false,
+        });
+
+        // WRGB <-> WR, the operand takes the data bus like a write
+        t[Command::WR as usize].push(TimeEntry {
+            cmd: Command::WRGB,
+            dist: 1,
+            val: s.nCCDS,
+            sibling: false,
+        });
+        t[Command::WRA as usize].push(TimeEntry {
+            cmd: Command::WRGB,
+            dist: 1,
+            val: s.nCCDS,
+            sibling: false,
+        });
+        t[Command::WRGB as usize].push(TimeEntry {
+            cmd: Command::WR,
+            dist: 1,
+            val: s.nCCDS,
+            sibling: false,
+        });
+        t[Command::WRGB as usize].push(TimeEntry {
+            cmd: Command::WRA,
+            dist: 1,
+            val: s.nCCDS,
+            sibling: false,
+        });
+        t[Command::WRGB as usize].push(TimeEntry {
+            cmd: Command::WRGB,
+            dist: 1,
+            val: s.nCCDS,
+            sibling: false,
+        });
+        t[Command::WRGB as usize].push(TimeEntry {
+            cmd: Command::MAC,
+            dist: 1,
+            val: s.nCWL + s.nBL,
+            sibling: false,
+        });
+
+        // PIM <-> RAS
+        t[Command::MAC as usize].push(TimeEntry {
+            cmd: Command::PREA,
+            dist: 1,
+            val: s.nRTP,
+            sibling: false,
+        });
+        t[Command::LDR as usize].push(TimeEntry {
+            cmd: Command::PREA,
+            dist: 1,
+            val: s.nRTP,
+            sibling: false,
+        });
+        t[Command::STR as usize].push(TimeEntry {
+            cmd: Command::PREA,
+            dist: 1,
+            val: s.nWR,
+            sibling: false,
+        });
+
+        let t = timing.get_level_mut(Level::BankGroup);
+
+        // PIM <-> PIM
+        t[Command::MAC as usize].push(TimeEntry {
+            cmd: Command::MAC,
+            dist: 1,
+            val: s.nCCDL,
+            sibling: false,
+        });
+        t[Command::MAC as usize].push(TimeEntry {
+            cmd: Command::LDR,
+            dist: 1,
+            val: s.nCCDL,
+            sibling: false,
+        });
+        t[Command::MAC as usize].push(TimeEntry {
+            cmd: Command::STR,
+            dist: 1,
+            val: s.nCCDL,
+            sibling: false,
+        });
+        t[Command::LDR as usize].push(TimeEntry {
+            cmd: Command::MAC,
+            dist: 1,
+            val: s.nCCDL,
+            sibling: false,
+        });
+        t[Command::LDR as usize].push(TimeEntry {
+            cmd: Command::LDR,
+            dist: 1,
+            val: s.nCCDL,
+            sibling: false,
+        });
+        t[Command::LDR as usize].push(TimeEntry {
+            cmd: Command::STR,
+            dist: 1,
+            val: s.nCCDL,
+            sibling: false,
+        });
+        t[Command::STR as usize].push(TimeEntry {
+            cmd: Command::MAC,
+            dist: 1,
+            val: s.nCCDL,
+            sibling: false,
+        });
+        t[Command::STR as usize].push(TimeEntry {
+            cmd: Command::LDR,
+            dist: 1,
+            val: s.nCCDL,
+            sibling: false,
+        });
+        t[Command::STR as usize].push(TimeEntry {
+            cmd: Command::STR,
+            dist: 1,
+            val: s.nCCDL,
+            sibling: false,
+        });
+
+        let t = timing.get_level_mut(Level::Bank);
+
+        // PIM <-> RAS
+        t[Command::ACT as usize].push(TimeEntry {
+            cmd: Command::MAC,
+            dist: 1,
+            val: s.nRCD,
+            sibling: false,
+        });
+        t[Command::ACT as usize].push(TimeEntry {
+            cmd: Command::LDR,
+            dist: 1,
+            val: s.nRCD,
+            sibling: false,
+        });
+        t[Command::ACT as usize].push(TimeEntry {
+            cmd: Command::STR,
+            dist: 1,
+            val: s.nRCD,
+            sibling: false,
+        });
+        t[Command::MAC as usize].push(TimeEntry {
+            cmd: Command::PRE,
+            dist: 1,
+            val: s.nRTP,
+            sibling: false,
+        });
+        t[Command::LDR as usize].push(TimeEntry {
+            cmd: Command::PRE,
+            dist: 1,
+            val: s.nRTP,
+            sibling: false,
+        });
+        t[Command::STR as usize].push(TimeEntry {
+            cmd: Command::PRE,
+            dist: 1,
+            val: s.nWR,
+            sibling: false,
+        });
     }
 
     pub fn get_speed(speed: &Speed) -> SpeedEntry {
@@ -1038,16 +1393,41 @@ impl DDR4 {
     }
 }
 impl DramSpec for DDR4 {
+    fn from_config(config: &Config) -> Result<Self, ConfigError> {
+        Ok(Self::new(config))
+    }
+    fn supports_per_bank_refresh() -> bool {
+        false
+    }
+    fn supports_pim() -> bool {
+        true
+    }
     fn get_first_cmd(req_type: &ReqType) -> Command {
         match req_type {
             ReqType::Read => Command::RD,
             ReqType::Write => Command::WR,
+            ReqType::Refresh => Command::REF,
+            ReqType::RefreshBank => unreachable!("DDR4 has no per-bank refresh"),
+            ReqType::PimMac => Command::MAC,
+            ReqType::PimMacAllBank => Command::MACAB,
+            ReqType::PimLoad => Command::LDR,
+            ReqType::PimStore => Command::STR,
+            ReqType::PimBroadcast => Command::WRGB,
         }
     }
 
-    fn get_pre_cmd<'a>(dram: &Dram<'a, Self>, cmd: &Command, child_id: u64) -> Option<Command> {
+    fn get_pre_cmd(dram: &Dram<Self>, cmd: &Command, child_id: u64) -> Option<Command> {
         match (&dram.level, cmd) {
-            (Level::Rank, Command::RD) | (Level::Rank, Command::WR) => match dram.state {
+            (
+                Level::Rank,
+                Command::RD
+                | Command::WR
+                | Command::MAC
+                | Command::LDR
+                | Command::STR
+                | Command::WRGB
+                | Command::MACAB,
+            ) => match dram.state {
                 dram::State::PowerUp => None,
                 dram::State::ActPowerDown => Some(Command::PDX),
                 dram::State::PrePowerDown => Some(Command::PDX),
@@ -1080,7 +1460,10 @@ impl DramSpec for DDR4 {
                 _ => unreachable!("invalid dram state"),
             },
 
-            (Level::Bank, Command::RD) | (Level::Bank, Command::WR) => match dram.state {
+            (
+                Level::Bank,
+                Command::RD | Command::WR | Command::MAC | Command::LDR | Command::STR,
+            ) => match dram.state {
                 dram::State::Closed => Some(Command::ACT),
                 dram::State::Opened(row_id) => {
                     if row_id == child_id {
@@ -1091,22 +1474,29 @@ impl DramSpec for DDR4 {
                 }
                 _ => unreachable!("invalid dram state"),
             },
+            (Level::Bank, Command::MACAB) => match dram.state {
+                dram::State::Closed => Some(Command::ACTAB),
+                dram::State::Opened(row_id) if row_id == child_id => None,
+                dram::State::Opened(_) => Some(Command::PREAB),
+                _ => unreachable!("invalid dram state"),
+            },
             _ => None,
         }
     }
 
     fn get_start_state(level: &Level) -> dram::State {
         match level {
-            Level::Channel => dram::State::NoUse,
             Level::Rank => dram::State::PowerUp,
-            Level::BankGroup => dram::State::NoUse,
             Level::Bank => dram::State::Closed,
             Level::Row => dram::State::Closed,
-            Level::Column => dram::State::NoUse,
-            Level::Max => dram::State::NoUse,
+            _ => dram::State::NoUse,
         }
     }
 
+    fn get_levels(&self) -> &[Level] {
+        &LEVELS
+    }
+
     fn get_addr_bits(&self) -> &[usize] {
         &self.addr_bits
     }
@@ -1125,20 +1515,27 @@ impl DramSpec for DDR4 {
             Command::RDA => Level::Column,
             Command::WRA => Level::Column,
             Command::REF => Level::Rank,
+            Command::REFSB => Level::Bank,
+            Command::REFPB => Level::Bank,
             Command::PDE => Level::Rank,
             Command::PDX => Level::Rank,
             Command::SRE => Level::Rank,
             Command::SRX => Level::Rank,
+            Command::MAC | Command::LDR | Command::STR => Level::Column,
+            Command::WRGB => Level::Rank,
+            Command::ACTAB => Level::Row,
+            Command::PREAB => Level::Bank,
+            Command::MACAB => Level::Column,
             Command::Max => unreachable!(),
         }
     }
 
     fn update_state(&self, dram: &mut Dram<Self>, cmd: &Command, child_id: u64) {
         match (dram.level, cmd) {
-            (Level::Bank, Command::ACT) => {
+            (Level::Bank, Command::ACT | Command::ACTAB) => {
                 dram.state = dram::State::Opened(child_id);
             }
-            (Level::Bank, Command::PRE | Command::RDA | Command::WRA) => {
+            (Level::Bank, Command::PRE | Command::PREAB | Command::RDA | Command::WRA) => {
                 dram.state = dram::State::Closed;
             }
             (Level::Rank, Command::PREA) => {
@@ -1172,11 +1569,27 @@ impl DramSpec for DDR4 {
             _ => {}
         }
     }
-    fn get_timming(&self, level: &Level, cmd: &Command) -> &[TimeEntry] {
-        return &self.timing[*level as usize][*cmd as usize];
+    fn get_timming(&self, depth: usize, cmd: &Command) -> &[TimeEntry] {
+        self.timing.get(depth, cmd)
     }
 
     fn get_read_latency(&self) -> u64 {
-        return self.read_latency;
+        self.read_latency
+    }
+
+    fn get_refresh_interval(&self) -> u64 {
+        self.speed_entry.nREFI
+    }
+
+    fn get_clk_ns(&self) -> f64 {
+        self.speed_entry.tCK
+    }
+
+    fn get_transaction_bytes(&self) -> u64 {
+        PREFETCH_SIZE * CHANNEL_WIDTH / 8
+    }
+
+    fn get_burst_cycles(&self) -> u64 {
+        self.speed_entry.nBL
     }
 }
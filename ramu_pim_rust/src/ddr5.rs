@@ -0,0 +1,1817 @@
+use std::str::FromStr;
+
+use crate::{
+    command::Command,
+    config::{Config, ConfigError},
+    dram::{self, log2, Dram, DramSpec, State, TimeEntry, TimingTable},
+    memory::Level,
+    request::ReqType,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[allow(non_camel_case_types)]
+pub enum DDR5Org {
+    DDR5_16Gb_x4,
+    DDR5_16Gb_x8,
+    DDR5_16Gb_x16,
+    DDR5_32Gb_x4,
+    DDR5_32Gb_x8,
+    DDR5_32Gb_x16,
+}
+impl FromStr for DDR5Org {
+    type Err = ();
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "DDR5_16Gb_x4" => DDR5Org::DDR5_16Gb_x4,
+            "DDR5_16Gb_x8" => DDR5Org::DDR5_16Gb_x8,
+            "DDR5_16Gb_x16" => DDR5Org::DDR5_16Gb_x16,
+            "DDR5_32Gb_x4" => DDR5Org::DDR5_32Gb_x4,
+            "DDR5_32Gb_x8" => DDR5Org::DDR5_32Gb_x8,
+            "DDR5_32Gb_x16" => DDR5Org::DDR5_32Gb_x16,
+            _ => return Err(()),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[allow(non_camel_case_types)]
+pub enum Speed {
+    DDR5_3200AN,
+    DDR5_3600AN,
+    DDR5_4000AN,
+    DDR5_4400AN,
+    DDR5_4800AN,
+    DDR5_5200AN,
+    DDR5_5600AN,
+    DDR5_6000AN,
+    DDR5_6400AN,
+}
+impl FromStr for Speed {
+    type Err = ();
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "DDR5_3200AN" => Speed::DDR5_3200AN,
+            "DDR5_3600AN" => Speed::DDR5_3600AN,
+            "DDR5_4000AN" => Speed::DDR5_4000AN,
+            "DDR5_4400AN" => Speed::DDR5_4400AN,
+            "DDR5_4800AN" => Speed::DDR5_4800AN,
+            "DDR5_5200AN" => Speed::DDR5_5200AN,
+            "DDR5_5600AN" => Speed::DDR5_5600AN,
+            "DDR5_6000AN" => Speed::DDR5_6000AN,
+            "DDR5_6400AN" => Speed::DDR5_6400AN,
+            _ => return Err(()),
+        })
+    }
+}
+
+/// the density(in Mb) and data width of a chip, and the number of its bank groups, banks, rows
+/// and columns
+struct OrgEntry {
+    size: usize,
+    dq: usize,
+    count: [usize; 4],
+}
+/// the latencies are from the JESD79-5 speed bins, the ones specified in ns are rounded up to
+/// the tCK of the bin
+#[allow(non_snake_case, dead_code)]
+pub struct SpeedEntry {
+    rate: u64,
+    freq: f64,
+    tCK: f64,
+    nBL: u64,
+    nCCDS: u64,
+    nCCDL: u64,
+    nRTRS: u64,
+    nCL: u64,
+    nRCD: u64,
+    nRP: u64,
+    nCWL: u64,
+    nRAS: u64,
+    nRC: u64,
+    nRTP: u64,
+    nWTRS: u64,
+    nWTRL: u64,
+    nWR: u64,
+    nRRDS: u64,
+    nRRDL: u64,
+    nFAW: u64,
+    nRFC: u64,
+    nRFCsb: u64,
+    nREFSBRD: u64,
+    nREFI: u64,
+    nPD: u64,
+    nXP: u64,
+    nCKESR: u64,
+    nXS: u64,
+}
+
+const LEVELS: [Level; 6] = [
+    Level::Channel,
+    Level::Rank,
+    Level::BankGroup,
+    Level::Bank,
+    Level::Row,
+    Level::Column,
+];
+/// 16n prefetch DDR, BL16
+const PREFETCH_SIZE: u64 = 16;
+/// every DIMM channel is split into 2 independent sub-channels, modeled as channels
+const SUB_CHANNELS: usize = 2;
+/// the width of a sub-channel, without ECC
+const CHANNEL_WIDTH: u64 = 32;
+/// ACT, RD, WR, RDA and WRA are encoded in two cycles on the cmd/addr bus, the others in one
+const COMMAND_CYCLES: [(Command, u64); 13] = [
+    (Command::ACT, 2),
+    (Command::PRE, 1),
+    (Command::PREA, 1),
+    (Command::RD, 2),
+    (Command::WR, 2),
+    (Command::RDA, 2),
+    (Command::WRA, 2),
+    (Command::REF, 1),
+    (Command::REFSB, 1),
+    (Command::PDE, 1),
+    (Command::PDX, 1),
+    (Command::SRE, 1),
+    (Command::SRX, 1),
+];
+pub struct DDR5 {
+    child_size: Vec<usize>,
+    addr_bits: Vec<usize>,
+    speed_entry: SpeedEntry,
+    timing: TimingTable,
+    read_latency: u64,
+}
+impl DDR5 {
+    pub fn new(config: &Config) -> Self {
+        let org = Self::get_org(&config.ddr5_org);
+        let child_size = vec![
+            config.channels * SUB_CHANNELS,
+            config.ranks,
+            org.count[0],
+            org.count[1],
+            org.count[2],
+            org.count[3],
+        ];
+        let addr_bits = child_size.iter().map(|x| log2(*x)).collect::<Vec<usize>>();
+        let mut speed_entry = Self::get_speed(&config.ddr5_speed);
+        Self::init_speed(&mut speed_entry, &org);
+        let mut timing = TimingTable::new(&LEVELS);
+        Self::init_timing(&mut timing, &speed_entry);
+        dram::add_all_bank_timing(&mut timing);
+        let read_latency = speed_entry.nCL + speed_entry.nBL;
+        Self {
+            child_size,
+            addr_bits,
+            speed_entry,
+            timing,
+            read_latency,
+        }
+    }
+    fn get_org(org: &DDR5Org) -> OrgEntry {
+        match org {
+            DDR5Org::DDR5_16Gb_x4 => OrgEntry {
+                size: 16 << 10,
+                dq: 4,
+                count: [8, 4, 1 << 16, 1 << 11],
+            },
+            DDR5Org::DDR5_16Gb_x8 => OrgEntry {
+                size: 16 << 10,
+                dq: 8,
+                count: [8, 4, 1 << 16, 1 << 10],
+            },
+            DDR5Org::DDR5_16Gb_x16 => OrgEntry {
+                size: 16 << 10,
+                dq: 16,
+                count: [4, 4, 1 << 16, 1 << 10],
+            },
+            DDR5Org::DDR5_32Gb_x4 => OrgEntry {
+                size: 32 << 10,
+                dq: 4,
+                count: [8, 4, 1 << 17, 1 << 11],
+            },
+            DDR5Org::DDR5_32Gb_x8 => OrgEntry {
+                size: 32 << 10,
+                dq: 8,
+                count: [8, 4, 1 << 17, 1 << 10],
+            },
+            DDR5Org::DDR5_32Gb_x16 => OrgEntry {
+                size: 32 << 10,
+                dq: 16,
+                count: [4, 4, 1 << 17, 1 << 10],
+            },
+        }
+    }
+    /// fill the density dependent timings, only the normal refresh mode is supported
+    fn init_speed(s: &mut SpeedEntry, org: &OrgEntry) {
+        /// tRFC1, tRFCsb and tREFSBRD in ns
+        const RFC_TABLE: [[f64; 3]; 2] = [[295.0, 130.0, 30.0], [410.0, 190.0, 30.0]];
+        /// tREFI in ns
+        const REFI: f64 = 3900.0;
+        let ck = |ns: f64| (ns / s.tCK - 0.01).ceil() as u64;
+        let density = match org.size >> 10 {
+            16 => 0,
+            32 => 1,
+            _ => unreachable!("invalid density"),
+        };
+        let [rfc, rfcsb, refsbrd] = RFC_TABLE[density];
+        s.nRFC = ck(rfc);
+        s.nRFCsb = ck(rfcsb);
+        s.nREFSBRD = ck(refsbrd);
+        s.nREFI = ck(REFI);
+        s.nXS = s.nRFC;
+        // the x16 chips have 2KB pages
+        s.nFAW = if org.dq == 16 { 40 } else { 32 };
+    }
+    fn init_timing(timing: &mut TimingTable, s: &SpeedEntry) {
+        /*** Channel ***/
+        let t = timing.get_level_mut(Level::Channel);
+
+        // CAS <-> CAS
+        t[Command::RD as usize].push(TimeEntry {
+            cmd: Command::RD,
+            dist: 1,
+            val: s.nBL,
+            sibling: false,
+        });
+        t[Command::RD as usize].push(TimeEntry {
+            cmd: Command::RDA,
+            dist: 1,
+            val: s.nBL,
+            sibling: false,
+        });
+        t[Command::RDA as usize].push(TimeEntry {
+            cmd: Command::RD,
+            dist: 1,
+            val: s.nBL,
+            sibling: false,
+        });
+        t[Command::RDA as usize].push(TimeEntry {
+            cmd: Command::RDA,
+            dist: 1,
+            val: s.nBL,
+            sibling: false,
+        });
+        t[Command::WR as usize].push(TimeEntry {
+            cmd: Command::WR,
+            dist: 1,
+            val: s.nBL,
+            sibling: false,
+        });
+        t[Command::WR as usize].push(TimeEntry {
+            cmd: Command::WRA,
+            dist: 1,
+            val: s.nBL,
+            sibling: false,
+        });
+        t[Command::WRA as usize].push(TimeEntry {
+            cmd: Command::WR,
+            dist: 1,
+            val: s.nBL,
+            sibling: false,
+        });
+        t[Command::WRA as usize].push(TimeEntry {
+            cmd: Command::WRA,
+            dist: 1,
+            val: s.nBL,
+            sibling: false,
+        });
+
+        /*** Rank ***/
+        let t = timing.get_level_mut(Level::Rank);
+
+        // CAS <-> CAS
+        t[Command::RD as usize].push(TimeEntry {
+            cmd: Command::RD,
+            dist: 1,
+            val: s.nCCDS,
+            sibling: false,
+        });
+        t[Command::RD as usize].push(TimeEntry {
+            cmd: Command::RDA,
+            dist: 1,
+            val: s.nCCDS,
+            sibling: false,
+        });
+        t[Command::RDA as usize].push(TimeEntry {
+            cmd: Command::RD,
+            dist: 1,
+            val: s.nCCDS,
+            sibling: false,
+        });
+        t[Command::RDA as usize].push(TimeEntry {
+            cmd: Command::RDA,
+            dist: 1,
+            val: s.nCCDS,
+            sibling: false,
+        });
+        t[Command::WR as usize].push(TimeEntry {
+            cmd: Command::WR,
+            dist: 1,
+            val: s.nCCDS,
+            sibling: false,
+        });
+        t[Command::WR as usize].push(TimeEntry {
+            cmd: Command::WRA,
+            dist: 1,
+            val: s.nCCDS,
+            sibling: false,
+        });
+        t[Command::WRA as usize].push(TimeEntry {
+            cmd: Command::WR,
+            dist: 1,
+            val: s.nCCDS,
+            sibling: false,
+        });
+        t[Command::WRA as usize].push(TimeEntry {
+            cmd: Command::WRA,
+            dist: 1,
+            val: s.nCCDS,
+            sibling: false,
+        });
+        t[Command::RD as usize].push(TimeEntry {
+            cmd: Command::WR,
+            dist: 1,
+            val: s.nCL + s.nBL + 2 - s.nCWL,
+            sibling: false,
+        });
+        t[Command::RD as usize].push(TimeEntry {
+            cmd: Command::WRA,
+            dist: 1,
+            val: s.nCL + s.nBL + 2 - s.nCWL,
+            sibling: false,
+        });
+        t[Command::RDA as usize].push(TimeEntry {
+            cmd: Command::WR,
+            dist: 1,
+            val: s.nCL + s.nBL + 2 - s.nCWL,
+            sibling: false,
+        });
+        t[Command::RDA as usize].push(TimeEntry {
+            cmd: Command::WRA,
+            dist: 1,
+            val: s.nCL + s.nBL + 2 - s.nCWL,
+            sibling: false,
+        });
+        t[Command::WR as usize].push(TimeEntry {
+            cmd: Command::RD,
+            dist: 1,
+            val: s.nCWL + s.nBL + s.nWTRS,
+            sibling: false,
+        });
+        t[Command::WR as usize].push(TimeEntry {
+            cmd: Command::RDA,
+            dist: 1,
+            val: s.nCWL + s.nBL + s.nWTRS,
+            sibling: false,
+        });
+        t[Command::WRA as usize].push(TimeEntry {
+            cmd: Command::RD,
+            dist: 1,
+            val: s.nCWL + s.nBL + s.nWTRS,
+            sibling: false,
+        });
+        t[Command::WRA as usize].push(TimeEntry {
+            cmd: Command::RDA,
+            dist: 1,
+            val: s.nCWL + s.nBL + s.nWTRS,
+            sibling: false,
+        });
+
+        // CAS <-> CAS (between sibling ranks)
+        t[Command::RD as usize].push(TimeEntry {
+            cmd: Command::RD,
+            dist: 1,
+            val: s.nBL + s.nRTRS,
+            sibling: true,
+        });
+        t[Command::RD as usize].push(TimeEntry {
+            cmd: Command::RDA,
+            dist: 1,
+            val: s.nBL + s.nRTRS,
+            sibling: true,
+        });
+        t[Command::RDA as usize].push(TimeEntry {
+            cmd: Command::RD,
+            dist: 1,
+            val: s.nBL + s.nRTRS,
+            sibling: true,
+        });
+        t[Command::RDA as usize].push(TimeEntry {
+            cmd: Command::RDA,
+            dist: 1,
+            val: s.nBL + s.nRTRS,
+            sibling: true,
+        });
+        t[Command::RD as usize].push(TimeEntry {
+            cmd: Command::WR,
+            dist: 1,
+            val: s.nCL + s.nBL + s.nRTRS - s.nCWL,
+            sibling: true,
+        });
+        t[Command::RD as usize].push(TimeEntry {
+            cmd: Command::WRA,
+            dist: 1,
+            val: s.nCL + s.nBL + s.nRTRS - s.nCWL,
+            sibling: true,
+        });
+        t[Command::RDA as usize].push(TimeEntry {
+            cmd: Command::WR,
+            dist: 1,
+            val: s.nCL + s.nBL + s.nRTRS - s.nCWL,
+            sibling: true,
+        });
+        t[Command::RDA as usize].push(TimeEntry {
+            cmd: Command::WRA,
+            dist: 1,
+            val: s.nCL + s.nBL + s.nRTRS - s.nCWL,
+            sibling: true,
+        });
+        t[Command::WR as usize].push(TimeEntry {
+            cmd: Command::WR,
+            dist: 1,
+            val: s.nBL + s.nRTRS,
+            sibling: true,
+        });
+        t[Command::WR as usize].push(TimeEntry {
+            cmd: Command::WRA,
+            dist: 1,
+            val: s.nBL + s.nRTRS,
+            sibling: true,
+        });
+        t[Command::WRA as usize].push(TimeEntry {
+            cmd: Command::WR,
+            dist: 1,
+            val: s.nBL + s.nRTRS,
+            sibling: true,
+        });
+        t[Command::WRA as usize].push(TimeEntry {
+            cmd: Command::WRA,
+            dist: 1,
+            val: s.nBL + s.nRTRS,
+            sibling: true,
+        });
+        t[Command::WR as usize].push(TimeEntry {
+            cmd: Command::RD,
+            dist: 1,
+            val: s.nCWL + s.nBL + s.nRTRS - s.nCL,
+            sibling: true,
+        });
+        t[Command::WR as usize].push(TimeEntry {
+            cmd: Command::RDA,
+            dist: 1,
+            val: s.nCWL + s.nBL + s.nRTRS - s.nCL,
+            sibling: true,
+        });
+        t[Command::WRA as usize].push(TimeEntry {
+            cmd: Command::RD,
+            dist: 1,
+            val: s.nCWL + s.nBL + s.nRTRS - s.nCL,
+            sibling: true,
+        });
+        t[Command::WRA as usize].push(TimeEntry {
+            cmd: Command::RDA,
+            dist: 1,
+            val: s.nCWL + s.nBL + s.nRTRS - s.nCL,
+            sibling: true,
+        });
+        t[Command::RD as usize].push(TimeEntry {
+            cmd: Command::PREA,
+            dist: 1,
+            val: s.nRTP,
+            sibling: false,
+        });
+        t[Command::WR as usize].push(TimeEntry {
+            cmd: Command::PREA,
+            dist: 1,
+            val: s.nCWL + s.nBL + s.nWR,
+            sibling: false,
+        });
+
+        // CAS <-> PD
+        t[Command::RD as usize].push(TimeEntry {
+            cmd: Command::PDE,
+            dist: 1,
+            val: s.nCL + s.nBL + 1,
+            sibling: false,
+        });
+        t[Command::RDA as usize].push(TimeEntry {
+            cmd: Command::PDE,
+            dist: 1,
+            val: s.nCL + s.nBL + 1,
+            sibling: false,
+        });
+        t[Command::WR as usize].push(TimeEntry {
+            cmd: Command::PDE,
+            dist: 1,
+            val: s.nCWL + s.nBL + s.nWR,
+            sibling: false,
+        });
+        t[Command::WRA as usize].push(TimeEntry {
+            cmd: Command::PDE,
+            dist: 1,
+            val: s.nCWL + s.nBL + s.nWR + 1,
+            sibling: false,
+        }); // +1 for pre
+        t[Command::PDX as usize].push(TimeEntry {
+            cmd: Command::RD,
+            dist: 1,
+            val: s.nXP,
+            sibling: false,
+        });
+        t[Command::PDX as usize].push(TimeEntry {
+            cmd: Command::RDA,
+            dist: 1,
+            val: s.nXP,
+            sibling: false,
+        });
+        t[Command::PDX as usize].push(TimeEntry {
+            cmd: Command::WR,
+            dist: 1,
+            val: s.nXP,
+            sibling: false,
+        });
+        t[Command::PDX as usize].push(TimeEntry {
+            cmd: Command::WRA,
+            dist: 1,
+            val: s.nXP,
+            sibling: false,
+        });
+
+        // CAS <-> SR: none (all banks have to be precharged)
+
+        // RAS <-> RAS
+        t[Command::ACT as usize].push(TimeEntry {
+            cmd: Command::ACT,
+            dist: 1,
+            val: s.nRRDS,
+            sibling: false,
+        });
+        t[Command::ACT as usize].push(TimeEntry {
+            cmd: Command::ACT,
+            dist: 4,
+            val: s.nFAW,
+            sibling: false,
+        });
+        t[Command::ACT as usize].push(TimeEntry {
+            cmd: Command::PREA,
+            dist: 1,
+            val: s.nRAS,
+            sibling: false,
+        });
+        t[Command::PREA as usize].push(TimeEntry {
+            cmd: Command::ACT,
+            dist: 1,
+            val: s.nRP,
+            sibling: false,
+        });
+
+        // RAS <-> REF
+        t[Command::ACT as usize].push(TimeEntry {
+            cmd: Command::REF,
+            dist: 1,
+            val: s.nRC,
+            sibling: false,
+        });
+        t[Command::PRE as usize].push(TimeEntry {
+            cmd: Command::REF,
+            dist: 1,
+            val: s.nRP,
+            sibling: false,
+        });
+        t[Command::PREA as usize].push(TimeEntry {
+            cmd: Command::REF,
+            dist: 1,
+            val: s.nRP,
+            sibling: false,
+        });
+        t[Command::RDA as usize].push(TimeEntry {
+            cmd: Command::REF,
+            dist: 1,
+            val: s.nRTP + s.nRP,
+            sibling: false,
+        });
+        t[Command::WRA as usize].push(TimeEntry {
+            cmd: Command::REF,
+            dist: 1,
+            val: s.nCWL + s.nBL + s.nWR + s.nRP,
+            sibling: false,
+        });
+        t[Command::REF as usize].push(TimeEntry {
+            cmd: Command::ACT,
+            dist: 1,
+            val: s.nRFC,
+            sibling: false,
+        });
+
+        // RAS <-> REFSB, the same bank is covered by the bank level
+        t[Command::ACT as usize].push(TimeEntry {
+            cmd: Command::REFSB,
+            dist: 1,
+            val: s.nRRDS,
+            sibling: false,
+        });
+        t[Command::REFSB as usize].push(TimeEntry {
+            cmd: Command::ACT,
+            dist: 1,
+            val: s.nREFSBRD,
+            sibling: false,
+        });
+        t[Command::PREA as usize].push(TimeEntry {
+            cmd: Command::REFSB,
+            dist: 1,
+            val: s.nRP,
+            sibling: false,
+        });
+
+        // RAS <-> PD
+        t[Command::ACT as usize].push(TimeEntry {
+            cmd: Command::PDE,
+            dist: 1,
+            val: 1,
+            sibling: false,
+        });
+        t[Command::PDX as usize].push(TimeEntry {
+            cmd: Command::ACT,
+            dist: 1,
+            val: s.nXP,
+            sibling: false,
+        });
+        t[Command::PDX as usize].push(TimeEntry {
+            cmd: Command::PRE,
+            dist: 1,
+            val: s.nXP,
+            sibling: false,
+        });
+        t[Command::PDX as usize].push(TimeEntry {
+            cmd: Command::PREA,
+            dist: 1,
+            val: s.nXP,
+            sibling: false,
+        });
+
+        // RAS <-> SR
+        t[Command::PRE as usize].push(TimeEntry {
+            cmd: Command::SRE,
+            dist: 1,
+            val: s.nRP,
+            sibling: false,
+        });
+        t[Command::PREA as usize].push(TimeEntry {
+            cmd: Command::SRE,
+            dist: 1,
+            val: s.nRP,
+            sibling: false,
+        });
+        t[Command::SRX as usize].push(TimeEntry {
+            cmd: Command::ACT,
+            dist: 1,
+            val: s.nXS,
+            sibling: false,
+        });
+
+        // REF <-> REF
+        t[Command::REF as usize].push(TimeEntry {
+            cmd: Command::REF,
+            dist: 1,
+            val: s.nRFC,
+            sibling: false,
+        });
+        t[Command::REF as usize].push(TimeEntry {
+            cmd: Command::REFSB,
+            dist: 1,
+            val: s.nRFC,
+            sibling: false,
+        });
+        t[Command::REFSB as usize].push(TimeEntry {
+            cmd: Command::REF,
+            dist: 1,
+            val: s.nRFCsb,
+            sibling: false,
+        });
+        t[Command::REFSB as usize].push(TimeEntry {
+            cmd: Command::REFSB,
+            dist: 1,
+            val: s.nREFSBRD,
+            sibling: false,
+        });
+
+        // REF <-> PD
+        t[Command::REF as usize].push(TimeEntry {
+            cmd: Command::PDE,
+            dist: 1,
+            val: 1,
+            sibling: false,
+        });
+        t[Command::REFSB as usize].push(TimeEntry {
+            cmd: Command::PDE,
+            dist: 1,
+            val: 1,
+            sibling: false,
+        });
+        t[Command::PDX as usize].push(TimeEntry {
+            cmd: Command::REF,
+            dist: 1,
+            val: s.nXP,
+            sibling: false,
+        });
+        t[Command::PDX as usize].push(TimeEntry {
+            cmd: Command::REFSB,
+            dist: 1,
+            val: s.nXP,
+            sibling: false,
+        });
+
+        // REF <-> SR
+        t[Command::SRX as usize].push(TimeEntry {
+            cmd: Command::REF,
+            dist: 1,
+            val: s.nXS,
+            sibling: false,
+        });
+        t[Command::SRX as usize].push(TimeEntry {
+            cmd: Command::REFSB,
+            dist: 1,
+            val: s.nXS,
+            sibling: false,
+        });
+
+        // PD <-> PD
+        t[Command::PDE as usize].push(TimeEntry {
+            cmd: Command::PDX,
+            dist: 1,
+            val: s.nPD,
+            sibling: false,
+        });
+        t[Command::PDX as usize].push(TimeEntry {
+            cmd: Command::PDE,
+            dist: 1,
+            val: s.nXP,
+            sibling: false,
+        });
+
+        // PD <-> SR
+        t[Command::PDX as usize].push(TimeEntry {
+            cmd: Command::SRE,
+            dist: 1,
+            val: s.nXP,
+            sibling: false,
+        });
+        t[Command::SRX as usize].push(TimeEntry {
+            cmd: Command::PDE,
+            dist: 1,
+            val: s.nXS,
+            sibling: false,
+        });
+
+        // SR <-> SR
+        t[Command::SRE as usize].push(TimeEntry {
+            cmd: Command::SRX,
+            dist: 1,
+            val: s.nCKESR,
+            sibling: false,
+        });
+        t[Command::SRX as usize].push(TimeEntry {
+            cmd: Command::SRE,
+            dist: 1,
+            val: s.nXS,
+            sibling: false,
+        });
+
+        /*** Bank Group ***/
+        let t = timing.get_level_mut(Level::BankGroup);
+
+        // CAS <-> CAS
+        t[Command::RD as usize].push(TimeEntry {
+            cmd: Command::RD,
+            dist: 1,
+            val: s.nCCDL,
+            sibling: false,
+        });
+        t[Command::RD as usize].push(TimeEntry {
+            cmd: Command::RDA,
+            dist: 1,
+            val: s.nCCDL,
+            sibling: false,
+        });
+        t[Command::RDA as usize].push(TimeEntry {
+            cmd: Command::RD,
+            dist: 1,
+            val: s.nCCDL,
+            sibling: false,
+        });
+        t[Command::RDA as usize].push(TimeEntry {
+            cmd: Command::RDA,
+            dist: 1,
+            val: s.nCCDL,
+            sibling: false,
+        });
+        t[Command::WR as usize].push(TimeEntry {
+            cmd: Command::WR,
+            dist: 1,
+            val: s.nCCDL,
+            sibling: false,
+        });
+        t[Command::WR as usize].push(TimeEntry {
+            cmd: Command::WRA,
+            dist: 1,
+            val: s.nCCDL,
+            sibling: false,
+        });
+        t[Command::WRA as usize].push(TimeEntry {
+            cmd: Command::WR,
+            dist: 1,
+            val: s.nCCDL,
+            sibling: false,
+        });
+        t[Command::WRA as usize].push(TimeEntry {
+            cmd: Command::WRA,
+            dist: 1,
+            val: s.nCCDL,
+            sibling: false,
+        });
+        t[Command::WR as usize].push(TimeEntry {
+            cmd: Command::RD,
+            dist: 1,
+            val: s.nCWL + s.nBL + s.nWTRL,
+            sibling: false,
+        });
+        t[Command::WR as usize].push(TimeEntry {
+            cmd: Command::RDA,
+            dist: 1,
+            val: s.nCWL + s.nBL + s.nWTRL,
+            sibling: false,
+        });
+        t[Command::WRA as usize].push(TimeEntry {
+            cmd: Command::RD,
+            dist: 1,
+            val: s.nCWL + s.nBL + s.nWTRL,
+            sibling: false,
+        });
+        t[Command::WRA as usize].push(TimeEntry {
+            cmd: Command::RDA,
+            dist: 1,
+            val: s.nCWL + s.nBL + s.nWTRL,
+            sibling: false,
+        });
+
+        // RAS <-> RAS
+        t[Command::ACT as usize].push(TimeEntry {
+            cmd: Command::ACT,
+            dist: 1,
+            val: s.nRRDL,
+            sibling: false,
+        });
+
+        /*** Bank ***/
+        let t = timing.get_level_mut(Level::Bank);
+
+        // CAS <-> RAS
+        t[Command::ACT as usize].push(TimeEntry {
+            cmd: Command::RD,
+            dist: 1,
+            val: s.nRCD,
+            sibling: false,
+        });
+        t[Command::ACT as usize].push(TimeEntry {
+            cmd: Command::RDA,
+            dist: 1,
+            val: s.nRCD,
+            sibling: false,
+        });
+        t[Command::ACT as usize].push(TimeEntry {
+            cmd: Command::WR,
+            dist: 1,
+            val: s.nRCD,
+            sibling: false,
+        });
+        t[Command::ACT as usize].push(TimeEntry {
+            cmd: Command::WRA,
+            dist: 1,
+            val: s.nRCD,
+            sibling: false,
+        });
+        t[Command::RD as usize].push(TimeEntry {
+            cmd: Command::PRE,
+            dist: 1,
+            val: s.nRTP,
+            sibling: false,
+        });
+        t[Command::WR as usize].push(TimeEntry {
+            cmd: Command::PRE,
+            dist: 1,
+            val: s.nCWL + s.nBL + s.nWR,
+            sibling: false,
+        });
+        t[Command::RDA as usize].push(TimeEntry {
+            cmd: Command::ACT,
+            dist: 1,
+            val: s.nRTP + s.nRP,
+            sibling: false,
+        });
+        t[Command::WRA as usize].push(TimeEntry {
+            cmd: Command::ACT,
+            dist: 1,
+            val: s.nCWL + s.nBL + s.nWR + s.nRP,
+            sibling: false,
+        });
+
+        // RAS <-> RAS
+        t[Command::ACT as usize].push(TimeEntry {
+            cmd: Command::ACT,
+            dist: 1,
+            val: s.nRC,
+            sibling: false,
+        });
+        t[Command::ACT as usize].push(TimeEntry {
+            cmd: Command::PRE,
+            dist: 1,
+            val: s.nRAS,
+            sibling: false,
+        });
+        t[Command::PRE as usize].push(TimeEntry {
+            cmd: Command::ACT,
+            dist: 1,
+            val: s.nRP,
+            sibling: false,
+        });
+
+        // RAS <-> REFSB
+        t[Command::ACT as usize].push(TimeEntry {
+            cmd: Command::REFSB,
+            dist: 1,
+            val: s.nRC,
+            sibling: false,
+        });
+        t[Command::PRE as usize].push(TimeEntry {
+            cmd: Command::REFSB,
+            dist: 1,
+            val: s.nRP,
+            sibling: false,
+        });
+        t[Command::RDA as usize].push(TimeEntry {
+            cmd: Command::REFSB,
+            dist: 1,
+            val: s.nRTP + s.nRP,
+            sibling: false,
+        });
+        t[Command::WRA as usize].push(TimeEntry {
+            cmd: Command::REFSB,
+            dist: 1,
+            val: s.nCWL + s.nBL + s.nWR + s.nRP,
+            sibling: false,
+        });
+        t[Command::REFSB as usize].push(TimeEntry {
+            cmd: Command::ACT,
+            dist: 1,
+            val: s.nRFCsb,
+            sibling: false,
+        });
+
+        // the next command waits for the command/address bus
+        let t = timing.get_level_mut(Level::Channel);
+        for (cmd, cycles) in COMMAND_CYCLES {
+            for (next, _) in COMMAND_CYCLES {
+                t[cmd as usize].push(TimeEntry {
+                    cmd: next,
+                    dist: 1,
+                    val: cycles,
+                    sibling: false,
+                });
+            }
+        }
+
+        /*** PIM ***/
+        // the PIM commands access the banks like RD and WR, their data never leaves the chip
+        let t = timing.get_level_mut(Level::Rank);
+
+        // PIM <-> PIM
+        t[Command::MAC as usize].push(TimeEntry {
+            cmd: Command::MAC,
+            dist: 1,
+            val: s.nCCDS,
+            sibling: false,
+        });
+        t[Command::MAC as usize].push(TimeEntry {
+            cmd: Command::LDR,
+            dist: 1,
+            val: s.nCCDS,
+            sibling: false,
+        });
+        t[Command::MAC as usize].push(TimeEntry {
+            cmd: Command::STR,
+            dist: 1,
+            val: s.nCCDS,
+            sibling: false,
+        });
+        t[Command::LDR as usize].push(TimeEntry {
+            cmd: Command::MAC,
+            dist: 1,
+            val: s.nCCDS,
+            sibling: false,
+        });
+        t[Command::LDR as usize].push(TimeEntry {
+            cmd: Command::LDR,
+            dist: 1,
+            val: s.nCCDS,
+            sibling: false,
+        });
+        t[Command::LDR as usize].push(TimeEntry {
+            cmd: Command::STR,
+            dist: 1,
+            val: s.nCCDS,
+            sibling: false,
+        });
+        t[Command::STR as usize].push(TimeEntry {
+            cmd: Command::MAC,
+            dist: 1,
+            val: s.nCCDS,
+            sibling: false,
+        });
+        t[Command::STR as usize].push(TimeEntry {
+            cmd: Command::LDR,
+            dist: 1,
+            val: s.nCCDS,
+            sibling: false,
+        });
+        t[Command::STR as usize].push(TimeEntry {
+            cmd: Command::STR,
+            dist: 1,
+            val: s.nCCDS,
+            sibling: false,
+        });
+
+        // WRGB <-> WR, the operand takes the data bus like a write
+        t[Command::WR as usize].push(TimeEntry {
+            cmd: Command::WRGB,
+            dist: 1,
+            val: s.nCCDS,
+            sibling: false,
+        });
+        t[Command::WRA as usize].push(TimeEntry {
+            cmd: Command::WRGB,
+            dist: 1,
+            val: s.nCCDS,
+            sibling: false,
+        });
+        t[Command::WRGB as usize].push(TimeEntry {
+            cmd: Command::WR,
+            dist: 1,
+            val: s.nCCDS,
+            sibling: false,
+        });
+        t[Command::WRGB as usize].push(TimeEntry {
+            cmd: Command::WRA,
+            dist: 1,
+            val: s.nCCDS,
+            sibling: false,
+        });
+        t[Command::WRGB as usize].push(TimeEntry {
+            cmd: Command::WRGB,
+            dist: 1,
+            val: s.nCCDS,
+            sibling: false,
+        });
+        t[Command::WRGB as usize].push(TimeEntry {
+            cmd: Command::MAC,
+            dist: 1,
+            val: s.nCWL + s.nBL,
+            sibling: false,
+        });
+
+        // PIM <-> RAS
+        t[Command::MAC as usize].push(TimeEntry {
+            cmd: Command::PREA,
+            dist: 1,
+            val: s.nRTP,
+            sibling: false,
+        });
+        t[Command::LDR as usize].push(TimeEntry {
+            cmd: Command::PREA,
+            dist: 1,
+            val: s.nRTP,
+            sibling: false,
+        });
+        t[Command::STR as usize].push(TimeEntry {
+            cmd: Command::PREA,
+            dist: 1,
+            val: s.nWR,
+            sibling: false,
+        });
+
+        let t = timing.get_level_mut(Level::BankGroup);
+
+        // PIM <-> PIM
+        t[Command::MAC as usize].push(TimeEntry {
+            cmd: Command::MAC,
+            dist: 1,
+            val: s.nCCDL,
+            sibling: false,
+        });
+        t[Command::MAC as usize].push(TimeEntry {
+            cmd: Command::LDR,
+            dist: 1,
+            val: s.nCCDL,
+            sibling: false,
+        });
+        t[Command::MAC as usize].push(TimeEntry {
+            cmd: Command::STR,
+            dist: 1,
+            val: s.nCCDL,
+            sibling: false,
+        });
+        t[Command::LDR as usize].push(TimeEntry {
+            cmd: Command::MAC,
+            dist: 1,
+            val: s.nCCDL,
+            sibling: false,
+        });
+        t[Command::LDR as usize].push(TimeEntry {
+            cmd: Command::LDR,
+            dist: 1,
+            val: s.nCCDL,
+            sibling: false,
+        });
+        t[Command::LDR as usize].push(TimeEntry {
+            cmd: Command::STR,
+            dist: 1,
+            val: s.nCCDL,
+            sibling: false,
+        });
+        t[Command::STR as usize].push(TimeEntry {
+            cmd: Command::MAC,
+            dist: 1,
+            val: s.nCCDL,
+            sibling: false,
+        });
+        t[Command::STR as usize].push(TimeEntry {
+            cmd: Command::LDR,
+            dist: 1,
+            val: s.nCCDL,
+            sibling: false,
+        });
+        t[Command::STR as usize].push(TimeEntry {
+            cmd: Command::STR,
+            dist: 1,
+            val: s.nCCDL,
+            sibling: false,
+        });
+
+        let t = timing.get_level_mut(Level::Bank);
+
+        // PIM <-> RAS
+        t[Command::ACT as usize].push(TimeEntry {
+            cmd: Command::MAC,
+            dist: 1,
+            val: s.nRCD,
+            sibling: false,
+        });
+        t[Command::ACT as usize].push(TimeEntry {
+            cmd: Command::LDR,
+            dist: 1,
+            val: s.nRCD,
+            sibling: false,
+        });
+        t[Command::ACT as usize].push(TimeEntry {
+            cmd: Command::STR,
+            dist: 1,
+            val: s.nRCD,
+            sibling: false,
+        });
+        t[Command::MAC as usize].push(TimeEntry {
+            cmd: Command::PRE,
+            dist: 1,
+            val: s.nRTP,
+            sibling: false,
+        });
+        t[Command::LDR as usize].push(TimeEntry {
+            cmd: Command::PRE,
+            dist: 1,
+            val: s.nRTP,
+            sibling: false,
+        });
+        t[Command::STR as usize].push(TimeEntry {
+            cmd: Command::PRE,
+            dist: 1,
+            val: s.nWR,
+            sibling: false,
+        });
+    }
+
+    pub fn get_speed(speed: &Speed) -> SpeedEntry {
+        match speed {
+            Speed::DDR5_3200AN => SpeedEntry {
+                rate: 3200,
+                freq: 3200.0 / 2.0,
+                tCK: 2000.0 / 3200.0,
+                nBL: 8,
+                nCCDS: 8,
+                nCCDL: 8,
+                nRTRS: 2,
+                nCL: 26,
+                nRCD: 26,
+                nRP: 26,
+                nCWL: 24,
+                nRAS: 52,
+                nRC: 78,
+                nRTP: 12,
+                nWTRS: 4,
+                nWTRL: 16,
+                nWR: 48,
+                nRRDS: 8,
+                nRRDL: 8,
+                nFAW: 0,
+                nRFC: 0,
+                nRFCsb: 0,
+                nREFSBRD: 0,
+                nREFI: 0,
+                nPD: 12,
+                nXP: 12,
+                nCKESR: 12,
+                nXS: 0,
+            },
+            Speed::DDR5_3600AN => SpeedEntry {
+                rate: 3600,
+                freq: 3600.0 / 2.0,
+                tCK: 2000.0 / 3600.0,
+                nBL: 8,
+                nCCDS: 8,
+                nCCDL: 9,
+                nRTRS: 2,
+                nCL: 30,
+                nRCD: 30,
+                nRP: 30,
+                nCWL: 28,
+                nRAS: 58,
+                nRC: 88,
+                nRTP: 14,
+                nWTRS: 5,
+                nWTRL: 18,
+                nWR: 54,
+                nRRDS: 8,
+                nRRDL: 9,
+                nFAW: 0,
+                nRFC: 0,
+                nRFCsb: 0,
+                nREFSBRD: 0,
+                nREFI: 0,
+                nPD: 14,
+                nXP: 14,
+                nCKESR: 14,
+                nXS: 0,
+            },
+            Speed::DDR5_4000AN => SpeedEntry {
+                rate: 4000,
+                freq: 4000.0 / 2.0,
+                tCK: 2000.0 / 4000.0,
+                nBL: 8,
+                nCCDS: 8,
+                nCCDL: 10,
+                nRTRS: 2,
+                nCL: 32,
+                nRCD: 32,
+                nRP: 32,
+                nCWL: 30,
+                nRAS: 64,
+                nRC: 96,
+                nRTP: 15,
+                nWTRS: 5,
+                nWTRL: 20,
+                nWR: 60,
+                nRRDS: 8,
+                nRRDL: 10,
+                nFAW: 0,
+                nRFC: 0,
+                nRFCsb: 0,
+                nREFSBRD: 0,
+                nREFI: 0,
+                nPD: 15,
+                nXP: 15,
+                nCKESR: 15,
+                nXS: 0,
+            },
+            Speed::DDR5_4400AN => SpeedEntry {
+                rate: 4400,
+                freq: 4400.0 / 2.0,
+                tCK: 2000.0 / 4400.0,
+                nBL: 8,
+                nCCDS: 8,
+                nCCDL: 11,
+                nRTRS: 2,
+                nCL: 36,
+                nRCD: 36,
+                nRP: 36,
+                nCWL: 34,
+                nRAS: 71,
+                nRC: 107,
+                nRTP: 17,
+                nWTRS: 6,
+                nWTRL: 22,
+                nWR: 66,
+                nRRDS: 8,
+                nRRDL: 11,
+                nFAW: 0,
+                nRFC: 0,
+                nRFCsb: 0,
+                nREFSBRD: 0,
+                nREFI: 0,
+                nPD: 17,
+                nXP: 17,
+                nCKESR: 17,
+                nXS: 0,
+            },
+            Speed::DDR5_4800AN => SpeedEntry {
+                rate: 4800,
+                freq: 4800.0 / 2.0,
+                tCK: 2000.0 / 4800.0,
+                nBL: 8,
+                nCCDS: 8,
+                nCCDL: 12,
+                nRTRS: 2,
+                nCL: 40,
+                nRCD: 39,
+                nRP: 39,
+                nCWL: 38,
+                nRAS: 77,
+                nRC: 116,
+                nRTP: 18,
+                nWTRS: 6,
+                nWTRL: 24,
+                nWR: 72,
+                nRRDS: 8,
+                nRRDL: 12,
+                nFAW: 0,
+                nRFC: 0,
+                nRFCsb: 0,
+                nREFSBRD: 0,
+                nREFI: 0,
+                nPD: 18,
+                nXP: 18,
+                nCKESR: 18,
+                nXS: 0,
+            },
+            Speed::DDR5_5200AN => SpeedEntry {
+                rate: 5200,
+                freq: 5200.0 / 2.0,
+                tCK: 2000.0 / 5200.0,
+                nBL: 8,
+                nCCDS: 8,
+                nCCDL: 13,
+                nRTRS: 2,
+                nCL: 42,
+                nRCD: 42,
+                nRP: 42,
+                nCWL: 40,
+                nRAS: 84,
+                nRC: 126,
+                nRTP: 20,
+                nWTRS: 7,
+                nWTRL: 26,
+                nWR: 78,
+                nRRDS: 8,
+                nRRDL: 13,
+                nFAW: 0,
+                nRFC: 0,
+                nRFCsb: 0,
+                nREFSBRD: 0,
+                nREFI: 0,
+                nPD: 20,
+                nXP: 20,
+                nCKESR: 20,
+                nXS: 0,
+            },
+            Speed::DDR5_5600AN => SpeedEntry {
+                rate: 5600,
+                freq: 5600.0 / 2.0,
+                tCK: 2000.0 / 5600.0,
+                nBL: 8,
+                nCCDS: 8,
+                nCCDL: 14,
+                nRTRS: 2,
+                nCL: 46,
+                nRCD: 45,
+                nRP: 45,
+                nCWL: 44,
+                nRAS: 90,
+                nRC: 135,
+                nRTP: 21,
+                nWTRS: 7,
+                nWTRL: 28,
+                nWR: 84,
+                nRRDS: 8,
+                nRRDL: 14,
+                nFAW: 0,
+                nRFC: 0,
+                nRFCsb: 0,
+                nREFSBRD: 0,
+                nREFI: 0,
+                nPD: 21,
+                nXP: 21,
+                nCKESR: 21,
+                nXS: 0,
+            },
+            Speed::DDR5_6000AN => SpeedEntry {
+                rate: 6000,
+                freq: 6000.0 / 2.0,
+                tCK: 2000.0 / 6000.0,
+                nBL: 8,
+                nCCDS: 8,
+                nCCDL: 15,
+                nRTRS: 2,
+                nCL: 50,
+                nRCD: 48,
+                nRP: 48,
+                nCWL: 48,
+                nRAS: 96,
+                nRC: 144,
+                nRTP: 23,
+                nWTRS: 8,
+                nWTRL: 30,
+                nWR: 90,
+                nRRDS: 8,
+                nRRDL: 15,
+                nFAW: 0,
+                nRFC: 0,
+                nRFCsb: 0,
+                nREFSBRD: 0,
+                nREFI: 0,
+                nPD: 23,
+                nXP: 23,
+                nCKESR: 23,
+                nXS: 0,
+            },
+            Speed::DDR5_6400AN => SpeedEntry {
+                rate: 6400,
+                freq: 6400.0 / 2.0,
+                tCK: 2000.0 / 6400.0,
+                nBL: 8,
+                nCCDS: 8,
+                nCCDL: 16,
+                nRTRS: 2,
+                nCL: 52,
+                nRCD: 52,
+                nRP: 52,
+                nCWL: 50,
+                nRAS: 103,
+                nRC: 155,
+                nRTP: 24,
+                nWTRS: 8,
+                nWTRL: 32,
+                nWR: 96,
+                nRRDS: 8,
+                nRRDL: 16,
+                nFAW: 0,
+                nRFC: 0,
+                nRFCsb: 0,
+                nREFSBRD: 0,
+                nREFI: 0,
+                nPD: 24,
+                nXP: 24,
+                nCKESR: 24,
+                nXS: 0,
+            },
+        }
+    }
+}
+impl DramSpec for DDR5 {
+    fn from_config(config: &Config) -> Result<Self, ConfigError> {
+        Ok(Self::new(config))
+    }
+    fn supports_per_bank_refresh() -> bool {
+        true
+    }
+    fn refreshes_same_bank() -> bool {
+        true
+    }
+    fn supports_pim() -> bool {
+        true
+    }
+    fn get_first_cmd(req_type: &ReqType) -> Command {
+        match req_type {
+            ReqType::Read => Command::RD,
+            ReqType::Write => Command::WR,
+            ReqType::Refresh => Command::REF,
+            ReqType::RefreshBank => Command::REFSB,
+            ReqType::PimMac => Command::MAC,
+            ReqType::PimMacAllBank => Command::MACAB,
+            ReqType::PimLoad => Command::LDR,
+            ReqType::PimStore => Command::STR,
+            ReqType::PimBroadcast => Command::WRGB,
+        }
+    }
+
+    fn get_pre_cmd(dram: &Dram<Self>, cmd: &Command, child_id: u64) -> Option<Command> {
+        match (&dram.level, cmd) {
+            (
+                Level::Rank,
+                Command::RD
+                | Command::WR
+                | Command::MAC
+                | Command::LDR
+                | Command::STR
+                | Command::WRGB
+                | Command::MACAB,
+            ) => match dram.state {
+                dram::State::PowerUp => None,
+                dram::State::ActPowerDown => Some(Command::PDX),
+                dram::State::PrePowerDown => Some(Command::PDX),
+                dram::State::SelfRefresh => Some(Command::SRX),
+                _ => unreachable!("invalid dram state"),
+            },
+            (Level::Rank, Command::REF) => {
+                if dram.children.iter().any(|bank_group| {
+                    bank_group
+                        .children
+                        .iter()
+                        .any(|bank| !matches!(bank.state, dram::State::Closed))
+                }) {
+                    Some(Command::PREA)
+                } else {
+                    Some(Command::REF)
+                }
+            }
+            (Level::Rank, Command::PDE) => match dram.state {
+                dram::State::PowerUp | dram::State::ActPowerDown | dram::State::PrePowerDown => {
+                    Some(Command::PDE)
+                }
+                dram::State::SelfRefresh => Some(Command::SRX),
+                _ => unreachable!("invalid dram state"),
+            },
+            (Level::Rank, Command::SRE) => match dram.state {
+                dram::State::PowerUp => Some(Command::SRE),
+                dram::State::ActPowerDown | dram::State::PrePowerDown => Some(Command::PDX),
+                dram::State::SelfRefresh => Some(Command::SRX),
+                _ => unreachable!("invalid dram state"),
+            },
+
+            (
+                Level::Bank,
+                Command::RD | Command::WR | Command::MAC | Command::LDR | Command::STR,
+            ) => match dram.state {
+                dram::State::Closed => Some(Command::ACT),
+                dram::State::Opened(row_id) => {
+                    if row_id == child_id {
+                        Some(*cmd)
+                    } else {
+                        Some(Command::PRE)
+                    }
+                }
+                _ => unreachable!("invalid dram state"),
+            },
+            (Level::Bank, Command::REFSB) => match dram.state {
+                dram::State::Closed => Some(Command::REFSB),
+                _ => Some(Command::PRE),
+            },
+            (Level::Bank, Command::MACAB) => match dram.state {
+                dram::State::Closed => Some(Command::ACTAB),
+                dram::State::Opened(row_id) if row_id == child_id => None,
+                dram::State::Opened(_) => Some(Command::PREAB),
+                _ => unreachable!("invalid dram state"),
+            },
+            _ => None,
+        }
+    }
+
+    fn get_start_state(level: &Level) -> dram::State {
+        match level {
+            Level::Rank => dram::State::PowerUp,
+            Level::Bank => dram::State::Closed,
+            Level::Row => dram::State::Closed,
+            _ => dram::State::NoUse,
+        }
+    }
+
+    fn get_levels(&self) -> &[Level] {
+        &LEVELS
+    }
+
+    fn get_addr_bits(&self) -> &[usize] {
+        &self.addr_bits
+    }
+
+    fn get_child_size(&self) -> &[usize] {
+        &self.child_size
+    }
+
+    fn get_scope(&self, cmd: &Command) -> Level {
+        match cmd {
+            Command::ACT | Command::ACTAB => Level::Row,
+            Command::PRE | Command::PREAB | Command::REFSB | Command::REFPB => Level::Bank,
+            Command::RD
+            | Command::WR
+            | Command::RDA
+            | Command::WRA
+            | Command::MAC
+            | Command::LDR
+            | Command::STR
+            | Command::MACAB => Level::Column,
+            Command::PREA
+            | Command::REF
+            | Command::PDE
+            | Command::PDX
+            | Command::SRE
+            | Command::SRX
+            | Command::WRGB => Level::Rank,
+            Command::Max => unreachable!(),
+        }
+    }
+
+    fn update_state(&self, dram: &mut Dram<Self>, cmd: &Command, child_id: u64) {
+        match (dram.level, cmd) {
+            (Level::Bank, Command::ACT | Command::ACTAB) => {
+                dram.state = dram::State::Opened(child_id);
+            }
+            (Level::Bank, Command::PRE | Command::PREAB | Command::RDA | Command::WRA) => {
+                dram.state = dram::State::Closed;
+            }
+            (Level::Rank, Command::PREA) => {
+                dram.children.iter_mut().for_each(|bank_group| {
+                    bank_group.children.iter_mut().for_each(|bank| {
+                        bank.state = dram::State::Closed;
+                    })
+                });
+            }
+            (Level::Rank, Command::PDE) => {
+                let banks_closed = dram.children.iter().all(|bank_group| {
+                    bank_group
+                        .children
+                        .iter()
+                        .all(|bank| bank.state == State::Closed)
+                });
+                dram.state = if banks_closed {
+                    State::PrePowerDown
+                } else {
+                    State::ActPowerDown
+                };
+            }
+            (Level::Rank, Command::PDX | Command::SRX) => {
+                dram.state = State::PowerUp;
+            }
+            (Level::Rank, Command::SRE) => {
+                dram.state = State::SelfRefresh;
+            }
+            _ => {}
+        }
+    }
+    fn get_timming(&self, depth: usize, cmd: &Command) -> &[TimeEntry] {
+        self.timing.get(depth, cmd)
+    }
+
+    fn get_read_latency(&self) -> u64 {
+        self.read_latency
+    }
+
+    fn get_refresh_interval(&self) -> u64 {
+        self.speed_entry.nREFI
+    }
+
+    fn get_clk_ns(&self) -> f64 {
+        self.speed_entry.tCK
+    }
+
+    fn get_transaction_bytes(&self) -> u64 {
+        PREFETCH_SIZE * CHANNEL_WIDTH / 8
+    }
+
+    fn get_burst_cycles(&self) -> u64 {
+        self.speed_entry.nBL
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use super::{DDR5Org, Speed, DDR5};
+    use crate::{
+        command::Command,
+        config::{Config, Standard},
+        controller::Controller,
+        dram::{Dram, DramSpec},
+        memory::{Level, MemoryTrait, SimpleMemory},
+        request::{ReqType, Request},
+        stats::StatValue,
+    };
+
+    fn get_timing(ddr5: &Arc<DDR5>, level: Level, from: Command, to: Command) -> u64 {
+        ddr5.get_timming(ddr5.get_level_index(level), &from)
+            .iter()
+            .find(|t| !t.sibling && t.cmd == to)
+            .unwrap()
+            .val
+    }
+
+    #[test]
+    fn test_ddr5() {
+        let config =
+            Config::parse("standard = DDR5\norg = DDR5_16Gb_x8\nspeed = DDR5_4800AN").unwrap();
+        assert_eq!(config.standard, Standard::DDR5);
+        assert_eq!(config.ddr5_org, DDR5Org::DDR5_16Gb_x8);
+        assert_eq!(config.ddr5_speed, Speed::DDR5_4800AN);
+        let ddr5 = Arc::new(DDR5::new(&config));
+        // the DIMM channel is split into 2 sub-channels
+        assert_eq!(ddr5.get_child_size(), [2, 1, 8, 4, 1 << 16, 1 << 10]);
+        assert_eq!(ddr5.get_read_latency(), 40 + 8);
+        assert_eq!(ddr5.get_transaction_bytes(), 64);
+        assert_eq!(ddr5.get_refresh_interval(), 9360);
+        let t = |level, from, to| get_timing(&ddr5, level, from, to);
+        assert_eq!(t(Level::Rank, Command::REF, Command::ACT), 708);
+        assert_eq!(t(Level::Rank, Command::REFSB, Command::REFSB), 72);
+        assert_eq!(t(Level::Bank, Command::REFSB, Command::ACT), 312);
+        assert_eq!(t(Level::Bank, Command::ACT, Command::RD), 39);
+        assert_eq!(t(Level::Rank, Command::RD, Command::RD), 8);
+        assert_eq!(t(Level::BankGroup, Command::RD, Command::RD), 12);
+        assert_eq!(t(Level::Rank, Command::ACT, Command::ACT), 8);
+        assert_eq!(t(Level::BankGroup, Command::ACT, Command::ACT), 12);
+        // ACT and RD take 2 cycles of the cmd/addr bus, PRE takes 1
+        assert_eq!(t(Level::Channel, Command::ACT, Command::PRE), 2);
+        assert_eq!(t(Level::Channel, Command::PRE, Command::ACT), 1);
+
+        // the x16 chips have 4 bank groups and 2KB pages
+        let config = Config::parse("standard = DDR5\norg = DDR5_32Gb_x16").unwrap();
+        let ddr5 = Arc::new(DDR5::new(&config));
+        assert_eq!(ddr5.get_child_size(), [2, 1, 4, 4, 1 << 17, 1 << 10]);
+        let faw = ddr5
+            .get_timming(ddr5.get_level_index(Level::Rank), &Command::ACT)
+            .iter()
+            .find(|t| t.dist == 4)
+            .unwrap()
+            .val;
+        assert_eq!(faw, 40);
+        assert_eq!(
+            get_timing(&ddr5, Level::Rank, Command::REF, Command::ACT),
+            984
+        );
+    }
+
+    #[test]
+    fn test_speed_bins() {
+        let bins = [
+            ("DDR5_3200AN", 26, 6240),
+            ("DDR5_3600AN", 30, 7020),
+            ("DDR5_4000AN", 32, 7800),
+            ("DDR5_4400AN", 36, 8580),
+            ("DDR5_4800AN", 40, 9360),
+            ("DDR5_5200AN", 42, 10140),
+            ("DDR5_5600AN", 46, 10920),
+            ("DDR5_6000AN", 50, 11700),
+            ("DDR5_6400AN", 52, 12480),
+        ];
+        for (speed, n_cl, n_refi) in bins {
+            let config = Config::parse(&format!("standard = DDR5\nspeed = {speed}")).unwrap();
+            let ddr5 = Arc::new(DDR5::new(&config));
+            assert_eq!(ddr5.get_read_latency(), n_cl + 8, "{speed}");
+            assert_eq!(ddr5.get_refresh_interval(), n_refi, "{speed}");
+        }
+        let ddr5 = Arc::new(DDR5::new(
+            &Config::parse("standard = DDR5\nspeed = DDR5_6400AN").unwrap(),
+        ));
+        assert_eq!(ddr5.get_clk_ns(), 0.3125);
+    }
+
+    #[test]
+    fn test_same_bank_refresh() {
+        let config = Config::parse("standard = DDR5\nper_bank_refresh = on").unwrap();
+        let ddr5 = Arc::new(DDR5::new(&config));
+        let n_refi = ddr5.get_refresh_interval();
+        let channel = Dram::new(ddr5.clone(), Level::Channel, ddr5.get_child_size());
+        let mut controller = Controller::new(&config, channel);
+        for clk in 1..=n_refi + 10 {
+            controller.tick(clk);
+        }
+        // a REFSB covers a bank index in the 8 bank groups, 4 of them refresh every bank once in
+        // nREFI
+        let rank = &controller.channel.children[0];
+        assert_eq!(rank.cmd_counts[Command::REF as usize], 0);
+        assert_eq!(rank.cmd_counts[Command::REFSB as usize], 4);
+        for bank in rank.get_nodes(Level::Bank) {
+            assert_eq!(bank.cmd_counts[Command::REFSB as usize], 1);
+        }
+        // the refreshed bank takes no ACT for nRFCsb in any bank group
+        let n_rfcsb = get_timing(&ddr5, Level::Bank, Command::REFSB, Command::ACT);
+        let refreshed = rank.children[0].children[0].prev[Command::REFSB as usize][0];
+        for bank_group in &rank.children {
+            let bank = &bank_group.children[0];
+            assert_eq!(bank.prev[Command::REFSB as usize][0], refreshed);
+            assert_eq!(
+                bank.get_next_avaliable_clk(&Command::ACT),
+                refreshed + n_rfcsb
+            );
+        }
+
+        // the requests are spread over the 2 sub-channels
+        let mut memory = SimpleMemory::with_config(&config, ddr5.clone()).unwrap();
+        for i in 0..32 {
+            memory
+                .try_send(Request::new(i << 6, ReqType::Write))
+                .unwrap();
+        }
+        let stats = memory.finish();
+        assert_eq!(stats.get_scalar("write_requests"), Some(32.0));
+        let Some(StatValue::Vector(per_channel)) = stats.get("incoming_requests_per_channel")
+        else {
+            panic!("missing incoming_requests_per_channel");
+        };
+        assert_eq!(per_channel, &vec![16.0; 2]);
+    }
+}
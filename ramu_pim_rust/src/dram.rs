@@ -1,6 +1,20 @@
-use std::collections::{BTreeMap, VecDeque};
+use std::{collections::VecDeque, sync::Arc};
 
-use crate::{command::Command, memory::Level, request::ReqType};
+use crate::{
+    command::Command,
+    config::{Config, ConfigError},
+    memory::Level,
+    pim::{PimUnitConfig, ProcessingUnit},
+    request::ReqType,
+};
+pub(crate) fn log2(mut x: usize) -> usize {
+    let mut i = 0;
+    while x > 1 {
+        x >>= 1;
+        i += 1;
+    }
+    i
+}
 #[derive(PartialEq, Eq)]
 pub enum State {
     Opened(u64),
@@ -11,30 +25,41 @@ pub enum State {
     SelfRefresh,
     NoUse,
 }
-pub struct Dram<'a, T: ?Sized> {
-    pub spec: &'a T,
+pub struct Dram<T: ?Sized> {
+    /// shared by all the nodes of the hierarchy
+    pub spec: Arc<T>,
+    pub id: u64,
     pub level: Level,
-    pub children: Vec<Dram<'a, T>>,
+    /// the index of `level` in `DramSpec::get_levels`, which is also the index of the address of
+    /// this node in addr_vec
+    pub depth: usize,
+    pub children: Vec<Dram<T>>,
     pub state: State,
     pub next_clk: Vec<u64>,
     pub prev: Vec<VecDeque<u64>>,
+    /// the number of each command issued to this node or its children
+    pub cmd_counts: Vec<u64>,
+    /// the processing unit of a bank, the odd banks of the pairs sharing a unit have none
+    pub pim_unit: Option<ProcessingUnit>,
 }
-impl<'a, T> Dram<'a, T>
+impl<T> Dram<T>
 where
     T: DramSpec,
 {
-    pub fn new(spec: &'a T, level: Level, child_size: &[usize]) -> Self {
+    pub fn new(spec: Arc<T>, level: Level, child_size: &[usize]) -> Self {
         let state = T::get_start_state(&level);
-        let child_level = level.next_level().unwrap();
+        let depth = spec.get_level_index(level);
         let mut children = vec![];
-        if !matches!(child_level, Level::Row) {
-            for _i in 0..child_size[u8::from(child_level) as usize] {
-                children.push(Dram::new(
-                    spec,
-                    Level::try_from(child_level).unwrap(),
-                    child_size,
-                ));
+        // the rows are not nodes, their states are kept by the banks
+        match spec.get_levels().get(depth + 1) {
+            Some(&child_level) if child_level != Level::Row => {
+                for i in 0..child_size[depth + 1] {
+                    let mut child = Dram::new(spec.clone(), child_level, child_size);
+                    child.id = i as u64;
+                    children.push(child);
+                }
             }
+            _ => {}
         }
         let mut prev = vec![];
         for i in 0..Command::Max as usize {
@@ -42,34 +67,63 @@ where
 
             let mut dist = 0;
             let cmd = Command::try_from(i as u8).unwrap();
-            for time_entry in spec.get_timming(&level, &cmd) {
+            for time_entry in spec.get_timming(depth, &cmd) {
                 dist = dist.max(time_entry.dist);
             }
             tmp.resize(dist, u64::MAX);
             prev.push(tmp);
         }
-        let mut next_clk = vec![0; Command::Max as usize];
+        let next_clk = vec![0; Command::Max as usize];
         Self {
             spec,
+            id: 0,
             level,
+            depth,
             state,
             children,
             next_clk,
             prev,
+            cmd_counts: vec![0; Command::Max as usize],
+            pim_unit: None,
+        }
+    }
+    /// attach the processing units to the banks under this node
+    pub fn attach_pim_units(&mut self, config: &PimUnitConfig) {
+        let attach_to_banks = self
+            .children
+            .first()
+            .is_some_and(|child| child.level == Level::Bank);
+        for (i, child) in self.children.iter_mut().enumerate() {
+            if attach_to_banks {
+                child.pim_unit =
+                    (i % config.banks_per_unit == 0).then(|| ProcessingUnit::new(*config));
+            } else {
+                child.attach_pim_units(config);
+            }
         }
     }
     pub fn decode(&self, cmd: &Command, addr_vec: &[u64]) -> Command {
-        let child_index = addr_vec[self.level as usize + 1];
-        if let Some(Command) = T::get_pre_cmd(self, cmd, child_index) {
-            return Command;
+        let child_index = addr_vec[self.depth + 1];
+        if let Some(pre_cmd) = T::get_pre_cmd(self, cmd, child_index) {
+            pre_cmd
         } else {
             match self.level {
-                Level::Bank => {
-                    return *cmd;
-                }
-                _ => {
-                    return self.children[child_index as usize].decode(cmd, addr_vec);
+                Level::Bank => *cmd,
+                _ if child_index == u64::MAX => {
+                    // an all-bank command, the targeted banks take one command together, so close
+                    // them all first if they are not ready for the same one
+                    let mut cmds = self
+                        .children
+                        .iter()
+                        .map(|child| child.decode(cmd, addr_vec));
+                    let first = cmds.next().unwrap();
+                    if cmds.all(|cmd| cmd == first) {
+                        first
+                    } else {
+                        Command::PREAB
+                    }
                 }
+                _ => self.children[child_index as usize].decode(cmd, addr_vec),
             }
         }
     }
@@ -80,56 +134,243 @@ where
     pub fn update(&mut self, cmd: &Command, addr_vec: &[u64], clk: u64) {
         self.update_state(cmd, addr_vec);
         self.update_timming(cmd, addr_vec, clk);
+        if ProcessingUnit::is_pim_cmd(cmd) {
+            self.update_pim(cmd, addr_vec, clk);
+        }
+    }
+    fn update_pim(&mut self, cmd: &Command, addr_vec: &[u64], clk: u64) {
+        let column = addr_vec[self.spec.get_level_index(Level::Column)];
+        for unit in self.get_pim_units(addr_vec) {
+            let unit = &mut self.children[unit];
+            if let Some(unit) = unit.pim_unit.as_mut() {
+                unit.issue(cmd, column, clk);
+            }
+        }
+        let child_index = addr_vec[self.depth + 1];
+        self.children
+            .iter_mut()
+            .filter(|child| child.level != Level::Bank)
+            .filter(|child| child_index == u64::MAX || child.id == child_index)
+            .for_each(|child| child.update_pim(cmd, addr_vec, clk));
+    }
+    /// the children of this node holding the processing units of the banks targeted by
+    /// `addr_vec`, once for every targeted bank
+    fn get_pim_units(&self, addr_vec: &[u64]) -> Vec<usize> {
+        let Some(unit) = self
+            .children
+            .first()
+            .and_then(|bank| bank.pim_unit.as_ref())
+        else {
+            return vec![];
+        };
+        let banks_per_unit = unit.config.banks_per_unit;
+        let child_index = addr_vec[self.depth + 1];
+        (0..self.children.len())
+            .filter(|&bank| child_index == u64::MAX || bank as u64 == child_index)
+            .map(|bank| bank - bank % banks_per_unit)
+            .collect()
+    }
+    /// return if the processing units of the banks targeted by `addr_vec` can take `cmd`
+    fn check_pim(&self, cmd: &Command, addr_vec: &[u64], clk: u64) -> bool {
+        let column = addr_vec[self.spec.get_level_index(Level::Column)];
+        self.get_pim_units(addr_vec).into_iter().all(|unit| {
+            self.children[unit]
+                .pim_unit
+                .as_ref()
+                .is_none_or(|unit| unit.is_ready(cmd, column, clk))
+        })
+    }
+    /// the cycle the registers of the processing units targeted by `addr_vec` hold their latest
+    /// values
+    pub fn get_pim_ready_clk(&self, addr_vec: &[u64]) -> u64 {
+        let column = addr_vec[self.spec.get_level_index(Level::Column)];
+        let ready = self
+            .get_pim_units(addr_vec)
+            .into_iter()
+            .filter_map(|unit| self.children[unit].pim_unit.as_ref())
+            .map(|unit| unit.get_register_clk(column))
+            .max()
+            .unwrap_or(0);
+        let child_index = addr_vec.get(self.depth + 1).copied();
+        self.children
+            .iter()
+            .filter(|child| child.level != Level::Bank)
+            .filter(|child| child_index == Some(u64::MAX) || Some(child.id) == child_index)
+            .map(|child| child.get_pim_ready_clk(addr_vec))
+            .fold(ready, u64::max)
     }
     fn update_state(&mut self, cmd: &Command, addr_vec: &[u64]) {
         tracing::debug!("update_state: {:?} {:?}", self.level, cmd);
-        let child_level = self.level.next_level();
-        self.spec
-            .update_state(self, cmd, child_level.unwrap() as u64);
+        let child_index = addr_vec[self.depth + 1];
+        self.cmd_counts[*cmd as usize] += 1;
+        // the spec updates this node, so it is held by another handle
+        let spec = self.spec.clone();
+        spec.update_state(self, cmd, child_index);
         if self.level == self.spec.get_scope(cmd) || self.children.is_empty() {
             return;
         }
-        tracing::debug!("update_state: {:?}", child_level);
-        let child_index = addr_vec[self.level as usize + 1] as usize;
-        self.children[child_index].update_state(cmd, addr_vec);
+        if child_index == u64::MAX {
+            self.children
+                .iter_mut()
+                .for_each(|child| child.update_state(cmd, addr_vec));
+        } else {
+            self.children[child_index as usize].update_state(cmd, addr_vec);
+        }
     }
     fn update_timming(&mut self, cmd: &Command, addr_vec: &[u64], clk: u64) {
+        // i am the target node
         if !self.prev[*cmd as usize].is_empty() {
             self.prev[*cmd as usize].pop_back();
             self.prev[*cmd as usize].push_front(clk);
         }
-        for timing in self.spec.get_timming(&self.level, cmd) {
+        for timing in self.spec.get_timming(self.depth, cmd) {
             if timing.sibling {
                 continue;
             }
-            let past = self.prev[*cmd as usize][timing.dist - 1 as usize];
+            let past = self.prev[*cmd as usize][timing.dist - 1];
             if past == u64::MAX {
                 continue;
             }
             let future = past + timing.val;
             self.next_clk[timing.cmd as usize] = future.max(self.next_clk[timing.cmd as usize]);
         }
-        self.children.iter_mut().for_each(|child| {
-            child.update_timming(cmd, addr_vec, clk);
-        });
+        // some commands have timings that are higher than their scope levels, so do not stop at
+        // the scope level. the children not selected by addr_vec are only siblings of the target
+        if !self.children.is_empty() {
+            let target = addr_vec.get(self.depth + 1).copied();
+            // an all-bank command targets every child above its scope level that addr_vec leaves
+            // unset
+            let broadcast = target == Some(u64::MAX)
+                && self.depth < self.spec.get_level_index(self.spec.get_scope(cmd));
+            self.children.iter_mut().for_each(|child| {
+                if broadcast || Some(child.id) == target {
+                    child.update_timming(cmd, addr_vec, clk);
+                } else {
+                    child.update_sibling_timming(cmd, clk);
+                }
+            });
+        }
+    }
+    /// i am not the target node, only one of its siblings
+    fn update_sibling_timming(&mut self, cmd: &Command, clk: u64) {
+        for timing in self.spec.get_timming(self.depth, cmd) {
+            if !timing.sibling {
+                continue;
+            }
+            let future = clk + timing.val;
+            self.next_clk[timing.cmd as usize] = future.max(self.next_clk[timing.cmd as usize]);
+        }
     }
     /// return if the command is ok to issue
     pub fn check(&self, cmd: &Command, addr_vec: &[u64], clk: u64) -> bool {
         if clk < self.get_next_avaliable_clk(cmd) {
             return false;
         }
-        if let Some(child_level) = self.level.next_level() {
-            if self.spec.get_scope(cmd) != self.level && !self.children.is_empty() {
-                let child_index = addr_vec[child_level as usize + 1];
-                return self.children[child_index as usize].check(cmd, addr_vec, clk);
+        if ProcessingUnit::is_pim_cmd(cmd) && !self.check_pim(cmd, addr_vec, clk) {
+            return false;
+        }
+        if self.spec.get_scope(cmd) != self.level && !self.children.is_empty() {
+            let child_index = addr_vec[self.depth + 1];
+            if child_index == u64::MAX {
+                return self
+                    .children
+                    .iter()
+                    .all(|child| child.check(cmd, addr_vec, clk));
             }
+            return self.children[child_index as usize].check(cmd, addr_vec, clk);
         }
         true
     }
+    /// return if the row targeted by `addr_vec` is the opened row of its bank
+    pub fn check_row_hit(&self, addr_vec: &[u64]) -> bool {
+        match self.level {
+            Level::Bank => {
+                let row = addr_vec[self.spec.get_level_index(Level::Row)];
+                matches!(self.state, State::Opened(opened) if opened == row)
+            }
+            _ => self
+                .get_child(addr_vec)
+                .is_some_and(|child| child.check_row_hit(addr_vec)),
+        }
+    }
+    /// return if the bank targeted by `addr_vec` has an opened row
+    pub fn check_row_open(&self, addr_vec: &[u64]) -> bool {
+        match self.level {
+            Level::Bank => matches!(self.state, State::Opened(_)),
+            _ => self
+                .get_child(addr_vec)
+                .is_some_and(|child| child.check_row_open(addr_vec)),
+        }
+    }
+    fn get_child(&self, addr_vec: &[u64]) -> Option<&Self> {
+        let child_index = *addr_vec.get(self.depth + 1)?;
+        self.children.get(child_index as usize)
+    }
+    /// collect the nodes of `level` under this node, in the order of their addresses
+    pub fn get_nodes(&self, level: Level) -> Vec<&Self> {
+        if self.level == level {
+            return vec![self];
+        }
+        self.children
+            .iter()
+            .flat_map(|child| child.get_nodes(level))
+            .collect()
+    }
     pub fn get_next_avaliable_clk(&self, cmd: &Command) -> u64 {
         self.next_clk[*cmd as usize]
     }
 }
+/// the all-bank PIM commands and the single bank commands they are broadcast from
+const ALL_BANK_CMDS: [(Command, Command); 3] = [
+    (Command::ACTAB, Command::ACT),
+    (Command::PREAB, Command::PRE),
+    (Command::MACAB, Command::MAC),
+];
+/// give the all-bank commands the timings of their single bank commands at every level. the
+/// activation windows (nFAW) do not apply to ACTAB, the PIM standard budgets the power of
+/// activating all the banks at once
+pub(crate) fn add_all_bank_timing(timing: &mut TimingTable) {
+    let variants = |cmd: Command| {
+        let mut cmds = vec![cmd];
+        cmds.extend(
+            ALL_BANK_CMDS
+                .iter()
+                .filter(|(_, single)| *single == cmd)
+                .map(|(all, _)| *all),
+        );
+        cmds
+    };
+    for t in timing.entries.iter_mut() {
+        let mut entries = vec![];
+        for (from, from_entries) in t.iter().enumerate() {
+            let from = Command::try_from(from as u8).unwrap();
+            for entry in from_entries {
+                for new_from in variants(from) {
+                    for new_to in variants(entry.cmd) {
+                        if (new_from, new_to) == (from, entry.cmd) {
+                            continue;
+                        }
+                        if entry.dist > 1
+                            && (new_from == Command::ACTAB || new_to == Command::ACTAB)
+                        {
+                            continue;
+                        }
+                        entries.push((
+                            new_from,
+                            TimeEntry {
+                                cmd: new_to,
+                                ..entry.clone()
+                            },
+                        ));
+                    }
+                }
+            }
+        }
+        for (from, entry) in entries {
+            t[from as usize].push(entry);
+        }
+    }
+}
 #[derive(Clone)]
 pub struct TimeEntry {
     pub cmd: Command,
@@ -137,14 +378,80 @@ pub struct TimeEntry {
     pub val: u64,
     pub sibling: bool,
 }
+/// the timings of the commands, indexed by the depth of the level in `DramSpec::get_levels`
+/// and then by the command
+#[derive(Clone)]
+pub struct TimingTable {
+    levels: Vec<Level>,
+    entries: Vec<Vec<Vec<TimeEntry>>>,
+}
+impl TimingTable {
+    pub fn new(levels: &[Level]) -> Self {
+        Self {
+            levels: levels.to_vec(),
+            entries: vec![vec![vec![]; Command::Max as usize]; levels.len()],
+        }
+    }
+    /// the timings of `level` indexed by the command, panic if the hierarchy has no `level`
+    pub fn get_level_mut(&mut self, level: Level) -> &mut [Vec<TimeEntry>] {
+        let depth = self
+            .levels
+            .iter()
+            .position(|&l| l == level)
+            .unwrap_or_else(|| panic!("{level:?} is not a level of this standard"));
+        &mut self.entries[depth]
+    }
+    pub fn get(&self, depth: usize, cmd: &Command) -> &[TimeEntry] {
+        &self.entries[depth][*cmd as usize]
+    }
+}
 pub trait DramSpec {
+    /// build the spec from the config, unlike `new` it reports the options the standard cannot
+    /// model instead of panicking
+    fn from_config(config: &Config) -> Result<Self, ConfigError>
+    where
+        Self: Sized;
+    /// if the standard has a command to refresh a single bank, see `Config::per_bank_refresh`
+    fn supports_per_bank_refresh() -> bool;
+    /// if a per-bank refresh covers the bank of the same index in every bank group at once, like
+    /// the same-bank refresh (REFsb) of DDR5
+    fn refreshes_same_bank() -> bool {
+        false
+    }
+    /// if the standard has the PIM commands, the PIM requests to the others are rejected
+    fn supports_pim() -> bool;
     fn get_first_cmd(req_type: &ReqType) -> Command;
-    fn get_pre_cmd<'a>(dram: &Dram<'a, Self>, cmd: &Command, child_id: u64) -> Option<Command>;
+    fn get_pre_cmd(dram: &Dram<Self>, cmd: &Command, child_id: u64) -> Option<Command>;
     fn get_start_state(level: &Level) -> State;
+    /// the levels of the hierarchy from the channel down to the column, addr_vec, the child sizes,
+    /// the address bits and the timings are all indexed in this order. besides the channel, the
+    /// bank, the row and the column, a standard can have any of the other levels
+    fn get_levels(&self) -> &[Level];
+    /// the index of `level` in `get_levels`
+    fn get_level_index(&self, level: Level) -> usize {
+        self.get_levels()
+            .iter()
+            .position(|&l| l == level)
+            .unwrap_or_else(|| panic!("{level:?} is not a level of this standard"))
+    }
+    /// the level the refreshes, the power-downs and the PIM global buffers belong to, the ranks
+    /// of most standards
+    fn get_rank_level(&self) -> Level {
+        Level::Rank
+    }
     fn get_addr_bits(&self) -> &[usize];
     fn get_child_size(&self) -> &[usize];
     fn get_scope(&self, cmd: &Command) -> Level;
     fn update_state(&self, dram: &mut Dram<Self>, cmd: &Command, child_id: u64);
-    fn get_timming(&self, level: &Level, cmd: &Command) -> &[TimeEntry];
+    /// the timings of `cmd` at the level of `depth` in `get_levels`
+    fn get_timming(&self, depth: usize, cmd: &Command) -> &[TimeEntry];
     fn get_read_latency(&self) -> u64;
+    /// the average interval between two refresh commands to the same rank (nREFI)
+    fn get_refresh_interval(&self) -> u64;
+    /// the period of a memory cycle in nanoseconds (tCK)
+    fn get_clk_ns(&self) -> f64;
+    /// the bytes transferred by one column access (prefetch size * channel width / 8)
+    fn get_transaction_bytes(&self) -> u64;
+    /// the cycles one column access takes the data bus (nBL)
+    fn get_burst_cycles(&self) -> u64;
 }
@@ -0,0 +1,151 @@
+use std::path::Path;
+
+use crate::{
+    config::{Config, ConfigError, Standard},
+    ddr3::DDR3,
+    ddr4::DDR4,
+    ddr5::DDR5,
+    gddr5::GDDR5,
+    gddr6::GDDR6,
+    hbm::HBM,
+    lpddr4::LPDDR4,
+    memory::{MemoryTrait, SimpleMemory},
+};
+
+/// build the memory of the standard picked by `standard = ...` in the config, like the
+/// `MemoryFactory` of ramulator, `SimpleMemory` is generic over the spec so the standard is
+/// chosen here at runtime. the options the standard cannot model are returned as errors
+pub fn create_memory(config: &Config) -> Result<Box<dyn MemoryTrait + Send>, ConfigError> {
+    Ok(match config.standard {
+        Standard::DDR3 => Box::new(SimpleMemory::<DDR3>::from_config(config)?),
+        Standard::DDR4 => Box::new(SimpleMemory::<DDR4>::from_config(config)?),
+        Standard::DDR5 => Box::new(SimpleMemory::<DDR5>::from_config(config)?),
+        Standard::GDDR5 => Box::new(SimpleMemory::<GDDR5>::from_config(config)?),
+        Standard::GDDR6 => Box::new(SimpleMemory::<GDDR6>::from_config(config)?),
+        Standard::HBM | Standard::HBM2 => Box::new(SimpleMemory::<HBM>::from_config(config)?),
+        Standard::LPDDR4 => Box::new(SimpleMemory::<LPDDR4>::from_config(config)?),
+    })
+}
+/// read a ramulator config file and build the memory of its standard
+pub fn create_memory_from_file(
+    path: impl AsRef<Path>,
+) -> Result<Box<dyn MemoryTrait + Send>, ConfigError> {
+    create_memory(&Config::from_file(path)?)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{create_memory, create_memory_from_file};
+    use crate::{
+        config::{Config, ConfigError},
+        ddr3::DDR3,
+        ddr4::DDR4,
+        ddr5::DDR5,
+        dram::DramSpec,
+        gddr5::GDDR5,
+        gddr6::GDDR6,
+        hbm::HBM,
+        lpddr4::LPDDR4,
+        request::{ReqType, Request},
+    };
+
+    #[test]
+    fn test_create_memory() {
+        for standard in [
+            "DDR3", "DDR4", "DDR5", "GDDR5", "GDDR6", "HBM", "HBM2", "LPDDR4",
+        ] {
+            let config = Config::parse(&format!("standard = {standard}")).unwrap();
+            let clk_ns = match standard {
+                "DDR3" => DDR3::new(&config).get_clk_ns(),
+                "DDR4" => DDR4::new(&config).get_clk_ns(),
+                "DDR5" => DDR5::new(&config).get_clk_ns(),
+                "GDDR5" => GDDR5::new(&config).get_clk_ns(),
+                "GDDR6" => GDDR6::new(&config).get_clk_ns(),
+                "LPDDR4" => LPDDR4::new(&config).get_clk_ns(),
+                _ => HBM::new(&config).get_clk_ns(),
+            };
+            let mut memory = create_memory(&config).unwrap();
+            assert_eq!(memory.clk_ns(), clk_ns, "{standard}");
+            memory.try_send(Request::new(0x40, ReqType::Read)).unwrap();
+            while memory.pending_requests() > 0 {
+                memory.tick();
+            }
+            assert_eq!(memory.try_recv().map(|req| req.addr), Some(0x40));
+        }
+
+        let path = concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/../ramulator/configs/HBM-config.cfg"
+        );
+        let memory = create_memory_from_file(path).unwrap();
+        assert_eq!(memory.get_channel_num(), 8);
+        assert!(create_memory_from_file("no/such/config.cfg").is_err());
+    }
+
+    #[test]
+    fn test_pim_support() {
+        for (standard, pim) in [
+            ("DDR3", false),
+            ("DDR4", true),
+            ("DDR5", true),
+            ("GDDR5", false),
+            ("GDDR6", true),
+            ("HBM", true),
+            ("LPDDR4", false),
+        ] {
+            let mut memory =
+                create_memory(&Config::parse(&format!("standard = {standard}")).unwrap()).unwrap();
+            let sent = memory.try_send(Request::new(0x40, ReqType::PimMac));
+            assert_eq!(sent.is_ok(), pim, "{standard}");
+            while memory.pending_requests() > 0 {
+                memory.tick();
+            }
+        }
+    }
+
+    #[test]
+    fn test_per_bank_refresh_support() {
+        for (standard, per_bank) in [
+            ("DDR3", false),
+            ("DDR4", false),
+            ("DDR5", true),
+            ("GDDR5", false),
+            ("GDDR6", false),
+            ("HBM", true),
+            ("LPDDR4", true),
+        ] {
+            let config = Config::parse(&format!("standard = {standard}")).unwrap();
+            let mut memory = create_memory(&config).unwrap();
+            let sent = memory.try_send(Request::new(0x40, ReqType::RefreshBank));
+            assert_eq!(sent.is_ok(), per_bank, "{standard}");
+            while memory.pending_requests() > 0 {
+                memory.tick();
+            }
+        }
+    }
+
+    #[test]
+    fn test_unsupported_config() {
+        let error = |content: &str| match create_memory(&Config::parse(content).unwrap()) {
+            Err(ConfigError::Unsupported { key, reason }) => (key, reason),
+            Err(e) => panic!("{content}: unexpected error {e}"),
+            Ok(_) => panic!("{content}: the memory should not be built"),
+        };
+        for standard in ["DDR3", "DDR4", "GDDR5", "GDDR6"] {
+            let content = format!("standard = {standard}\nper_bank_refresh = on");
+            assert_eq!(error(&content).0, "per_bank_refresh");
+        }
+        assert_eq!(error("standard = GDDR5\nranks = 2").0, "ranks");
+        assert_eq!(error("standard = GDDR6\nranks = 2").0, "ranks");
+        assert_eq!(
+            error("standard = HBM\npseudo_channel = on").0,
+            "pseudo_channel"
+        );
+        let (key, reason) = error("standard = HBM\norg = HBM2_4Gb");
+        assert_eq!(key, "speed");
+        assert_eq!(reason, "HBM_1Gbps does not match the org HBM2_4Gb");
+
+        let config = "standard = HBM2\norg = HBM2_4Gb\nspeed = HBM2_2Gbps\npseudo_channel = on";
+        assert!(create_memory(&Config::parse(config).unwrap()).is_ok());
+    }
+}
@@ -0,0 +1,424 @@
+use std::collections::HashMap;
+
+use crate::request::ReqType;
+
+/// the bytes of a line, the payload of a read or a write
+pub const LINE_BYTES: usize = 64;
+/// the bytes allocated at once for the lines written
+const PAGE_BYTES: u64 = 4096;
+/// the fp16 lanes of a line the PIM units compute on
+const LANES: usize = LINE_BYTES / 2;
+/// 1.0 in fp16
+const FP16_ONE: u16 = 0x3c00;
+
+pub type Line = [u8; LINE_BYTES];
+
+/// the data held by the memory and the PIM units, the memory is sparse and only the pages written
+/// are allocated, reading the others returns zeros
+pub struct FunctionalMemory {
+    pages: HashMap<u64, Box<[u8]>>,
+    /// the registers of the PIM units, keyed by the addr_vec of the unit down to the bank and the
+    /// register, a register never written holds zeros
+    registers: HashMap<(Vec<u64>, usize), Line>,
+    /// the global buffers of the ranks, keyed by the addr_vec of the rank, a buffer never written
+    /// holds ones so a MAC adds its column to the register
+    global_buffers: HashMap<Vec<u64>, Line>,
+}
+impl FunctionalMemory {
+    pub fn new() -> Self {
+        Self {
+            pages: HashMap::new(),
+            registers: HashMap::new(),
+            global_buffers: HashMap::new(),
+        }
+    }
+    /// read the line at `addr`
+    pub fn read(&self, addr: u64) -> Line {
+        let (page, offset) = Self::locate(addr);
+        let mut line = [0; LINE_BYTES];
+        if let Some(page) = self.pages.get(&page) {
+            line.copy_from_slice(&page[offset..offset + LINE_BYTES]);
+        }
+        line
+    }
+    /// write the line at `addr`
+    pub fn write(&mut self, addr: u64, line: &Line) {
+        let (page, offset) = Self::locate(addr);
+        let page = self
+            .pages
+            .entry(page)
+            .or_insert_with(|| vec![0; PAGE_BYTES as usize].into_boxed_slice());
+        page[offset..offset + LINE_BYTES].copy_from_slice(line);
+    }
+    /// the page and the offset in the page of the line at `addr`
+    fn locate(addr: u64) -> (u64, usize) {
+        let addr = addr & !(LINE_BYTES as u64 - 1);
+        (addr / PAGE_BYTES, (addr % PAGE_BYTES) as usize)
+    }
+    /// execute a PIM request on the line at `addr`, `unit` and `rank` are the addr_vecs of the
+    /// processing unit and of the rank, `register` is picked by the column of `addr`
+    ///
+    /// PimLoad: register = line
+    ///
+    /// PimMac, PimMacAllBank: register += line * global buffer, lane by lane
+    ///
+    /// PimStore: line = register, then the register is cleared for the next accumulation
+    ///
+    /// PimBroadcast: global buffer = line
+    pub fn execute(
+        &mut self,
+        req_type: ReqType,
+        addr: u64,
+        unit: &[u64],
+        rank: &[u64],
+        register: usize,
+    ) {
+        let key = (unit.to_vec(), register);
+        match req_type {
+            ReqType::PimLoad => {
+                let line = self.read(addr);
+                self.registers.insert(key, line);
+            }
+            ReqType::PimMac | ReqType::PimMacAllBank => {
+                let line = self.read(addr);
+                let operand = self.global_buffers.get(rank).copied().unwrap_or_else(ones);
+                let register = self.registers.entry(key).or_insert([0; LINE_BYTES]);
+                let (line, operand, acc) =
+                    (to_lanes(&line), to_lanes(&operand), to_lanes(register));
+                let mut result = [0; LANES];
+                for i in 0..LANES {
+                    let value = f16_to_f32(acc[i]) + f16_to_f32(line[i]) * f16_to_f32(operand[i]);
+                    result[i] = f32_to_f16(value);
+                }
+                *register = from_lanes(&result);
+            }
+            ReqType::PimStore => {
+                let line = self.registers.remove(&key).unwrap_or([0; LINE_BYTES]);
+                self.write(addr, &line);
+            }
+            ReqType::PimBroadcast => {
+                let line = self.read(addr);
+                self.global_buffers.insert(rank.to_vec(), line);
+            }
+            ReqType::Read | ReqType::Write | ReqType::Refresh | ReqType::RefreshBank => {}
+        }
+    }
+}
+impl Default for FunctionalMemory {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// a line of fp16 ones, the global buffer a MAC adds its column to the register with
+pub fn ones() -> Line {
+    from_lanes(&[FP16_ONE; LANES])
+}
+/// split a line into its little-endian fp16 lanes
+pub fn to_lanes(line: &Line) -> [u16; LANES] {
+    let mut lanes = [0; LANES];
+    for (lane, bytes) in lanes.iter_mut().zip(line.chunks_exact(2)) {
+        *lane = u16::from_le_bytes([bytes[0], bytes[1]]);
+    }
+    lanes
+}
+/// build a line from its fp16 lanes
+pub fn from_lanes(lanes: &[u16; LANES]) -> Line {
+    let mut line = [0; LINE_BYTES];
+    for (bytes, lane) in line.chunks_exact_mut(2).zip(lanes) {
+        bytes.copy_from_slice(&lane.to_le_bytes());
+    }
+    line
+}
+pub fn f16_to_f32(half: u16) -> f32 {
+    let sign = if half & 0x8000 != 0 { -1.0 } else { 1.0 };
+    let exp = ((half >> 10) & 0x1f) as i32;
+    let mantissa = (half & 0x3ff) as f32;
+    sign * match exp {
+        0 => mantissa * 2f32.powi(-24),
+        0x1f if mantissa == 0.0 => f32::INFINITY,
+        0x1f => f32::NAN,
+        _ => (1024.0 + mantissa) * 2f32.powi(exp - 25),
+    }
+}
+/// round `value` to the nearest fp16, ties to even
+pub fn f32_to_f16(value: f32) -> u16 {
+    let bits = value.to_bits();
+    let sign = ((bits >> 16) & 0x8000) as u16;
+    let abs = value.abs();
+    if value.is_nan() {
+        return sign | 0x7e00;
+    }
+    if abs >= 65520.0 {
+        return sign | 0x7c00;
+    }
+    if abs < 2f32.powi(-14) {
+        // a subnormal, rounding up to 0x400 gives the smallest normal
+        let scaled = abs * 2f32.powi(24);
+        let floor = scaled.floor();
+        let rounded = match scaled - floor {
+            half if half > 0.5 => floor + 1.0,
+            half if half == 0.5 && floor % 2.0 == 1.0 => floor + 1.0,
+            _ => floor,
+        };
+        return sign | rounded as u16;
+    }
+    let exp = ((bits >> 23) & 0xff) as i32 - 127 + 15;
+    let mantissa = bits & 0x7f_ffff;
+    let half = ((exp as u32) << 10) | (mantissa >> 13);
+    let rest = mantissa & 0x1fff;
+    // a carry out of the mantissa goes into the exponent
+    let round = rest > 0x1000 || (rest == 0x1000 && half & 1 == 1);
+    sign | (half + round as u32) as u16
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use super::{f16_to_f32, f32_to_f16, from_lanes, to_lanes, FunctionalMemory, LINE_BYTES};
+    use crate::{
+        config::Config,
+        ddr4::DDR4,
+        kernel::{ExecutionMode, Kernel, KernelRunner, Placement},
+        memory::{MemoryTrait, SimpleMemory},
+        request::{ReqType, Request},
+    };
+
+    #[test]
+    fn test_fp16() {
+        for value in [0.0, 1.0, -2.5, 0.1, 65504.0, 2f32.powi(-24), 1000.25] {
+            let half = f32_to_f16(value);
+            assert!((f16_to_f32(half) - value).abs() <= value.abs() / 1024.0);
+        }
+        assert_eq!(f32_to_f16(1.0), 0x3c00);
+        assert_eq!(f32_to_f16(65520.0), 0x7c00);
+        // 2049 is half-way between 2048 and 2050, it rounds to the even 2048
+        assert_eq!(f16_to_f32(f32_to_f16(2049.0)), 2048.0);
+        assert_eq!(f16_to_f32(f32_to_f16(2051.0)), 2052.0);
+    }
+
+    #[test]
+    fn test_sparse_memory() {
+        let mut memory = FunctionalMemory::new();
+        assert_eq!(memory.read(1 << 40), [0; LINE_BYTES]);
+        memory.write(4096 + 64, &[7; LINE_BYTES]);
+        assert_eq!(memory.read(4096 + 64 + 3), [7; LINE_BYTES]);
+        assert_eq!(memory.read(4096), [0; LINE_BYTES]);
+        assert_eq!(memory.pages.len(), 1);
+    }
+
+    /// a line of 32 lanes holding `f(i)` for lane i
+    fn line(f: impl Fn(usize) -> f32) -> [u8; LINE_BYTES] {
+        let lanes: Vec<u16> = (0..32).map(|i| f32_to_f16(f(i))).collect();
+        from_lanes(&lanes.try_into().unwrap())
+    }
+
+    fn wait_all(memory: &mut impl MemoryTrait) -> Vec<Request> {
+        while memory.pending_requests() > 0 {
+            memory.tick();
+        }
+        std::iter::from_fn(|| memory.try_recv()).collect()
+    }
+
+    #[test]
+    fn test_read_write_data() {
+        let config = Config::default();
+        let ddr4 = Arc::new(DDR4::new(&config));
+        let mut memory = SimpleMemory::with_config(&config, ddr4.clone()).unwrap();
+        memory
+            .try_send(Request::with_data(0x1000, line(|i| i as f32)))
+            .unwrap();
+        memory
+            .try_send(Request::new(0x1000, ReqType::Read))
+            .unwrap();
+        memory
+            .try_send(Request::new(0x2000, ReqType::Read))
+            .unwrap();
+        let reads: Vec<_> = wait_all(&mut memory)
+            .into_iter()
+            .filter(|req| req.req_type == ReqType::Read)
+            .collect();
+        assert_eq!(reads.len(), 2);
+        for req in reads {
+            let expected = match req.addr {
+                0x1000 => line(|i| i as f32),
+                _ => [0; LINE_BYTES],
+            };
+            assert_eq!(*req.data.unwrap(), expected);
+        }
+
+        // a write rejected by the full queue does not reach the data
+        let mut sent = 0;
+        while memory
+            .try_send(Request::with_data(0x3000, line(|_| sent as f32)))
+            .is_ok()
+        {
+            sent += 1;
+        }
+        assert_eq!(
+            memory.get_functional().read(0x3000),
+            line(|_| (sent - 1) as f32)
+        );
+    }
+
+    #[test]
+    fn test_pim_gemv() {
+        // the banks of a pair share a unit, the 8 units of 8 registers take 64 rows in a pass
+        let config = Config {
+            pim_banks_per_unit: 2,
+            ..Default::default()
+        };
+        let ddr4 = Arc::new(DDR4::new(&config));
+        let placement = Placement {
+            a: 0,
+            b: 1 << 24,
+            out: 1 << 25,
+        };
+        let runner = KernelRunner::new(&config, ddr4.clone(), placement);
+        let mut memory = SimpleMemory::with_config(&config, ddr4.clone()).unwrap();
+        let (rows, cols) = (100, 96);
+        let matrix = |i: u64, k: u64| ((i + k) % 5) as f32;
+        let x = |k: u64| (k % 3) as f32 - 1.0;
+        for j in 0..cols / 32 {
+            let data = line(|l| x(j * 32 + l as u64));
+            memory.get_functional().write(placement.b + j * 64, &data);
+            for i in 0..rows {
+                let addr = runner.gemv_matrix_addr(&memory, cols, i, j);
+                let data = line(|l| matrix(i, j * 32 + l as u64));
+                memory.get_functional().write(addr, &data);
+            }
+        }
+        runner.run(
+            &mut memory,
+            &Kernel::Gemv { rows, cols },
+            ExecutionMode::Pim,
+        );
+        // the lanes of the line of a row add up to its element of the host GEMV
+        for i in 0..rows {
+            let addr = runner.gemv_out_addr(&memory, i);
+            let lanes = to_lanes(&memory.get_functional().read(addr)).map(f16_to_f32);
+            let expected: f32 = (0..cols).map(|k| matrix(i, k) * x(k)).sum();
+            assert_eq!(lanes.iter().sum::<f32>(), expected, "row {i}");
+        }
+    }
+
+    #[test]
+    fn test_pim_kernels() {
+        let config = Config::default();
+        let ddr4 = Arc::new(DDR4::new(&config));
+        let placement = Placement {
+            a: 0,
+            b: 1 << 24,
+            out: 1 << 25,
+        };
+        let runner = KernelRunner::new(&config, ddr4.clone(), placement);
+        let mut memory = SimpleMemory::with_config(&config, ddr4.clone()).unwrap();
+        let columns = 4;
+        for c in 0..columns {
+            let a = line(|i| (c * 32 + i) as f32);
+            let b = line(|i| (i % 4) as f32);
+            memory
+                .try_send(Request::with_data(c as u64 * 64, a))
+                .unwrap();
+            memory
+                .try_send(Request::with_data(placement.b + c as u64 * 64, b))
+                .unwrap();
+        }
+        wait_all(&mut memory);
+        let read_out = |memory: &mut SimpleMemory<DDR4>, columns: u64| {
+            for c in 0..columns {
+                memory
+                    .try_send(Request::new(placement.out + c * 64, ReqType::Read))
+                    .unwrap();
+            }
+            let mut lines = wait_all(memory);
+            lines.sort_by_key(|req| req.addr);
+            lines
+                .into_iter()
+                .flat_map(|req| to_lanes(&req.data.unwrap()).map(f16_to_f32))
+                .collect::<Vec<_>>()
+        };
+
+        // c = a + b, the global buffer holds ones
+        let len = columns as u64 * 32;
+        runner.run(&mut memory, &Kernel::VectorAdd { len }, ExecutionMode::Pim);
+        let out = read_out(&mut memory, columns as u64);
+        for (i, value) in out.iter().enumerate() {
+            assert_eq!(*value, (i + i % 4) as f32);
+        }
+
+        // the partial sums of the registers add up to the dot product
+        runner.run(&mut memory, &Kernel::DotProduct { len }, ExecutionMode::Pim);
+        let sum: f32 = read_out(&mut memory, columns as u64).iter().sum();
+        let expected: usize = (0..len as usize).map(|i| i * (i % 4)).sum();
+        assert_eq!(sum, expected as f32);
+
+        // b = s * a with the first column of b broadcast as s
+        runner.run(
+            &mut memory,
+            &Kernel::VectorScale { len },
+            ExecutionMode::Pim,
+        );
+        let out = read_out(&mut memory, columns as u64);
+        for (i, value) in out.iter().enumerate() {
+            assert_eq!(*value, (i * (i % 4)) as f32);
+        }
+
+        // the scalar broadcast by VectorScale does not scale the next add
+        runner.run(&mut memory, &Kernel::VectorAdd { len }, ExecutionMode::Pim);
+        let out = read_out(&mut memory, columns as u64);
+        for (i, value) in out.iter().enumerate() {
+            assert_eq!(*value, (i + i % 4) as f32);
+        }
+    }
+
+    #[test]
+    fn test_pim_embedding_sum() {
+        let config = Config::default();
+        let ddr4 = Arc::new(DDR4::new(&config));
+        let placement = Placement {
+            a: 0,
+            b: 1 << 24,
+            out: 1 << 25,
+        };
+        let runner = KernelRunner::new(&config, ddr4.clone(), placement);
+        let mut memory = SimpleMemory::with_config(&config, ddr4.clone()).unwrap();
+        // the 130 columns of a row take the 128 slots of the 16 units in 2 passes
+        let (dim, rows) = (130 * 32, 6);
+        let table = |index: u64, k: u64| (index * 10 + k % 7) as f32;
+        for index in 0..rows {
+            for j in 0..dim / 32 {
+                let addr = runner.embedding_table_addr(&memory, dim, index, j);
+                let data = line(|l| table(index, j * 32 + l as u64));
+                memory.get_functional().write(addr, &data);
+            }
+        }
+        // a scalar left in the global buffer by an earlier kernel
+        memory.get_functional().write(placement.b, &line(|_| 3.0));
+        runner.run(
+            &mut memory,
+            &Kernel::VectorScale { len: 32 },
+            ExecutionMode::Pim,
+        );
+
+        let indices = vec![3, 5, 3];
+        runner.run(
+            &mut memory,
+            &Kernel::EmbeddingSum {
+                dim,
+                indices: indices.clone(),
+            },
+            ExecutionMode::Pim,
+        );
+        for j in 0..dim / 32 {
+            let addr = runner.embedding_out_addr(&memory, j);
+            let lanes = to_lanes(&memory.get_functional().read(addr)).map(f16_to_f32);
+            for (l, value) in lanes.iter().enumerate() {
+                let k = j * 32 + l as u64;
+                let expected: f32 = indices.iter().map(|&index| table(index, k)).sum();
+                assert_eq!(*value, expected, "element {k}");
+            }
+        }
+    }
+}
@@ -0,0 +1,1250 @@
+use std::str::FromStr;
+
+use crate::{
+    command::Command,
+    config::{Config, ConfigError},
+    dram::{self, log2, Dram, DramSpec, State, TimeEntry, TimingTable},
+    memory::Level,
+    request::ReqType,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[allow(non_camel_case_types)]
+pub enum GDDR5Org {
+    GDDR5_512Mb_x16,
+    GDDR5_512Mb_x32,
+    GDDR5_1Gb_x16,
+    GDDR5_1Gb_x32,
+    GDDR5_2Gb_x16,
+    GDDR5_2Gb_x32,
+    GDDR5_4Gb_x16,
+    GDDR5_4Gb_x32,
+    GDDR5_8Gb_x16,
+    GDDR5_8Gb_x32,
+}
+impl FromStr for GDDR5Org {
+    type Err = ();
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "GDDR5_512Mb_x16" => GDDR5Org::GDDR5_512Mb_x16,
+            "GDDR5_512Mb_x32" => GDDR5Org::GDDR5_512Mb_x32,
+            "GDDR5_1Gb_x16" => GDDR5Org::GDDR5_1Gb_x16,
+            "GDDR5_1Gb_x32" => GDDR5Org::GDDR5_1Gb_x32,
+            "GDDR5_2Gb_x16" => GDDR5Org::GDDR5_2Gb_x16,
+            "GDDR5_2Gb_x32" => GDDR5Org::GDDR5_2Gb_x32,
+            "GDDR5_4Gb_x16" => GDDR5Org::GDDR5_4Gb_x16,
+            "GDDR5_4Gb_x32" => GDDR5Org::GDDR5_4Gb_x32,
+            "GDDR5_8Gb_x16" => GDDR5Org::GDDR5_8Gb_x16,
+            "GDDR5_8Gb_x32" => GDDR5Org::GDDR5_8Gb_x32,
+            _ => return Err(()),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[allow(non_camel_case_types)]
+pub enum Speed {
+    GDDR5_4000,
+    GDDR5_4500,
+    GDDR5_5000,
+    GDDR5_5500,
+    GDDR5_6000,
+    GDDR5_6500,
+    GDDR5_7000,
+}
+impl FromStr for Speed {
+    type Err = ();
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "GDDR5_4000" => Speed::GDDR5_4000,
+            "GDDR5_4500" => Speed::GDDR5_4500,
+            "GDDR5_5000" => Speed::GDDR5_5000,
+            "GDDR5_5500" => Speed::GDDR5_5500,
+            "GDDR5_6000" => Speed::GDDR5_6000,
+            "GDDR5_6500" => Speed::GDDR5_6500,
+            "GDDR5_7000" => Speed::GDDR5_7000,
+            _ => return Err(()),
+        })
+    }
+}
+
+/// the density(in Mb) and data width of a chip, and the number of its bank groups, banks, rows
+/// and columns
+#[allow(dead_code)]
+struct OrgEntry {
+    size: usize,
+    dq: usize,
+    count: [usize; 4],
+}
+#[allow(non_snake_case, dead_code)]
+pub struct SpeedEntry {
+    rate: u64,
+    freq: f64,
+    tCK: f64,
+    nBL: u64,
+    nCCDS: u64,
+    nCCDL: u64,
+    nCL: u64,
+    nRCDR: u64,
+    nRCDW: u64,
+    nRP: u64,
+    nCWL: u64,
+    nRAS: u64,
+    nRC: u64,
+    nPPD: u64,
+    nRTP: u64,
+    nWTR: u64,
+    nWR: u64,
+    nRRD: u64,
+    nFAW: u64,
+    n32AW: u64,
+    nRFC: u64,
+    nREFI: u64,
+    nPD: u64,
+    nXPN: u64,
+    nLK: u64,
+    nCKESR: u64,
+    nXS: u64,
+    nXSDLL: u64,
+}
+
+const LEVELS: [Level; 6] = [
+    Level::Channel,
+    Level::Rank,
+    Level::BankGroup,
+    Level::Bank,
+    Level::Row,
+    Level::Column,
+];
+/// 8n prefetch QDR
+const PREFETCH_SIZE: u64 = 8;
+const CHANNEL_WIDTH: u64 = 64;
+pub struct GDDR5 {
+    child_size: Vec<usize>,
+    addr_bits: Vec<usize>,
+    speed_entry: SpeedEntry,
+    timing: TimingTable,
+    read_latency: u64,
+}
+impl GDDR5 {
+    pub fn new(config: &Config) -> Self {
+        assert_eq!(config.ranks, 1, "GDDR5 rank number is fixed to 1");
+        let org = Self::get_org(&config.gddr5_org);
+        let child_size = vec![
+            config.channels,
+            1,
+            org.count[0],
+            org.count[1],
+            org.count[2],
+            org.count[3],
+        ];
+        let addr_bits = child_size.iter().map(|x| log2(*x)).collect::<Vec<usize>>();
+        let mut speed_entry = Self::get_speed(&config.gddr5_speed);
+        Self::init_speed(&mut speed_entry, &org);
+        let mut timing = TimingTable::new(&LEVELS);
+        Self::init_timing(&mut timing, &speed_entry);
+        let read_latency = speed_entry.nCL + speed_entry.nBL;
+        Self {
+            child_size,
+            addr_bits,
+            speed_entry,
+            timing,
+            read_latency,
+        }
+    }
+    /// the column address of GDDR5 is unique for a burst, e.g. 64 column addresses correspond to
+    /// 512 columns actually, so 3 bits are added to the column address bits in JEDEC standard
+    fn get_org(org: &GDDR5Org) -> OrgEntry {
+        match org {
+            GDDR5Org::GDDR5_512Mb_x16 => OrgEntry {
+                size: 512,
+                dq: 16,
+                count: [4, 2, 1 << 12, 1 << (7 + 3)],
+            },
+            GDDR5Org::GDDR5_512Mb_x32 => OrgEntry {
+                size: 512,
+                dq: 32,
+                count: [4, 2, 1 << 12, 1 << (6 + 3)],
+            },
+            GDDR5Org::GDDR5_1Gb_x16 => OrgEntry {
+                size: 1 << 10,
+                dq: 16,
+                count: [4, 4, 1 << 12, 1 << (7 + 3)],
+            },
+            GDDR5Org::GDDR5_1Gb_x32 => OrgEntry {
+                size: 1 << 10,
+                dq: 32,
+                count: [4, 4, 1 << 12, 1 << (6 + 3)],
+            },
+            GDDR5Org::GDDR5_2Gb_x16 => OrgEntry {
+                size: 2 << 10,
+                dq: 16,
+                count: [4, 4, 1 << 13, 1 << (7 + 3)],
+            },
+            GDDR5Org::GDDR5_2Gb_x32 => OrgEntry {
+                size: 2 << 10,
+                dq: 32,
+                count: [4, 4, 1 << 13, 1 << (6 + 3)],
+            },
+            GDDR5Org::GDDR5_4Gb_x16 => OrgEntry {
+                size: 4 << 10,
+                dq: 16,
+                count: [4, 4, 1 << 14, 1 << (7 + 3)],
+            },
+            // ramulator lists the density of this one as 2Gb, a typo
+            GDDR5Org::GDDR5_4Gb_x32 => OrgEntry {
+                size: 4 << 10,
+                dq: 32,
+                count: [4, 4, 1 << 14, 1 << (6 + 3)],
+            },
+            GDDR5Org::GDDR5_8Gb_x16 => OrgEntry {
+                size: 8 << 10,
+                dq: 16,
+                count: [4, 4, 1 << 14, 1 << (8 + 3)],
+            },
+            GDDR5Org::GDDR5_8Gb_x32 => OrgEntry {
+                size: 8 << 10,
+                dq: 32,
+                count: [4, 4, 1 << 14, 1 << (7 + 3)],
+            },
+        }
+    }
+    /// fill the density and speed dependent timings, the nRFC values are borrowed from DDR3
+    fn init_speed(s: &mut SpeedEntry, org: &OrgEntry) {
+        const REFIL_TABLE: [u64; 7] = [3900, 4388, 4875, 5363, 5850, 6338, 6825];
+        const REFIS_TABLE: [u64; 7] = [1900, 2138, 2375, 2613, 2850, 3088, 3325];
+        const RFC_TABLE: [[u64; 7]; 5] = [
+            [90, 102, 113, 124, 135, 147, 158],
+            [110, 124, 138, 152, 165, 179, 193],
+            [160, 180, 200, 220, 240, 260, 280],
+            [260, 293, 325, 358, 390, 423, 455],
+            [350, 394, 438, 482, 525, 569, 613],
+        ];
+        let speed = match s.rate {
+            4000 => 0,
+            4500 => 1,
+            5000 => 2,
+            5500 => 3,
+            6000 => 4,
+            6500 => 5,
+            7000 => 6,
+            _ => unreachable!("invalid speed rate"),
+        };
+        let density = match org.size >> 9 {
+            1 => 0,
+            2 => 1,
+            4 => 2,
+            8 => 3,
+            16 => 4,
+            _ => unreachable!("invalid density"),
+        };
+        // the chips no larger than 1Gb have a longer refresh interval
+        s.nREFI = if org.size <= 1 << 10 {
+            REFIL_TABLE[speed]
+        } else {
+            REFIS_TABLE[speed]
+        };
+        s.nRFC = RFC_TABLE[density][speed];
+    }
+    fn init_timing(timing: &mut TimingTable, s: &SpeedEntry) {
+        /*** Channel ***/
+        let t = timing.get_level_mut(Level::Channel);
+
+        // CAS <-> CAS
+        t[Command::RD as usize].push(TimeEntry {
+            cmd: Command::RD,
+            dist: 1,
+            val: s.nBL,
+            sibling: false,
+        });
+        t[Command::RD as usize].push(TimeEntry {
+            cmd: Command::RDA,
+            dist: 1,
+            val: s.nBL,
+            sibling: false,
+        });
+        t[Command::RDA as usize].push(TimeEntry {
+            cmd: Command::RD,
+            dist: 1,
+            val: s.nBL,
+            sibling: false,
+        });
+        t[Command::RDA as usize].push(TimeEntry {
+            cmd: Command::RDA,
+            dist: 1,
+            val: s.nBL,
+            sibling: false,
+        });
+        t[Command::WR as usize].push(TimeEntry {
+            cmd: Command::WR,
+            dist: 1,
+            val: s.nBL,
+            sibling: false,
+        });
+        t[Command::WR as usize].push(TimeEntry {
+            cmd: Command::WRA,
+            dist: 1,
+            val: s.nBL,
+            sibling: false,
+        });
+        t[Command::WRA as usize].push(TimeEntry {
+            cmd: Command::WR,
+            dist: 1,
+            val: s.nBL,
+            sibling: false,
+        });
+        t[Command::WRA as usize].push(TimeEntry {
+            cmd: Command::WRA,
+            dist: 1,
+            val: s.nBL,
+            sibling: false,
+        });
+
+        /*** Rank ***/
+        let t = timing.get_level_mut(Level::Rank);
+
+        // CAS <-> CAS
+        t[Command::RD as usize].push(TimeEntry {
+            cmd: Command::RD,
+            dist: 1,
+            val: s.nCCDS,
+            sibling: false,
+        });
+        t[Command::RD as usize].push(TimeEntry {
+            cmd: Command::RDA,
+            dist: 1,
+            val: s.nCCDS,
+            sibling: false,
+        });
+        t[Command::RDA as usize].push(TimeEntry {
+            cmd: Command::RD,
+            dist: 1,
+            val: s.nCCDS,
+            sibling: false,
+        });
+        t[Command::RDA as usize].push(TimeEntry {
+            cmd: Command::RDA,
+            dist: 1,
+            val: s.nCCDS,
+            sibling: false,
+        });
+        t[Command::WR as usize].push(TimeEntry {
+            cmd: Command::WR,
+            dist: 1,
+            val: s.nCCDS,
+            sibling: false,
+        });
+        t[Command::WR as usize].push(TimeEntry {
+            cmd: Command::WRA,
+            dist: 1,
+            val: s.nCCDS,
+            sibling: false,
+        });
+        t[Command::WRA as usize].push(TimeEntry {
+            cmd: Command::WR,
+            dist: 1,
+            val: s.nCCDS,
+            sibling: false,
+        });
+        t[Command::WRA as usize].push(TimeEntry {
+            cmd: Command::WRA,
+            dist: 1,
+            val: s.nCCDS,
+            sibling: false,
+        });
+        t[Command::RD as usize].push(TimeEntry {
+            cmd: Command::WR,
+            dist: 1,
+            val: s.nCL + s.nCCDS + 2 - s.nCWL,
+            sibling: false,
+        });
+        t[Command::RD as usize].push(TimeEntry {
+            cmd: Command::WRA,
+            dist: 1,
+            val: s.nCL + s.nCCDS + 2 - s.nCWL,
+            sibling: false,
+        });
+        t[Command::RDA as usize].push(TimeEntry {
+            cmd: Command::WR,
+            dist: 1,
+            val: s.nCL + s.nCCDS + 2 - s.nCWL,
+            sibling: false,
+        });
+        t[Command::RDA as usize].push(TimeEntry {
+            cmd: Command::WRA,
+            dist: 1,
+            val: s.nCL + s.nCCDS + 2 - s.nCWL,
+            sibling: false,
+        });
+        t[Command::WR as usize].push(TimeEntry {
+            cmd: Command::RD,
+            dist: 1,
+            val: s.nCWL + s.nBL + s.nWTR,
+            sibling: false,
+        });
+        t[Command::WR as usize].push(TimeEntry {
+            cmd: Command::RDA,
+            dist: 1,
+            val: s.nCWL + s.nBL + s.nWTR,
+            sibling: false,
+        });
+        t[Command::WRA as usize].push(TimeEntry {
+            cmd: Command::RD,
+            dist: 1,
+            val: s.nCWL + s.nBL + s.nWTR,
+            sibling: false,
+        });
+        t[Command::WRA as usize].push(TimeEntry {
+            cmd: Command::RDA,
+            dist: 1,
+            val: s.nCWL + s.nBL + s.nWTR,
+            sibling: false,
+        });
+        t[Command::RD as usize].push(TimeEntry {
+            cmd: Command::PREA,
+            dist: 1,
+            val: s.nRTP,
+            sibling: false,
+        });
+        t[Command::WR as usize].push(TimeEntry {
+            cmd: Command::PREA,
+            dist: 1,
+            val: s.nCWL + s.nBL + s.nWR,
+            sibling: false,
+        });
+
+        // CAS <-> PD
+        t[Command::RD as usize].push(TimeEntry {
+            cmd: Command::PDE,
+            dist: 1,
+            val: s.nCL + s.nBL + 1,
+            sibling: false,
+        });
+        t[Command::RDA as usize].push(TimeEntry {
+            cmd: Command::PDE,
+            dist: 1,
+            val: s.nCL + s.nBL + 1,
+            sibling: false,
+        });
+        t[Command::WR as usize].push(TimeEntry {
+            cmd: Command::PDE,
+            dist: 1,
+            val: s.nCWL + s.nBL + s.nWR,
+            sibling: false,
+        });
+        t[Command::WRA as usize].push(TimeEntry {
+            cmd: Command::PDE,
+            dist: 1,
+            val: s.nCWL + s.nBL + s.nWR + 1,
+            sibling: false,
+        }); // +1 for pre
+        t[Command::PDX as usize].push(TimeEntry {
+            cmd: Command::RD,
+            dist: 1,
+            val: s.nXPN,
+            sibling: false,
+        });
+        t[Command::PDX as usize].push(TimeEntry {
+            cmd: Command::RDA,
+            dist: 1,
+            val: s.nXPN,
+            sibling: false,
+        });
+        t[Command::PDX as usize].push(TimeEntry {
+            cmd: Command::WR,
+            dist: 1,
+            val: s.nXPN,
+            sibling: false,
+        });
+        t[Command::PDX as usize].push(TimeEntry {
+            cmd: Command::WRA,
+            dist: 1,
+            val: s.nXPN,
+            sibling: false,
+        });
+
+        // CAS <-> SR: none (all banks have to be precharged)
+
+        // RAS <-> RAS
+        t[Command::ACT as usize].push(TimeEntry {
+            cmd: Command::ACT,
+            dist: 1,
+            val: s.nRRD,
+            sibling: false,
+        });
+        t[Command::ACT as usize].push(TimeEntry {
+            cmd: Command::ACT,
+            dist: 4,
+            val: s.nFAW,
+            sibling: false,
+        });
+        t[Command::ACT as usize].push(TimeEntry {
+            cmd: Command::ACT,
+            dist: 32,
+            val: s.n32AW,
+            sibling: false,
+        });
+        t[Command::ACT as usize].push(TimeEntry {
+            cmd: Command::PREA,
+            dist: 1,
+            val: s.nRAS,
+            sibling: false,
+        });
+        t[Command::PREA as usize].push(TimeEntry {
+            cmd: Command::ACT,
+            dist: 1,
+            val: s.nRP,
+            sibling: false,
+        });
+        t[Command::PRE as usize].push(TimeEntry {
+            cmd: Command::PRE,
+            dist: 1,
+            val: s.nPPD,
+            sibling: false,
+        });
+
+        // RAS <-> REF
+        t[Command::PRE as usize].push(TimeEntry {
+            cmd: Command::REF,
+            dist: 1,
+            val: s.nRP,
+            sibling: false,
+        });
+        t[Command::PREA as usize].push(TimeEntry {
+            cmd: Command::REF,
+            dist: 1,
+            val: s.nRP,
+            sibling: false,
+        });
+        t[Command::REF as usize].push(TimeEntry {
+            cmd: Command::ACT,
+            dist: 1,
+            val: s.nRFC,
+            sibling: false,
+        });
+
+        // RAS <-> PD
+        t[Command::ACT as usize].push(TimeEntry {
+            cmd: Command::PDE,
+            dist: 1,
+            val: 1,
+            sibling: false,
+        });
+        t[Command::PDX as usize].push(TimeEntry {
+            cmd: Command::ACT,
+            dist: 1,
+            val: s.nXPN,
+            sibling: false,
+        });
+        t[Command::PDX as usize].push(TimeEntry {
+            cmd: Command::PRE,
+            dist: 1,
+            val: s.nXPN,
+            sibling: false,
+        });
+        t[Command::PDX as usize].push(TimeEntry {
+            cmd: Command::PREA,
+            dist: 1,
+            val: s.nXPN,
+            sibling: false,
+        });
+
+        // RAS <-> SR
+        t[Command::PRE as usize].push(TimeEntry {
+            cmd: Command::SRE,
+            dist: 1,
+            val: s.nRP,
+            sibling: false,
+        });
+        t[Command::PREA as usize].push(TimeEntry {
+            cmd: Command::SRE,
+            dist: 1,
+            val: s.nRP,
+            sibling: false,
+        });
+        t[Command::SRX as usize].push(TimeEntry {
+            cmd: Command::ACT,
+            dist: 1,
+            val: s.nXS,
+            sibling: false,
+        });
+
+        // REF <-> REF
+        t[Command::REF as usize].push(TimeEntry {
+            cmd: Command::REF,
+            dist: 1,
+            val: s.nRFC,
+            sibling: false,
+        });
+
+        // REF <-> PD
+        t[Command::REF as usize].push(TimeEntry {
+            cmd: Command::PDE,
+            dist: 1,
+            val: 1,
+            sibling: false,
+        });
+        t[Command::PDX as usize].push(TimeEntry {
+            cmd: Command::REF,
+            dist: 1,
+            val: s.nXPN,
+            sibling: false,
+        });
+
+        // REF <-> SR
+        t[Command::SRX as usize].push(TimeEntry {
+            cmd: Command::REF,
+            dist: 1,
+            val: s.nXS,
+            sibling: false,
+        });
+
+        // PD <-> PD
+        t[Command::PDE as usize].push(TimeEntry {
+            cmd: Command::PDX,
+            dist: 1,
+            val: s.nPD,
+            sibling: false,
+        });
+        t[Command::PDX as usize].push(TimeEntry {
+            cmd: Command::PDE,
+            dist: 1,
+            val: s.nXPN,
+            sibling: false,
+        });
+
+        // PD <-> SR
+        t[Command::PDX as usize].push(TimeEntry {
+            cmd: Command::SRE,
+            dist: 1,
+            val: s.nXPN,
+            sibling: false,
+        });
+        t[Command::SRX as usize].push(TimeEntry {
+            cmd: Command::PDE,
+            dist: 1,
+            val: s.nXS,
+            sibling: false,
+        });
+
+        // SR <-> SR
+        t[Command::SRE as usize].push(TimeEntry {
+            cmd: Command::SRX,
+            dist: 1,
+            val: s.nCKESR,
+            sibling: false,
+        });
+        t[Command::SRX as usize].push(TimeEntry {
+            cmd: Command::SRE,
+            dist: 1,
+            val: s.nXS,
+            sibling: false,
+        });
+
+        /*** Bank Group ***/
+        let t = timing.get_level_mut(Level::BankGroup);
+
+        // CAS <-> CAS
+        t[Command::RD as usize].push(TimeEntry {
+            cmd: Command::RD,
+            dist: 1,
+            val: s.nCCDL,
+            sibling: false,
+        });
+        t[Command::RD as usize].push(TimeEntry {
+            cmd: Command::RDA,
+            dist: 1,
+            val: s.nCCDL,
+            sibling: false,
+        });
+        t[Command::RDA as usize].push(TimeEntry {
+            cmd: Command::RD,
+            dist: 1,
+            val: s.nCCDL,
+            sibling: false,
+        });
+        t[Command::RDA as usize].push(TimeEntry {
+            cmd: Command::RDA,
+            dist: 1,
+            val: s.nCCDL,
+            sibling: false,
+        });
+        t[Command::WR as usize].push(TimeEntry {
+            cmd: Command::WR,
+            dist: 1,
+            val: s.nCCDL,
+            sibling: false,
+        });
+        t[Command::WR as usize].push(TimeEntry {
+            cmd: Command::WRA,
+            dist: 1,
+            val: s.nCCDL,
+            sibling: false,
+        });
+        t[Command::WRA as usize].push(TimeEntry {
+            cmd: Command::WR,
+            dist: 1,
+            val: s.nCCDL,
+            sibling: false,
+        });
+        t[Command::WRA as usize].push(TimeEntry {
+            cmd: Command::WRA,
+            dist: 1,
+            val: s.nCCDL,
+            sibling: false,
+        });
+
+        /*** Bank ***/
+        let t = timing.get_level_mut(Level::Bank);
+
+        // CAS <-> RAS
+        t[Command::ACT as usize].push(TimeEntry {
+            cmd: Command::RD,
+            dist: 1,
+            val: s.nRCDR,
+            sibling: false,
+        });
+        t[Command::ACT as usize].push(TimeEntry {
+            cmd: Command::RDA,
+            dist: 1,
+            val: s.nRCDR,
+            sibling: false,
+        });
+        t[Command::ACT as usize].push(TimeEntry {
+            cmd: Command::WR,
+            dist: 1,
+            val: s.nRCDW,
+            sibling: false,
+        });
+        t[Command::ACT as usize].push(TimeEntry {
+            cmd: Command::WRA,
+            dist: 1,
+            val: s.nRCDW,
+            sibling: false,
+        });
+        t[Command::RD as usize].push(TimeEntry {
+            cmd: Command::PRE,
+            dist: 1,
+            val: s.nRTP,
+            sibling: false,
+        });
+        t[Command::WR as usize].push(TimeEntry {
+            cmd: Command::PRE,
+            dist: 1,
+            val: s.nCWL + s.nBL + s.nWR,
+            sibling: false,
+        });
+        t[Command::RDA as usize].push(TimeEntry {
+            cmd: Command::ACT,
+            dist: 1,
+            val: s.nRTP + s.nRP,
+            sibling: false,
+        });
+        t[Command::WRA as usize].push(TimeEntry {
+            cmd: Command::ACT,
+            dist: 1,
+            val: s.nCWL + s.nBL + s.nWR + s.nRP,
+            sibling: false,
+        });
+
+        // RAS <-> RAS
+        t[Command::ACT as usize].push(TimeEntry {
+            cmd: Command::ACT,
+            dist: 1,
+            val: s.nRC,
+            sibling: false,
+        });
+        t[Command::ACT as usize].push(TimeEntry {
+            cmd: Command::PRE,
+            dist: 1,
+            val: s.nRAS,
+            sibling: false,
+        });
+        t[Command::PRE as usize].push(TimeEntry {
+            cmd: Command::ACT,
+            dist: 1,
+            val: s.nRP,
+            sibling: false,
+        });
+    }
+
+    pub fn get_speed(speed: &Speed) -> SpeedEntry {
+        match speed {
+            Speed::GDDR5_4000 => SpeedEntry {
+                rate: 4000,
+                freq: 8.0 * 500.0 / 4.0,
+                tCK: 1.0,
+                nBL: 2,
+                nCCDS: 2,
+                nCCDL: 3,
+                nCL: 12,
+                nRCDR: 12,
+                nRCDW: 10,
+                nRP: 12,
+                nCWL: 3,
+                nRAS: 28,
+                nRC: 40,
+                nPPD: 1,
+                nRTP: 2,
+                nWTR: 5,
+                nWR: 12,
+                nRRD: 6,
+                nFAW: 23,
+                n32AW: 184,
+                nRFC: 0,
+                nREFI: 0,
+                nPD: 10,
+                nXPN: 10,
+                nLK: 0,
+                nCKESR: 0,
+                nXS: 0,
+                nXSDLL: 0,
+            },
+            Speed::GDDR5_4500 => SpeedEntry {
+                rate: 4500,
+                freq: 9.0 * 500.0 / 4.0,
+                tCK: 8.0 / 9.0,
+                nBL: 2,
+                nCCDS: 2,
+                nCCDL: 3,
+                nCL: 14,
+                nRCDR: 14,
+                nRCDW: 12,
+                nRP: 14,
+                nCWL: 4,
+                nRAS: 32,
+                nRC: 46,
+                nPPD: 2,
+                nRTP: 2,
+                nWTR: 6,
+                nWR: 14,
+                nRRD: 7,
+                nFAW: 26,
+                n32AW: 207,
+                nRFC: 0,
+                nREFI: 0,
+                nPD: 10,
+                nXPN: 10,
+                nLK: 0,
+                nCKESR: 0,
+                nXS: 0,
+                nXSDLL: 0,
+            },
+            Speed::GDDR5_5000 => SpeedEntry {
+                rate: 5000,
+                freq: 10.0 * 500.0 / 4.0,
+                tCK: 8.0 / 10.0,
+                nBL: 2,
+                nCCDS: 2,
+                nCCDL: 3,
+                nCL: 15,
+                nRCDR: 15,
+                nRCDW: 13,
+                nRP: 15,
+                nCWL: 4,
+                nRAS: 35,
+                nRC: 50,
+                nPPD: 2,
+                nRTP: 2,
+                nWTR: 7,
+                nWR: 15,
+                nRRD: 7,
+                nFAW: 29,
+                n32AW: 230,
+                nRFC: 0,
+                nREFI: 0,
+                nPD: 10,
+                nXPN: 10,
+                nLK: 0,
+                nCKESR: 0,
+                nXS: 0,
+                nXSDLL: 0,
+            },
+            Speed::GDDR5_5500 => SpeedEntry {
+                rate: 5500,
+                freq: 11.0 * 500.0 / 4.0,
+                tCK: 8.0 / 11.0,
+                nBL: 2,
+                nCCDS: 2,
+                nCCDL: 3,
+                nCL: 17,
+                nRCDR: 17,
+                nRCDW: 14,
+                nRP: 17,
+                nCWL: 5,
+                nRAS: 39,
+                nRC: 56,
+                nPPD: 2,
+                nRTP: 2,
+                nWTR: 7,
+                nWR: 17,
+                nRRD: 8,
+                nFAW: 32,
+                n32AW: 253,
+                nRFC: 0,
+                nREFI: 0,
+                nPD: 10,
+                nXPN: 10,
+                nLK: 0,
+                nCKESR: 0,
+                nXS: 0,
+                nXSDLL: 0,
+            },
+            Speed::GDDR5_6000 => SpeedEntry {
+                rate: 6000,
+                freq: 12.0 * 500.0 / 4.0,
+                tCK: 8.0 / 12.0,
+                nBL: 2,
+                nCCDS: 2,
+                nCCDL: 3,
+                nCL: 18,
+                nRCDR: 18,
+                nRCDW: 15,
+                nRP: 18,
+                nCWL: 5,
+                nRAS: 42,
+                nRC: 60,
+                nPPD: 2,
+                nRTP: 2,
+                nWTR: 8,
+                nWR: 18,
+                nRRD: 9,
+                nFAW: 35,
+                n32AW: 276,
+                nRFC: 0,
+                nREFI: 0,
+                nPD: 10,
+                nXPN: 10,
+                nLK: 0,
+                nCKESR: 0,
+                nXS: 0,
+                nXSDLL: 0,
+            },
+            Speed::GDDR5_6500 => SpeedEntry {
+                rate: 6500,
+                freq: 13.0 * 500.0 / 4.0,
+                tCK: 8.0 / 13.0,
+                nBL: 2,
+                nCCDS: 2,
+                nCCDL: 3,
+                nCL: 20,
+                nRCDR: 20,
+                nRCDW: 17,
+                nRP: 20,
+                nCWL: 5,
+                nRAS: 46,
+                nRC: 66,
+                nPPD: 2,
+                nRTP: 2,
+                nWTR: 9,
+                nWR: 20,
+                nRRD: 9,
+                nFAW: 38,
+                n32AW: 299,
+                nRFC: 0,
+                nREFI: 0,
+                nPD: 10,
+                nXPN: 10,
+                nLK: 0,
+                nCKESR: 0,
+                nXS: 0,
+                nXSDLL: 0,
+            },
+            Speed::GDDR5_7000 => SpeedEntry {
+                rate: 7000,
+                freq: 14.0 * 500.0 / 4.0,
+                tCK: 8.0 / 14.0,
+                nBL: 2,
+                nCCDS: 2,
+                nCCDL: 3,
+                nCL: 21,
+                nRCDR: 21,
+                nRCDW: 18,
+                nRP: 21,
+                nCWL: 6,
+                nRAS: 49,
+                nRC: 70,
+                nPPD: 2,
+                nRTP: 2,
+                nWTR: 9,
+                nWR: 21,
+                nRRD: 10,
+                nFAW: 41,
+                n32AW: 322,
+                nRFC: 0,
+                nREFI: 0,
+                nPD: 10,
+                nXPN: 10,
+                nLK: 0,
+                nCKESR: 0,
+                nXS: 0,
+                nXSDLL: 0,
+            },
+        }
+    }
+}
+impl DramSpec for GDDR5 {
+    fn from_config(config: &Config) -> Result<Self, ConfigError> {
+        if config.ranks != 1 {
+            return Err(ConfigError::Unsupported {
+                key: "ranks".to_string(),
+                reason: format!("GDDR5 has a single rank, found {}", config.ranks),
+            });
+        }
+        Ok(Self::new(config))
+    }
+    fn supports_per_bank_refresh() -> bool {
+        false
+    }
+    fn supports_pim() -> bool {
+        false
+    }
+    fn get_first_cmd(req_type: &ReqType) -> Command {
+        match req_type {
+            ReqType::Read => Command::RD,
+            ReqType::Write => Command::WR,
+            ReqType::Refresh => Command::REF,
+            ReqType::RefreshBank => unreachable!("GDDR5 has no per-bank refresh"),
+            ReqType::PimMac
+            | ReqType::PimMacAllBank
+            | ReqType::PimLoad
+            | ReqType::PimStore
+            | ReqType::PimBroadcast => {
+                unreachable!("GDDR5 has no PIM commands")
+            }
+        }
+    }
+
+    fn get_pre_cmd(dram: &Dram<Self>, cmd: &Command, child_id: u64) -> Option<Command> {
+        match (&dram.level, cmd) {
+            (Level::Rank, Command::RD) | (Level::Rank, Command::WR) => match dram.state {
+                dram::State::PowerUp => None,
+                dram::State::ActPowerDown => Some(Command::PDX),
+                dram::State::PrePowerDown => Some(Command::PDX),
+                dram::State::SelfRefresh => Some(Command::SRX),
+                _ => unreachable!("invalid dram state"),
+            },
+            (Level::Rank, Command::REF) => {
+                if dram.children.iter().any(|bank_group| {
+                    bank_group
+                        .children
+                        .iter()
+                        .any(|bank| !matches!(bank.state, dram::State::Closed))
+                }) {
+                    Some(Command::PREA)
+                } else {
+                    Some(Command::REF)
+                }
+            }
+            (Level::Rank, Command::PDE) => match dram.state {
+                dram::State::PowerUp | dram::State::ActPowerDown | dram::State::PrePowerDown => {
+                    Some(Command::PDE)
+                }
+                dram::State::SelfRefresh => Some(Command::SRX),
+                _ => unreachable!("invalid dram state"),
+            },
+            (Level::Rank, Command::SRE) => match dram.state {
+                dram::State::PowerUp | dram::State::SelfRefresh => Some(Command::SRE),
+                dram::State::ActPowerDown | dram::State::PrePowerDown => Some(Command::PDX),
+                _ => unreachable!("invalid dram state"),
+            },
+
+            (Level::Bank, Command::RD) | (Level::Bank, Command::WR) => match dram.state {
+                dram::State::Closed => Some(Command::ACT),
+                dram::State::Opened(row_id) => {
+                    if row_id == child_id {
+                        Some(*cmd)
+                    } else {
+                        Some(Command::PRE)
+                    }
+                }
+                _ => unreachable!("invalid dram state"),
+            },
+            _ => None,
+        }
+    }
+
+    fn get_start_state(level: &Level) -> dram::State {
+        match level {
+            Level::Rank => dram::State::PowerUp,
+            Level::Bank => dram::State::Closed,
+            Level::Row => dram::State::Closed,
+            _ => dram::State::NoUse,
+        }
+    }
+
+    fn get_levels(&self) -> &[Level] {
+        &LEVELS
+    }
+
+    fn get_addr_bits(&self) -> &[usize] {
+        &self.addr_bits
+    }
+
+    fn get_child_size(&self) -> &[usize] {
+        &self.child_size
+    }
+
+    fn get_scope(&self, cmd: &Command) -> Level {
+        match cmd {
+            Command::ACT | Command::ACTAB => Level::Row,
+            Command::PRE | Command::PREAB | Command::REFSB | Command::REFPB => Level::Bank,
+            Command::RD
+            | Command::WR
+            | Command::RDA
+            | Command::WRA
+            | Command::MAC
+            | Command::LDR
+            | Command::STR
+            | Command::MACAB => Level::Column,
+            Command::PREA
+            | Command::REF
+            | Command::PDE
+            | Command::PDX
+            | Command::SRE
+            | Command::SRX
+            | Command::WRGB => Level::Rank,
+            Command::Max => unreachable!(),
+        }
+    }
+
+    fn update_state(&self, dram: &mut Dram<Self>, cmd: &Command, child_id: u64) {
+        match (dram.level, cmd) {
+            (Level::Bank, Command::ACT) => {
+                dram.state = dram::State::Opened(child_id);
+            }
+            (Level::Bank, Command::PRE | Command::RDA | Command::WRA) => {
+                dram.state = dram::State::Closed;
+            }
+            (Level::Rank, Command::PREA) => {
+                dram.children.iter_mut().for_each(|bank_group| {
+                    bank_group.children.iter_mut().for_each(|bank| {
+                        bank.state = dram::State::Closed;
+                    })
+                });
+            }
+            (Level::Rank, Command::PDE) => {
+                let banks_closed = dram.children.iter().all(|bank_group| {
+                    bank_group
+                        .children
+                        .iter()
+                        .all(|bank| bank.state == State::Closed)
+                });
+                dram.state = if banks_closed {
+                    State::PrePowerDown
+                } else {
+                    State::ActPowerDown
+                };
+            }
+            (Level::Rank, Command::PDX | Command::SRX) => {
+                dram.state = State::PowerUp;
+            }
+            (Level::Rank, Command::SRE) => {
+                dram.state = State::SelfRefresh;
+            }
+            _ => {}
+        }
+    }
+    fn get_timming(&self, depth: usize, cmd: &Command) -> &[TimeEntry] {
+        self.timing.get(depth, cmd)
+    }
+
+    fn get_read_latency(&self) -> u64 {
+        self.read_latency
+    }
+
+    fn get_refresh_interval(&self) -> u64 {
+        self.speed_entry.nREFI
+    }
+
+    fn get_clk_ns(&self) -> f64 {
+        self.speed_entry.tCK
+    }
+
+    fn get_transaction_bytes(&self) -> u64 {
+        PREFETCH_SIZE * CHANNEL_WIDTH / 8
+    }
+
+    fn get_burst_cycles(&self) -> u64 {
+        self.speed_entry.nBL
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use super::{GDDR5Org, Speed, GDDR5};
+    use crate::{
+        command::Command,
+        config::{Config, Standard},
+        controller::Controller,
+        dram::{Dram, DramSpec},
+        memory::Level,
+        request::{ReqType, Request},
+    };
+
+    fn get_timing(
+        gddr5: &Arc<GDDR5>,
+        level: Level,
+        from: Command,
+        to: Command,
+        dist: usize,
+    ) -> u64 {
+        gddr5
+            .get_timming(gddr5.get_level_index(level), &from)
+            .iter()
+            .find(|t| t.cmd == to && t.dist == dist)
+            .unwrap()
+            .val
+    }
+
+    #[test]
+    fn test_gddr5() {
+        let config =
+            Config::parse(include_str!("../../ramulator/configs/GDDR5-config.cfg")).unwrap();
+        assert_eq!(config.standard, Standard::GDDR5);
+        assert_eq!(config.gddr5_org, GDDR5Org::GDDR5_8Gb_x16);
+        assert_eq!(config.gddr5_speed, Speed::GDDR5_6000);
+        let gddr5 = Arc::new(GDDR5::new(&config));
+        assert_eq!(gddr5.get_child_size(), [1, 1, 4, 4, 1 << 14, 1 << 11]);
+        assert_eq!(gddr5.get_read_latency(), 18 + 2);
+        assert_eq!(gddr5.get_refresh_interval(), 2850);
+        assert_eq!(gddr5.get_transaction_bytes(), 64);
+        let t = |level, from, to, dist| get_timing(&gddr5, level, from, to, dist);
+        assert_eq!(t(Level::Rank, Command::REF, Command::ACT, 1), 525);
+        assert_eq!(t(Level::Rank, Command::ACT, Command::ACT, 4), 35);
+        assert_eq!(t(Level::Rank, Command::ACT, Command::ACT, 32), 276);
+        assert_eq!(t(Level::Bank, Command::ACT, Command::RD, 1), 18);
+        assert_eq!(t(Level::Bank, Command::ACT, Command::WR, 1), 15);
+
+        // a row hit, then a row conflict in the same bank
+        let channel = Dram::new(gddr5.clone(), Level::Channel, gddr5.get_child_size());
+        let mut controller = Controller::new(&config, channel);
+        for row in [0, 0, 1] {
+            let req = Request::with_addr_vec(vec![0, 0, 1, 2, row, 0], ReqType::Read);
+            controller.try_enqueue(req).unwrap();
+        }
+        let mut finished = 0;
+        let mut clk = 0;
+        while finished < 3 {
+            clk += 1;
+            controller.tick(clk);
+            finished += controller.finished_queue.drain(..).count();
+        }
+        assert_eq!(controller.stats.row_hits, 1);
+        assert_eq!(controller.stats.row_conflicts, 1);
+        let bank = &controller.channel.children[0].children[1].children[2];
+        assert_eq!(bank.cmd_counts[Command::ACT as usize], 2);
+    }
+
+    #[test]
+    fn test_org() {
+        let config = Config::parse("standard = GDDR5\norg = GDDR5_512Mb_x32\nspeed = GDDR5_4000");
+        let gddr5 = Arc::new(GDDR5::new(&config.unwrap()));
+        assert_eq!(gddr5.get_child_size(), [1, 1, 4, 2, 1 << 12, 1 << 9]);
+        // the small chips have a longer refresh interval
+        assert_eq!(gddr5.get_refresh_interval(), 3900);
+        assert_eq!(gddr5.get_clk_ns(), 1.0);
+    }
+}
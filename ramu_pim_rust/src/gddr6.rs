@@ -0,0 +1,1285 @@
+use std::str::FromStr;
+
+use crate::{
+    command::Command,
+    config::{Config, ConfigError},
+    dram::{self, log2, Dram, DramSpec, State, TimeEntry, TimingTable},
+    memory::Level,
+    request::ReqType,
+};
+
+/// the density is per device, which has 2 independent x16 channels
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[allow(non_camel_case_types)]
+pub enum GDDR6Org {
+    GDDR6_8Gb,
+    GDDR6_16Gb,
+}
+impl FromStr for GDDR6Org {
+    type Err = ();
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "GDDR6_8Gb" => GDDR6Org::GDDR6_8Gb,
+            "GDDR6_16Gb" => GDDR6Org::GDDR6_16Gb,
+            _ => return Err(()),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[allow(non_camel_case_types)]
+pub enum Speed {
+    GDDR6_12000,
+    GDDR6_14000,
+    GDDR6_16000,
+}
+impl FromStr for Speed {
+    type Err = ();
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "GDDR6_12000" => Speed::GDDR6_12000,
+            "GDDR6_14000" => Speed::GDDR6_14000,
+            "GDDR6_16000" => Speed::GDDR6_16000,
+            _ => return Err(()),
+        })
+    }
+}
+
+/// the density(in Mb) of a channel, and the number of its bank groups, banks, rows and columns
+struct OrgEntry {
+    size: usize,
+    count: [usize; 4],
+}
+/// the data rate is 8 times of CK (WCK is 4 times of CK and DDR), a BL16 burst takes 2 cycles.
+/// the latencies specified in ns are rounded up to the tCK of each speed
+#[allow(non_snake_case, dead_code)]
+pub struct SpeedEntry {
+    rate: u64,
+    freq: f64,
+    tCK: f64,
+    nBL: u64,
+    nCCDS: u64,
+    nCCDL: u64,
+    nCL: u64,
+    nRCDR: u64,
+    nRCDW: u64,
+    nRP: u64,
+    nCWL: u64,
+    nRAS: u64,
+    nRC: u64,
+    nPPD: u64,
+    nRTP: u64,
+    nWTRS: u64,
+    nWTRL: u64,
+    nWR: u64,
+    nRRDS: u64,
+    nRRDL: u64,
+    nFAW: u64,
+    nRFC: u64,
+    nREFI: u64,
+    nPD: u64,
+    nXPN: u64,
+    nCKESR: u64,
+    nXS: u64,
+}
+
+const LEVELS: [Level; 6] = [
+    Level::Channel,
+    Level::Rank,
+    Level::BankGroup,
+    Level::Bank,
+    Level::Row,
+    Level::Column,
+];
+/// 16n prefetch
+const PREFETCH_SIZE: u64 = 16;
+/// every device has 2 independent channels
+const DEVICE_CHANNELS: usize = 2;
+const CHANNEL_WIDTH: u64 = 16;
+pub struct GDDR6 {
+    child_size: Vec<usize>,
+    addr_bits: Vec<usize>,
+    speed_entry: SpeedEntry,
+    timing: TimingTable,
+    read_latency: u64,
+}
+impl GDDR6 {
+    pub fn new(config: &Config) -> Self {
+        assert_eq!(config.ranks, 1, "GDDR6 rank number is fixed to 1");
+        let org = Self::get_org(&config.gddr6_org);
+        let child_size = vec![
+            config.channels * DEVICE_CHANNELS,
+            1,
+            org.count[0],
+            org.count[1],
+            org.count[2],
+            org.count[3],
+        ];
+        let addr_bits = child_size.iter().map(|x| log2(*x)).collect::<Vec<usize>>();
+        let mut speed_entry = Self::get_speed(&config.gddr6_speed);
+        Self::init_speed(&mut speed_entry, &org);
+        let mut timing = TimingTable::new(&LEVELS);
+        Self::init_timing(&mut timing, &speed_entry);
+        dram::add_all_bank_timing(&mut timing);
+        let read_latency = speed_entry.nCL + speed_entry.nBL;
+        Self {
+            child_size,
+            addr_bits,
+            speed_entry,
+            timing,
+            read_latency,
+        }
+    }
+    /// like GDDR5, the columns are counted in the unit of the channel width, 4 bits are added to
+    /// the column address bits of a 16n burst
+    fn get_org(org: &GDDR6Org) -> OrgEntry {
+        match org {
+            GDDR6Org::GDDR6_8Gb => OrgEntry {
+                size: 4 << 10,
+                count: [4, 4, 1 << 14, 1 << (6 + 4)],
+            },
+            GDDR6Org::GDDR6_16Gb => OrgEntry {
+                size: 8 << 10,
+                count: [4, 4, 1 << 15, 1 << (6 + 4)],
+            },
+        }
+    }
+    /// fill the density dependent timings
+    fn init_speed(s: &mut SpeedEntry, org: &OrgEntry) {
+        /// tRFCab of a channel in ns
+        const RFC_TABLE: [f64; 2] = [220.0, 280.0];
+        /// tREFI in ns
+        const REFI: f64 = 1900.0;
+        let ck = |ns: f64| (ns / s.tCK - 0.01).ceil() as u64;
+        let density = match org.size >> 10 {
+            4 => 0,
+            8 => 1,
+            _ => unreachable!("invalid density"),
+        };
+        s.nRFC = ck(RFC_TABLE[density]);
+        s.nREFI = ck(REFI);
+        s.nXS = s.nRFC + ck(10.0);
+    }
+    fn init_timing(timing: &mut TimingTable, s: &SpeedEntry) {
+        /*** Channel ***/
+        let t = timing.get_level_mut(Level::Channel);
+
+        // CAS <-> CAS
+        t[Command::RD as usize].push(TimeEntry {
+            cmd: Command::RD,
+            dist: 1,
+            val: s.nBL,
+            sibling: false,
+        });
+        t[Command::RD as usize].push(TimeEntry {
+            cmd: Command::RDA,
+            dist: 1,
+            val: s.nBL,
+            sibling: false,
+        });
+        t[Command::RDA as usize].push(TimeEntry {
+            cmd: Command::RD,
+            dist: 1,
+            val: s.nBL,
+            sibling: false,
+        });
+        t[Command::RDA as usize].push(TimeEntry {
+            cmd: Command::RDA,
+            dist: 1,
+            val: s.nBL,
+            sibling: false,
+        });
+        t[Command::WR as usize].push(TimeEntry {
+            cmd: Command::WR,
+            dist: 1,
+            val: s.nBL,
+            sibling: false,
+        });
+        t[Command::WR as usize].push(TimeEntry {
+            cmd: Command::WRA,
+            dist: 1,
+            val: s.nBL,
+            sibling: false,
+        });
+        t[Command::WRA as usize].push(TimeEntry {
+            cmd: Command::WR,
+            dist: 1,
+            val: s.nBL,
+            sibling: false,
+        });
+        t[Command::WRA as usize].push(TimeEntry {
+            cmd: Command::WRA,
+            dist: 1,
+            val: s.nBL,
+            sibling: false,
+        });
+
+        /*** Rank ***/
+        let t = timing.get_level_mut(Level::Rank);
+
+        // CAS <-> CAS
+        t[Command::RD as usize].push(TimeEntry {
+            cmd: Command::RD,
+            dist: 1,
+            val: s.nCCDS,
+            sibling: false,
+        });
+        t[Command::RD as usize].push(TimeEntry {
+            cmd: Command::RDA,
+            dist: 1,
+            val: s.nCCDS,
+            sibling: false,
+        });
+        t[Command::RDA as usize].push(TimeEntry {
+            cmd: Command::RD,
+            dist: 1,
+            val: s.nCCDS,
+            sibling: false,
+        });
+        t[Command::RDA as usize].push(TimeEntry {
+            cmd: Command::RDA,
+            dist: 1,
+            val: s.nCCDS,
+            sibling: false,
+        });
+        t[Command::WR as usize].push(TimeEntry {
+            cmd: Command::WR,
+            dist: 1,
+            val: s.nCCDS,
+            sibling: false,
+        });
+        t[Command::WR as usize].push(TimeEntry {
+            cmd: Command::WRA,
+            dist: 1,
+            val: s.nCCDS,
+            sibling: false,
+        });
+        t[Command::WRA as usize].push(TimeEntry {
+            cmd: Command::WR,
+            dist: 1,
+            val: s.nCCDS,
+            sibling: false,
+        });
+        t[Command::WRA as usize].push(TimeEntry {
+            cmd: Command::WRA,
+            dist: 1,
+            val: s.nCCDS,
+            sibling: false,
+        });
+        t[Command::RD as usize].push(TimeEntry {
+            cmd: Command::WR,
+            dist: 1,
+            val: s.nCL + s.nCCDS + 2 - s.nCWL,
+            sibling: false,
+        });
+        t[Command::RD as usize].push(TimeEntry {
+            cmd: Command::WRA,
+            dist: 1,
+            val: s.nCL + s.nCCDS + 2 - s.nCWL,
+            sibling: false,
+        });
+        t[Command::RDA as usize].push(TimeEntry {
+            cmd: Command::WR,
+            dist: 1,
+            val: s.nCL + s.nCCDS + 2 - s.nCWL,
+            sibling: false,
+        });
+        t[Command::RDA as usize].push(TimeEntry {
+            cmd: Command::WRA,
+            dist: 1,
+            val: s.nCL + s.nCCDS + 2 - s.nCWL,
+            sibling: false,
+        });
+        t[Command::WR as usize].push(TimeEntry {
+            cmd: Command::RD,
+            dist: 1,
+            val: s.nCWL + s.nBL + s.nWTRS,
+            sibling: false,
+        });
+        t[Command::WR as usize].push(TimeEntry {
+            cmd: Command::RDA,
+            dist: 1,
+            val: s.nCWL + s.nBL + s.nWTRS,
+            sibling: false,
+        });
+        t[Command::WRA as usize].push(TimeEntry {
+            cmd: Command::RD,
+            dist: 1,
+            val: s.nCWL + s.nBL + s.nWTRS,
+            sibling: false,
+        });
+        t[Command::WRA as usize].push(TimeEntry {
+            cmd: Command::RDA,
+            dist: 1,
+            val: s.nCWL + s.nBL + s.nWTRS,
+            sibling: false,
+        });
+        t[Command::RD as usize].push(TimeEntry {
+            cmd: Command::PREA,
+            dist: 1,
+            val: s.nRTP,
+            sibling: false,
+        });
+        t[Command::WR as usize].push(TimeEntry {
+            cmd: Command::PREA,
+            dist: 1,
+            val: s.nCWL + s.nBL + s.nWR,
+            sibling: false,
+        });
+
+        // CAS <-> PD
+        t[Command::RD as usize].push(TimeEntry {
+            cmd: Command::PDE,
+            dist: 1,
+            val: s.nCL + s.nBL + 1,
+            sibling: false,
+        });
+        t[Command::RDA as usize].push(TimeEntry {
+            cmd: Command::PDE,
+            dist: 1,
+            val: s.nCL + s.nBL + 1,
+            sibling: false,
+        });
+        t[Command::WR as usize].push(TimeEntry {
+            cmd: Command::PDE,
+            dist: 1,
+            val: s.nCWL + s.nBL + s.nWR,
+            sibling: false,
+        });
+        t[Command::WRA as usize].push(TimeEntry {
+            cmd: Command::PDE,
+            dist: 1,
+            val: s.nCWL + s.nBL + s.nWR + 1,
+            sibling: false,
+        }); // +1 for pre
+        t[Command::PDX as usize].push(TimeEntry {
+            cmd: Command::RD,
+            dist: 1,
+            val: s.nXPN,
+            sibling: false,
+        });
+        t[Command::PDX as usize].push(TimeEntry {
+            cmd: Command::RDA,
+            dist: 1,
+            val: s.nXPN,
+            sibling: false,
+        });
+        t[Command::PDX as usize].push(TimeEntry {
+            cmd: Command::WR,
+            dist: 1,
+            val: s.nXPN,
+            sibling: false,
+        });
+        t[Command::PDX as usize].push(TimeEntry {
+            cmd: Command::WRA,
+            dist: 1,
+            val: s.nXPN,
+            sibling: false,
+        });
+
+        // CAS <-> SR: none (all banks have to be precharged)
+
+        // RAS <-> RAS
+        t[Command::ACT as usize].push(TimeEntry {
+            cmd: Command::ACT,
+            dist: 1,
+            val: s.nRRDS,
+            sibling: false,
+        });
+        t[Command::ACT as usize].push(TimeEntry {
+            cmd: Command::ACT,
+            dist: 4,
+            val: s.nFAW,
+            sibling: false,
+        });
+        t[Command::ACT as usize].push(TimeEntry {
+            cmd: Command::PREA,
+            dist: 1,
+            val: s.nRAS,
+            sibling: false,
+        });
+        t[Command::PREA as usize].push(TimeEntry {
+            cmd: Command::ACT,
+            dist: 1,
+            val: s.nRP,
+            sibling: false,
+        });
+        t[Command::PRE as usize].push(TimeEntry {
+            cmd: Command::PRE,
+            dist: 1,
+            val: s.nPPD,
+            sibling: false,
+        });
+
+        // RAS <-> REF
+        t[Command::PRE as usize].push(TimeEntry {
+            cmd: Command::REF,
+            dist: 1,
+            val: s.nRP,
+            sibling: false,
+        });
+        t[Command::PREA as usize].push(TimeEntry {
+            cmd: Command::REF,
+            dist: 1,
+            val: s.nRP,
+            sibling: false,
+        });
+        t[Command::REF as usize].push(TimeEntry {
+            cmd: Command::ACT,
+            dist: 1,
+            val: s.nRFC,
+            sibling: false,
+        });
+
+        // RAS <-> PD
+        t[Command::ACT as usize].push(TimeEntry {
+            cmd: Command::PDE,
+            dist: 1,
+            val: 1,
+            sibling: false,
+        });
+        t[Command::PDX as usize].push(TimeEntry {
+            cmd: Command::ACT,
+            dist: 1,
+            val: s.nXPN,
+            sibling: false,
+        });
+        t[Command::PDX as usize].push(TimeEntry {
+            cmd: Command::PRE,
+            dist: 1,
+            val: s.nXPN,
+            sibling: false,
+        });
+        t[Command::PDX as usize].push(TimeEntry {
+            cmd: Command::PREA,
+            dist: 1,
+            val: s.nXPN,
+            sibling: false,
+        });
+
+        // RAS <-> SR
+        t[Command::PRE as usize].push(TimeEntry {
+            cmd: Command::SRE,
+            dist: 1,
+            val: s.nRP,
+            sibling: false,
+        });
+        t[Command::PREA as usize].push(TimeEntry {
+            cmd: Command::SRE,
+            dist: 1,
+            val: s.nRP,
+            sibling: false,
+        });
+        t[Command::SRX as usize].push(TimeEntry {
+            cmd: Command::ACT,
+            dist: 1,
+            val: s.nXS,
+            sibling: false,
+        });
+
+        // REF <-> REF
+        t[Command::REF as usize].push(TimeEntry {
+            cmd: Command::REF,
+            dist: 1,
+            val: s.nRFC,
+            sibling: false,
+        });
+
+        // REF <-> PD
+        t[Command::REF as usize].push(TimeEntry {
+            cmd: Command::PDE,
+            dist: 1,
+            val: 1,
+            sibling: false,
+        });
+        t[Command::PDX as usize].push(TimeEntry {
+            cmd: Command::REF,
+            dist: 1,
+            val: s.nXPN,
+            sibling: false,
+        });
+
+        // REF <-> SR
+        t[Command::SRX as usize].push(TimeEntry {
+            cmd: Command::REF,
+            dist: 1,
+            val: s.nXS,
+            sibling: false,
+        });
+
+        // PD <-> PD
+        t[Command::PDE as usize].push(TimeEntry {
+            cmd: Command::PDX,
+            dist: 1,
+            val: s.nPD,
+            sibling: false,
+        });
+        t[Command::PDX as usize].push(TimeEntry {
+            cmd: Command::PDE,
+            dist: 1,
+            val: s.nXPN,
+            sibling: false,
+        });
+
+        // PD <-> SR
+        t[Command::PDX as usize].push(TimeEntry {
+            cmd: Command::SRE,
+            dist: 1,
+            val: s.nXPN,
+            sibling: false,
+        });
+        t[Command::SRX as usize].push(TimeEntry {
+            cmd: Command::PDE,
+            dist: 1,
+            val: s.nXS,
+            sibling: false,
+        });
+
+        // SR <-> SR
+        t[Command::SRE as usize].push(TimeEntry {
+            cmd: Command::SRX,
+            dist: 1,
+            val: s.nCKESR,
+            sibling: false,
+        });
+        t[Command::SRX as usize].push(TimeEntry {
+            cmd: Command::SRE,
+            dist: 1,
+            val: s.nXS,
+            sibling: false,
+        });
+
+        /*** Bank Group ***/
+        let t = timing.get_level_mut(Level::BankGroup);
+
+        // CAS <-> CAS
+        t[Command::RD as usize].push(TimeEntry {
+            cmd: Command::RD,
+            dist: 1,
+            val: s.nCCDL,
+            sibling: false,
+        });
+        t[Command::RD as usize].push(TimeEntry {
+            cmd: Command::RDA,
+            dist: 1,
+            val: s.nCCDL,
+            sibling: false,
+        });
+        t[Command::RDA as usize].push(TimeEntry {
+            cmd: Command::RD,
+            dist: 1,
+            val: s.nCCDL,
+            sibling: false,
+        });
+        t[Command::RDA as usize].push(TimeEntry {
+            cmd: Command::RDA,
+            dist: 1,
+            val: s.nCCDL,
+            sibling: false,
+        });
+        t[Command::WR as usize].push(TimeEntry {
+            cmd: Command::WR,
+            dist: 1,
+            val: s.nCCDL,
+            sibling: false,
+        });
+        t[Command::WR as usize].push(TimeEntry {
+            cmd: Command::WRA,
+            dist: 1,
+            val: s.nCCDL,
+            sibling: false,
+        });
+        t[Command::WRA as usize].push(TimeEntry {
+            cmd: Command::WR,
+            dist: 1,
+            val: s.nCCDL,
+            sibling: false,
+        });
+        t[Command::WRA as usize].push(TimeEntry {
+            cmd: Command::WRA,
+            dist: 1,
+            val: s.nCCDL,
+            sibling: false,
+        });
+        t[Command::WR as usize].push(TimeEntry {
+            cmd: Command::RD,
+            dist: 1,
+            val: s.nCWL + s.nBL + s.nWTRL,
+            sibling: false,
+        });
+        t[Command::WR as usize].push(TimeEntry {
+            cmd: Command::RDA,
+            dist: 1,
+            val: s.nCWL + s.nBL + s.nWTRL,
+            sibling: false,
+        });
+        t[Command::WRA as usize].push(TimeEntry {
+            cmd: Command::RD,
+            dist: 1,
+            val: s.nCWL + s.nBL + s.nWTRL,
+            sibling: false,
+        });
+        t[Command::WRA as usize].push(TimeEntry {
+            cmd: Command::RDA,
+            dist: 1,
+            val: s.nCWL + s.nBL + s.nWTRL,
+            sibling: false,
+        });
+
+        // RAS <-> RAS
+        t[Command::ACT as usize].push(TimeEntry {
+            cmd: Command::ACT,
+            dist: 1,
+            val: s.nRRDL,
+            sibling: false,
+        });
+
+        /*** Bank ***/
+        let t = timing.get_level_mut(Level::Bank);
+
+        // CAS <-> RAS
+        t[Command::ACT as usize].push(TimeEntry {
+            cmd: Command::RD,
+            dist: 1,
+            val: s.nRCDR,
+            sibling: false,
+        });
+        t[Command::ACT as usize].push(TimeEntry {
+            cmd: Command::RDA,
+            dist: 1,
+            val: s.nRCDR,
+            sibling: false,
+        });
+        t[Command::ACT as usize].push(TimeEntry {
+            cmd: Command::WR,
+            dist: 1,
+            val: s.nRCDW,
+            sibling: false,
+        });
+        t[Command::ACT as usize].push(TimeEntry {
+            cmd: Command::WRA,
+            dist: 1,
+            val: s.nRCDW,
+            sibling: false,
+        });
+        t[Command::RD as usize].push(TimeEntry {
+            cmd: Command::PRE,
+            dist: 1,
+            val: s.nRTP,
+            sibling: false,
+        });
+        t[Command::WR as usize].push(TimeEntry {
+            cmd: Command::PRE,
+            dist: 1,
+            val: s.nCWL + s.nBL + s.nWR,
+            sibling: false,
+        });
+        t[Command::RDA as usize].push(TimeEntry {
+            cmd: Command::ACT,
+            dist: 1,
+            val: s.nRTP + s.nRP,
+            sibling: false,
+        });
+        t[Command::WRA as usize].push(TimeEntry {
+            cmd: Command::ACT,
+            dist: 1,
+            val: s.nCWL + s.nBL + s.nWR + s.nRP,
+            sibling: false,
+        });
+
+        // RAS <-> RAS
+        t[Command::ACT as usize].push(TimeEntry {
+            cmd: Command::ACT,
+            dist: 1,
+            val: s.nRC,
+            sibling: false,
+        });
+        t[Command::ACT as usize].push(TimeEntry {
+            cmd: Command::PRE,
+            dist: 1,
+            val: s.nRAS,
+            sibling: false,
+        });
+        t[Command::PRE as usize].push(TimeEntry {
+            cmd: Command::ACT,
+            dist: 1,
+            val: s.nRP,
+            sibling: false,
+        });
+
+        /*** PIM ***/
+        // the PIM commands access the banks like RD and WR, their data never leaves the chip
+        let t = timing.get_level_mut(Level::Rank);
+
+        // PIM <-> PIM
+        t[Command::MAC as usize].push(TimeEntry {
+            cmd: Command::MAC,
+            dist: 1,
+            val: s.nCCDS,
+            sibling: false,
+        });
+        t[Command::MAC as usize].push(TimeEntry {
+            cmd: Command::LDR,
+            dist: 1,
+            val: s.nCCDS,
+            sibling: false,
+        });
+        t[Command::MAC as usize].push(TimeEntry {
+            cmd: Command::STR,
+            dist: 1,
+            val: s.nCCDS,
+            sibling: false,
+        });
+        t[Command::LDR as usize].push(TimeEntry {
+            cmd: Command::MAC,
+            dist: 1,
+            val: s.nCCDS,
+            sibling: false,
+        });
+        t[Command::LDR as usize].push(TimeEntry {
+            cmd: Command::LDR,
+            dist: 1,
+            val: s.nCCDS,
+            sibling: false,
+        });
+        t[Command::LDR as usize].push(TimeEntry {
+            cmd: Command::STR,
+            dist: 1,
+            val: s.nCCDS,
+            sibling: false,
+        });
+        t[Command::STR as usize].push(TimeEntry {
+            cmd: Command::MAC,
+            dist: 1,
+            val: s.nCCDS,
+            sibling: false,
+        });
+        t[Command::STR as usize].push(TimeEntry {
+            cmd: Command::LDR,
+            dist: 1,
+            val: s.nCCDS,
+            sibling: false,
+        });
+        t[Command::STR as usize].push(TimeEntry {
+            cmd: Command::STR,
+            dist: 1,
+            val: s.nCCDS,
+            sibling: false,
+        });
+
+        // WRGB <-> WR, the operand takes the data bus like a write
+        t[Command::WR as usize].push(TimeEntry {
+            cmd: Command::WRGB,
+            dist: 1,
+            val: s.nCCDS,
+            sibling: false,
+        });
+        t[Command::WRA as usize].push(TimeEntry {
+            cmd: Command::WRGB,
+            dist: 1,
+            val: s.nCCDS,
+            sibling: false,
+        });
+        t[Command::WRGB as usize].push(TimeEntry {
+            cmd: Command::WR,
+            dist: 1,
+            val: s.nCCDS,
+            sibling: false,
+        });
+        t[Command::WRGB as usize].push(TimeEntry {
+            cmd: Command::WRA,
+            dist: 1,
+            val: s.nCCDS,
+            sibling: false,
+        });
+        t[Command::WRGB as usize].push(TimeEntry {
+            cmd: Command::WRGB,
+            dist: 1,
+            val: s.nCCDS,
+            sibling: false,
+        });
+        t[Command::WRGB as usize].push(TimeEntry {
+            cmd: Command::MAC,
+            dist: 1,
+            val: s.nCWL + s.nBL,
+            sibling: false,
+        });
+
+        // PIM <-> RAS
+        t[Command::MAC as usize].push(TimeEntry {
+            cmd: Command::PREA,
+            dist: 1,
+            val: s.nRTP,
+            sibling: false,
+        });
+        t[Command::LDR as usize].push(TimeEntry {
+            cmd: Command::PREA,
+            dist: 1,
+            val: s.nRTP,
+            sibling: false,
+        });
+        t[Command::STR as usize].push(TimeEntry {
+            cmd: Command::PREA,
+            dist: 1,
+            val: s.nWR,
+            sibling: false,
+        });
+
+        let t = timing.get_level_mut(Level::BankGroup);
+
+        // PIM <-> PIM
+        t[Command::MAC as usize].push(TimeEntry {
+            cmd: Command::MAC,
+            dist: 1,
+            val: s.nCCDL,
+            sibling: false,
+        });
+        t[Command::MAC as usize].push(TimeEntry {
+            cmd: Command::LDR,
+            dist: 1,
+            val: s.nCCDL,
+            sibling: false,
+        });
+        t[Command::MAC as usize].push(TimeEntry {
+            cmd: Command::STR,
+            dist: 1,
+            val: s.nCCDL,
+            sibling: false,
+        });
+        t[Command::LDR as usize].push(TimeEntry {
+            cmd: Command::MAC,
+            dist: 1,
+            val: s.nCCDL,
+            sibling: false,
+        });
+        t[Command::LDR as usize].push(TimeEntry {
+            cmd: Command::LDR,
+            dist: 1,
+            val: s.nCCDL,
+            sibling: false,
+        });
+        t[Command::LDR as usize].push(TimeEntry {
+            cmd: Command::STR,
+            dist: 1,
+            val: s.nCCDL,
+            sibling: false,
+        });
+        t[Command::STR as usize].push(TimeEntry {
+            cmd: Command::MAC,
+            dist: 1,
+            val: s.nCCDL,
+            sibling: false,
+        });
+        t[Command::STR as usize].push(TimeEntry {
+            cmd: Command::LDR,
+            dist: 1,
+            val: s.nCCDL,
+            sibling: false,
+        });
+        t[Command::STR as usize].push(TimeEntry {
+            cmd: Command::STR,
+            dist: 1,
+            val: s.nCCDL,
+            sibling: false,
+        });
+
+        let t = timing.get_level_mut(Level::Bank);
+
+        // PIM <-> RAS
+        t[Command::ACT as usize].push(TimeEntry {
+            cmd: Command::MAC,
+            dist: 1,
+            val: s.nRCDR,
+            sibling: false,
+        });
+        t[Command::ACT as usize].push(TimeEntry {
+            cmd: Command::LDR,
+            dist: 1,
+            val: s.nRCDR,
+            sibling: false,
+        });
+        t[Command::ACT as usize].push(TimeEntry {
+            cmd: Command::STR,
+            dist: 1,
+            val: s.nRCDW,
+            sibling: false,
+        });
+        t[Command::MAC as usize].push(TimeEntry {
+            cmd: Command::PRE,
+            dist: 1,
+            val: s.nRTP,
+            sibling: false,
+        });
+        t[Command::LDR as usize].push(TimeEntry {
+            cmd: Command::PRE,
+            dist: 1,
+            val: s.nRTP,
+            sibling: false,
+        });
+        t[Command::STR as usize].push(TimeEntry {
+            cmd: Command::PRE,
+            dist: 1,
+            val: s.nWR,
+            sibling: false,
+        });
+    }
+
+    pub fn get_speed(speed: &Speed) -> SpeedEntry {
+        match speed {
+            Speed::GDDR6_12000 => SpeedEntry {
+                rate: 12000,
+                freq: 12000.0 / 8.0,
+                tCK: 8000.0 / 12000.0,
+                nBL: 2,
+                nCCDS: 2,
+                nCCDL: 3,
+                nCL: 20,
+                nRCDR: 27,
+                nRCDW: 18,
+                nRP: 27,
+                nCWL: 6,
+                nRAS: 42,
+                nRC: 69,
+                nPPD: 2,
+                nRTP: 5,
+                nWTRS: 8,
+                nWTRL: 12,
+                nWR: 27,
+                nRRDS: 8,
+                nRRDL: 9,
+                nFAW: 24,
+                nRFC: 0,
+                nREFI: 0,
+                nPD: 12,
+                nXPN: 12,
+                nCKESR: 12,
+                nXS: 0,
+            },
+            Speed::GDDR6_14000 => SpeedEntry {
+                rate: 14000,
+                freq: 14000.0 / 8.0,
+                tCK: 8000.0 / 14000.0,
+                nBL: 2,
+                nCCDS: 2,
+                nCCDL: 3,
+                nCL: 24,
+                nRCDR: 32,
+                nRCDW: 21,
+                nRP: 32,
+                nCWL: 7,
+                nRAS: 49,
+                nRC: 81,
+                nPPD: 2,
+                nRTP: 6,
+                nWTRS: 9,
+                nWTRL: 14,
+                nWR: 32,
+                nRRDS: 9,
+                nRRDL: 11,
+                nFAW: 28,
+                nRFC: 0,
+                nREFI: 0,
+                nPD: 14,
+                nXPN: 14,
+                nCKESR: 14,
+                nXS: 0,
+            },
+            Speed::GDDR6_16000 => SpeedEntry {
+                rate: 16000,
+                freq: 16000.0 / 8.0,
+                tCK: 8000.0 / 16000.0,
+                nBL: 2,
+                nCCDS: 2,
+                nCCDL: 3,
+                nCL: 26,
+                nRCDR: 36,
+                nRCDW: 24,
+                nRP: 36,
+                nCWL: 8,
+                nRAS: 56,
+                nRC: 92,
+                nPPD: 2,
+                nRTP: 6,
+                nWTRS: 10,
+                nWTRL: 15,
+                nWR: 36,
+                nRRDS: 10,
+                nRRDL: 12,
+                nFAW: 32,
+                nRFC: 0,
+                nREFI: 0,
+                nPD: 15,
+                nXPN: 15,
+                nCKESR: 15,
+                nXS: 0,
+            },
+        }
+    }
+}
+impl DramSpec for GDDR6 {
+    fn from_config(config: &Config) -> Result<Self, ConfigError> {
+        if config.ranks != 1 {
+            return Err(ConfigError::Unsupported {
+                key: "ranks".to_string(),
+                reason: format!("GDDR6 has a single rank, found {}", config.ranks),
+            });
+        }
+        Ok(Self::new(config))
+    }
+    fn supports_per_bank_refresh() -> bool {
+        false
+    }
+    fn supports_pim() -> bool {
+        true
+    }
+    fn get_first_cmd(req_type: &ReqType) -> Command {
+        match req_type {
+            ReqType::Read => Command::RD,
+            ReqType::Write => Command::WR,
+            ReqType::Refresh => Command::REF,
+            ReqType::RefreshBank => unreachable!("GDDR6 has no per-bank refresh"),
+            ReqType::PimMac => Command::MAC,
+            ReqType::PimMacAllBank => Command::MACAB,
+            ReqType::PimLoad => Command::LDR,
+            ReqType::PimStore => Command::STR,
+            ReqType::PimBroadcast => Command::WRGB,
+        }
+    }
+
+    fn get_pre_cmd(dram: &Dram<Self>, cmd: &Command, child_id: u64) -> Option<Command> {
+        match (&dram.level, cmd) {
+            (
+                Level::Rank,
+                Command::RD
+                | Command::WR
+                | Command::MAC
+                | Command::LDR
+                | Command::STR
+                | Command::WRGB
+                | Command::MACAB,
+            ) => match dram.state {
+                dram::State::PowerUp => None,
+                dram::State::ActPowerDown => Some(Command::PDX),
+                dram::State::PrePowerDown => Some(Command::PDX),
+                dram::State::SelfRefresh => Some(Command::SRX),
+                _ => unreachable!("invalid dram state"),
+            },
+            (Level::Rank, Command::REF) => {
+                if dram.children.iter().any(|bank_group| {
+                    bank_group
+                        .children
+                        .iter()
+                        .any(|bank| !matches!(bank.state, dram::State::Closed))
+                }) {
+                    Some(Command::PREA)
+                } else {
+                    Some(Command::REF)
+                }
+            }
+            (Level::Rank, Command::PDE) => match dram.state {
+                dram::State::PowerUp | dram::State::ActPowerDown | dram::State::PrePowerDown => {
+                    Some(Command::PDE)
+                }
+                dram::State::SelfRefresh => Some(Command::SRX),
+                _ => unreachable!("invalid dram state"),
+            },
+            (Level::Rank, Command::SRE) => match dram.state {
+                dram::State::PowerUp | dram::State::SelfRefresh => Some(Command::SRE),
+                dram::State::ActPowerDown | dram::State::PrePowerDown => Some(Command::PDX),
+                _ => unreachable!("invalid dram state"),
+            },
+
+            (
+                Level::Bank,
+                Command::RD | Command::WR | Command::MAC | Command::LDR | Command::STR,
+            ) => match dram.state {
+                dram::State::Closed => Some(Command::ACT),
+                dram::State::Opened(row_id) => {
+                    if row_id == child_id {
+                        Some(*cmd)
+                    } else {
+                        Some(Command::PRE)
+                    }
+                }
+                _ => unreachable!("invalid dram state"),
+            },
+            (Level::Bank, Command::MACAB) => match dram.state {
+                dram::State::Closed => Some(Command::ACTAB),
+                dram::State::Opened(row_id) if row_id == child_id => None,
+                dram::State::Opened(_) => Some(Command::PREAB),
+                _ => unreachable!("invalid dram state"),
+            },
+            _ => None,
+        }
+    }
+
+    fn get_start_state(level: &Level) -> dram::State {
+        match level {
+            Level::Rank => dram::State::PowerUp,
+            Level::Bank => dram::State::Closed,
+            Level::Row => dram::State::Closed,
+            _ => dram::State::NoUse,
+        }
+    }
+
+    fn get_levels(&self) -> &[Level] {
+        &LEVELS
+    }
+
+    fn get_addr_bits(&self) -> &[usize] {
+        &self.addr_bits
+    }
+
+    fn get_child_size(&self) -> &[usize] {
+        &self.child_size
+    }
+
+    fn get_scope(&self, cmd: &Command) -> Level {
+        match cmd {
+            Command::ACT | Command::ACTAB => Level::Row,
+            Command::PRE | Command::PREAB | Command::REFSB | Command::REFPB => Level::Bank,
+            Command::RD
+            | Command::WR
+            | Command::RDA
+            | Command::WRA
+            | Command::MAC
+            | Command::LDR
+            | Command::STR
+            | Command::MACAB => Level::Column,
+            Command::PREA
+            | Command::REF
+            | Command::PDE
+            | Command::PDX
+            | Command::SRE
+            | Command::SRX
+            | Command::WRGB => Level::Rank,
+            Command::Max => unreachable!(),
+        }
+    }
+
+    fn update_state(&self, dram: &mut Dram<Self>, cmd: &Command, child_id: u64) {
+        match (dram.level, cmd) {
+            (Level::Bank, Command::ACT | Command::ACTAB) => {
+                dram.state = dram::State::Opened(child_id);
+            }
+            (Level::Bank, Command::PRE | Command::PREAB | Command::RDA | Command::WRA) => {
+                dram.state = dram::State::Closed;
+            }
+            (Level::Rank, Command::PREA) => {
+                dram.children.iter_mut().for_each(|bank_group| {
+                    bank_group.children.iter_mut().for_each(|bank| {
+                        bank.state = dram::State::Closed;
+                    })
+                });
+            }
+            (Level::Rank, Command::PDE) => {
+                let banks_closed = dram.children.iter().all(|bank_group| {
+                    bank_group
+                        .children
+                        .iter()
+                        .all(|bank| bank.state == State::Closed)
+                });
+                dram.state = if banks_closed {
+                    State::PrePowerDown
+                } else {
+                    State::ActPowerDown
+                };
+            }
+            (Level::Rank, Command::PDX | Command::SRX) => {
+                dram.state = State::PowerUp;
+            }
+            (Level::Rank, Command::SRE) => {
+                dram.state = State::SelfRefresh;
+            }
+            _ => {}
+        }
+    }
+    fn get_timming(&self, depth: usize, cmd: &Command) -> &[TimeEntry] {
+        self.timing.get(depth, cmd)
+    }
+
+    fn get_read_latency(&self) -> u64 {
+        self.read_latency
+    }
+
+    fn get_refresh_interval(&self) -> u64 {
+        self.speed_entry.nREFI
+    }
+
+    fn get_clk_ns(&self) -> f64 {
+        self.speed_entry.tCK
+    }
+
+    fn get_transaction_bytes(&self) -> u64 {
+        PREFETCH_SIZE * CHANNEL_WIDTH / 8
+    }
+
+    fn get_burst_cycles(&self) -> u64 {
+        self.speed_entry.nBL
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use super::{GDDR6Org, Speed, GDDR6};
+    use crate::{
+        command::Command,
+        config::{Config, Standard},
+        dram::DramSpec,
+        memory::{Level, MemoryTrait, SimpleMemory},
+        request::{ReqType, Request},
+        stats::StatValue,
+    };
+
+    fn get_timing(gddr6: &Arc<GDDR6>, level: Level, from: Command, to: Command) -> u64 {
+        gddr6
+            .get_timming(gddr6.get_level_index(level), &from)
+            .iter()
+            .find(|t| t.cmd == to)
+            .unwrap()
+            .val
+    }
+
+    #[test]
+    fn test_gddr6() {
+        let config =
+            Config::parse("standard = GDDR6\norg = GDDR6_16Gb\nspeed = GDDR6_16000").unwrap();
+        assert_eq!(config.standard, Standard::GDDR6);
+        assert_eq!(config.gddr6_org, GDDR6Org::GDDR6_16Gb);
+        assert_eq!(config.gddr6_speed, Speed::GDDR6_16000);
+        let gddr6 = Arc::new(GDDR6::new(&config));
+        // the 2 channels of the device
+        assert_eq!(gddr6.get_child_size(), [2, 1, 4, 4, 1 << 15, 1 << 10]);
+        assert_eq!(gddr6.get_read_latency(), 26 + 2);
+        assert_eq!(gddr6.get_refresh_interval(), 3800);
+        assert_eq!(gddr6.get_transaction_bytes(), 32);
+        assert_eq!(gddr6.get_clk_ns(), 0.5);
+        let t = |level, from, to| get_timing(&gddr6, level, from, to);
+        assert_eq!(t(Level::Rank, Command::REF, Command::ACT), 560);
+        assert_eq!(t(Level::Rank, Command::ACT, Command::ACT), 10);
+        assert_eq!(t(Level::BankGroup, Command::ACT, Command::ACT), 12);
+        assert_eq!(t(Level::Rank, Command::RD, Command::RD), 2);
+        assert_eq!(t(Level::BankGroup, Command::RD, Command::RD), 3);
+        assert_eq!(t(Level::Bank, Command::ACT, Command::RD), 36);
+        assert_eq!(t(Level::Bank, Command::ACT, Command::WR), 24);
+
+        // 2 devices, 4 channels
+        let config = Config::parse("standard = GDDR6\nchannels = 2").unwrap();
+        let gddr6 = Arc::new(GDDR6::new(&config));
+        let mut memory = SimpleMemory::with_config(&config, gddr6.clone()).unwrap();
+        for i in 0..64 {
+            memory
+                .try_send(Request::new(i << 6, ReqType::Read))
+                .unwrap();
+        }
+        let stats = memory.finish();
+        assert_eq!(stats.get_scalar("read_requests"), Some(64.0));
+        let Some(StatValue::Vector(per_channel)) = stats.get("incoming_requests_per_channel")
+        else {
+            panic!("missing incoming_requests_per_channel");
+        };
+        assert_eq!(per_channel, &vec![16.0; 4]);
+    }
+}
@@ -0,0 +1,1534 @@
+use std::str::FromStr;
+
+use crate::{
+    command::Command,
+    config::{Config, ConfigError},
+    dram::{self, log2, Dram, DramSpec, State, TimeEntry, TimingTable},
+    memory::Level,
+    request::ReqType,
+};
+
+/// the density is per channel, each stack comes with 8 channels
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[allow(non_camel_case_types)]
+pub enum HBMOrg {
+    HBM_1Gb,
+    HBM_2Gb,
+    HBM_4Gb,
+    HBM2_4Gb,
+    HBM2_8Gb,
+}
+impl FromStr for HBMOrg {
+    type Err = ();
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "HBM_1Gb" => HBMOrg::HBM_1Gb,
+            "HBM_2Gb" => HBMOrg::HBM_2Gb,
+            "HBM_4Gb" => HBMOrg::HBM_4Gb,
+            "HBM2_4Gb" => HBMOrg::HBM2_4Gb,
+            "HBM2_8Gb" => HBMOrg::HBM2_8Gb,
+            _ => return Err(()),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[allow(non_camel_case_types)]
+pub enum Speed {
+    HBM_1Gbps,
+    HBM2_2Gbps,
+}
+impl FromStr for Speed {
+    type Err = ();
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "HBM_1Gbps" => Speed::HBM_1Gbps,
+            "HBM2_2Gbps" => Speed::HBM2_2Gbps,
+            _ => return Err(()),
+        })
+    }
+}
+
+/// the density(in Mb) and data width of a channel, and the number of its bank groups, banks,
+/// rows and columns
+struct OrgEntry {
+    size: usize,
+    dq: usize,
+    count: [usize; 4],
+}
+#[allow(non_snake_case, dead_code)]
+pub struct SpeedEntry {
+    rate: u64,
+    freq: f64,
+    tCK: f64,
+    nBL: u64,
+    nCCDS: u64,
+    nCCDL: u64,
+    nCL: u64,
+    nRCDR: u64,
+    nRCDW: u64,
+    nRP: u64,
+    nCWL: u64,
+    nRAS: u64,
+    nRC: u64,
+    nRTP: u64,
+    nWTRS: u64,
+    nWTRL: u64,
+    nWR: u64,
+    nRRDS: u64,
+    nRRDL: u64,
+    nFAW: u64,
+    nRFC: u64,
+    nREFI: u64,
+    nREFI1B: u64,
+    nPD: u64,
+    nXP: u64,
+    nCKESR: u64,
+    nXS: u64,
+}
+
+const LEVELS: [Level; 6] = [
+    Level::Channel,
+    Level::Rank,
+    Level::BankGroup,
+    Level::Bank,
+    Level::Row,
+    Level::Column,
+];
+/// the levels in the pseudo channel mode, the pseudo channels take the place of the ranks
+const PSEUDO_CHANNEL_LEVELS: [Level; 6] = [
+    Level::Channel,
+    Level::PseudoChannel,
+    Level::BankGroup,
+    Level::Bank,
+    Level::Row,
+    Level::Column,
+];
+/// burst length could be 2 and 4 (choose 4 here), 2n prefetch
+const PREFETCH_SIZE: u64 = 4;
+/// HBM2 splits every channel into 2 pseudo channels in the pseudo channel mode
+const PSEUDO_CHANNELS: usize = 2;
+
+/// HBM and HBM2, in the pseudo channel mode the ranks are replaced by the pseudo channels of a
+/// channel, they share the command bus but each one has its own banks and a half-width data bus
+pub struct HBM {
+    levels: &'static [Level],
+    child_size: Vec<usize>,
+    addr_bits: Vec<usize>,
+    channel_width: u64,
+    speed_entry: SpeedEntry,
+    timing: TimingTable,
+    read_latency: u64,
+}
+impl HBM {
+    pub fn new(config: &Config) -> Self {
+        let pseudo_channel = config.pseudo_channel;
+        assert!(
+            !pseudo_channel || matches!(config.hbm_org, HBMOrg::HBM2_4Gb | HBMOrg::HBM2_8Gb),
+            "the pseudo channel mode is only supported by HBM2"
+        );
+        let mut org = Self::get_org(&config.hbm_org);
+        let ranks = if pseudo_channel {
+            // each pseudo channel gets half of the row buffer and half of the data bus
+            org.count[3] /= PSEUDO_CHANNELS;
+            org.dq /= PSEUDO_CHANNELS;
+            PSEUDO_CHANNELS
+        } else {
+            config.ranks
+        };
+        let child_size = vec![
+            config.channels,
+            ranks,
+            org.count[0],
+            org.count[1],
+            org.count[2],
+            org.count[3],
+        ];
+        let addr_bits = child_size.iter().map(|x| log2(*x)).collect::<Vec<usize>>();
+        let mut speed_entry = Self::get_speed(&config.hbm_speed);
+        Self::init_speed(&mut speed_entry, &org);
+        let levels: &'static [Level] = if pseudo_channel {
+            &PSEUDO_CHANNEL_LEVELS
+        } else {
+            &LEVELS
+        };
+        let mut timing = TimingTable::new(levels);
+        Self::init_timing(&mut timing, &speed_entry, pseudo_channel);
+        dram::add_all_bank_timing(&mut timing);
+        let read_latency = speed_entry.nCL + speed_entry.nBL;
+        Self {
+            levels,
+            child_size,
+            addr_bits,
+            channel_width: org.dq as u64,
+            speed_entry,
+            timing,
+            read_latency,
+        }
+    }
+    fn get_org(org: &HBMOrg) -> OrgEntry {
+        match org {
+            HBMOrg::HBM_1Gb => OrgEntry {
+                size: 1 << 10,
+                dq: 128,
+                count: [4, 2, 1 << 13, 1 << (6 + 1)],
+            },
+            HBMOrg::HBM_2Gb => OrgEntry {
+                size: 2 << 10,
+                dq: 128,
+                count: [4, 2, 1 << 14, 1 << (6 + 1)],
+            },
+            HBMOrg::HBM_4Gb => OrgEntry {
+                size: 4 << 10,
+                dq: 128,
+                count: [4, 4, 1 << 14, 1 << (6 + 1)],
+            },
+            HBMOrg::HBM2_4Gb => OrgEntry {
+                size: 4 << 10,
+                dq: 128,
+                count: [4, 4, 1 << 14, 1 << (6 + 1)],
+            },
+            HBMOrg::HBM2_8Gb => OrgEntry {
+                size: 8 << 10,
+                dq: 128,
+                count: [4, 4, 1 << 15, 1 << (6 + 1)],
+            },
+        }
+    }
+    /// fill the density dependent timings
+    fn init_speed(s: &mut SpeedEntry, org: &OrgEntry) {
+        // nRFC, nREFI1B, nXS
+        let (rfc, refi1b, xs) = match (s.rate, org.size >> 10) {
+            (1000, 1) => (55, 64, 60),
+            (1000, 2) => (80, 128, 85),
+            (1000, 4) => (130, 256, 135),
+            (2000, 4) => (260, 243, 270),
+            (2000, 8) => (350, 243, 360),
+            _ => unreachable!("the speed does not match the org"),
+        };
+        s.nRFC = rfc;
+        s.nREFI1B = refi1b;
+        s.nXS = xs;
+    }
+    fn init_timing(timing: &mut TimingTable, s: &SpeedEntry, pseudo_channel: bool) {
+        let rank = if pseudo_channel {
+            Level::PseudoChannel
+        } else {
+            Level::Rank
+        };
+        /*** Channel ***/
+        let t = timing.get_level_mut(Level::Channel);
+
+        // CAS <-> CAS, the pseudo channels have their own data buses
+        if !pseudo_channel {
+            t[Command::RD as usize].push(TimeEntry {
+                cmd: Command::RD,
+                dist: 1,
+                val: s.nBL,
+                sibling: false,
+            });
+            t[Command::RD as usize].push(TimeEntry {
+                cmd: Command::RDA,
+                dist: 1,
+                val: s.nBL,
+                sibling: false,
+            });
+            t[Command::RDA as usize].push(TimeEntry {
+                cmd: Command::RD,
+                dist: 1,
+                val: s.nBL,
+                sibling: false,
+            });
+            t[Command::RDA as usize].push(TimeEntry {
+                cmd: Command::RDA,
+                dist: 1,
+                val: s.nBL,
+                sibling: false,
+            });
+            t[Command::WR as usize].push(TimeEntry {
+                cmd: Command::WR,
+                dist: 1,
+                val: s.nBL,
+                sibling: false,
+            });
+            t[Command::WR as usize].push(TimeEntry {
+                cmd: Command::WRA,
+                dist: 1,
+                val: s.nBL,
+                sibling: false,
+            });
+            t[Command::WRA as usize].push(TimeEntry {
+                cmd: Command::WR,
+                dist: 1,
+                val: s.nBL,
+                sibling: false,
+            });
+            t[Command::WRA as usize].push(TimeEntry {
+                cmd: Command::WRA,
+                dist: 1,
+                val: s.nBL,
+                sibling: false,
+            });
+        }
+
+        /*** Rank ***/
+        let t = timing.get_level_mut(rank);
+
+        // CAS <-> CAS
+        t[Command::RD as usize].push(TimeEntry {
+            cmd: Command::RD,
+            dist: 1,
+            val: s.nCCDS,
+            sibling: false,
+        });
+        t[Command::RD as usize].push(TimeEntry {
+            cmd: Command::RDA,
+            dist: 1,
+            val: s.nCCDS,
+            sibling: false,
+        });
+        t[Command::RDA as usize].push(TimeEntry {
+            cmd: Command::RD,
+            dist: 1,
+            val: s.nCCDS,
+            sibling: false,
+        });
+        t[Command::RDA as usize].push(TimeEntry {
+            cmd: Command::RDA,
+            dist: 1,
+            val: s.nCCDS,
+            sibling: false,
+        });
+        t[Command::WR as usize].push(TimeEntry {
+            cmd: Command::WR,
+            dist: 1,
+            val: s.nCCDS,
+            sibling: false,
+        });
+        t[Command::WR as usize].push(TimeEntry {
+            cmd: Command::WRA,
+            dist: 1,
+            val: s.nCCDS,
+            sibling: false,
+        });
+        t[Command::WRA as usize].push(TimeEntry {
+            cmd: Command::WR,
+            dist: 1,
+            val: s.nCCDS,
+            sibling: false,
+        });
+        t[Command::WRA as usize].push(TimeEntry {
+            cmd: Command::WRA,
+            dist: 1,
+            val: s.nCCDS,
+            sibling: false,
+        });
+        t[Command::RD as usize].push(TimeEntry {
+            cmd: Command::WR,
+            dist: 1,
+            val: s.nCL + s.nCCDS + 2 - s.nCWL,
+            sibling: false,
+        });
+        t[Command::RD as usize].push(TimeEntry {
+            cmd: Command::WRA,
+            dist: 1,
+            val: s.nCL + s.nCCDS + 2 - s.nCWL,
+            sibling: false,
+        });
+        t[Command::RDA as usize].push(TimeEntry {
+            cmd: Command::WR,
+            dist: 1,
+            val: s.nCL + s.nCCDS + 2 - s.nCWL,
+            sibling: false,
+        });
+        t[Command::RDA as usize].push(TimeEntry {
+            cmd: Command::WRA,
+            dist: 1,
+            val: s.nCL + s.nCCDS + 2 - s.nCWL,
+            sibling: false,
+        });
+        t[Command::WR as usize].push(TimeEntry {
+            cmd: Command::RD,
+            dist: 1,
+            val: s.nCWL + s.nBL + s.nWTRS,
+            sibling: false,
+        });
+        t[Command::WR as usize].push(TimeEntry {
+            cmd: Command::RDA,
+            dist: 1,
+            val: s.nCWL + s.nBL + s.nWTRS,
+            sibling: false,
+        });
+        t[Command::WRA as usize].push(TimeEntry {
+            cmd: Command::RD,
+            dist: 1,
+            val: s.nCWL + s.nBL + s.nWTRS,
+            sibling: false,
+        });
+        t[Command::WRA as usize].push(TimeEntry {
+            cmd: Command::RDA,
+            dist: 1,
+            val: s.nCWL + s.nBL + s.nWTRS,
+            sibling: false,
+        });
+
+        t[Command::RD as usize].push(TimeEntry {
+            cmd: Command::PREA,
+            dist: 1,
+            val: s.nRTP,
+            sibling: false,
+        });
+        t[Command::WR as usize].push(TimeEntry {
+            cmd: Command::PREA,
+            dist: 1,
+            val: s.nCWL + s.nBL + s.nWR,
+            sibling: false,
+        });
+
+        // CAS <-> PD
+        t[Command::RD as usize].push(TimeEntry {
+            cmd: Command::PDE,
+            dist: 1,
+            val: s.nCL + s.nBL + 1,
+            sibling: false,
+        });
+        t[Command::RDA as usize].push(TimeEntry {
+            cmd: Command::PDE,
+            dist: 1,
+            val: s.nCL + s.nBL + 1,
+            sibling: false,
+        });
+        t[Command::WR as usize].push(TimeEntry {
+            cmd: Command::PDE,
+            dist: 1,
+            val: s.nCWL + s.nBL + s.nWR,
+            sibling: false,
+        });
+        t[Command::WRA as usize].push(TimeEntry {
+            cmd: Command::PDE,
+            dist: 1,
+            val: s.nCWL + s.nBL + s.nWR + 1,
+            sibling: false,
+        }); // +1 for pre
+        t[Command::PDX as usize].push(TimeEntry {
+            cmd: Command::RD,
+            dist: 1,
+            val: s.nXP,
+            sibling: false,
+        });
+        t[Command::PDX as usize].push(TimeEntry {
+            cmd: Command::RDA,
+            dist: 1,
+            val: s.nXP,
+            sibling: false,
+        });
+        t[Command::PDX as usize].push(TimeEntry {
+            cmd: Command::WR,
+            dist: 1,
+            val: s.nXP,
+            sibling: false,
+        });
+        t[Command::PDX as usize].push(TimeEntry {
+            cmd: Command::WRA,
+            dist: 1,
+            val: s.nXP,
+            sibling: false,
+        });
+
+        // CAS <-> SR: none (all banks have to be precharged)
+
+        // RAS <-> RAS
+        t[Command::ACT as usize].push(TimeEntry {
+            cmd: Command::ACT,
+            dist: 1,
+            val: s.nRRDS,
+            sibling: false,
+        });
+        t[Command::ACT as usize].push(TimeEntry {
+            cmd: Command::ACT,
+            dist: 4,
+            val: s.nFAW,
+            sibling: false,
+        });
+        t[Command::ACT as usize].push(TimeEntry {
+            cmd: Command::PREA,
+            dist: 1,
+            val: s.nRAS,
+            sibling: false,
+        });
+        t[Command::PREA as usize].push(TimeEntry {
+            cmd: Command::ACT,
+            dist: 1,
+            val: s.nRP,
+            sibling: false,
+        });
+
+        // RAS <-> REF
+        t[Command::PRE as usize].push(TimeEntry {
+            cmd: Command::REF,
+            dist: 1,
+            val: s.nRP,
+            sibling: false,
+        });
+        t[Command::PREA as usize].push(TimeEntry {
+            cmd: Command::REF,
+            dist: 1,
+            val: s.nRP,
+            sibling: false,
+        });
+        t[Command::REF as usize].push(TimeEntry {
+            cmd: Command::ACT,
+            dist: 1,
+            val: s.nRFC,
+            sibling: false,
+        });
+
+        // RAS <-> PD
+        t[Command::ACT as usize].push(TimeEntry {
+            cmd: Command::PDE,
+            dist: 1,
+            val: 1,
+            sibling: false,
+        });
+        t[Command::PDX as usize].push(TimeEntry {
+            cmd: Command::ACT,
+            dist: 1,
+            val: s.nXP,
+            sibling: false,
+        });
+        t[Command::PDX as usize].push(TimeEntry {
+            cmd: Command::PRE,
+            dist: 1,
+            val: s.nXP,
+            sibling: false,
+        });
+        t[Command::PDX as usize].push(TimeEntry {
+            cmd: Command::PREA,
+            dist: 1,
+            val: s.nXP,
+            sibling: false,
+        });
+
+        // RAS <-> SR
+        t[Command::PRE as usize].push(TimeEntry {
+            cmd: Command::SRE,
+            dist: 1,
+            val: s.nRP,
+            sibling: false,
+        });
+        t[Command::PREA as usize].push(TimeEntry {
+            cmd: Command::SRE,
+            dist: 1,
+            val: s.nRP,
+            sibling: false,
+        });
+        t[Command::SRX as usize].push(TimeEntry {
+            cmd: Command::ACT,
+            dist: 1,
+            val: s.nXS,
+            sibling: false,
+        });
+
+        // REF <-> REF
+        t[Command::REF as usize].push(TimeEntry {
+            cmd: Command::REF,
+            dist: 1,
+            val: s.nRFC,
+            sibling: false,
+        });
+
+        // REF <-> PD
+        t[Command::REF as usize].push(TimeEntry {
+            cmd: Command::PDE,
+            dist: 1,
+            val: 1,
+            sibling: false,
+        });
+        t[Command::PDX as usize].push(TimeEntry {
+            cmd: Command::REF,
+            dist: 1,
+            val: s.nXP,
+            sibling: false,
+        });
+
+        // REF <-> SR
+        t[Command::SRX as usize].push(TimeEntry {
+            cmd: Command::REF,
+            dist: 1,
+            val: s.nXS,
+            sibling: false,
+        });
+
+        // PD <-> PD
+        t[Command::PDE as usize].push(TimeEntry {
+            cmd: Command::PDX,
+            dist: 1,
+            val: s.nPD,
+            sibling: false,
+        });
+        t[Command::PDX as usize].push(TimeEntry {
+            cmd: Command::PDE,
+            dist: 1,
+            val: s.nXP,
+            sibling: false,
+        });
+
+        // PD <-> SR
+        t[Command::PDX as usize].push(TimeEntry {
+            cmd: Command::SRE,
+            dist: 1,
+            val: s.nXP,
+            sibling: false,
+        });
+        t[Command::SRX as usize].push(TimeEntry {
+            cmd: Command::PDE,
+            dist: 1,
+            val: s.nXS,
+            sibling: false,
+        });
+
+        // SR <-> SR
+        t[Command::SRE as usize].push(TimeEntry {
+            cmd: Command::SRX,
+            dist: 1,
+            val: s.nCKESR,
+            sibling: false,
+        });
+        t[Command::SRX as usize].push(TimeEntry {
+            cmd: Command::SRE,
+            dist: 1,
+            val: s.nXS,
+            sibling: false,
+        });
+
+        /*** Bank Group ***/
+        let t = timing.get_level_mut(Level::BankGroup);
+        // CAS <-> CAS
+        t[Command::RD as usize].push(TimeEntry {
+            cmd: Command::RD,
+            dist: 1,
+            val: s.nCCDL,
+            sibling: false,
+        });
+        t[Command::RD as usize].push(TimeEntry {
+            cmd: Command::RDA,
+            dist: 1,
+            val: s.nCCDL,
+            sibling: false,
+        });
+        t[Command::RDA as usize].push(TimeEntry {
+            cmd: Command::RD,
+            dist: 1,
+            val: s.nCCDL,
+            sibling: false,
+        });
+        t[Command::RDA as usize].push(TimeEntry {
+            cmd: Command::RDA,
+            dist: 1,
+            val: s.nCCDL,
+            sibling: false,
+        });
+        t[Command::WR as usize].push(TimeEntry {
+            cmd: Command::WR,
+            dist: 1,
+            val: s.nCCDL,
+            sibling: false,
+        });
+        t[Command::WR as usize].push(TimeEntry {
+            cmd: Command::WRA,
+            dist: 1,
+            val: s.nCCDL,
+            sibling: false,
+        });
+        t[Command::WRA as usize].push(TimeEntry {
+            cmd: Command::WR,
+            dist: 1,
+            val: s.nCCDL,
+            sibling: false,
+        });
+        t[Command::WRA as usize].push(TimeEntry {
+            cmd: Command::WRA,
+            dist: 1,
+            val: s.nCCDL,
+            sibling: false,
+        });
+        t[Command::WR as usize].push(TimeEntry {
+            cmd: Command::WR,
+            dist: 1,
+            val: s.nCCDL,
+            sibling: false,
+        });
+        t[Command::WR as usize].push(TimeEntry {
+            cmd: Command::WRA,
+            dist: 1,
+            val: s.nCCDL,
+            sibling: false,
+        });
+        t[Command::WRA as usize].push(TimeEntry {
+            cmd: Command::WR,
+            dist: 1,
+            val: s.nCCDL,
+            sibling: false,
+        });
+        t[Command::WRA as usize].push(TimeEntry {
+            cmd: Command::WRA,
+            dist: 1,
+            val: s.nCCDL,
+            sibling: false,
+        });
+        t[Command::WR as usize].push(TimeEntry {
+            cmd: Command::RD,
+            dist: 1,
+            val: s.nCWL + s.nBL + s.nWTRL,
+            sibling: false,
+        });
+        t[Command::WR as usize].push(TimeEntry {
+            cmd: Command::RDA,
+            dist: 1,
+            val: s.nCWL + s.nBL + s.nWTRL,
+            sibling: false,
+        });
+        t[Command::WRA as usize].push(TimeEntry {
+            cmd: Command::RD,
+            dist: 1,
+            val: s.nCWL + s.nBL + s.nWTRL,
+            sibling: false,
+        });
+        t[Command::WRA as usize].push(TimeEntry {
+            cmd: Command::RDA,
+            dist: 1,
+            val: s.nCWL + s.nBL + s.nWTRL,
+            sibling: false,
+        });
+
+        // RAS <-> RAS
+        t[Command::ACT as usize].push(TimeEntry {
+            cmd: Command::ACT,
+            dist: 1,
+            val: s.nRRDL,
+            sibling: false,
+        });
+
+        /*** Bank ***/
+        let t = timing.get_level_mut(Level::Bank);
+
+        // CAS <-> RAS
+        t[Command::ACT as usize].push(TimeEntry {
+            cmd: Command::RD,
+            dist: 1,
+            val: s.nRCDR,
+            sibling: false,
+        });
+        t[Command::ACT as usize].push(TimeEntry {
+            cmd: Command::RDA,
+            dist: 1,
+            val: s.nRCDR,
+            sibling: false,
+        });
+        t[Command::ACT as usize].push(TimeEntry {
+            cmd: Command::WR,
+            dist: 1,
+            val: s.nRCDW,
+            sibling: false,
+        });
+        t[Command::ACT as usize].push(TimeEntry {
+            cmd: Command::WRA,
+            dist: 1,
+            val: s.nRCDW,
+            sibling: false,
+        });
+
+        t[Command::RD as usize].push(TimeEntry {
+            cmd: Command::PRE,
+            dist: 1,
+            val: s.nRTP,
+            sibling: false,
+        });
+        t[Command::WR as usize].push(TimeEntry {
+            cmd: Command::PRE,
+            dist: 1,
+            val: s.nCWL + s.nBL + s.nWR,
+            sibling: false,
+        });
+
+        t[Command::RDA as usize].push(TimeEntry {
+            cmd: Command::ACT,
+            dist: 1,
+            val: s.nRTP + s.nRP,
+            sibling: false,
+        });
+        t[Command::WRA as usize].push(TimeEntry {
+            cmd: Command::ACT,
+            dist: 1,
+            val: s.nCWL + s.nBL + s.nWR + s.nRP,
+            sibling: false,
+        });
+
+        // RAS <-> RAS
+        t[Command::ACT as usize].push(TimeEntry {
+            cmd: Command::ACT,
+            dist: 1,
+            val: s.nRC,
+            sibling: false,
+        });
+        t[Command::ACT as usize].push(TimeEntry {
+            cmd: Command::PRE,
+            dist: 1,
+            val: s.nRAS,
+            sibling: false,
+        });
+        t[Command::PRE as usize].push(TimeEntry {
+            cmd: Command::ACT,
+            dist: 1,
+            val: s.nRP,
+            sibling: false,
+        });
+
+        // REFSB
+        t[Command::PRE as usize].push(TimeEntry {
+            cmd: Command::REFSB,
+            dist: 1,
+            val: s.nRP,
+            sibling: false,
+        });
+        t[Command::REFSB as usize].push(TimeEntry {
+            cmd: Command::REFSB,
+            dist: 1,
+            val: s.nRFC,
+            sibling: false,
+        });
+        t[Command::REFSB as usize].push(TimeEntry {
+            cmd: Command::ACT,
+            dist: 1,
+            val: s.nRFC,
+            sibling: false,
+        });
+
+        /*** PIM ***/
+        // the PIM commands access the banks like RD and WR, their data never leaves the chip
+        let t = timing.get_level_mut(rank);
+
+        // PIM <-> PIM
+        t[Command::MAC as usize].push(TimeEntry {
+            cmd: Command::MAC,
+            dist: 1,
+            val: s.nCCDS,
+            sibling: false,
+        });
+        t[Command::MAC as usize].push(TimeEntry {
+            cmd: Command::LDR,
+            dist: 1,
+            val: s.nCCDS,
+            sibling: false,
+        });
+        t[Command::MAC as usize].push(TimeEntry {
+            cmd: Command::STR,
+            dist: 1,
+            val: s.nCCDS,
+            sibling: false,
+        });
+        t[Command::LDR as usize].push(TimeEntry {
+            cmd: Command::MAC,
+            dist: 1,
+            val: s.nCCDS,
+            sibling: false,
+        });
+        t[Command::LDR as usize].push(TimeEntry {
+            cmd: Command::LDR,
+            dist: 1,
+            val: s.nCCDS,
+            sibling: false,
+        });
+        t[Command::LDR as usize].push(TimeEntry {
+            cmd: Command::STR,
+            dist: 1,
+            val: s.nCCDS,
+            sibling: false,
+        });
+        t[Command::STR as usize].push(TimeEntry {
+            cmd: Command::MAC,
+            dist: 1,
+            val: s.nCCDS,
+            sibling: false,
+        });
+        t[Command::STR as usize].push(TimeEntry {
+            cmd: Command::LDR,
+            dist: 1,
+            val: s.nCCDS,
+            sibling: false,
+        });
+        t[Command::STR as usize].push(TimeEntry {
+            cmd: Command::STR,
+            dist: 1,
+            val: s.nCCDS,
+            sibling: false,
+        });
+
+        // WRGB <-> WR, the operand takes the data bus like a write
+        t[Command::WR as usize].push(TimeEntry {
+            cmd: Command::WRGB,
+            dist: 1,
+            val: s.nCCDS,
+            sibling: false,
+        });
+        t[Command::WRA as usize].push(TimeEntry {
+            cmd: Command::WRGB,
+            dist: 1,
+            val: s.nCCDS,
+            sibling: false,
+        });
+        t[Command::WRGB as usize].push(TimeEntry {
+            cmd: Command::WR,
+            dist: 1,
+            val: s.nCCDS,
+            sibling: false,
+        });
+        t[Command::WRGB as usize].push(TimeEntry {
+            cmd: Command::WRA,
+            dist: 1,
+            val: s.nCCDS,
+            sibling: false,
+        });
+        t[Command::WRGB as usize].push(TimeEntry {
+            cmd: Command::WRGB,
+            dist: 1,
+            val: s.nCCDS,
+            sibling: false,
+        });
+        t[Command::WRGB as usize].push(TimeEntry {
+            cmd: Command::MAC,
+            dist: 1,
+            val: s.nCWL + s.nBL,
+            sibling: false,
+        });
+
+        // PIM <-> RAS
+        t[Command::MAC as usize].push(TimeEntry {
+            cmd: Command::PREA,
+            dist: 1,
+            val: s.nRTP,
+            sibling: false,
+        });
+        t[Command::LDR as usize].push(TimeEntry {
+            cmd: Command::PREA,
+            dist: 1,
+            val: s.nRTP,
+            sibling: false,
+        });
+        t[Command::STR as usize].push(TimeEntry {
+            cmd: Command::PREA,
+            dist: 1,
+            val: s.nWR,
+            sibling: false,
+        });
+
+        let t = timing.get_level_mut(Level::BankGroup);
+
+        // PIM <-> PIM
+        t[Command::MAC as usize].push(TimeEntry {
+            cmd: Command::MAC,
+            dist: 1,
+            val: s.nCCDL,
+            sibling: false,
+        });
+        t[Command::MAC as usize].push(TimeEntry {
+            cmd: Command::LDR,
+            dist: 1,
+            val: s.nCCDL,
+            sibling: false,
+        });
+        t[Command::MAC as usize].push(TimeEntry {
+            cmd: Command::STR,
+            dist: 1,
+            val: s.nCCDL,
+            sibling: false,
+        });
+        t[Command::LDR as usize].push(TimeEntry {
+            cmd: Command::MAC,
+            dist: 1,
+            val: s.nCCDL,
+            sibling: false,
+        });
+        t[Command::LDR as usize].push(TimeEntry {
+            cmd: Command::LDR,
+            dist: 1,
+            val: s.nCCDL,
+            sibling: false,
+        });
+        t[Command::LDR as usize].push(TimeEntry {
+            cmd: Command::STR,
+            dist: 1,
+            val: s.nCCDL,
+            sibling: false,
+        });
+        t[Command::STR as usize].push(TimeEntry {
+            cmd: Command::MAC,
+            dist: 1,
+            val: s.nCCDL,
+            sibling: false,
+        });
+        t[Command::STR as usize].push(TimeEntry {
+            cmd: Command::LDR,
+            dist: 1,
+            val: s.nCCDL,
+            sibling: false,
+        });
+        t[Command::STR as usize].push(TimeEntry {
+            cmd: Command::STR,
+            dist: 1,
+            val: s.nCCDL,
+            sibling: false,
+        });
+
+        let t = timing.get_level_mut(Level::Bank);
+
+        // PIM <-> RAS
+        t[Command::ACT as usize].push(TimeEntry {
+            cmd: Command::MAC,
+            dist: 1,
+            val: s.nRCDR,
+            sibling: false,
+        });
+        t[Command::ACT as usize].push(TimeEntry {
+            cmd: Command::LDR,
+            dist: 1,
+            val: s.nRCDR,
+            sibling: false,
+        });
+        t[Command::ACT as usize].push(TimeEntry {
+            cmd: Command::STR,
+            dist: 1,
+            val: s.nRCDW,
+            sibling: false,
+        });
+        t[Command::MAC as usize].push(TimeEntry {
+            cmd: Command::PRE,
+            dist: 1,
+            val: s.nRTP,
+            sibling: false,
+        });
+        t[Command::LDR as usize].push(TimeEntry {
+            cmd: Command::PRE,
+            dist: 1,
+            val: s.nRTP,
+            sibling: false,
+        });
+        t[Command::STR as usize].push(TimeEntry {
+            cmd: Command::PRE,
+            dist: 1,
+            val: s.nWR,
+            sibling: false,
+        });
+    }
+
+    pub fn get_speed(speed: &Speed) -> SpeedEntry {
+        match speed {
+            Speed::HBM_1Gbps => SpeedEntry {
+                rate: 1000,
+                freq: 500.0,
+                tCK: 2.0,
+                nBL: 2,
+                nCCDS: 2,
+                nCCDL: 3,
+                nCL: 7,
+                nRCDR: 7,
+                nRCDW: 6,
+                nRP: 7,
+                nCWL: 4,
+                nRAS: 17,
+                nRC: 24,
+                nRTP: 7,
+                nWTRS: 2,
+                nWTRL: 4,
+                nWR: 8,
+                nRRDS: 4,
+                nRRDL: 5,
+                nFAW: 20,
+                nRFC: 0,
+                nREFI: 1950,
+                nREFI1B: 0,
+                nPD: 5,
+                nXP: 5,
+                nCKESR: 5,
+                nXS: 0,
+            },
+            Speed::HBM2_2Gbps => SpeedEntry {
+                rate: 2000,
+                freq: 1000.0,
+                tCK: 1.0,
+                nBL: 2,
+                nCCDS: 2,
+                nCCDL: 4,
+                nCL: 14,
+                nRCDR: 14,
+                nRCDW: 10,
+                nRP: 14,
+                nCWL: 4,
+                nRAS: 34,
+                nRC: 48,
+                nRTP: 5,
+                nWTRS: 3,
+                nWTRL: 8,
+                nWR: 16,
+                nRRDS: 4,
+                nRRDL: 6,
+                nFAW: 16,
+                nRFC: 0,
+                nREFI: 3900,
+                nREFI1B: 0,
+                nPD: 6,
+                nXP: 8,
+                nCKESR: 7,
+                nXS: 0,
+            },
+        }
+    }
+}
+
+impl DramSpec for HBM {
+    fn from_config(config: &Config) -> Result<Self, ConfigError> {
+        let hbm2_org = matches!(config.hbm_org, HBMOrg::HBM2_4Gb | HBMOrg::HBM2_8Gb);
+        let hbm2_speed = matches!(config.hbm_speed, Speed::HBM2_2Gbps);
+        if hbm2_org != hbm2_speed {
+            return Err(ConfigError::Unsupported {
+                key: "speed".to_string(),
+                reason: format!(
+                    "{:?} does not match the org {:?}",
+                    config.hbm_speed, config.hbm_org
+                ),
+            });
+        }
+        if config.pseudo_channel && !hbm2_org {
+            return Err(ConfigError::Unsupported {
+                key: "pseudo_channel".to_string(),
+                reason: format!("{:?} is not an HBM2 org", config.hbm_org),
+            });
+        }
+        Ok(Self::new(config))
+    }
+    fn supports_per_bank_refresh() -> bool {
+        true
+    }
+    fn supports_pim() -> bool {
+        true
+    }
+    fn get_first_cmd(req_type: &ReqType) -> Command {
+        match req_type {
+            ReqType::Read => Command::RD,
+            ReqType::Write => Command::WR,
+            ReqType::Refresh => Command::REF,
+            ReqType::RefreshBank => Command::REFSB,
+            ReqType::PimMac => Command::MAC,
+            ReqType::PimMacAllBank => Command::MACAB,
+            ReqType::PimLoad => Command::LDR,
+            ReqType::PimStore => Command::STR,
+            ReqType::PimBroadcast => Command::WRGB,
+        }
+    }
+
+    fn get_pre_cmd(dram: &Dram<Self>, cmd: &Command, child_id: u64) -> Option<Command> {
+        match (&dram.level, cmd) {
+            (
+                Level::Rank | Level::PseudoChannel,
+                Command::RD
+                | Command::WR
+                | Command::MAC
+                | Command::LDR
+                | Command::STR
+                | Command::WRGB
+                | Command::MACAB,
+            ) => match dram.state {
+                dram::State::PowerUp => None,
+                dram::State::ActPowerDown => Some(Command::PDX),
+                dram::State::PrePowerDown => Some(Command::PDX),
+                dram::State::SelfRefresh => Some(Command::SRX),
+                _ => unreachable!("invalid dram state"),
+            },
+            (Level::Rank | Level::PseudoChannel, Command::REF) => {
+                if dram.children.iter().any(|bank_group| {
+                    bank_group
+                        .children
+                        .iter()
+                        .any(|bank| !matches!(bank.state, dram::State::Closed))
+                }) {
+                    Some(Command::PREA)
+                } else {
+                    Some(Command::REF)
+                }
+            }
+            (Level::Rank | Level::PseudoChannel, Command::PDE) => match dram.state {
+                dram::State::PowerUp | dram::State::ActPowerDown | dram::State::PrePowerDown => {
+                    Some(Command::PDE)
+                }
+                dram::State::SelfRefresh => Some(Command::SRX),
+                _ => unreachable!("invalid dram state"),
+            },
+            (Level::Rank | Level::PseudoChannel, Command::SRE) => match dram.state {
+                dram::State::PowerUp | dram::State::SelfRefresh => Some(Command::SRE),
+                dram::State::ActPowerDown | dram::State::PrePowerDown => Some(Command::PDX),
+                _ => unreachable!("invalid dram state"),
+            },
+
+            (
+                Level::Bank,
+                Command::RD | Command::WR | Command::MAC | Command::LDR | Command::STR,
+            ) => match dram.state {
+                dram::State::Closed => Some(Command::ACT),
+                dram::State::Opened(row_id) => {
+                    if row_id == child_id {
+                        Some(*cmd)
+                    } else {
+                        Some(Command::PRE)
+                    }
+                }
+                _ => unreachable!("invalid dram state"),
+            },
+            (Level::Bank, Command::REFSB) => match dram.state {
+                dram::State::Closed => Some(Command::REFSB),
+                _ => Some(Command::PRE),
+            },
+            (Level::Bank, Command::MACAB) => match dram.state {
+                dram::State::Closed => Some(Command::ACTAB),
+                dram::State::Opened(row_id) if row_id == child_id => None,
+                dram::State::Opened(_) => Some(Command::PREAB),
+                _ => unreachable!("invalid dram state"),
+            },
+            _ => None,
+        }
+    }
+
+    fn get_start_state(level: &Level) -> dram::State {
+        match level {
+            Level::Rank | Level::PseudoChannel => dram::State::PowerUp,
+            Level::Bank => dram::State::Closed,
+            Level::Row => dram::State::Closed,
+            _ => dram::State::NoUse,
+        }
+    }
+
+    fn get_levels(&self) -> &[Level] {
+        self.levels
+    }
+
+    fn get_rank_level(&self) -> Level {
+        self.levels[1]
+    }
+
+    fn get_addr_bits(&self) -> &[usize] {
+        &self.addr_bits
+    }
+
+    fn get_child_size(&self) -> &[usize] {
+        &self.child_size
+    }
+
+    fn get_scope(&self, cmd: &Command) -> Level {
+        match cmd {
+            Command::ACT | Command::ACTAB => Level::Row,
+            Command::PRE | Command::PREAB | Command::REFSB | Command::REFPB => Level::Bank,
+            Command::RD
+            | Command::WR
+            | Command::RDA
+            | Command::WRA
+            | Command::MAC
+            | Command::LDR
+            | Command::STR
+            | Command::MACAB => Level::Column,
+            Command::PREA
+            | Command::REF
+            | Command::PDE
+            | Command::PDX
+            | Command::SRE
+            | Command::SRX
+            | Command::WRGB => self.get_rank_level(),
+            Command::Max => unreachable!(),
+        }
+    }
+
+    fn update_state(&self, dram: &mut Dram<Self>, cmd: &Command, child_id: u64) {
+        let banks_closed = |dram: &Dram<Self>| {
+            dram.children.iter().all(|bank_group| {
+                bank_group
+                    .children
+                    .iter()
+                    .all(|bank| bank.state == State::Closed)
+            })
+        };
+        match (dram.level, cmd) {
+            (Level::Bank, Command::ACT | Command::ACTAB) => {
+                dram.state = State::Opened(child_id);
+            }
+            (Level::Bank, Command::PRE | Command::PREAB | Command::RDA | Command::WRA) => {
+                dram.state = State::Closed;
+            }
+            (Level::Rank | Level::PseudoChannel, Command::PREA) => {
+                dram.children.iter_mut().for_each(|bank_group| {
+                    bank_group.children.iter_mut().for_each(|bank| {
+                        bank.state = State::Closed;
+                    })
+                });
+            }
+            (Level::Rank | Level::PseudoChannel, Command::PDE) => {
+                dram.state = if banks_closed(dram) {
+                    State::PrePowerDown
+                } else {
+                    State::ActPowerDown
+                };
+            }
+            (Level::Rank | Level::PseudoChannel, Command::PDX | Command::SRX) => {
+                dram.state = State::PowerUp;
+            }
+            (Level::Rank | Level::PseudoChannel, Command::SRE) => {
+                dram.state = State::SelfRefresh;
+            }
+            _ => {}
+        }
+    }
+    fn get_timming(&self, depth: usize, cmd: &Command) -> &[TimeEntry] {
+        self.timing.get(depth, cmd)
+    }
+
+    fn get_read_latency(&self) -> u64 {
+        self.read_latency
+    }
+
+    fn get_refresh_interval(&self) -> u64 {
+        self.speed_entry.nREFI
+    }
+
+    fn get_clk_ns(&self) -> f64 {
+        self.speed_entry.tCK
+    }
+
+    fn get_transaction_bytes(&self) -> u64 {
+        PREFETCH_SIZE * self.channel_width / 8
+    }
+
+    fn get_burst_cycles(&self) -> u64 {
+        self.speed_entry.nBL
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use super::{HBMOrg, Speed, HBM};
+    use crate::{
+        command::Command,
+        config::{Config, Standard},
+        controller::Controller,
+        dram::{Dram, DramSpec, State},
+        memory::{Level, MemoryTrait, SimpleMemory},
+        request::{ReqType, Request},
+    };
+
+    fn get_timing(hbm: &Arc<HBM>, level: Level, from: Command, to: Command) -> Option<u64> {
+        hbm.get_timming(hbm.get_level_index(level), &from)
+            .iter()
+            .find(|t| t.cmd == to)
+            .map(|t| t.val)
+    }
+
+    /// issue the reads and return the cycles when they finish
+    fn run_reads(config: &Config, hbm: &Arc<HBM>, addr_vecs: &[Vec<u64>]) -> Vec<u64> {
+        let channel = Dram::new(hbm.clone(), Level::Channel, hbm.get_child_size());
+        let mut controller = Controller::new(config, channel);
+        for addr_vec in addr_vecs {
+            let req = Request::with_addr_vec(addr_vec.clone(), ReqType::Read);
+            controller.try_enqueue(req).unwrap();
+        }
+        let mut finished = vec![];
+        let mut clk = 0;
+        while finished.len() < addr_vecs.len() {
+            clk += 1;
+            controller.tick(clk);
+            finished.extend(controller.finished_queue.drain(..).map(|_| clk));
+        }
+        finished
+    }
+
+    #[test]
+    fn test_hbm() {
+        let config = Config::parse(include_str!("../../ramulator/configs/HBM-config.cfg")).unwrap();
+        assert_eq!(config.standard, Standard::HBM);
+        assert_eq!(config.hbm_org, HBMOrg::HBM_4Gb);
+        assert_eq!(config.hbm_speed, Speed::HBM_1Gbps);
+        let hbm = Arc::new(HBM::new(&config));
+        assert_eq!(hbm.get_child_size(), [8, 1, 4, 4, 1 << 14, 1 << 7]);
+        assert_eq!(hbm.get_read_latency(), 7 + 2);
+        assert_eq!(hbm.get_transaction_bytes(), 64);
+        assert_eq!(hbm.get_clk_ns(), 2.0);
+        assert_eq!(
+            get_timing(&hbm, Level::Rank, Command::REF, Command::ACT),
+            Some(130)
+        );
+        assert_eq!(
+            get_timing(&hbm, Level::Bank, Command::REFSB, Command::ACT),
+            Some(130)
+        );
+        assert_eq!(
+            get_timing(&hbm, Level::Channel, Command::RD, Command::RD),
+            Some(2)
+        );
+        assert_eq!(
+            get_timing(&hbm, Level::BankGroup, Command::RD, Command::RD),
+            Some(3)
+        );
+
+        // the reads to another bank group only wait for nCCDS, the same bank group for nCCDL
+        let other_bank_group = run_reads(
+            &config,
+            &hbm,
+            &[vec![0, 0, 0, 0, 0, 0], vec![0, 0, 1, 0, 0, 0]],
+        );
+        let same_bank_group = run_reads(
+            &config,
+            &hbm,
+            &[vec![0, 0, 0, 0, 0, 0], vec![0, 0, 0, 1, 0, 0]],
+        );
+        assert_eq!(same_bank_group[0], other_bank_group[0]);
+        assert!(same_bank_group[1] > other_bank_group[1]);
+
+        // an 8-channel stack
+        let mut memory = SimpleMemory::with_config(&config, hbm.clone()).unwrap();
+        for i in 0..64 {
+            memory
+                .try_send(Request::new(i << 6, ReqType::Read))
+                .unwrap();
+        }
+        let stats = memory.finish();
+        assert_eq!(stats.get_scalar("read_requests"), Some(64.0));
+        let Some(crate::stats::StatValue::Vector(per_channel)) =
+            stats.get("incoming_requests_per_channel")
+        else {
+            panic!("missing incoming_requests_per_channel");
+        };
+        assert_eq!(per_channel, &vec![8.0; 8]);
+    }
+
+    #[test]
+    fn test_hbm2_pseudo_channel() {
+        let config = Config::parse(
+            "standard = HBM2\nchannels = 8\norg = HBM2_8Gb\nspeed = HBM2_2Gbps\npseudo_channel = on",
+        )
+        .unwrap();
+        assert!(config.pseudo_channel);
+        let hbm = Arc::new(HBM::new(&config));
+        assert_eq!(hbm.get_child_size(), [8, 2, 4, 4, 1 << 15, 1 << 6]);
+        assert_eq!(hbm.get_transaction_bytes(), 32);
+        assert_eq!(
+            get_timing(&hbm, Level::PseudoChannel, Command::REF, Command::ACT),
+            Some(350)
+        );
+        // the pseudo channels do not share the data bus
+        assert_eq!(
+            get_timing(&hbm, Level::Channel, Command::RD, Command::RD),
+            None
+        );
+        assert_eq!(
+            get_timing(&hbm, Level::PseudoChannel, Command::RD, Command::RD),
+            Some(2)
+        );
+
+        // the pseudo channels are refreshed independently
+        let channel = Dram::new(hbm.clone(), Level::Channel, hbm.get_child_size());
+        assert_eq!(channel.children.len(), 2);
+        let mut controller = Controller::new(&config, channel);
+        for clk in 1..=hbm.get_refresh_interval() + 10 {
+            controller.tick(clk);
+        }
+        for pseudo_channel in &controller.channel.children {
+            assert_eq!(pseudo_channel.cmd_counts[Command::REF as usize], 1);
+        }
+
+        let mut memory = SimpleMemory::with_config(&config, hbm.clone()).unwrap();
+        for i in 0..32 {
+            memory
+                .try_send(Request::new(i << 6, ReqType::Write))
+                .unwrap();
+        }
+        let stats = memory.finish();
+        assert_eq!(stats.get_scalar("write_requests"), Some(32.0));
+    }
+
+    #[test]
+    fn test_all_bank_pim() {
+        let config = Config::parse("standard = HBM2\norg = HBM2_8Gb\nspeed = HBM2_2Gbps").unwrap();
+        let hbm = Arc::new(HBM::new(&config));
+        let n_rcdr = get_timing(&hbm, Level::Bank, Command::ACTAB, Command::MACAB).unwrap();
+        assert_eq!(
+            Some(n_rcdr),
+            get_timing(&hbm, Level::Bank, Command::ACT, Command::MAC)
+        );
+        // the activation window limits the ACTs, but not ACTAB
+        let faw = |from| {
+            hbm.get_timming(hbm.get_level_index(Level::Rank), &from)
+                .iter()
+                .any(|t| t.dist > 1)
+        };
+        assert!(faw(Command::ACT));
+        assert!(!faw(Command::ACTAB));
+
+        // one ACTAB opens the row in all the banks of the rank
+        let mut channel = Dram::new(hbm.clone(), Level::Channel, hbm.get_child_size());
+        let all_banks = [0, 0, u64::MAX, u64::MAX, 5, 0];
+        assert_eq!(channel.decode(&Command::MACAB, &all_banks), Command::ACTAB);
+        channel.update(&Command::ACTAB, &all_banks, 0);
+        let banks = channel.get_nodes(Level::Bank);
+        assert_eq!(banks.len(), 16);
+        for bank in &banks {
+            assert!(bank.state == State::Opened(5));
+            assert_eq!(bank.cmd_counts[Command::ACTAB as usize], 1);
+        }
+        assert_eq!(channel.children[0].cmd_counts[Command::ACTAB as usize], 1);
+        assert_eq!(channel.decode(&Command::MACAB, &all_banks), Command::MACAB);
+        assert!(!channel.check(&Command::MACAB, &all_banks, n_rcdr - 1));
+        assert!(channel.check(&Command::MACAB, &all_banks, n_rcdr));
+        channel.update(&Command::MACAB, &all_banks, n_rcdr);
+
+        // another row needs all the banks precharged first
+        let other_row = [0, 0, u64::MAX, u64::MAX, 6, 0];
+        assert_eq!(channel.decode(&Command::MACAB, &other_row), Command::PREAB);
+        // when the banks disagree, they are all closed too
+        channel.update(&Command::PRE, &[0, 0, 3, 3, 5, 0], 100);
+        assert_eq!(channel.decode(&Command::MACAB, &all_banks), Command::PREAB);
+        channel.update(&Command::PREAB, &all_banks, 100);
+        assert!(channel
+            .get_nodes(Level::Bank)
+            .iter()
+            .all(|bank| bank.state == State::Closed));
+
+        // the bank group set targets only its banks
+        channel.update(&Command::ACTAB, &[0, 0, 1, u64::MAX, 7, 0], 200);
+        for (i, bank_group) in channel.children[0].children.iter().enumerate() {
+            for bank in &bank_group.children {
+                assert_eq!(bank.state == State::Opened(7), i == 1);
+            }
+        }
+
+        // the all-bank MAC through the controller
+        let channel = Dram::new(hbm.clone(), Level::Channel, hbm.get_child_size());
+        let mut controller = Controller::new(&config, channel);
+        for column in 0..2 {
+            let addr_vec = vec![0, 0, u64::MAX, u64::MAX, 5, column];
+            controller
+                .try_enqueue(Request::with_addr_vec(addr_vec, ReqType::PimMacAllBank))
+                .unwrap();
+        }
+        let mut finished = 0;
+        let mut clk = 0;
+        while finished < 2 {
+            clk += 1;
+            controller.tick(clk);
+            finished += controller.finished_queue.drain(..).count();
+        }
+        for bank in controller.channel.get_nodes(Level::Bank) {
+            assert_eq!(bank.cmd_counts[Command::ACTAB as usize], 1);
+            assert_eq!(bank.cmd_counts[Command::MACAB as usize], 2);
+            assert_eq!(bank.cmd_counts[Command::ACT as usize], 0);
+        }
+        assert_eq!(controller.row_table.table.len(), 16);
+        assert_eq!(controller.row_table.get_hits(&[0, 0, 2, 3, 5, 0], false), 2);
+    }
+}
@@ -0,0 +1,532 @@
+use std::sync::Arc;
+
+#[cfg(feature = "functional")]
+use crate::functional;
+use crate::{
+    config::Config,
+    dram::DramSpec,
+    memory::{Level, MemoryTrait},
+    request::{ReqType, Request},
+};
+
+/// the bytes of an element, the fp16 of the PIM units
+const ELEMENT_BYTES: u64 = 2;
+
+/// the common kernels of the PIM workloads, the sizes are in elements
+#[derive(Debug, Clone)]
+pub enum Kernel {
+    /// y = A * x, `A` is a row-major `rows` x `cols` matrix for the host, the PIM units find it
+    /// at `KernelRunner::gemv_matrix_addr` and leave y at `KernelRunner::gemv_out_addr`
+    Gemv { rows: u64, cols: u64 },
+    /// c = a + b, the PIM units add b with a MAC by the ones broadcast from `out`
+    VectorAdd { len: u64 },
+    /// b = s * a, the scalar s is at the address of b
+    VectorScale { len: u64 },
+    /// the dot product of a and b, the PIM units leave the partial sums of their registers in the
+    /// consecutive columns of the result
+    DotProduct { len: u64 },
+    /// sum the rows of the embedding table picked by `indices`, each row has `dim` elements. the
+    /// table is row-major for the host, the PIM units find it at
+    /// `KernelRunner::embedding_table_addr` and leave the sum at `KernelRunner::embedding_out_addr`
+    EmbeddingSum { dim: u64, indices: Vec<u64> },
+}
+
+/// who executes the kernel
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExecutionMode {
+    /// the PIM units compute next to the banks, only the commands take the channel
+    Pim,
+    /// the host reads the operands and writes the results back through the channel
+    Host,
+}
+
+/// the physical addresses of the operands
+///
+/// a PIM unit only computes on its banks and a request picks the register by its column, so the
+/// other PIM kernels need the same offsets of a, b and out to map to the same unit and to the
+/// same column modulo the registers, e.g. the starts of the rows of one bank. GEMV and
+/// EmbeddingSum lay out their operands themselves, see `KernelRunner::gemv_matrix_addr` and
+/// `KernelRunner::embedding_table_addr`
+#[derive(Debug, Clone, Copy)]
+pub struct Placement {
+    /// the matrix of GEMV, the table of EmbeddingSum or the first vector
+    pub a: u64,
+    /// the vector x of GEMV, the scalar of VectorScale or the second vector
+    pub b: u64,
+    /// the result
+    pub out: u64,
+}
+
+/// the outcome of a kernel run
+#[derive(Debug, Clone, Copy)]
+pub struct KernelReport {
+    /// the memory cycles from the first request sent to the last one finished
+    pub cycles: u64,
+    pub requests: u64,
+    /// the bytes of the columns accessed by the requests
+    pub bytes: u64,
+    /// the bytes per cycle over the peak bandwidth of the channels, a PIM kernel goes over 1 when
+    /// the banks compute in parallel
+    pub bandwidth_utilization: f64,
+}
+
+/// generate the command streams of the kernels for a `DramSpec` and run them on a memory
+pub struct KernelRunner<T> {
+    spec: Arc<T>,
+    placement: Placement,
+    /// the registers of a PIM unit, the partial sums of a reduction are spread over them
+    registers: u64,
+    banks_per_unit: u64,
+}
+impl<T> KernelRunner<T>
+where
+    T: DramSpec,
+{
+    pub fn new(config: &Config, spec: Arc<T>, placement: Placement) -> Self {
+        Self {
+            spec,
+            placement,
+            registers: config.pim_registers as u64,
+            banks_per_unit: config.pim_banks_per_unit as u64,
+        }
+    }
+    /// the address of the `block`-th column of the row `row` of the `cols` wide matrix of GEMV
+    /// in the PIM mode, where the host puts the matrix before the run
+    pub fn gemv_matrix_addr(
+        &self,
+        memory: &dyn MemoryTrait,
+        cols: u64,
+        row: u64,
+        block: u64,
+    ) -> u64 {
+        let layout = PimLayout::new(self, memory);
+        let blocks = (cols * ELEMENT_BYTES).div_ceil(self.spec.get_transaction_bytes());
+        memory.get_addr(&layout.matrix_addr_vec(blocks, row, block))
+    }
+    /// the line the PIM units store the partial sums of the row `row` to, its lanes add up to the
+    /// element of the result
+    pub fn gemv_out_addr(&self, memory: &dyn MemoryTrait, row: u64) -> u64 {
+        let layout = PimLayout::new(self, memory);
+        memory.get_addr(&layout.out_addr_vec(row))
+    }
+    /// the address of the `block`-th column of the row `index` of the `dim` wide table of
+    /// EmbeddingSum in the PIM mode, where the host puts the table before the run
+    pub fn embedding_table_addr(
+        &self,
+        memory: &dyn MemoryTrait,
+        dim: u64,
+        index: u64,
+        block: u64,
+    ) -> u64 {
+        let layout = PimLayout::new(self, memory);
+        let blocks = (dim * ELEMENT_BYTES).div_ceil(self.spec.get_transaction_bytes());
+        memory.get_addr(&layout.table_addr_vec(layout.passes(blocks), index, block))
+    }
+    /// the line the PIM units store the `block`-th column of the sum of EmbeddingSum to
+    pub fn embedding_out_addr(&self, memory: &dyn MemoryTrait, block: u64) -> u64 {
+        let layout = PimLayout::new(self, memory);
+        memory.get_addr(&layout.out_addr_vec(block))
+    }
+    /// the requests of `kernel`, in the order they are sent, the memory maps their addresses
+    pub fn requests(
+        &self,
+        memory: &dyn MemoryTrait,
+        kernel: &Kernel,
+        mode: ExecutionMode,
+    ) -> Vec<Request> {
+        // the memory would reject the PIM requests forever
+        assert!(
+            mode == ExecutionMode::Host || T::supports_pim(),
+            "the standard has no PIM commands"
+        );
+        let column_bytes = self.spec.get_transaction_bytes();
+        let columns = |elements: u64| (elements * ELEMENT_BYTES).div_ceil(column_bytes);
+        // the requests to the consecutive columns of an operand
+        let stream = |base: u64, elements: u64, req_type: ReqType| {
+            (0..columns(elements)).map(move |i| Request::new(base + i * column_bytes, req_type))
+        };
+        let Placement { a, b, out } = self.placement;
+        let mut reqs = vec![];
+        match (kernel, mode) {
+            (&Kernel::Gemv { rows, cols }, ExecutionMode::Pim) => {
+                // every pass broadcasts the columns of x, multiplies each one with the same
+                // column of the rows in the slots, then stores the slots
+                let layout = PimLayout::new(self, memory);
+                assert!(
+                    layout.in_rank(&memory.get_addr_vec(b)),
+                    "x of GEMV must be in the rank of the matrix"
+                );
+                let slots = layout.slots();
+                let blocks = columns(cols);
+                for pass in 0..layout.passes(rows) {
+                    let pass_rows = pass * slots..rows.min((pass + 1) * slots);
+                    for j in 0..blocks {
+                        reqs.push(Request::new(b + j * column_bytes, ReqType::PimBroadcast));
+                        reqs.extend(pass_rows.clone().map(|i| {
+                            let addr_vec = layout.matrix_addr_vec(blocks, i, j);
+                            Request::with_addr_vec(addr_vec, ReqType::PimMac)
+                        }));
+                    }
+                    reqs.extend(pass_rows.map(|i| {
+                        Request::with_addr_vec(layout.out_addr_vec(i), ReqType::PimStore)
+                    }));
+                }
+            }
+            (&Kernel::Gemv { rows, cols }, ExecutionMode::Host) => {
+                reqs.extend(stream(b, cols, ReqType::Read));
+                let row_bytes = cols * ELEMENT_BYTES;
+                for i in 0..rows {
+                    reqs.extend(stream(a + i * row_bytes, cols, ReqType::Read));
+                }
+                reqs.extend(stream(out, rows, ReqType::Write));
+            }
+            (&Kernel::VectorAdd { len }, ExecutionMode::Pim) => {
+                // an earlier kernel may have left another operand in the global buffer
+                reqs.push(write_ones(out));
+                reqs.push(Request::new(out, ReqType::PimBroadcast));
+                for i in 0..columns(len) {
+                    let offset = i * column_bytes;
+                    reqs.push(Request::new(a + offset, ReqType::PimLoad));
+                    reqs.push(Request::new(b + offset, ReqType::PimMac));
+                    reqs.push(Request::new(out + offset, ReqType::PimStore));
+                }
+            }
+            (&Kernel::VectorAdd { len }, ExecutionMode::Host) => {
+                for i in 0..columns(len) {
+                    let offset = i * column_bytes;
+                    reqs.push(Request::new(a + offset, ReqType::Read));
+                    reqs.push(Request::new(b + offset, ReqType::Read));
+                    reqs.push(Request::new(out + offset, ReqType::Write));
+                }
+            }
+            (&Kernel::VectorScale { len }, ExecutionMode::Pim) => {
+                reqs.push(Request::new(b, ReqType::PimBroadcast));
+                for i in 0..columns(len) {
+                    let offset = i * column_bytes;
+                    reqs.push(Request::new(a + offset, ReqType::PimMac));
+                    reqs.push(Request::new(out + offset, ReqType::PimStore));
+                }
+            }
+            (&Kernel::VectorScale { len }, ExecutionMode::Host) => {
+                reqs.push(Request::new(b, ReqType::Read));
+                for i in 0..columns(len) {
+                    let offset = i * column_bytes;
+                    reqs.push(Request::new(a + offset, ReqType::Read));
+                    reqs.push(Request::new(out + offset, ReqType::Write));
+                }
+            }
+            (&Kernel::DotProduct { len }, ExecutionMode::Pim) => {
+                // broadcast a column of a and multiply it with the same column of b, the column
+                // picks the register accumulating it
+                for i in 0..columns(len) {
+                    let offset = i * column_bytes;
+                    reqs.push(Request::new(a + offset, ReqType::PimBroadcast));
+                    reqs.push(Request::new(b + offset, ReqType::PimMac));
+                }
+                reqs.extend(stream(
+                    out,
+                    columns(len).min(self.registers) * column_bytes / ELEMENT_BYTES,
+                    ReqType::PimStore,
+                ));
+            }
+            (&Kernel::DotProduct { len }, ExecutionMode::Host) => {
+                for i in 0..columns(len) {
+                    let offset = i * column_bytes;
+                    reqs.push(Request::new(a + offset, ReqType::Read));
+                    reqs.push(Request::new(b + offset, ReqType::Read));
+                }
+                reqs.push(Request::new(out, ReqType::Write));
+            }
+            (Kernel::EmbeddingSum { dim, indices }, ExecutionMode::Pim) => {
+                // every pass adds the columns of the picked rows in the slots by the ones of the
+                // global buffer, then stores the slots
+                let layout = PimLayout::new(self, memory);
+                let slots = layout.slots();
+                let blocks = columns(*dim);
+                let passes = layout.passes(blocks);
+                reqs.push(write_ones(out));
+                reqs.push(Request::new(out, ReqType::PimBroadcast));
+                for pass in 0..passes {
+                    let pass_blocks = pass * slots..blocks.min((pass + 1) * slots);
+                    for &index in indices {
+                        reqs.extend(pass_blocks.clone().map(|j| {
+                            let addr_vec = layout.table_addr_vec(passes, index, j);
+                            Request::with_addr_vec(addr_vec, ReqType::PimMac)
+                        }));
+                    }
+                    reqs.extend(pass_blocks.map(|j| {
+                        Request::with_addr_vec(layout.out_addr_vec(j), ReqType::PimStore)
+                    }));
+                }
+            }
+            (Kernel::EmbeddingSum { dim, indices }, ExecutionMode::Host) => {
+                let row_bytes = dim * ELEMENT_BYTES;
+                for index in indices {
+                    reqs.extend(stream(a + index * row_bytes, *dim, ReqType::Read));
+                }
+                reqs.extend(stream(out, *dim, ReqType::Write));
+            }
+        }
+        reqs
+    }
+    /// send the requests of `kernel` to `memory` and wait for all of them to finish
+    pub fn run(
+        &self,
+        memory: &mut dyn MemoryTrait,
+        kernel: &Kernel,
+        mode: ExecutionMode,
+    ) -> KernelReport {
+        let reqs = self.requests(memory, kernel, mode);
+        let requests = reqs.len() as u64;
+        let mut cycles = 0;
+        for mut req in reqs {
+            while let Err(rejected) = memory.try_send(req) {
+                req = rejected;
+                memory.tick();
+                cycles += 1;
+            }
+        }
+        while memory.pending_requests() > 0 {
+            memory.tick();
+            cycles += 1;
+        }
+        while memory.try_recv().is_some() {}
+
+        let bytes = requests * self.spec.get_transaction_bytes();
+        let channels = self.spec.get_child_size()[0] as u64;
+        let peak_bytes_per_cycle = (channels * self.spec.get_transaction_bytes()) as f64
+            / self.spec.get_burst_cycles() as f64;
+        KernelReport {
+            cycles,
+            requests,
+            bytes,
+            bandwidth_utilization: bytes as f64 / (cycles.max(1) as f64 * peak_bytes_per_cycle),
+        }
+    }
+}
+
+/// the write of a line of ones to `addr`, the PIM units take it as the global buffer to add the
+/// columns of their MACs
+fn write_ones(addr: u64) -> Request {
+    #[cfg(feature = "functional")]
+    return Request::with_data(addr, functional::ones());
+    #[cfg(not(feature = "functional"))]
+    Request::new(addr, ReqType::Write)
+}
+
+/// where GEMV and EmbeddingSum keep their operands in the PIM mode. an element of the result, a
+/// row of GEMV or a column of the sum of EmbeddingSum, is accumulated by one register of one unit,
+/// its slot, the units of the rank of `a` take the consecutive elements in turn so their MACs go
+/// to different banks, the elements beyond the slots of the rank are computed in later passes
+///
+/// a unit keeps its lines in its bank from the DRAM row of `a` on, the line l of the register r
+/// is the line `l * registers + r` of the unit, which maps it to the column of the register. the
+/// `block`-th column of the row of GEMV in the pass p is the line `p * blocks + block` of its
+/// slot, the column of the row `index` of the table of EmbeddingSum in the pass p is the line
+/// `index * passes + p`. the results of the slots of the pass p are their lines p from the DRAM
+/// row of `out`, which has to be in the rank of `a`
+struct PimLayout {
+    /// the addr_vecs of the units down to their first bank
+    units: Vec<Vec<u64>>,
+    registers: u64,
+    out_row: u64,
+    rank_index: usize,
+    row_index: usize,
+    column_index: usize,
+    columns: u64,
+}
+impl PimLayout {
+    fn new<T>(runner: &KernelRunner<T>, memory: &dyn MemoryTrait) -> Self
+    where
+        T: DramSpec,
+    {
+        let spec = &*runner.spec;
+        let Placement { a, out, .. } = runner.placement;
+        let rank_index = spec.get_level_index(spec.get_rank_level());
+        let bank_index = spec.get_level_index(Level::Bank);
+        let row_index = spec.get_level_index(Level::Row);
+        let column_index = spec.get_level_index(Level::Column);
+        let child_size = spec.get_child_size();
+        let columns = child_size[column_index] as u64;
+        let base = memory.get_addr_vec(a);
+        let out_vec = memory.get_addr_vec(out);
+        assert!(
+            out_vec[..=rank_index] == base[..=rank_index],
+            "the result must be in the rank of the operands of the PIM units"
+        );
+        assert!(
+            [&base, &out_vec].iter().all(|addr_vec| {
+                addr_vec[rank_index + 1..=bank_index]
+                    .iter()
+                    .all(|&i| i == 0)
+                    && addr_vec[column_index] == 0
+            }),
+            "the operands and the result must start a row of the first bank"
+        );
+        assert_eq!(
+            columns % runner.registers,
+            0,
+            "the registers must divide the columns of a row"
+        );
+        let mut units = vec![base];
+        for level in rank_index + 1..=bank_index {
+            let step = if level == bank_index {
+                runner.banks_per_unit as usize
+            } else {
+                1
+            };
+            units = (0..child_size[level])
+                .step_by(step)
+                .flat_map(|child| {
+                    units.iter().map(move |unit| {
+                        let mut unit = unit.clone();
+                        unit[level] = child as u64;
+                        unit
+                    })
+                })
+                .collect();
+        }
+        Self {
+            units,
+            registers: runner.registers,
+            out_row: out_vec[row_index],
+            rank_index,
+            row_index,
+            column_index,
+            columns,
+        }
+    }
+    /// the elements computed in a pass
+    fn slots(&self) -> u64 {
+        self.units.len() as u64 * self.registers
+    }
+    /// the passes computing `elements`
+    fn passes(&self, elements: u64) -> u64 {
+        elements.div_ceil(self.slots())
+    }
+    /// the pass, the unit and the register of the element
+    fn slot(&self, element: u64) -> (u64, usize, u64) {
+        let units = self.units.len() as u64;
+        let slot = element % self.slots();
+        (
+            element / self.slots(),
+            (slot % units) as usize,
+            slot / units,
+        )
+    }
+    fn in_rank(&self, addr_vec: &[u64]) -> bool {
+        addr_vec[..=self.rank_index] == self.units[0][..=self.rank_index]
+    }
+    /// the `line`-th line of the register kept by the unit from the DRAM row `first_row` on
+    fn line_addr_vec(&self, unit: usize, register: u64, first_row: u64, line: u64) -> Vec<u64> {
+        let line = line * self.registers + register;
+        let mut addr_vec = self.units[unit].clone();
+        addr_vec[self.row_index] = first_row + line / self.columns;
+        addr_vec[self.column_index] = line % self.columns;
+        addr_vec
+    }
+    fn matrix_addr_vec(&self, blocks: u64, row: u64, block: u64) -> Vec<u64> {
+        let (pass, unit, register) = self.slot(row);
+        let first_row = self.units[unit][self.row_index];
+        self.line_addr_vec(unit, register, first_row, pass * blocks + block)
+    }
+    fn table_addr_vec(&self, passes: u64, index: u64, block: u64) -> Vec<u64> {
+        let (pass, unit, register) = self.slot(block);
+        let first_row = self.units[unit][self.row_index];
+        self.line_addr_vec(unit, register, first_row, index * passes + pass)
+    }
+    fn out_addr_vec(&self, element: u64) -> Vec<u64> {
+        let (pass, unit, register) = self.slot(element);
+        self.line_addr_vec(unit, register, self.out_row, pass)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use super::{ExecutionMode, Kernel, KernelRunner, Placement};
+    use crate::{
+        config::Config,
+        ddr4::DDR4,
+        memory::{MemoryTrait, SimpleMemory},
+        request::ReqType,
+    };
+
+    const PLACEMENT: Placement = Placement {
+        a: 0,
+        b: 1 << 24,
+        out: 1 << 25,
+    };
+
+    #[test]
+    fn test_requests() {
+        let config = Config::default();
+        let ddr4 = Arc::new(DDR4::new(&config));
+        let runner = KernelRunner::new(&config, ddr4.clone(), PLACEMENT);
+        let memory = SimpleMemory::with_config(&config, ddr4.clone()).unwrap();
+        let count = |kernel: &Kernel, mode, req_type: ReqType| {
+            runner
+                .requests(&memory, kernel, mode)
+                .iter()
+                .filter(|req| req.req_type == req_type)
+                .count()
+        };
+        // a 64-byte column holds 32 elements
+        let gemv = Kernel::Gemv {
+            rows: 64,
+            cols: 128,
+        };
+        assert_eq!(count(&gemv, ExecutionMode::Pim, ReqType::PimBroadcast), 4);
+        assert_eq!(count(&gemv, ExecutionMode::Pim, ReqType::PimMac), 4 * 64);
+        // a row per register of the 16 banks, the partial sums of every row are stored
+        assert_eq!(count(&gemv, ExecutionMode::Pim, ReqType::PimStore), 64);
+        assert_eq!(count(&gemv, ExecutionMode::Host, ReqType::Read), 4 + 4 * 64);
+        assert_eq!(count(&gemv, ExecutionMode::Host, ReqType::Write), 2);
+
+        // the ones are written and broadcast before the columns
+        let add = Kernel::VectorAdd { len: 320 };
+        assert_eq!(
+            runner.requests(&memory, &add, ExecutionMode::Pim).len(),
+            2 + 30
+        );
+        let dot = Kernel::DotProduct { len: 320 };
+        // the 10 columns are accumulated into the 8 registers
+        assert_eq!(count(&dot, ExecutionMode::Pim, ReqType::PimStore), 8);
+        let embedding = Kernel::EmbeddingSum {
+            dim: 64,
+            indices: vec![3, 7, 3],
+        };
+        let reqs = runner.requests(&memory, &embedding, ExecutionMode::Host);
+        assert_eq!(reqs.len(), 3 * 2 + 2);
+        assert_eq!(reqs[2].addr, 7 * 128);
+        assert_eq!(
+            count(&embedding, ExecutionMode::Pim, ReqType::PimMac),
+            3 * 2
+        );
+        assert_eq!(count(&embedding, ExecutionMode::Pim, ReqType::PimStore), 2);
+    }
+
+    #[test]
+    fn test_run_kernel() {
+        let config = Config::default();
+        let ddr4 = Arc::new(DDR4::new(&config));
+        let runner = KernelRunner::new(&config, ddr4.clone(), PLACEMENT);
+        let kernel = Kernel::VectorScale { len: 4096 };
+
+        let mut memory = SimpleMemory::with_config(&config, ddr4.clone()).unwrap();
+        let host = runner.run(&mut memory, &kernel, ExecutionMode::Host);
+        assert_eq!(host.requests, 1 + 2 * 128);
+        assert_eq!(host.bytes, host.requests * 64);
+        assert!(host.bandwidth_utilization > 0.0 && host.bandwidth_utilization <= 1.0);
+        assert_eq!(memory.pending_requests(), 0);
+
+        let mut memory = SimpleMemory::with_config(&config, ddr4.clone()).unwrap();
+        let pim = runner.run(&mut memory, &kernel, ExecutionMode::Pim);
+        assert_eq!(pim.requests, host.requests);
+        assert!(pim.cycles > 0);
+        let stats = memory.get_stats();
+        assert_eq!(stats.get_scalar("pim_requests"), Some(pim.requests as f64));
+        assert_eq!(stats.get_scalar("pim_alu_ops_0"), Some(128.0 * 2.0));
+    }
+}
@@ -1,19 +1,37 @@
+use rust_memory::{memory_from_file, memory_from_str, RustMemory};
 use tracing::metadata::LevelFilter;
 use tracing_subscriber::{
     prelude::__tracing_subscriber_SubscriberExt, util::SubscriberInitExt, EnvFilter,
 };
 
+pub(crate) mod capi;
+pub(crate) mod cmdtrace;
 pub(crate) mod command;
 pub(crate) mod config;
 pub(crate) mod controller;
+pub mod ddr3;
 pub mod ddr4;
+pub mod ddr5;
 pub(crate) mod dram;
+pub mod factory;
+#[cfg(feature = "functional")]
+pub mod functional;
+pub mod gddr5;
+pub mod gddr6;
+pub mod hbm;
+pub mod kernel;
+pub mod lpddr4;
+pub mod mapping;
 pub mod memory;
+pub(crate) mod pim;
+pub(crate) mod pimmode;
 pub(crate) mod refresh;
 pub(crate) mod request;
 pub(crate) mod rowpolicy;
 pub(crate) mod rowtable;
+pub(crate) mod rust_memory;
 pub(crate) mod scheduler;
+pub mod stats;
 #[cxx::bridge]
 mod ffi {
     extern "Rust" {
@@ -25,6 +43,14 @@ mod ffi {
         fn rinfo_with_target(target: &str, msg: &str);
         fn rerror_with_target(target: &str, msg: &str);
 
+        type RustMemory;
+        fn memory_from_file(path: &str) -> Result<Box<RustMemory>>;
+        fn memory_from_str(content: &str) -> Result<Box<RustMemory>>;
+        fn try_send(self: &mut RustMemory, addr: u64, is_write: bool) -> bool;
+        fn tick(self: &mut RustMemory);
+        fn try_recv(self: &mut RustMemory, addr: &mut u64) -> bool;
+        fn pending_requests(self: &RustMemory) -> usize;
+        fn finish(self: &mut RustMemory) -> String;
     }
 }
 fn init_logger() {
@@ -63,6 +89,8 @@ fn rerror_with_target(target: &str, msg: &str) {
 
 #[cfg(test)]
 mod test {
+    use std::sync::Arc;
+
     use crate::{
         config::Config,
         controller::Controller,
@@ -85,12 +113,12 @@ mod test {
     fn test_memory() {
         init_logger();
         let config = Config::default();
-        let ddr4 = DDR4::new(&config);
+        let ddr4 = Arc::new(DDR4::new(&config));
         let child_size = ddr4.get_child_size();
         let num_channels = child_size[0];
         let mut controllers = vec![];
         for _i in 0..num_channels {
-            let channel = Dram::new(&ddr4, crate::memory::Level::Channel, &child_size);
+            let channel = Dram::new(ddr4.clone(), crate::memory::Level::Channel, child_size);
             let controller = Controller::new(&config, channel);
             controllers.push(controller);
         }
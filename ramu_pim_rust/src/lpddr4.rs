@@ -0,0 +1,1220 @@
+use std::str::FromStr;
+
+use crate::{
+    command::Command,
+    config::{Config, ConfigError},
+    dram::{self, log2, Dram, DramSpec, State, TimeEntry, TimingTable},
+    memory::Level,
+    request::ReqType,
+};
+
+/// the density is per die, the density of a channel is half of it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[allow(non_camel_case_types)]
+pub enum LPDDR4Org {
+    LPDDR4_4Gb_x16,
+    LPDDR4_6Gb_x16,
+    LPDDR4_8Gb_x16,
+}
+impl FromStr for LPDDR4Org {
+    type Err = ();
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "LPDDR4_4Gb_x16" => LPDDR4Org::LPDDR4_4Gb_x16,
+            "LPDDR4_6Gb_x16" => LPDDR4Org::LPDDR4_6Gb_x16,
+            "LPDDR4_8Gb_x16" => LPDDR4Org::LPDDR4_8Gb_x16,
+            _ => return Err(()),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[allow(non_camel_case_types)]
+pub enum Speed {
+    LPDDR4_1600,
+    LPDDR4_2400,
+    LPDDR4_3200,
+}
+impl FromStr for Speed {
+    type Err = ();
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "LPDDR4_1600" => Speed::LPDDR4_1600,
+            "LPDDR4_2400" => Speed::LPDDR4_2400,
+            "LPDDR4_3200" => Speed::LPDDR4_3200,
+            _ => return Err(()),
+        })
+    }
+}
+
+/// the density(in Mb) of a channel, and the number of its rows and columns
+struct OrgEntry {
+    size: usize,
+    count: [usize; 2],
+}
+/// LPDDR4 is 16n prefetch. the latencies in JESD209-4 count from and to the end of each
+/// command, they are converted as if all commands take only 1 cycle like other standards.
+/// CL-RCD-RPpb are set to the same value although CL is not explicitly specified, CWL is made
+/// up, half of CL
+#[allow(non_snake_case, dead_code)]
+pub struct SpeedEntry {
+    rate: u64,
+    freq: f64,
+    tCK: f64,
+    nBL: u64,
+    nCCD: u64,
+    nRTRS: u64,
+    nDQSCK: u64,
+    nCL: u64,
+    nRCD: u64,
+    nRPpb: u64,
+    nRPab: u64,
+    nCWL: u64,
+    nRAS: u64,
+    nRC: u64,
+    nRTP: u64,
+    nWTR: u64,
+    nWR: u64,
+    nPPD: u64,
+    nRRD: u64,
+    nFAW: u64,
+    nRFCab: u64,
+    nRFCpb: u64,
+    nREFI: u64,
+    nCKE: u64,
+    nXP: u64,
+    nSR: u64,
+    nXSR: u64,
+}
+
+const LEVELS: [Level; 5] = [
+    Level::Channel,
+    Level::Rank,
+    Level::Bank,
+    Level::Row,
+    Level::Column,
+];
+/// 16n prefetch DDR
+const PREFETCH_SIZE: u64 = 16;
+const CHANNEL_WIDTH: u64 = 32;
+const BANKS: usize = 8;
+/// the commands are multiplexed on the cmd/addr bus and every command is encoded in two
+/// cycles, ACT, RD, WR, RDA and WRA are sent as two commands
+const COMMAND_CYCLES: [(Command, u64); 13] = [
+    (Command::ACT, 4),
+    (Command::PRE, 2),
+    (Command::PREA, 2),
+    (Command::RD, 4),
+    (Command::WR, 4),
+    (Command::RDA, 4),
+    (Command::WRA, 4),
+    (Command::REF, 2),
+    (Command::REFPB, 2),
+    (Command::PDE, 2),
+    (Command::PDX, 2),
+    (Command::SRE, 2),
+    (Command::SRX, 2),
+];
+pub struct LPDDR4 {
+    child_size: Vec<usize>,
+    addr_bits: Vec<usize>,
+    speed_entry: SpeedEntry,
+    timing: TimingTable,
+    read_latency: u64,
+}
+impl LPDDR4 {
+    pub fn new(config: &Config) -> Self {
+        let org = Self::get_org(&config.lpddr4_org);
+        let child_size = vec![
+            config.channels,
+            config.ranks,
+            BANKS,
+            org.count[0],
+            org.count[1],
+        ];
+        let addr_bits = child_size.iter().map(|x| log2(*x)).collect::<Vec<usize>>();
+        let mut speed_entry = Self::get_speed(&config.lpddr4_speed);
+        Self::init_speed(&mut speed_entry, &org);
+        let mut timing = TimingTable::new(&LEVELS);
+        Self::init_timing(&mut timing, &speed_entry);
+        let read_latency = speed_entry.nCL + speed_entry.nDQSCK + speed_entry.nBL;
+        Self {
+            child_size,
+            addr_bits,
+            speed_entry,
+            timing,
+            read_latency,
+        }
+    }
+    fn get_org(org: &LPDDR4Org) -> OrgEntry {
+        match org {
+            LPDDR4Org::LPDDR4_4Gb_x16 => OrgEntry {
+                size: 2 << 10,
+                count: [1 << 14, 1 << 10],
+            },
+            LPDDR4Org::LPDDR4_6Gb_x16 => OrgEntry {
+                size: 3 << 10,
+                count: [3 << 13, 1 << 10],
+            },
+            LPDDR4Org::LPDDR4_8Gb_x16 => OrgEntry {
+                size: 4 << 10,
+                count: [1 << 15, 1 << 10],
+            },
+        }
+    }
+    /// fill the density dependent timings, in DRAM cycles
+    fn init_speed(s: &mut SpeedEntry, org: &OrgEntry) {
+        const RFCPB_TABLE: [[u64; 3]; 3] = [[48, 72, 96], [72, 108, 144], [72, 108, 144]];
+        const RFCAB_TABLE: [[u64; 3]; 3] = [[104, 156, 208], [144, 216, 288], [144, 216, 288]];
+        /// the 1X refresh mode
+        const REFI_TABLE: [u64; 3] = [3124, 4685, 6247];
+        const XSR_TABLE: [[u64; 3]; 3] = [[110, 165, 220], [150, 225, 300], [150, 225, 300]];
+        let speed = match s.rate {
+            1600 => 0,
+            2400 => 1,
+            3200 => 2,
+            _ => unreachable!("invalid speed rate"),
+        };
+        let density = match org.size >> 10 {
+            2 => 0,
+            3 => 1,
+            4 => 2,
+            _ => unreachable!("12Gb/16Gb is still TBD"),
+        };
+        s.nRFCpb = RFCPB_TABLE[density][speed];
+        s.nRFCab = RFCAB_TABLE[density][speed];
+        s.nREFI = REFI_TABLE[speed];
+        s.nXSR = XSR_TABLE[density][speed];
+    }
+    fn init_timing(timing: &mut TimingTable, s: &SpeedEntry) {
+        /*** Channel ***/
+        let t = timing.get_level_mut(Level::Channel);
+
+        // CAS <-> CAS
+        t[Command::RD as usize].push(TimeEntry {
+            cmd: Command::RD,
+            dist: 1,
+            val: s.nBL,
+            sibling: false,
+        });
+        t[Command::RD as usize].push(TimeEntry {
+            cmd: Command::RDA,
+            dist: 1,
+            val: s.nBL,
+            sibling: false,
+        });
+        t[Command::RDA as usize].push(TimeEntry {
+            cmd: Command::RD,
+            dist: 1,
+            val: s.nBL,
+            sibling: false,
+        });
+        t[Command::RDA as usize].push(TimeEntry {
+            cmd: Command::RDA,
+            dist: 1,
+            val: s.nBL,
+            sibling: false,
+        });
+        t[Command::WR as usize].push(TimeEntry {
+            cmd: Command::WR,
+            dist: 1,
+            val: s.nBL,
+            sibling: false,
+        });
+        t[Command::WR as usize].push(TimeEntry {
+            cmd: Command::WRA,
+            dist: 1,
+            val: s.nBL,
+            sibling: false,
+        });
+        t[Command::WRA as usize].push(TimeEntry {
+            cmd: Command::WR,
+            dist: 1,
+            val: s.nBL,
+            sibling: false,
+        });
+        t[Command::WRA as usize].push(TimeEntry {
+            cmd: Command::WRA,
+            dist: 1,
+            val: s.nBL,
+            sibling: false,
+        });
+
+        /*** Rank ***/
+        let t = timing.get_level_mut(Level::Rank);
+
+        // CAS <-> CAS
+        t[Command::RD as usize].push(TimeEntry {
+            cmd: Command::RD,
+            dist: 1,
+            val: s.nCCD,
+            sibling: false,
+        });
+        t[Command::RD as usize].push(TimeEntry {
+            cmd: Command::RDA,
+            dist: 1,
+            val: s.nCCD,
+            sibling: false,
+        });
+        t[Command::RDA as usize].push(TimeEntry {
+            cmd: Command::RD,
+            dist: 1,
+            val: s.nCCD,
+            sibling: false,
+        });
+        t[Command::RDA as usize].push(TimeEntry {
+            cmd: Command::RDA,
+            dist: 1,
+            val: s.nCCD,
+            sibling: false,
+        });
+        t[Command::WR as usize].push(TimeEntry {
+            cmd: Command::WR,
+            dist: 1,
+            val: s.nCCD,
+            sibling: false,
+        });
+        t[Command::WR as usize].push(TimeEntry {
+            cmd: Command::WRA,
+            dist: 1,
+            val: s.nCCD,
+            sibling: false,
+        });
+        t[Command::WRA as usize].push(TimeEntry {
+            cmd: Command::WR,
+            dist: 1,
+            val: s.nCCD,
+            sibling: false,
+        });
+        t[Command::WRA as usize].push(TimeEntry {
+            cmd: Command::WRA,
+            dist: 1,
+            val: s.nCCD,
+            sibling: false,
+        });
+
+        t[Command::RD as usize].push(TimeEntry {
+            cmd: Command::WR,
+            dist: 1,
+            val: s.nCL + s.nBL + s.nDQSCK + 1 - s.nCWL,
+            sibling: false,
+        });
+        t[Command::RD as usize].push(TimeEntry {
+            cmd: Command::WRA,
+            dist: 1,
+            val: s.nCL + s.nBL + s.nDQSCK + 1 - s.nCWL,
+            sibling: false,
+        });
+        t[Command::RDA as usize].push(TimeEntry {
+            cmd: Command::WR,
+            dist: 1,
+            val: s.nCL + s.nBL + s.nDQSCK + 1 - s.nCWL,
+            sibling: false,
+        });
+        t[Command::RDA as usize].push(TimeEntry {
+            cmd: Command::WRA,
+            dist: 1,
+            val: s.nCL + s.nBL + s.nDQSCK + 1 - s.nCWL,
+            sibling: false,
+        });
+
+        t[Command::WR as usize].push(TimeEntry {
+            cmd: Command::RD,
+            dist: 1,
+            val: s.nCWL + s.nBL + s.nWTR + 1,
+            sibling: false,
+        });
+        t[Command::WR as usize].push(TimeEntry {
+            cmd: Command::RDA,
+            dist: 1,
+            val: s.nCWL + s.nBL + s.nWTR + 1,
+            sibling: false,
+        });
+        t[Command::WRA as usize].push(TimeEntry {
+            cmd: Command::RD,
+            dist: 1,
+            val: s.nCWL + s.nBL + s.nWTR + 1,
+            sibling: false,
+        });
+        t[Command::WRA as usize].push(TimeEntry {
+            cmd: Command::RDA,
+            dist: 1,
+            val: s.nCWL + s.nBL + s.nWTR + 1,
+            sibling: false,
+        });
+
+        // CAS <-> CAS (between sibling ranks)
+        t[Command::RD as usize].push(TimeEntry {
+            cmd: Command::RD,
+            dist: 1,
+            val: s.nBL + s.nRTRS,
+            sibling: true,
+        });
+        t[Command::RD as usize].push(TimeEntry {
+            cmd: Command::RDA,
+            dist: 1,
+            val: s.nBL + s.nRTRS,
+            sibling: true,
+        });
+        t[Command::RDA as usize].push(TimeEntry {
+            cmd: Command::RD,
+            dist: 1,
+            val: s.nBL + s.nRTRS,
+            sibling: true,
+        });
+        t[Command::RDA as usize].push(TimeEntry {
+            cmd: Command::RDA,
+            dist: 1,
+            val: s.nBL + s.nRTRS,
+            sibling: true,
+        });
+        t[Command::RD as usize].push(TimeEntry {
+            cmd: Command::WR,
+            dist: 1,
+            val: s.nBL + s.nRTRS,
+            sibling: true,
+        });
+        t[Command::RD as usize].push(TimeEntry {
+            cmd: Command::WRA,
+            dist: 1,
+            val: s.nBL + s.nRTRS,
+            sibling: true,
+        });
+        t[Command::RDA as usize].push(TimeEntry {
+            cmd: Command::WR,
+            dist: 1,
+            val: s.nBL + s.nRTRS,
+            sibling: true,
+        });
+        t[Command::RDA as usize].push(TimeEntry {
+            cmd: Command::WRA,
+            dist: 1,
+            val: s.nBL + s.nRTRS,
+            sibling: true,
+        });
+        t[Command::RD as usize].push(TimeEntry {
+            cmd: Command::WR,
+            dist: 1,
+            val: s.nCL + s.nBL + s.nDQSCK + 1 + s.nRTRS - s.nCWL,
+            sibling: true,
+        });
+        t[Command::RD as usize].push(TimeEntry {
+            cmd: Command::WRA,
+            dist: 1,
+            val: s.nCL + s.nBL + s.nDQSCK + 1 + s.nRTRS - s.nCWL,
+            sibling: true,
+        });
+        t[Command::RDA as usize].push(TimeEntry {
+            cmd: Command::WR,
+            dist: 1,
+            val: s.nCL + s.nBL + s.nDQSCK + 1 + s.nRTRS - s.nCWL,
+            sibling: true,
+        });
+        t[Command::RDA as usize].push(TimeEntry {
+            cmd: Command::WRA,
+            dist: 1,
+            val: s.nCL + s.nBL + s.nDQSCK + 1 + s.nRTRS - s.nCWL,
+            sibling: true,
+        });
+        t[Command::WR as usize].push(TimeEntry {
+            cmd: Command::RD,
+            dist: 1,
+            val: (s.nCWL + s.nBL + s.nRTRS).saturating_sub(s.nCL),
+            sibling: true,
+        });
+        t[Command::WR as usize].push(TimeEntry {
+            cmd: Command::RDA,
+            dist: 1,
+            val: (s.nCWL + s.nBL + s.nRTRS).saturating_sub(s.nCL),
+            sibling: true,
+        });
+        t[Command::WRA as usize].push(TimeEntry {
+            cmd: Command::RD,
+            dist: 1,
+            val: (s.nCWL + s.nBL + s.nRTRS).saturating_sub(s.nCL),
+            sibling: true,
+        });
+        t[Command::WRA as usize].push(TimeEntry {
+            cmd: Command::RDA,
+            dist: 1,
+            val: (s.nCWL + s.nBL + s.nRTRS).saturating_sub(s.nCL),
+            sibling: true,
+        });
+
+        // CAS <-> PREA
+        t[Command::RD as usize].push(TimeEntry {
+            cmd: Command::PREA,
+            dist: 1,
+            val: s.nRTP,
+            sibling: false,
+        });
+        t[Command::WR as usize].push(TimeEntry {
+            cmd: Command::PREA,
+            dist: 1,
+            val: s.nCWL + s.nBL + s.nWR,
+            sibling: false,
+        });
+
+        // CAS <-> PD
+        t[Command::RD as usize].push(TimeEntry {
+            cmd: Command::PDE,
+            dist: 1,
+            val: s.nCL + s.nBL + 1,
+            sibling: false,
+        });
+        t[Command::RDA as usize].push(TimeEntry {
+            cmd: Command::PDE,
+            dist: 1,
+            val: s.nCL + s.nBL + 1,
+            sibling: false,
+        });
+        t[Command::WR as usize].push(TimeEntry {
+            cmd: Command::PDE,
+            dist: 1,
+            val: s.nCWL + s.nBL + s.nWR,
+            sibling: false,
+        });
+        t[Command::WRA as usize].push(TimeEntry {
+            cmd: Command::PDE,
+            dist: 1,
+            val: s.nCWL + s.nBL + s.nWR + 1,
+            sibling: false,
+        }); // +1 for pre
+        t[Command::PDX as usize].push(TimeEntry {
+            cmd: Command::RD,
+            dist: 1,
+            val: s.nXP,
+            sibling: false,
+        });
+        t[Command::PDX as usize].push(TimeEntry {
+            cmd: Command::RDA,
+            dist: 1,
+            val: s.nXP,
+            sibling: false,
+        });
+        t[Command::PDX as usize].push(TimeEntry {
+            cmd: Command::WR,
+            dist: 1,
+            val: s.nXP,
+            sibling: false,
+        });
+        t[Command::PDX as usize].push(TimeEntry {
+            cmd: Command::WRA,
+            dist: 1,
+            val: s.nXP,
+            sibling: false,
+        });
+
+        // RAS <-> RAS
+        t[Command::ACT as usize].push(TimeEntry {
+            cmd: Command::ACT,
+            dist: 1,
+            val: s.nRRD,
+            sibling: false,
+        });
+        t[Command::ACT as usize].push(TimeEntry {
+            cmd: Command::ACT,
+            dist: 4,
+            val: s.nFAW,
+            sibling: false,
+        });
+        t[Command::ACT as usize].push(TimeEntry {
+            cmd: Command::PREA,
+            dist: 1,
+            val: s.nRAS,
+            sibling: false,
+        });
+        t[Command::PREA as usize].push(TimeEntry {
+            cmd: Command::ACT,
+            dist: 1,
+            val: s.nRPab,
+            sibling: false,
+        });
+        t[Command::PRE as usize].push(TimeEntry {
+            cmd: Command::PRE,
+            dist: 1,
+            val: s.nPPD,
+            sibling: false,
+        });
+
+        // RAS <-> REF
+        t[Command::PRE as usize].push(TimeEntry {
+            cmd: Command::REF,
+            dist: 1,
+            val: s.nRPpb,
+            sibling: false,
+        });
+        t[Command::PREA as usize].push(TimeEntry {
+            cmd: Command::REF,
+            dist: 1,
+            val: s.nRPab,
+            sibling: false,
+        });
+        t[Command::REF as usize].push(TimeEntry {
+            cmd: Command::ACT,
+            dist: 1,
+            val: s.nRFCab,
+            sibling: false,
+        });
+
+        // RAS <-> PD
+        t[Command::ACT as usize].push(TimeEntry {
+            cmd: Command::PDE,
+            dist: 1,
+            val: 1,
+            sibling: false,
+        });
+        t[Command::PDX as usize].push(TimeEntry {
+            cmd: Command::ACT,
+            dist: 1,
+            val: s.nXP,
+            sibling: false,
+        });
+        t[Command::PDX as usize].push(TimeEntry {
+            cmd: Command::PRE,
+            dist: 1,
+            val: s.nXP,
+            sibling: false,
+        });
+        t[Command::PDX as usize].push(TimeEntry {
+            cmd: Command::PREA,
+            dist: 1,
+            val: s.nXP,
+            sibling: false,
+        });
+
+        // RAS <-> SR
+        t[Command::PRE as usize].push(TimeEntry {
+            cmd: Command::SRE,
+            dist: 1,
+            val: s.nRPpb,
+            sibling: false,
+        });
+        t[Command::PREA as usize].push(TimeEntry {
+            cmd: Command::SRE,
+            dist: 1,
+            val: s.nRPab,
+            sibling: false,
+        });
+        t[Command::SRX as usize].push(TimeEntry {
+            cmd: Command::ACT,
+            dist: 1,
+            val: s.nXSR,
+            sibling: false,
+        });
+
+        // REF <-> REF
+        t[Command::REF as usize].push(TimeEntry {
+            cmd: Command::REF,
+            dist: 1,
+            val: s.nRFCab,
+            sibling: false,
+        });
+        t[Command::REF as usize].push(TimeEntry {
+            cmd: Command::REFPB,
+            dist: 1,
+            val: s.nRFCab,
+            sibling: false,
+        });
+        t[Command::REFPB as usize].push(TimeEntry {
+            cmd: Command::REF,
+            dist: 1,
+            val: s.nRFCpb,
+            sibling: false,
+        });
+
+        // REF <-> PD
+        t[Command::REF as usize].push(TimeEntry {
+            cmd: Command::PDE,
+            dist: 1,
+            val: 1,
+            sibling: false,
+        });
+        t[Command::REFPB as usize].push(TimeEntry {
+            cmd: Command::PDE,
+            dist: 1,
+            val: 1,
+            sibling: false,
+        });
+        t[Command::PDX as usize].push(TimeEntry {
+            cmd: Command::REF,
+            dist: 1,
+            val: s.nXP,
+            sibling: false,
+        });
+        t[Command::PDX as usize].push(TimeEntry {
+            cmd: Command::REFPB,
+            dist: 1,
+            val: s.nXP,
+            sibling: false,
+        });
+
+        // REF <-> SR
+        t[Command::SRX as usize].push(TimeEntry {
+            cmd: Command::REF,
+            dist: 1,
+            val: s.nXSR,
+            sibling: false,
+        });
+        t[Command::SRX as usize].push(TimeEntry {
+            cmd: Command::REFPB,
+            dist: 1,
+            val: s.nXSR,
+            sibling: false,
+        });
+
+        // PD <-> PD
+        t[Command::PDE as usize].push(TimeEntry {
+            cmd: Command::PDX,
+            dist: 1,
+            val: s.nCKE,
+            sibling: false,
+        });
+        t[Command::PDX as usize].push(TimeEntry {
+            cmd: Command::PDE,
+            dist: 1,
+            val: s.nXP,
+            sibling: false,
+        });
+
+        // PD <-> SR
+        t[Command::PDX as usize].push(TimeEntry {
+            cmd: Command::SRE,
+            dist: 1,
+            val: s.nXP,
+            sibling: false,
+        });
+        t[Command::SRX as usize].push(TimeEntry {
+            cmd: Command::PDE,
+            dist: 1,
+            val: s.nXSR,
+            sibling: false,
+        });
+
+        // SR <-> SR
+        t[Command::SRE as usize].push(TimeEntry {
+            cmd: Command::SRX,
+            dist: 1,
+            val: s.nSR,
+            sibling: false,
+        });
+        t[Command::SRX as usize].push(TimeEntry {
+            cmd: Command::SRE,
+            dist: 1,
+            val: s.nXSR,
+            sibling: false,
+        });
+
+        /*** Bank ***/
+        let t = timing.get_level_mut(Level::Bank);
+
+        // CAS <-> RAS
+        t[Command::ACT as usize].push(TimeEntry {
+            cmd: Command::RD,
+            dist: 1,
+            val: s.nRCD,
+            sibling: false,
+        });
+        t[Command::ACT as usize].push(TimeEntry {
+            cmd: Command::RDA,
+            dist: 1,
+            val: s.nRCD,
+            sibling: false,
+        });
+        t[Command::ACT as usize].push(TimeEntry {
+            cmd: Command::WR,
+            dist: 1,
+            val: s.nRCD,
+            sibling: false,
+        });
+        t[Command::ACT as usize].push(TimeEntry {
+            cmd: Command::WRA,
+            dist: 1,
+            val: s.nRCD,
+            sibling: false,
+        });
+
+        t[Command::RD as usize].push(TimeEntry {
+            cmd: Command::PRE,
+            dist: 1,
+            val: s.nRTP,
+            sibling: false,
+        });
+        t[Command::WR as usize].push(TimeEntry {
+            cmd: Command::PRE,
+            dist: 1,
+            val: s.nCWL + s.nBL + s.nWR,
+            sibling: false,
+        });
+
+        t[Command::RDA as usize].push(TimeEntry {
+            cmd: Command::ACT,
+            dist: 1,
+            val: s.nRTP + s.nRPpb,
+            sibling: false,
+        });
+        t[Command::WRA as usize].push(TimeEntry {
+            cmd: Command::ACT,
+            dist: 1,
+            val: s.nCWL + s.nBL + s.nWR + s.nRPpb,
+            sibling: false,
+        });
+
+        // RAS <-> RAS
+        t[Command::ACT as usize].push(TimeEntry {
+            cmd: Command::ACT,
+            dist: 1,
+            val: s.nRC,
+            sibling: false,
+        });
+        t[Command::ACT as usize].push(TimeEntry {
+            cmd: Command::PRE,
+            dist: 1,
+            val: s.nRAS,
+            sibling: false,
+        });
+        t[Command::PRE as usize].push(TimeEntry {
+            cmd: Command::ACT,
+            dist: 1,
+            val: s.nRPpb,
+            sibling: false,
+        });
+        t[Command::PRE as usize].push(TimeEntry {
+            cmd: Command::REFPB,
+            dist: 1,
+            val: s.nRPpb,
+            sibling: false,
+        });
+
+        // between different banks
+        t[Command::ACT as usize].push(TimeEntry {
+            cmd: Command::REFPB,
+            dist: 1,
+            val: s.nRRD,
+            sibling: true,
+        });
+        t[Command::REFPB as usize].push(TimeEntry {
+            cmd: Command::ACT,
+            dist: 1,
+            val: s.nRRD,
+            sibling: true,
+        });
+
+        // REFPB
+        t[Command::REFPB as usize].push(TimeEntry {
+            cmd: Command::REFPB,
+            dist: 1,
+            val: s.nRFCpb,
+            sibling: false,
+        });
+        t[Command::REFPB as usize].push(TimeEntry {
+            cmd: Command::ACT,
+            dist: 1,
+            val: s.nRFCpb,
+            sibling: false,
+        });
+
+        // the next command waits for the cmd/addr bus
+        let t = timing.get_level_mut(Level::Channel);
+        for (cmd, cycles) in COMMAND_CYCLES {
+            for (next, _) in COMMAND_CYCLES {
+                t[cmd as usize].push(TimeEntry {
+                    cmd: next,
+                    dist: 1,
+                    val: cycles,
+                    sibling: false,
+                });
+            }
+        }
+    }
+
+    pub fn get_speed(speed: &Speed) -> SpeedEntry {
+        match speed {
+            Speed::LPDDR4_1600 => SpeedEntry {
+                rate: 1600,
+                freq: 400.0 * 2.0,
+                tCK: 2.5 / 2.0,
+                nBL: 8,
+                nCCD: 8,
+                nRTRS: 2,
+                nDQSCK: 1,
+                nCL: 15 + 3,
+                nRCD: 15,
+                nRPpb: 15 - 2,
+                nRPab: 17 - 2,
+                nCWL: 8 + 3,
+                nRAS: 34,
+                nRC: 47,
+                nRTP: 8 + 2,
+                nWTR: 8,
+                nWR: 15 - 1,
+                nPPD: 4,
+                nRRD: 8,
+                nFAW: 32,
+                nRFCab: 0,
+                nRFCpb: 0,
+                nREFI: 0,
+                nCKE: 0,
+                nXP: 6,
+                nSR: 12,
+                nXSR: 0,
+            },
+            Speed::LPDDR4_2400 => SpeedEntry {
+                rate: 2400,
+                freq: 400.0 * 3.0,
+                tCK: 2.5 / 3.0,
+                nBL: 8,
+                nCCD: 8,
+                nRTRS: 2,
+                nDQSCK: 2,
+                nCL: 22 + 3,
+                nRCD: 22,
+                nRPpb: 22 - 2,
+                nRPab: 26 - 2,
+                nCWL: 11 + 3,
+                nRAS: 51,
+                nRC: 71,
+                nRTP: 9 + 2,
+                nWTR: 12,
+                nWR: 22 - 1,
+                nPPD: 4,
+                nRRD: 12,
+                nFAW: 48,
+                nRFCab: 0,
+                nRFCpb: 0,
+                nREFI: 0,
+                nCKE: 0,
+                nXP: 9,
+                nSR: 18,
+                nXSR: 0,
+            },
+            Speed::LPDDR4_3200 => SpeedEntry {
+                rate: 3200,
+                freq: 400.0 * 4.0,
+                tCK: 2.5 / 4.0,
+                nBL: 8,
+                nCCD: 8,
+                nRTRS: 2,
+                nDQSCK: 3,
+                nCL: 29 + 3,
+                nRCD: 29,
+                nRPpb: 29 - 2,
+                nRPab: 34 - 2,
+                nCWL: 15 + 3,
+                nRAS: 68,
+                nRC: 95,
+                nRTP: 12 + 2,
+                nWTR: 16,
+                nWR: 29 - 1,
+                nPPD: 4,
+                nRRD: 16,
+                nFAW: 64,
+                nRFCab: 0,
+                nRFCpb: 0,
+                nREFI: 0,
+                nCKE: 0,
+                nXP: 12,
+                nSR: 24,
+                nXSR: 0,
+            },
+        }
+    }
+}
+
+impl DramSpec for LPDDR4 {
+    fn from_config(config: &Config) -> Result<Self, ConfigError> {
+        Ok(Self::new(config))
+    }
+    fn supports_per_bank_refresh() -> bool {
+        true
+    }
+    fn supports_pim() -> bool {
+        false
+    }
+    fn get_first_cmd(req_type: &ReqType) -> Command {
+        match req_type {
+            ReqType::Read => Command::RD,
+            ReqType::Write => Command::WR,
+            ReqType::Refresh => Command::REF,
+            ReqType::RefreshBank => Command::REFPB,
+            ReqType::PimMac
+            | ReqType::PimMacAllBank
+            | ReqType::PimLoad
+            | ReqType::PimStore
+            | ReqType::PimBroadcast => {
+                unreachable!("LPDDR4 has no PIM commands")
+            }
+        }
+    }
+
+    fn get_pre_cmd(dram: &Dram<Self>, cmd: &Command, child_id: u64) -> Option<Command> {
+        match (&dram.level, cmd) {
+            (Level::Rank, Command::RD) | (Level::Rank, Command::WR) => match dram.state {
+                dram::State::PowerUp => None,
+                dram::State::ActPowerDown => Some(Command::PDX),
+                dram::State::PrePowerDown => Some(Command::PDX),
+                dram::State::SelfRefresh => Some(Command::SRX),
+                _ => unreachable!("invalid dram state"),
+            },
+            (Level::Rank, Command::REF) => {
+                if dram
+                    .children
+                    .iter()
+                    .any(|bank| !matches!(bank.state, dram::State::Closed))
+                {
+                    Some(Command::PREA)
+                } else {
+                    Some(Command::REF)
+                }
+            }
+            (Level::Rank, Command::PDE) => match dram.state {
+                dram::State::PowerUp | dram::State::ActPowerDown | dram::State::PrePowerDown => {
+                    Some(Command::PDE)
+                }
+                dram::State::SelfRefresh => Some(Command::SRX),
+                _ => unreachable!("invalid dram state"),
+            },
+            (Level::Rank, Command::SRE) => match dram.state {
+                dram::State::PowerUp | dram::State::SelfRefresh => Some(Command::SRE),
+                dram::State::ActPowerDown | dram::State::PrePowerDown => Some(Command::PDX),
+                _ => unreachable!("invalid dram state"),
+            },
+
+            (Level::Bank, Command::RD) | (Level::Bank, Command::WR) => match dram.state {
+                dram::State::Closed => Some(Command::ACT),
+                dram::State::Opened(row_id) => {
+                    if row_id == child_id {
+                        Some(*cmd)
+                    } else {
+                        Some(Command::PRE)
+                    }
+                }
+                _ => unreachable!("invalid dram state"),
+            },
+            (Level::Bank, Command::REFPB) => match dram.state {
+                dram::State::Closed => Some(Command::REFPB),
+                _ => Some(Command::PRE),
+            },
+            _ => None,
+        }
+    }
+
+    fn get_start_state(level: &Level) -> dram::State {
+        match level {
+            Level::Rank => dram::State::PowerUp,
+            Level::Bank => dram::State::Closed,
+            Level::Row => dram::State::Closed,
+            _ => dram::State::NoUse,
+        }
+    }
+
+    fn get_levels(&self) -> &[Level] {
+        &LEVELS
+    }
+
+    fn get_addr_bits(&self) -> &[usize] {
+        &self.addr_bits
+    }
+
+    fn get_child_size(&self) -> &[usize] {
+        &self.child_size
+    }
+
+    fn get_scope(&self, cmd: &Command) -> Level {
+        match cmd {
+            Command::ACT | Command::ACTAB => Level::Row,
+            Command::PRE | Command::PREAB | Command::REFSB | Command::REFPB => Level::Bank,
+            Command::RD
+            | Command::WR
+            | Command::RDA
+            | Command::WRA
+            | Command::MAC
+            | Command::LDR
+            | Command::STR
+            | Command::MACAB => Level::Column,
+            Command::PREA
+            | Command::REF
+            | Command::PDE
+            | Command::PDX
+            | Command::SRE
+            | Command::SRX
+            | Command::WRGB => Level::Rank,
+            Command::Max => unreachable!(),
+        }
+    }
+
+    fn update_state(&self, dram: &mut Dram<Self>, cmd: &Command, child_id: u64) {
+        let banks_closed =
+            |dram: &Dram<Self>| dram.children.iter().all(|bank| bank.state == State::Closed);
+        match (dram.level, cmd) {
+            (Level::Bank, Command::ACT) => {
+                dram.state = State::Opened(child_id);
+            }
+            (Level::Bank, Command::PRE | Command::RDA | Command::WRA) => {
+                dram.state = State::Closed;
+            }
+            (Level::Rank, Command::PREA) => {
+                dram.children.iter_mut().for_each(|bank| {
+                    bank.state = State::Closed;
+                });
+            }
+            (Level::Rank, Command::PDE) => {
+                dram.state = if banks_closed(dram) {
+                    State::PrePowerDown
+                } else {
+                    State::ActPowerDown
+                };
+            }
+            (Level::Rank, Command::PDX | Command::SRX) => {
+                dram.state = State::PowerUp;
+            }
+            (Level::Rank, Command::SRE) => {
+                dram.state = State::SelfRefresh;
+            }
+            _ => {}
+        }
+    }
+    fn get_timming(&self, depth: usize, cmd: &Command) -> &[TimeEntry] {
+        self.timing.get(depth, cmd)
+    }
+
+    fn get_read_latency(&self) -> u64 {
+        self.read_latency
+    }
+
+    fn get_refresh_interval(&self) -> u64 {
+        self.speed_entry.nREFI
+    }
+
+    fn get_clk_ns(&self) -> f64 {
+        self.speed_entry.tCK
+    }
+
+    fn get_transaction_bytes(&self) -> u64 {
+        PREFETCH_SIZE * CHANNEL_WIDTH / 8
+    }
+
+    fn get_burst_cycles(&self) -> u64 {
+        self.speed_entry.nBL
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use super::{LPDDR4Org, Speed, LPDDR4};
+    use crate::{
+        command::Command,
+        config::{Config, Standard},
+        controller::Controller,
+        dram::{Dram, DramSpec},
+        memory::{Level, MemoryTrait, SimpleMemory},
+        request::{ReqType, Request},
+    };
+
+    fn get_timing(lpddr4: &Arc<LPDDR4>, level: Level, from: Command, to: Command) -> u64 {
+        lpddr4
+            .get_timming(lpddr4.get_level_index(level), &from)
+            .iter()
+            .find(|t| !t.sibling && t.cmd == to)
+            .unwrap()
+            .val
+    }
+
+    #[test]
+    fn test_lpddr4() {
+        let config =
+            Config::parse(include_str!("../../ramulator/configs/LPDDR4-config.cfg")).unwrap();
+        assert_eq!(config.standard, Standard::LPDDR4);
+        assert_eq!(config.lpddr4_org, LPDDR4Org::LPDDR4_8Gb_x16);
+        assert_eq!(config.lpddr4_speed, Speed::LPDDR4_2400);
+        let lpddr4 = Arc::new(LPDDR4::new(&config));
+        assert_eq!(lpddr4.get_child_size(), [2, 1, 8, 1 << 15, 1 << 10]);
+        assert_eq!(lpddr4.get_read_latency(), 25 + 2 + 8);
+        assert_eq!(lpddr4.get_refresh_interval(), 4685);
+        assert_eq!(lpddr4.get_transaction_bytes(), 64);
+        let t = |level, from, to| get_timing(&lpddr4, level, from, to);
+        assert_eq!(t(Level::Rank, Command::REF, Command::ACT), 216);
+        assert_eq!(t(Level::Bank, Command::REFPB, Command::ACT), 108);
+        assert_eq!(t(Level::Rank, Command::REFPB, Command::REF), 108);
+        // the read after a write to a sibling rank is not delayed at this speed
+        assert_eq!(
+            lpddr4
+                .get_timming(lpddr4.get_level_index(Level::Rank), &Command::WR)
+                .iter()
+                .find(|t| t.sibling && t.cmd == Command::RD)
+                .unwrap()
+                .val,
+            0
+        );
+    }
+
+    #[test]
+    fn test_command_bus() {
+        let lpddr4 = Arc::new(LPDDR4::new(&Config::default()));
+        let mut channel = Dram::new(lpddr4.clone(), Level::Channel, lpddr4.get_child_size());
+        let bank = |bank: u64| [0, 0, bank, 0, 0];
+        // ACT is sent in 4 cycles, the command to another bank waits for the bus
+        channel.update(&Command::ACT, &bank(0), 10);
+        assert_eq!(channel.get_next_avaliable_clk(&Command::PRE), 14);
+        assert!(!channel.check(&Command::PRE, &bank(1), 13));
+        assert!(channel.check(&Command::PRE, &bank(1), 14));
+        // PRE is sent in 2 cycles
+        channel.update(&Command::PRE, &bank(1), 14);
+        assert_eq!(channel.get_next_avaliable_clk(&Command::REF), 16);
+    }
+
+    #[test]
+    fn test_per_bank_refresh() {
+        let all_bank = Config::parse("standard = LPDDR4\nchannels = 1").unwrap();
+        let per_bank =
+            Config::parse("standard = LPDDR4\nchannels = 1\nper_bank_refresh = on").unwrap();
+        assert!(per_bank.per_bank_refresh);
+        let lpddr4 = Arc::new(LPDDR4::new(&all_bank));
+        let n_refi = lpddr4.get_refresh_interval();
+        let run = |config: &Config| {
+            let channel = Dram::new(lpddr4.clone(), Level::Channel, lpddr4.get_child_size());
+            let mut controller = Controller::new(config, channel);
+            for clk in 1..=n_refi + 10 {
+                controller.tick(clk);
+            }
+            controller.channel
+        };
+
+        let channel = run(&all_bank);
+        let rank = &channel.children[0];
+        assert_eq!(rank.cmd_counts[Command::REF as usize], 1);
+        assert_eq!(rank.cmd_counts[Command::REFPB as usize], 0);
+
+        // every bank is refreshed once in nREFI, in round robin every nREFI / 8 cycles
+        let channel = run(&per_bank);
+        let rank = &channel.children[0];
+        assert_eq!(rank.cmd_counts[Command::REF as usize], 0);
+        assert_eq!(rank.cmd_counts[Command::REFPB as usize], 8);
+        let refreshed = rank
+            .children
+            .iter()
+            .map(|bank| bank.prev[Command::REFPB as usize][0])
+            .collect::<Vec<_>>();
+        for (bank, clk) in refreshed.iter().enumerate() {
+            assert_eq!(*clk, (bank as u64 + 1) * (n_refi / 8));
+        }
+
+        let mut memory = SimpleMemory::with_config(&per_bank, lpddr4.clone()).unwrap();
+        for i in 0..32 {
+            memory
+                .try_send(Request::new(i << 6, ReqType::Read))
+                .unwrap();
+        }
+        let stats = memory.finish();
+        assert_eq!(stats.get_scalar("read_requests"), Some(32.0));
+
+        // the support comes from the spec, not from the `standard` key
+        let config = Config {
+            per_bank_refresh: true,
+            ..Default::default()
+        };
+        assert!(SimpleMemory::with_config(&config, lpddr4.clone()).is_ok());
+    }
+}
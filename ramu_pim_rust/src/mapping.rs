@@ -0,0 +1,246 @@
+use std::path::Path;
+
+use crate::{config::ConfigError, memory::Level};
+
+/// a bit-level address mapping loaded from a ramulator `.map` file
+///
+/// each bit of a level's address is the xor of one or more bits of the physical address, the
+/// physical address here does not include the cacheline offset bits
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct MappingFile {
+    /// `scheme[level][bit]` is the source bits of that bit, indexed by the kind of the level
+    scheme: Vec<Vec<Vec<usize>>>,
+}
+
+/// the names of the levels in the mapping files
+const LEVEL_NAMES: [(&str, Level); 9] = [
+    ("Ch", Level::Channel),
+    ("Pc", Level::PseudoChannel),
+    ("Ra", Level::Rank),
+    ("Bg", Level::BankGroup),
+    ("Pu", Level::PimUnit),
+    ("Ba", Level::Bank),
+    ("Sa", Level::SubArray),
+    ("Ro", Level::Row),
+    ("Co", Level::Column),
+];
+
+impl MappingFile {
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, ConfigError> {
+        Self::parse(&std::fs::read_to_string(path)?)
+    }
+    /// parse the lines of a mapping file, the possible line types are:
+    ///
+    /// - direct bit assignment: `Ba 2 = 13`
+    /// - direct range assignment: `Ba 2:0 = 5:3`
+    /// - xor bit assignment: `Ba 0 = 0 13`
+    pub fn parse(content: &str) -> Result<Self, ConfigError> {
+        let mut mapping = MappingFile {
+            scheme: vec![vec![]; Level::Max as usize],
+        };
+        for (i, line) in content.lines().enumerate() {
+            let line_no = i + 1;
+            let line = line.split('#').next().unwrap();
+            let tokens = line.split_whitespace().collect::<Vec<_>>();
+            let (name, target, sources) = match tokens[..] {
+                [] => continue,
+                [name, target, "=", ref sources @ ..] if !sources.is_empty() => {
+                    (name, target, sources)
+                }
+                _ => {
+                    return Err(ConfigError::Syntax {
+                        line: line_no,
+                        content: line.trim().to_string(),
+                    })
+                }
+            };
+            let level = LEVEL_NAMES
+                .iter()
+                .find(|(level_name, _)| *level_name == name)
+                .map(|(_, level)| *level as usize)
+                .ok_or_else(|| ConfigError::UnknownKey {
+                    line: line_no,
+                    key: name.to_string(),
+                })?;
+            let invalid_value = |value: &str| ConfigError::InvalidValue {
+                line: line_no,
+                key: name.to_string(),
+                value: value.to_string(),
+            };
+            let targets = parse_range(target).ok_or_else(|| invalid_value(target))?;
+            if targets.len() == 1 {
+                let sources = sources
+                    .iter()
+                    .map(|source| source.parse().map_err(|_| invalid_value(source)))
+                    .collect::<Result<Vec<_>, _>>()?;
+                mapping.add(level, targets[0], sources);
+            } else {
+                // a range is assigned from a range of the same width
+                let source = sources.join(" ");
+                let sources = parse_range(&source)
+                    .filter(|sources| sources.len() == targets.len())
+                    .ok_or_else(|| invalid_value(&source))?;
+                for (target, source) in targets.into_iter().zip(sources) {
+                    mapping.add(level, target, vec![source]);
+                }
+            }
+        }
+        Ok(mapping)
+    }
+    fn add(&mut self, level: usize, target: usize, sources: Vec<usize>) {
+        let bits = &mut self.scheme[level];
+        if bits.len() <= target {
+            bits.resize(target + 1, vec![]);
+        }
+        bits[target].extend(sources);
+    }
+    /// the number of bits assigned to each kind of level
+    pub fn get_level_bits(&self) -> Vec<usize> {
+        self.scheme.iter().map(|bits| bits.len()).collect()
+    }
+
+    /// map the physical address (without the cacheline offset) to `addr_vec`, which is indexed
+    /// by `levels`
+    pub fn apply(&self, addr: u64, levels: &[Level], addr_vec: &mut [u64]) {
+        for (index, level) in levels.iter().enumerate() {
+            addr_vec[index] = self.scheme[*level as usize]
+                .iter()
+                .enumerate()
+                .map(|(target, sources)| {
+                    let bit = sources
+                        .iter()
+                        .fold(0, |bit, &source| bit ^ ((addr >> source) & 1));
+                    bit << target
+                })
+                .fold(0, |value, bit| value | bit);
+        }
+    }
+
+    /// recover the physical address (without the cacheline offset) from `addr_vec`, the bits
+    /// that can not be determined by the mapping are left as 0
+    pub fn reverse(&self, levels: &[Level], addr_vec: &[u64]) -> u64 {
+        // every target bit gives an equation: the xor of its sources equals the bit, solve the
+        // equations that only have one unknown source until no more source can be determined
+        let mut equations = vec![];
+        for (index, level) in levels.iter().enumerate() {
+            for (target, sources) in self.scheme[*level as usize].iter().enumerate() {
+                equations.push((sources, (addr_vec[index] >> target) & 1));
+            }
+        }
+        let mut known = 0u64;
+        let mut addr = 0u64;
+        let mut progress = true;
+        while progress {
+            progress = false;
+            for (sources, value) in equations.iter() {
+                let mut unknown = sources.iter().filter(|&&source| (known >> source) & 1 == 0);
+                let (Some(&source), None) = (unknown.next(), unknown.next()) else {
+                    continue;
+                };
+                let bit = sources
+                    .iter()
+                    .filter(|&&other| other != source)
+                    .fold(*value, |bit, &other| bit ^ ((addr >> other) & 1));
+                addr |= bit << source;
+                known |= 1 << source;
+                progress = true;
+            }
+        }
+        addr
+    }
+}
+
+/// parse `N` or `N:M` into the bits from the lower one to the higher one
+fn parse_range(range: &str) -> Option<Vec<usize>> {
+    let (high, low) = match range.split_once(':') {
+        Some((high, low)) => (high.trim().parse().ok()?, low.trim().parse().ok()?),
+        None => {
+            let bit = range.parse().ok()?;
+            (bit, bit)
+        }
+    };
+    let (low, high) = if low <= high {
+        (low, high)
+    } else {
+        (high, low)
+    };
+    Some((low..=high).collect())
+}
+
+#[cfg(test)]
+mod test {
+    use super::MappingFile;
+    use crate::{config::ConfigError, memory::Level};
+
+    const LEVELS: [Level; 6] = [
+        Level::Channel,
+        Level::Rank,
+        Level::BankGroup,
+        Level::Bank,
+        Level::Row,
+        Level::Column,
+    ];
+
+    /// a DDR4 row interleaving mapping with the bank randomized by the row bits
+    const DDR4_MAPPING: &str = "
+        # Co: 10, Bg: 2, Ba: 2, Ro: 15
+        Co 9:0 = 9:0
+        Bg 1:0 = 11:10
+        Ba 0 = 12 20   # xor with the row bit 6
+        Ba 1 = 13 21
+        Ro 14:0 = 28:14
+    ";
+
+    #[test]
+    fn test_mapping_file() {
+        let mapping = MappingFile::parse(DDR4_MAPPING).unwrap();
+        assert_eq!(mapping.get_level_bits(), [0, 0, 2, 2, 15, 10, 0, 0, 0]);
+        let mut addr_vec = vec![0; LEVELS.len()];
+        let addr = (3 << 14) | (1 << 12) | (2 << 10) | 5;
+        mapping.apply(addr, &LEVELS, &mut addr_vec);
+        assert_eq!(addr_vec, [0, 0, 2, 1, 3, 5]);
+        // the row bit 6 flips the bank bit 0
+        mapping.apply(addr | (1 << 20), &LEVELS, &mut addr_vec);
+        assert_eq!(addr_vec, [0, 0, 2, 0, 3 | (1 << 6), 5]);
+
+        for addr in [0, addr, addr | (1 << 20), 0x1234_5678 & ((1 << 29) - 1)] {
+            mapping.apply(addr, &LEVELS, &mut addr_vec);
+            assert_eq!(mapping.reverse(&LEVELS, &addr_vec), addr);
+        }
+    }
+
+    #[test]
+    fn test_ramulator_mapping_files() {
+        let mapping = MappingFile::parse(include_str!(
+            "../../ramulator/mappings/row_interleaving_randomized.map"
+        ))
+        .unwrap();
+        assert_eq!(mapping.get_level_bits(), [0, 0, 0, 3, 32, 6, 0, 0, 0]);
+        let mut addr_vec = vec![0; LEVELS.len()];
+        let addr = (1 << 40) | (1 << 13) | (1 << 6) | 1;
+        mapping.apply(addr, &LEVELS, &mut addr_vec);
+        assert_eq!(addr_vec[Level::Bank as usize], 0);
+        assert_eq!(mapping.reverse(&LEVELS, &addr_vec), addr);
+
+        // the levels only some standards have are mapped the same way
+        let mapping = MappingFile::parse("Sa 0 = 1").unwrap();
+        let levels = [
+            Level::Channel,
+            Level::Rank,
+            Level::Bank,
+            Level::SubArray,
+            Level::Row,
+            Level::Column,
+        ];
+        let mut addr_vec = vec![0; levels.len()];
+        mapping.apply(0b10, &levels, &mut addr_vec);
+        assert_eq!(addr_vec, [0, 0, 0, 1, 0, 0]);
+
+        let err = MappingFile::parse("Xx 0 = 1").unwrap_err();
+        assert!(matches!(err, ConfigError::UnknownKey { line: 1, .. }));
+        let err = MappingFile::parse("Ba 1:0 = 4:2").unwrap_err();
+        assert!(matches!(err, ConfigError::InvalidValue { line: 1, .. }));
+        let err = MappingFile::parse("\nBa 1 13").unwrap_err();
+        assert!(matches!(err, ConfigError::Syntax { line: 2, .. }));
+    }
+}
@@ -1,12 +1,16 @@
-use std::collections::VecDeque;
+use std::{collections::VecDeque, str::FromStr, sync::Arc};
 
 use num_enum::{IntoPrimitive, TryFromPrimitive};
 
+#[cfg(feature = "functional")]
+use crate::functional::FunctionalMemory;
 use crate::{
-    config::Config,
+    config::{Config, ConfigError},
     controller::Controller,
     dram::{Dram, DramSpec},
-    request::Request,
+    mapping::MappingFile,
+    request::{ReqType, Request},
+    stats::StatList,
 };
 
 pub trait MemoryTrait {
@@ -15,58 +19,372 @@ pub trait MemoryTrait {
     fn try_send(&mut self, req: Request) -> Result<(), Request>;
     fn try_recv(&mut self) -> Option<Request>;
     fn pending_requests(&self) -> usize;
-    fn finish(&mut self);
+    /// drain the queues, return the final stats of the simulation
+    fn finish(&mut self) -> StatList;
+    /// collect the stats of the memory and all its channels
+    fn get_stats(&self) -> StatList;
+    fn get_channel_num(&self) -> usize;
+    /// the channel the address is mapped to
+    fn get_channel_id(&self, addr: u64) -> usize;
+    /// map the physical address to the index of every level
+    fn get_addr_vec(&self, addr: u64) -> Vec<u64>;
+    /// the reverse of the address mapping, return the physical address of `addr_vec`
+    fn get_addr(&self, addr_vec: &[u64]) -> u64;
 }
 
-pub struct SimpleMemory<'a, T> {
+/// the counters of the whole memory, see `MemoryTrait::get_stats`
+#[derive(Default)]
+pub struct MemoryStats {
+    pub incoming_requests: u64,
+    pub read_requests: u64,
+    pub write_requests: u64,
+    pub pim_requests: u64,
+    pub incoming_requests_per_channel: Vec<u64>,
+    pub incoming_read_reqs_per_channel: Vec<u64>,
+    /// the sums of the queue lengths of all channels over all cycles
+    pub in_queue_req_num_sum: u64,
+    pub in_queue_read_req_num_sum: u64,
+    pub in_queue_write_req_num_sum: u64,
+}
+
+/// the cacheline offset bits of the physical address
+const TX_BITS: usize = 6;
+/// the cycles `finish` keeps ticking to drain the queues before giving up
+const MAX_DRAIN_CYCLES: u64 = 1_000_000;
+
+pub struct SimpleMemory<T> {
     clk: u64,
+    clk_ns: f64,
+    levels: Vec<Level>,
     addr_bits: Vec<usize>,
-    mapping_type: MappingType,
-    controllers: Vec<Controller<'a, T>>,
+    /// the indices of the levels sliced from the lowest bits of the address to the highest ones
+    slice_order: Vec<usize>,
+    /// overrides `slice_order` when set
+    mapping_file: Option<MappingFile>,
+    controllers: Vec<Controller<T>>,
     ret_queue: VecDeque<Request>,
+    stats: MemoryStats,
+    /// the data of the lines, the requests move it in the order they are accepted
+    #[cfg(feature = "functional")]
+    functional: FunctionalMemory,
+    /// the registers and the banks of a PIM unit, to find the unit of a request
+    #[cfg(feature = "functional")]
+    pim_registers: u64,
+    #[cfg(feature = "functional")]
+    pim_banks_per_unit: u64,
 }
-impl<'a, T> SimpleMemory<'a, T>
+impl<T> SimpleMemory<T>
 where
     T: DramSpec,
 {
-    pub fn new(config: &Config, controllers: Vec<Controller<'a, T>>, spec: &'a T) -> Self {
+    pub fn new(config: &Config, controllers: Vec<Controller<T>>, spec: &T) -> Self {
         SimpleMemory {
             clk: 0,
+            clk_ns: spec.get_clk_ns(),
+            levels: spec.get_levels().to_vec(),
             addr_bits: spec.get_addr_bits().to_vec(),
-            mapping_type: config.mapping_type,
+            slice_order: config.mapping_type.get_slice_order(spec.get_levels()),
+            mapping_file: None,
+            stats: MemoryStats {
+                incoming_requests_per_channel: vec![0; controllers.len()],
+                incoming_read_reqs_per_channel: vec![0; controllers.len()],
+                ..Default::default()
+            },
             controllers,
             ret_queue: Default::default(),
+            #[cfg(feature = "functional")]
+            functional: FunctionalMemory::new(),
+            #[cfg(feature = "functional")]
+            pim_registers: config.pim_registers as u64,
+            #[cfg(feature = "functional")]
+            pim_banks_per_unit: config.pim_banks_per_unit as u64,
         }
     }
-    pub fn with_config(config: &Config, spec: &'a T) -> Self {
+    /// build the channels of `spec`, return an error if the config asks for a feature the
+    /// standard does not have
+    pub fn with_config(config: &Config, spec: Arc<T>) -> Result<Self, ConfigError> {
+        if config.per_bank_refresh && !T::supports_per_bank_refresh() {
+            return Err(ConfigError::Unsupported {
+                key: "per_bank_refresh".to_string(),
+                reason: "the standard has no per-bank refresh command".to_string(),
+            });
+        }
         let mut controllers = vec![];
         let child_size = spec.get_child_size();
-        for _i in 0..child_size[0] {
-            controllers.push(Controller::new(
-                config,
-                Dram::new(spec, Level::Channel, child_size),
-            ));
+        for i in 0..child_size[0] {
+            let mut channel = Dram::new(spec.clone(), Level::Channel, child_size);
+            channel.id = i as u64;
+            controllers.push(Controller::new(config, channel));
+        }
+        let mut memory = SimpleMemory::new(config, controllers, &spec);
+        if let Some(path) = &config.mapping_file {
+            memory.set_mapping_file(MappingFile::from_file(path)?)?;
+        }
+        Ok(memory)
+    }
+    /// build the spec and the memory from the config alone, the options the standard cannot
+    /// model are reported instead of panicking
+    pub fn from_config(config: &Config) -> Result<Self, ConfigError> {
+        Self::with_config(config, Arc::new(T::from_config(config)?))
+    }
+    /// map the addresses with a bit-level mapping file instead of the `MappingType`, return an
+    /// error if it assigns more bits to a level than the organization has
+    pub fn set_mapping_file(&mut self, mapping_file: MappingFile) -> Result<(), ConfigError> {
+        for (level, bits) in mapping_file.get_level_bits().into_iter().enumerate() {
+            if bits == 0 {
+                continue;
+            }
+            let level = Level::try_from(level as u8).unwrap();
+            let available = self
+                .levels
+                .iter()
+                .position(|&l| l == level)
+                .map_or(0, |index| self.addr_bits[index]);
+            if bits > available {
+                return Err(ConfigError::Unsupported {
+                    key: "mapping_file".to_string(),
+                    reason: format!(
+                        "{bits} bits are assigned to {level:?}, only {available} are available"
+                    ),
+                });
+            }
+        }
+        self.mapping_file = Some(mapping_file);
+        Ok(())
+    }
+    /// the data of the lines, to set up the operands or check the results without the timing
+    #[cfg(feature = "functional")]
+    pub fn get_functional(&mut self) -> &mut FunctionalMemory {
+        &mut self.functional
+    }
+    /// a read takes the data of its line and a write stores its payload, a write without payload
+    /// leaves the line unchanged. the request was just accepted to the back of its queue, a
+    /// rejected one never gets here and leaves the data untouched
+    #[cfg(feature = "functional")]
+    fn access_data(&mut self, channel: usize, req_type: ReqType) {
+        let controller = &self.controllers[channel];
+        let queue = match req_type {
+            ReqType::Read => &controller.read_queue,
+            ReqType::Write => &controller.write_queue,
+            _ => return,
+        };
+        let addr = self.get_addr(&queue.queue.back().unwrap().addr_vec);
+        let controller = &mut self.controllers[channel];
+        match req_type {
+            ReqType::Read => {
+                let req = controller.read_queue.queue.back_mut().unwrap();
+                req.data = Some(Box::new(self.functional.read(addr)));
+            }
+            _ => {
+                let req = controller.write_queue.queue.back().unwrap();
+                if let Some(data) = &req.data {
+                    self.functional.write(addr, data);
+                }
+            }
+        }
+    }
+    /// run a PIM request on the data of every bank it targets, once it is accepted
+    #[cfg(feature = "functional")]
+    fn execute_pim(&mut self, req_type: ReqType, addr_vec: &[u64]) {
+        let index = |level| self.levels.iter().position(|&l| l == level).unwrap();
+        let rank = index(self.controllers[0].channel.spec.get_rank_level());
+        let (bank, column) = (index(Level::Bank), index(Level::Column));
+        // expand the levels set to `u64::MAX` to all their children
+        let mut targets = vec![addr_vec.to_vec()];
+        for (level, &bits) in self.addr_bits.iter().enumerate() {
+            if addr_vec[level] == u64::MAX {
+                targets = targets
+                    .into_iter()
+                    .flat_map(|target| {
+                        (0..1 << bits).map(move |child| {
+                            let mut target = target.clone();
+                            target[level] = child;
+                            target
+                        })
+                    })
+                    .collect();
+            }
+        }
+        for target in targets {
+            let addr = self.get_addr(&target);
+            let mut unit = target[..=bank].to_vec();
+            unit[bank] /= self.pim_banks_per_unit;
+            let register = (target[column] % self.pim_registers) as usize;
+            self.functional
+                .execute(req_type, addr, &unit, &target[..=rank], register);
+        }
+    }
+    /// dump the counters of the whole memory, the channels are not included
+    fn collect_stats(&self, stats: &mut StatList) {
+        let s = &self.stats;
+        stats.scalar(
+            "dram_cycles",
+            "Number of DRAM cycles simulated",
+            0,
+            self.clk as f64,
+        );
+        stats.scalar(
+            "incoming_requests",
+            "Number of incoming requests to DRAM",
+            0,
+            s.incoming_requests as f64,
+        );
+        stats.scalar(
+            "read_requests",
+            "Number of incoming read requests to DRAM",
+            0,
+            s.read_requests as f64,
+        );
+        stats.scalar(
+            "write_requests",
+            "Number of incoming write requests to DRAM",
+            0,
+            s.write_requests as f64,
+        );
+        stats.scalar(
+            "pim_requests",
+            "Number of incoming PIM requests to DRAM",
+            0,
+            s.pim_requests as f64,
+        );
+        stats.vector(
+            "incoming_requests_per_channel",
+            "Number of incoming requests to each DRAM channel",
+            s.incoming_requests_per_channel
+                .iter()
+                .map(|&n| n as f64)
+                .collect(),
+        );
+        stats.vector(
+            "incoming_read_reqs_per_channel",
+            "Number of incoming read requests to each DRAM channel",
+            s.incoming_read_reqs_per_channel
+                .iter()
+                .map(|&n| n as f64)
+                .collect(),
+        );
+        let queues = [
+            (
+                s.in_queue_req_num_sum,
+                "in_queue_req_num",
+                "read/write queue length",
+            ),
+            (
+                s.in_queue_read_req_num_sum,
+                "in_queue_read_req_num",
+                "read queue length",
+            ),
+            (
+                s.in_queue_write_req_num_sum,
+                "in_queue_write_req_num",
+                "write queue length",
+            ),
+        ];
+        for (sum, name, desc) in queues {
+            stats.scalar(
+                format!("{name}_sum"),
+                &format!("Sum of {desc}"),
+                0,
+                sum as f64,
+            );
+            let avg = if self.clk == 0 {
+                0.0
+            } else {
+                sum as f64 / self.clk as f64
+            };
+            stats.scalar(
+                format!("{name}_avg"),
+                &format!("Average of {desc} per memory cycle"),
+                6,
+                avg,
+            );
         }
-        SimpleMemory::new(config, controllers, spec)
     }
 }
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum MappingType {
     ChRaBaRoCo,
     RoBaRaCoCh,
     CoRoBaRaCh,
     RoCoBaRaCh,
 }
+impl FromStr for MappingType {
+    type Err = ();
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "ChRaBaRoCo" => MappingType::ChRaBaRoCo,
+            "RoBaRaCoCh" => MappingType::RoBaRaCoCh,
+            "CoRoBaRaCh" => MappingType::CoRoBaRaCh,
+            "RoCoBaRaCh" => MappingType::RoCoBaRaCh,
+            _ => return Err(()),
+        })
+    }
+}
 impl MappingType {
-    fn get_slice_sequence(&self) -> [usize; 5] {
+    /// the levels sliced from the lowest bits of the address to the highest ones, the pseudo
+    /// channels take the bits of the ranks, the subarrays and the PIM units are next to the banks
+    fn get_slice_sequence(&self) -> &'static [Level] {
+        use Level::*;
         match self {
-            MappingType::ChRaBaRoCo => [4, 3, 2, 1, 0],
-            MappingType::RoBaRaCoCh => [0, 4, 1, 2, 3],
-            MappingType::CoRoBaRaCh => [0, 1, 2, 3, 4],
-            MappingType::RoCoBaRaCh => [0, 1, 2, 4, 3],
+            MappingType::ChRaBaRoCo => &[
+                Column,
+                Row,
+                SubArray,
+                Bank,
+                PimUnit,
+                BankGroup,
+                PseudoChannel,
+                Rank,
+                Channel,
+            ],
+            MappingType::RoBaRaCoCh => &[
+                Channel,
+                Column,
+                PseudoChannel,
+                Rank,
+                BankGroup,
+                PimUnit,
+                Bank,
+                SubArray,
+                Row,
+            ],
+            MappingType::CoRoBaRaCh => &[
+                Channel,
+                PseudoChannel,
+                Rank,
+                BankGroup,
+                PimUnit,
+                Bank,
+                SubArray,
+                Row,
+                Column,
+            ],
+            MappingType::RoCoBaRaCh => &[
+                Channel,
+                PseudoChannel,
+                Rank,
+                BankGroup,
+                PimUnit,
+                Bank,
+                SubArray,
+                Column,
+                Row,
+            ],
         }
     }
+    /// the indices in `levels` sliced from the lowest bits of the address to the highest ones,
+    /// the levels the mapping does not know about take the highest bits
+    pub fn get_slice_order(&self, levels: &[Level]) -> Vec<usize> {
+        let sequence = self.get_slice_sequence();
+        let known = sequence
+            .iter()
+            .filter_map(|level| levels.iter().position(|l| l == level));
+        let unknown = (0..levels.len()).filter(|&index| !sequence.contains(&levels[index]));
+        known.chain(unknown).collect()
+    }
 }
+/// the kinds of the levels, every `DramSpec` picks its own hierarchy from them in
+/// `DramSpec::get_levels`
 #[derive(Debug, Clone, Copy, TryFromPrimitive, IntoPrimitive, PartialEq, Eq)]
 #[repr(u8)]
 pub enum Level {
@@ -76,23 +394,15 @@ pub enum Level {
     Bank,
     Row,
     Column,
+    /// the pseudo channels of HBM2, they share the command bus of their channel
+    PseudoChannel,
+    /// the subarrays of a bank, each one has its own row buffer like in SALP
+    SubArray,
+    /// the processing units of a PIM device, each one serves a group of banks
+    PimUnit,
     Max,
 }
 
-impl Level {
-    pub fn next_level(&self) -> Option<Level> {
-        match self {
-            Level::Channel => Some(Level::Rank),
-            Level::Rank => Some(Level::BankGroup),
-            Level::BankGroup => Some(Level::Bank),
-            Level::Bank => Some(Level::Row),
-            Level::Row => Some(Level::Column),
-            Level::Column => Some(Level::Max),
-            Level::Max => None,
-        }
-    }
-}
-
 fn slicing_lower_bits(addr: &mut u64, bits: usize) -> u64 {
     let mask = (1 << bits) - 1;
     let lower_bits = *addr & mask;
@@ -102,64 +412,336 @@ fn slicing_lower_bits(addr: &mut u64, bits: usize) -> u64 {
 fn clear_lower_bits(addr: &mut u64, bits: usize) {
     *addr >>= bits;
 }
-fn setup_addr_vec(mut addr: u64, addr_bits: &[usize], addr_vec: &mut [u64], sequence: &[usize]) {
-    for &level in sequence.iter() {
+fn setup_addr_vec(mut addr: u64, addr_bits: &[usize], addr_vec: &mut [u64], order: &[usize]) {
+    for &level in order.iter() {
         addr_vec[level] = slicing_lower_bits(&mut addr, addr_bits[level]);
     }
 }
 
-impl<'a, T> MemoryTrait for SimpleMemory<'a, T>
+impl<T> MemoryTrait for SimpleMemory<T>
 where
     T: DramSpec,
 {
     fn clk_ns(&self) -> f64 {
-        todo!()
+        self.clk_ns
     }
 
     fn tick(&mut self) {
         self.clk += 1;
+        for controller in self.controllers.iter() {
+            let read_queue_length =
+                (controller.read_queue.size() + controller.pending_queue.len()) as u64;
+            let write_queue_length = controller.write_queue.size() as u64;
+            self.stats.in_queue_req_num_sum += read_queue_length + write_queue_length;
+            self.stats.in_queue_read_req_num_sum += read_queue_length;
+            self.stats.in_queue_write_req_num_sum += write_queue_length;
+        }
         for controller in self.controllers.iter_mut() {
             controller.tick(self.clk);
-            if let Some(req) = controller.finished_queue.pop_front() {
-                self.ret_queue.push_back(req);
-            }
+            self.ret_queue.extend(controller.finished_queue.drain(..));
         }
     }
 
     fn try_send(&mut self, mut req: Request) -> Result<(), Request> {
         if !req.done_setup {
-            req.addr_vec.resize(self.addr_bits.len(), 0);
-            let mut addr = req.addr;
-            clear_lower_bits(&mut addr, 6);
-
-            setup_addr_vec(
-                addr,
-                &self.addr_bits,
-                &mut req.addr_vec,
-                &self.mapping_type.get_slice_sequence(),
-            );
+            req.addr_vec = self.get_addr_vec(req.addr);
             req.done_setup = true;
         }
-        self.controllers[req.addr_vec[0] as usize].try_enqueue(req)
+        // the channels are the roots of the hierarchy
+        let channel = req.addr_vec[0] as usize;
+        let req_type = req.req_type;
+        #[cfg(feature = "functional")]
+        let pim_addr_vec = matches!(
+            req_type,
+            ReqType::PimMac
+                | ReqType::PimMacAllBank
+                | ReqType::PimLoad
+                | ReqType::PimStore
+                | ReqType::PimBroadcast
+        )
+        .then(|| req.addr_vec.clone());
+        self.controllers[channel].try_enqueue(req)?;
+        #[cfg(feature = "functional")]
+        self.access_data(channel, req_type);
+        #[cfg(feature = "functional")]
+        if let Some(addr_vec) = pim_addr_vec {
+            self.execute_pim(req_type, &addr_vec);
+        }
+        match req_type {
+            ReqType::Read => {
+                self.stats.read_requests += 1;
+                self.stats.incoming_read_reqs_per_channel[channel] += 1;
+            }
+            ReqType::Write => self.stats.write_requests += 1,
+            ReqType::PimMac
+            | ReqType::PimMacAllBank
+            | ReqType::PimLoad
+            | ReqType::PimStore
+            | ReqType::PimBroadcast => self.stats.pim_requests += 1,
+            ReqType::Refresh | ReqType::RefreshBank => {}
+        }
+        self.stats.incoming_requests += 1;
+        self.stats.incoming_requests_per_channel[channel] += 1;
+        Ok(())
     }
 
     fn pending_requests(&self) -> usize {
-        self.controllers
-            .iter()
-            .map(|c| {
-                c.read_queue.size()
-                    + c.write_queue.size()
-                    + c.act_queue.size()
-                    + c.other_queue.size()
-            })
-            .sum()
+        self.controllers.iter().map(|c| c.pending_requests()).sum()
     }
 
-    fn finish(&mut self) {
-        todo!()
+    fn finish(&mut self) -> StatList {
+        let start = self.clk;
+        while self.pending_requests() > 0 && self.clk - start < MAX_DRAIN_CYCLES {
+            self.tick();
+        }
+        let mut stats = StatList::default();
+        self.collect_stats(&mut stats);
+        for controller in &self.controllers {
+            controller.finish(&mut stats);
+        }
+        stats
     }
 
     fn try_recv(&mut self) -> Option<Request> {
         self.ret_queue.pop_front()
     }
+
+    fn get_stats(&self) -> StatList {
+        let mut stats = StatList::default();
+        self.collect_stats(&mut stats);
+        for controller in &self.controllers {
+            controller.collect_stats(&mut stats);
+        }
+        stats
+    }
+
+    fn get_channel_num(&self) -> usize {
+        self.controllers.len()
+    }
+
+    fn get_channel_id(&self, addr: u64) -> usize {
+        self.get_addr_vec(addr)[0] as usize
+    }
+
+    fn get_addr_vec(&self, mut addr: u64) -> Vec<u64> {
+        let mut addr_vec = vec![0; self.addr_bits.len()];
+        clear_lower_bits(&mut addr, TX_BITS);
+        match &self.mapping_file {
+            Some(mapping_file) => mapping_file.apply(addr, &self.levels, &mut addr_vec),
+            None => setup_addr_vec(addr, &self.addr_bits, &mut addr_vec, &self.slice_order),
+        }
+        addr_vec
+    }
+
+    fn get_addr(&self, addr_vec: &[u64]) -> u64 {
+        let addr = match &self.mapping_file {
+            Some(mapping_file) => mapping_file.reverse(&self.levels, addr_vec),
+            None => self.slice_order.iter().rev().fold(0, |addr, &level| {
+                (addr << self.addr_bits[level]) | addr_vec[level]
+            }),
+        };
+        addr << TX_BITS
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use super::{setup_addr_vec, Level, MappingType, MemoryTrait, SimpleMemory, TX_BITS};
+    use crate::{
+        config::{Config, ConfigError},
+        ddr3::DDR3,
+        ddr4::DDR4,
+        dram::DramSpec,
+        mapping::MappingFile,
+        request::{ReqType, Request},
+        stats::StatValue,
+    };
+
+    #[test]
+    fn test_memory_stats() {
+        let config = Config {
+            channels: 2,
+            ..Default::default()
+        };
+        let ddr4 = Arc::new(DDR4::new(&config));
+        let mut mem = SimpleMemory::with_config(&config, ddr4.clone()).unwrap();
+        mem.try_send(Request::new(0, ReqType::Read)).unwrap();
+        mem.try_send(Request::new(1 << 6, ReqType::Read)).unwrap();
+        mem.try_send(Request::new(0, ReqType::Write)).unwrap();
+        let mut received = 0;
+        while received < 3 {
+            mem.tick();
+            received += std::iter::from_fn(|| mem.try_recv()).count();
+        }
+        let stats = mem.get_stats();
+        assert_eq!(stats.get_scalar("incoming_requests"), Some(3.0));
+        assert_eq!(stats.get_scalar("read_requests"), Some(2.0));
+        assert_eq!(stats.get_scalar("write_requests"), Some(1.0));
+        assert_eq!(
+            stats.get("incoming_read_reqs_per_channel"),
+            Some(&StatValue::Vector(vec![1.0, 1.0]))
+        );
+        // every channel is dumped with its own id
+        assert_eq!(
+            stats.get_scalar("read_row_misses_channel_1_core"),
+            Some(1.0)
+        );
+        let text = stats.to_text();
+        assert!(text
+            .lines()
+            .any(|line| line.trim_start().starts_with("dram_cycles")));
+        assert!(stats.to_json().contains("\"incoming_requests\": 3,"));
+    }
+
+    #[test]
+    fn test_finish_drains_queues() {
+        let config = Config::default();
+        let ddr4 = Arc::new(DDR4::new(&config));
+        let mut mem = SimpleMemory::with_config(&config, ddr4.clone()).unwrap();
+        assert_eq!(mem.clk_ns(), 0.625);
+        for i in 0..8 {
+            mem.try_send(Request::new(i << 6, ReqType::Read)).unwrap();
+            mem.try_send(Request::new(i << 20, ReqType::Write)).unwrap();
+        }
+        mem.tick();
+        assert!(mem.pending_requests() > 0);
+        let stats = mem.finish();
+        assert_eq!(mem.pending_requests(), 0);
+        assert_eq!(std::iter::from_fn(|| mem.try_recv()).count(), 16);
+        assert_eq!(stats.get_scalar("unfinished_requests_0"), Some(0.0));
+        assert_eq!(stats.get_scalar("dram_cycles"), Some(mem.clk as f64));
+        assert!(stats.get_scalar("read_latency_avg_0").unwrap() > 0.0);
+    }
+
+    #[test]
+    fn test_mapping_file_routing() {
+        let config = Config::default();
+        let ddr4 = Arc::new(DDR4::new(&config));
+        let mut mem = SimpleMemory::with_config(&config, ddr4.clone()).unwrap();
+        let addr_vec = vec![0, 0, 1, 2, 3, 4];
+        let addr = mem.get_addr(&addr_vec);
+        assert_eq!(addr & ((1 << TX_BITS) - 1), 0);
+        let mut req = Request::new(addr, ReqType::Read);
+        req.addr_vec.resize(mem.levels.len(), 0);
+        setup_addr_vec(
+            addr >> TX_BITS,
+            &mem.addr_bits,
+            &mut req.addr_vec,
+            &mem.slice_order,
+        );
+        assert_eq!(req.addr_vec, addr_vec);
+
+        // put the bank bits right above the column bits, xor-ed with the lowest row bits
+        let mapping_file = MappingFile::parse(
+            "Co 9:0 = 9:0\nBa 1:0 = 11:10\nBg 0 = 12 14\nBg 1 = 13 15\nRo 14:0 = 28:14",
+        )
+        .unwrap();
+        mem.set_mapping_file(mapping_file).unwrap();
+        let addr_vec = vec![0, 0, 1, 2, 3, 4];
+        let addr = mem.get_addr(&addr_vec);
+        assert_eq!(addr >> TX_BITS, (3 << 14) | (2 << 12) | (2 << 10) | 4);
+        mem.try_send(Request::new(addr, ReqType::Read)).unwrap();
+        let bank = &mem.controllers[0].read_queue.queue[0].addr_vec;
+        assert_eq!(bank, &addr_vec);
+    }
+
+    #[test]
+    fn test_mapping_file_config() {
+        let path = std::env::temp_dir().join(format!("ramu_pim_{}.map", std::process::id()));
+        std::fs::write(
+            &path,
+            "Co 9:0 = 9:0\nBa 1:0 = 11:10\nBg 1:0 = 13:12\nRo 14:0 = 28:14",
+        )
+        .unwrap();
+        let config = Config::parse(&format!("mapping_file = {}", path.display())).unwrap();
+        let mut mem = SimpleMemory::<DDR4>::from_config(&config).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let addr_vec = vec![0, 0, 1, 2, 3, 4];
+        let addr = (3 << 14) | (1 << 12) | (2 << 10) | 4;
+        assert_eq!(mem.get_addr(&addr_vec) >> TX_BITS, addr);
+        mem.try_send(Request::new(addr << TX_BITS, ReqType::Read))
+            .unwrap();
+        assert_eq!(mem.controllers[0].read_queue.queue[0].addr_vec, addr_vec);
+
+        // the 8 banks of the map do not fit the 4 per bank group of DDR4
+        let path = concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/../ramulator/mappings/row_interleaving.map"
+        );
+        let config = Config::parse(&format!("mapping_file = {path}")).unwrap();
+        let err = SimpleMemory::<DDR4>::from_config(&config).err().unwrap();
+        assert_eq!(
+            err.to_string(),
+            "unsupported `mapping_file`: 3 bits are assigned to Bank, only 2 are available"
+        );
+        let config = Config::parse("mapping_file = no/such/file.map").unwrap();
+        assert!(matches!(
+            SimpleMemory::<DDR4>::from_config(&config),
+            Err(ConfigError::Io(_))
+        ));
+        let config = Config::parse("mapping_file = defaultmapping").unwrap();
+        assert!(config.mapping_file.is_none());
+    }
+
+    #[test]
+    fn test_slice_order() {
+        let config = Config::default();
+        let ddr3 = Arc::new(DDR3::new(&config));
+        // DDR3 has no bank groups, the mapping skips them
+        assert_eq!(
+            MappingType::RoBaRaCoCh.get_slice_order(ddr3.get_levels()),
+            [0, 4, 1, 2, 3]
+        );
+        // the pseudo channels of HBM take the bits of the ranks, the subarrays are under the banks
+        let levels = |rank| {
+            [
+                Level::Channel,
+                rank,
+                Level::BankGroup,
+                Level::Bank,
+                Level::Row,
+                Level::Column,
+            ]
+        };
+        assert_eq!(
+            MappingType::ChRaBaRoCo.get_slice_order(&levels(Level::PseudoChannel)),
+            MappingType::ChRaBaRoCo.get_slice_order(&levels(Level::Rank))
+        );
+        let levels = [
+            Level::Channel,
+            Level::Rank,
+            Level::Bank,
+            Level::SubArray,
+            Level::Row,
+            Level::Column,
+        ];
+        assert_eq!(
+            MappingType::ChRaBaRoCo.get_slice_order(&levels),
+            [5, 4, 3, 2, 1, 0]
+        );
+        let mut mem = SimpleMemory::with_config(&config, ddr3.clone()).unwrap();
+        let addr_vec = vec![0, 0, 5, 7, 9];
+        let addr = mem.get_addr(&addr_vec);
+        assert_eq!(addr >> TX_BITS, (7 << 13) | (5 << 10) | 9);
+        mem.try_send(Request::new(addr, ReqType::Read)).unwrap();
+        assert_eq!(mem.controllers[0].read_queue.queue[0].addr_vec, addr_vec);
+    }
+
+    #[test]
+    fn test_owned_memory() {
+        fn assert_owned<T: Send + 'static>(_: &T) {}
+        let mut mem = SimpleMemory::<DDR4>::from_config(&Config::default()).unwrap();
+        assert_owned(&mem);
+        // the memory is not tied to the scope of its spec, so it moves to another thread
+        let handle = std::thread::spawn(move || {
+            mem.try_send(Request::new(0, ReqType::Read)).unwrap();
+            while mem.pending_requests() > 0 {
+                mem.tick();
+            }
+            mem.try_recv().is_some()
+        });
+        assert!(handle.join().unwrap());
+    }
 }
@@ -0,0 +1,248 @@
+use crate::{command::Command, config::Config};
+
+/// the bits of a SIMD lane, the fp16 lanes of HBM-PIM
+const LANE_BITS: u64 = 16;
+
+/// the shape of the processing units, shared by all of them
+#[derive(Debug, Clone, Copy)]
+pub struct PimUnitConfig {
+    /// the registers of a unit, a PIM command picks one by its column address
+    pub registers: usize,
+    /// the lanes of the ALU
+    pub simd_width: u64,
+    /// the cycles from an op entering the ALU to its result in the register
+    pub alu_latency: u64,
+    /// the cycles between two ops entering the ALU
+    pub alu_interval: u64,
+    /// the banks sharing a unit, 1 for a unit per bank or 2 for a unit per bank pair
+    pub banks_per_unit: usize,
+    /// the bytes of a column the unit reads or writes
+    pub column_bytes: u64,
+    /// the cycles from a column command to its data at the unit
+    pub data_latency: u64,
+}
+impl PimUnitConfig {
+    pub fn new(config: &Config, column_bytes: u64, data_latency: u64) -> Self {
+        assert!(
+            matches!(config.pim_banks_per_unit, 1 | 2),
+            "a PIM unit serves a bank or a bank pair"
+        );
+        assert!(
+            config.pim_registers > 0 && config.pim_simd_width > 0 && config.pim_alu_interval > 0
+        );
+        Self {
+            registers: config.pim_registers,
+            simd_width: config.pim_simd_width,
+            alu_latency: config.pim_alu_latency,
+            alu_interval: config.pim_alu_interval,
+            banks_per_unit: config.pim_banks_per_unit,
+            column_bytes,
+            data_latency,
+        }
+    }
+    /// the ALU ops to process a column
+    fn ops_per_column(&self) -> u64 {
+        (self.column_bytes * 8).div_ceil(self.simd_width * LANE_BITS)
+    }
+}
+
+/// the near-bank processing unit driven by the PIM commands of its banks
+pub struct ProcessingUnit {
+    pub config: PimUnitConfig,
+    /// the cycle the operand loaded into each register arrives
+    operands: Vec<u64>,
+    /// the cycle the last result accumulated into each register is ready, the accumulations
+    /// into a register are pipelined
+    results: Vec<u64>,
+    /// the cycle the ALU accepts the ops of the next column
+    alu_free: u64,
+    /// the ALU ops issued
+    pub ops: u64,
+    /// the cycles the ALU is taking ops
+    pub busy_cycles: u64,
+    pub loads: u64,
+    pub stores: u64,
+}
+impl ProcessingUnit {
+    pub fn new(config: PimUnitConfig) -> Self {
+        Self {
+            config,
+            operands: vec![0; config.registers],
+            results: vec![0; config.registers],
+            alu_free: 0,
+            ops: 0,
+            busy_cycles: 0,
+            loads: 0,
+            stores: 0,
+        }
+    }
+    /// return if the unit can take `cmd` to the register of `column` at `clk`, the column of a
+    /// MAC is processed as soon as it arrives, there is no buffer for it
+    pub fn is_ready(&self, cmd: &Command, column: u64, clk: u64) -> bool {
+        let register = self.get_register(column);
+        let arrival = clk + self.config.data_latency;
+        match cmd {
+            Command::MAC | Command::MACAB => {
+                arrival >= self.alu_free && arrival >= self.operands[register]
+            }
+            Command::LDR => arrival >= self.results[register],
+            Command::STR => clk >= self.get_register_clk(column),
+            _ => true,
+        }
+    }
+    /// take `cmd` to the register of `column` at `clk`
+    pub fn issue(&mut self, cmd: &Command, column: u64, clk: u64) {
+        let register = self.get_register(column);
+        let arrival = clk + self.config.data_latency;
+        match cmd {
+            Command::MAC | Command::MACAB => {
+                // the columns of a bank pair broadcast at the same cycle are processed in turn
+                let start = arrival.max(self.alu_free).max(self.operands[register]);
+                let ops = self.config.ops_per_column();
+                let occupancy = ops * self.config.alu_interval;
+                self.alu_free = start + occupancy;
+                self.results[register] =
+                    start + occupancy - self.config.alu_interval + self.config.alu_latency;
+                self.ops += ops;
+                self.busy_cycles += occupancy;
+            }
+            Command::LDR => {
+                self.operands[register] = arrival;
+                self.loads += 1;
+            }
+            Command::STR => self.stores += 1,
+            _ => {}
+        }
+    }
+    /// the cycle the register of `column` holds its latest value
+    pub fn get_register_clk(&self, column: u64) -> u64 {
+        let register = self.get_register(column);
+        self.operands[register].max(self.results[register])
+    }
+    fn get_register(&self, column: u64) -> usize {
+        (column % self.config.registers as u64) as usize
+    }
+    /// return if `cmd` is taken by the processing units
+    pub fn is_pim_cmd(cmd: &Command) -> bool {
+        matches!(
+            cmd,
+            Command::MAC | Command::MACAB | Command::LDR | Command::STR
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use super::{PimUnitConfig, ProcessingUnit};
+    use crate::{
+        command::Command,
+        config::Config,
+        controller::Controller,
+        ddr4::DDR4,
+        dram::{Dram, DramSpec},
+        memory::Level,
+        request::{ReqType, Request},
+        stats::StatList,
+    };
+
+    fn unit() -> ProcessingUnit {
+        ProcessingUnit::new(PimUnitConfig {
+            registers: 8,
+            simd_width: 16,
+            alu_latency: 4,
+            alu_interval: 2,
+            banks_per_unit: 1,
+            column_bytes: 64,
+            data_latency: 10,
+        })
+    }
+
+    #[test]
+    fn test_processing_unit() {
+        let mut unit = unit();
+        // a 64-byte column takes 2 ops of 16 fp16 lanes
+        unit.issue(&Command::LDR, 1, 0);
+        assert_eq!(unit.get_register_clk(1), 10);
+        assert!(!unit.is_ready(&Command::STR, 1, 9));
+        assert!(unit.is_ready(&Command::MAC, 1, 0));
+        unit.issue(&Command::MAC, 1, 0);
+        // the ops enter the ALU at 10 and 12, the last result is ready 4 cycles later
+        assert_eq!(unit.get_register_clk(1), 16);
+        assert_eq!((unit.ops, unit.busy_cycles), (2, 4));
+        // the ALU is busy until 14, so the next column can not arrive earlier
+        assert!(!unit.is_ready(&Command::MAC, 2, 3));
+        assert!(unit.is_ready(&Command::MAC, 2, 4));
+        // the registers are picked by the column address
+        assert!(!unit.is_ready(&Command::STR, 9, 15));
+        assert!(unit.is_ready(&Command::STR, 1, 16));
+        unit.issue(&Command::STR, 1, 16);
+        assert_eq!((unit.loads, unit.stores), (1, 1));
+    }
+
+    #[test]
+    fn test_bank_pair() {
+        let mut unit = unit();
+        // the columns of both banks of a pair are processed in turn
+        unit.issue(&Command::MACAB, 0, 0);
+        unit.issue(&Command::MACAB, 0, 0);
+        assert_eq!(unit.get_register_clk(0), 10 + 4 + 4 - 2 + 4);
+        assert_eq!(unit.busy_cycles, 8);
+    }
+
+    /// run a kernel of `macs` MACs with all-bank commands, return the stats of the channel
+    fn run_kernel(config: &Config, macs: u64) -> StatList {
+        let ddr4 = Arc::new(DDR4::new(config));
+        let channel = Dram::new(ddr4.clone(), Level::Channel, ddr4.get_child_size());
+        let mut controller = Controller::new(config, channel);
+        let all_banks = |column| vec![0, 0, u64::MAX, u64::MAX, 0, column];
+        let mut reqs = vec![Request::with_addr_vec(all_banks(0), ReqType::PimLoad)];
+        for column in 0..macs {
+            reqs.push(Request::with_addr_vec(
+                all_banks(column),
+                ReqType::PimMacAllBank,
+            ));
+        }
+        reqs.push(Request::with_addr_vec(all_banks(1), ReqType::PimStore));
+        let total = reqs.len();
+        for req in reqs {
+            controller.try_enqueue(req).unwrap();
+        }
+        let mut finished = 0;
+        let mut clk = 0;
+        while finished < total {
+            clk += 1;
+            controller.tick(clk);
+            finished += controller.finished_queue.drain(..).count();
+        }
+        let mut stats = StatList::default();
+        controller.collect_stats(&mut stats);
+        stats
+    }
+
+    #[test]
+    fn test_pim_kernel() {
+        let mut config = Config::default();
+        let stats = run_kernel(&config, 16);
+        let get = |stats: &StatList, name: &str| stats.get_scalar(name).unwrap();
+        // 16 banks, a 64-byte column takes 2 ops
+        assert_eq!(get(&stats, "pim_alu_ops_0"), 16.0 * 16.0 * 2.0);
+        assert_eq!(get(&stats, "pim_requests_0"), 18.0);
+        let memory_bound = get(&stats, "pim_finish_cycles_0");
+
+        // a slow ALU makes the kernel compute bound
+        config.pim_alu_interval = 16;
+        let stats = run_kernel(&config, 16);
+        let compute_bound = get(&stats, "pim_finish_cycles_0");
+        assert!(compute_bound > memory_bound + 15.0 * 16.0);
+        assert!(get(&stats, "pim_alu_utilization_0") > 0.5);
+
+        // a bank pair shares a unit, so the unit processes both columns of an all-bank MAC
+        config.pim_alu_interval = 1;
+        config.pim_banks_per_unit = 2;
+        let stats = run_kernel(&config, 16);
+        assert_eq!(get(&stats, "pim_alu_ops_0"), 16.0 * 16.0 * 2.0);
+        assert!(get(&stats, "pim_finish_cycles_0") >= memory_bound);
+    }
+}
@@ -0,0 +1,181 @@
+use std::str::FromStr;
+
+use crate::{command::Command, config::Config};
+
+/// Drain: enter the PIM mode after the reads and writes are all served
+///
+/// Immediate: enter the PIM mode as soon as a PIM request arrives, the reads and writes wait
+///
+/// Batch: enter the PIM mode when `batch_size` PIM requests are waiting or the host is idle
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PimDrainPolicy {
+    #[default]
+    Drain,
+    Immediate,
+    Batch,
+}
+impl FromStr for PimDrainPolicy {
+    type Err = ();
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "Drain" => PimDrainPolicy::Drain,
+            "Immediate" => PimDrainPolicy::Immediate,
+            "Batch" => PimDrainPolicy::Batch,
+            _ => return Err(()),
+        })
+    }
+}
+/// a switch between the host and the PIM modes in progress
+pub struct Transition {
+    /// entering the PIM mode or leaving it
+    pub entering: bool,
+    /// the commands of the sequence issued so far, to all the ranks in turn
+    pub issued: usize,
+    /// the cycle the device is in the new mode, set after the last command of the sequence
+    pub done_clk: Option<u64>,
+}
+/// the switches of the device between the host mode, serving the reads and writes, and the PIM
+/// mode, serving the PIM requests. like the SB and AB-PIM modes of HBM-PIM, a switch closes all
+/// the rows and issues a sequence of commands to a reserved row of every rank
+pub struct PimMode {
+    /// the PIM requests are served along the reads and writes when the modes are not switched
+    pub enabled: bool,
+    pub policy: PimDrainPolicy,
+    pub batch_size: usize,
+    pub entry_sequence: Vec<Command>,
+    pub exit_sequence: Vec<Command>,
+    /// the cycles after the last command of the sequence before the device is in the new mode
+    pub entry_latency: u64,
+    pub exit_latency: u64,
+    pub transition: Option<Transition>,
+}
+impl PimMode {
+    pub fn new(config: &Config) -> Self {
+        Self {
+            enabled: config.pim_mode_switch,
+            policy: config.pim_drain_policy,
+            batch_size: config.pim_batch_size,
+            entry_sequence: config.pim_entry_sequence.clone(),
+            exit_sequence: config.pim_exit_sequence.clone(),
+            entry_latency: config.pim_entry_latency,
+            exit_latency: config.pim_exit_latency,
+            transition: None,
+        }
+    }
+    /// return if the waiting PIM requests should take the device from the host
+    pub fn should_enter(&self, pim_waiting: usize, host_waiting: usize) -> bool {
+        pim_waiting > 0
+            && match self.policy {
+                PimDrainPolicy::Drain => host_waiting == 0,
+                PimDrainPolicy::Immediate => true,
+                PimDrainPolicy::Batch => pim_waiting >= self.batch_size || host_waiting == 0,
+            }
+    }
+    pub fn get_sequence(&self, entering: bool) -> &[Command] {
+        if entering {
+            &self.entry_sequence
+        } else {
+            &self.exit_sequence
+        }
+    }
+    pub fn get_latency(&self, entering: bool) -> u64 {
+        if entering {
+            self.entry_latency
+        } else {
+            self.exit_latency
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use super::PimDrainPolicy;
+    use crate::{
+        config::Config,
+        controller::Controller,
+        ddr4::DDR4,
+        dram::{Dram, DramSpec},
+        memory::Level,
+        request::{ReqType, Request},
+        stats::StatList,
+    };
+
+    /// send the reads to bank 0 and the PIM requests to bank 1, return the stats and the cycles
+    /// the last read and the last PIM request finish
+    fn run(config: &Config, reads: u64, pims: u64) -> (StatList, u64, u64) {
+        let ddr4 = Arc::new(DDR4::new(config));
+        let channel = Dram::new(ddr4.clone(), Level::Channel, ddr4.get_child_size());
+        let mut controller = Controller::new(config, channel);
+        for i in 0..reads.max(pims) {
+            if i < reads {
+                let req = Request::with_addr_vec(vec![0, 0, 0, 0, 0, i], ReqType::Read);
+                controller.try_enqueue(req).unwrap();
+            }
+            if i < pims {
+                let req = Request::with_addr_vec(vec![0, 0, 0, 1, 0, i], ReqType::PimMac);
+                controller.try_enqueue(req).unwrap();
+            }
+        }
+        let (mut last_read, mut last_pim) = (0, 0);
+        let mut clk = 0;
+        while controller.pending_requests() > 0 {
+            clk += 1;
+            controller.tick(clk);
+            for req in controller.finished_queue.drain(..) {
+                match req.req_type {
+                    ReqType::Read => last_read = clk,
+                    _ => last_pim = clk,
+                }
+            }
+        }
+        let mut stats = StatList::default();
+        controller.collect_stats(&mut stats);
+        (stats, last_read, last_pim)
+    }
+
+    #[test]
+    fn test_pim_mode_switch() {
+        let mut config = Config::parse(
+            "pim_mode_switch = on\npim_entry_sequence = ACT,PRE,ACT,PRE\npim_exit_latency = 20",
+        )
+        .unwrap();
+        assert_eq!(config.pim_drain_policy, PimDrainPolicy::Drain);
+        let get = |stats: &StatList, name: &str| stats.get_scalar(name).unwrap();
+
+        // the host traffic is drained before the PIM requests
+        let (stats, last_read, last_pim) = run(&config, 16, 16);
+        assert_eq!(get(&stats, "pim_mode_switches_0"), 1.0);
+        assert_eq!(get(&stats, "host_blocked_cycles_0"), 0.0);
+        assert!(last_read < last_pim);
+        let drain_transition = get(&stats, "pim_transition_cycles_0");
+        assert!(drain_transition > 20.0);
+        assert!(get(&stats, "pim_mode_cycles_0") > 0.0);
+
+        // the reads wait for the PIM requests and both switches
+        config.pim_drain_policy = PimDrainPolicy::Immediate;
+        let (stats, last_read, last_pim) = run(&config, 16, 16);
+        assert_eq!(get(&stats, "pim_mode_switches_0"), 1.0);
+        assert!(last_pim < last_read);
+        assert!(get(&stats, "host_blocked_cycles_0") >= drain_transition);
+
+        // without switching the modes the PIM requests do not wait for a transition
+        let (_, _, switched) = run(&config, 0, 16);
+        config.pim_mode_switch = false;
+        let (stats, _, inline) = run(&config, 0, 16);
+        assert_eq!(get(&stats, "pim_mode_switches_0"), 0.0);
+        assert!(inline < switched);
+    }
+
+    #[test]
+    fn test_batch_policy() {
+        let mut config = Config::parse("pim_mode_switch = on\npim_drain_policy = Batch").unwrap();
+        config.pim_batch_size = 4;
+        let (_, last_read, last_pim) = run(&config, 64, 4);
+        assert!(last_pim < last_read);
+        config.pim_batch_size = 8;
+        let (_, last_read, last_pim) = run(&config, 64, 4);
+        assert!(last_read < last_pim);
+    }
+}
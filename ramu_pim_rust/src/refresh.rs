@@ -1,8 +1,213 @@
-#[derive(Default)]
-pub struct Refresh {}
+use crate::{
+    config::Config,
+    controller::Queue,
+    dram::{Dram, DramSpec},
+    memory::Level,
+    request::{ReqType, Request},
+};
+
+/// refresh scheduler, every rank receive a REF every nREFI cycles. in per-bank mode the banks
+/// of a rank are refreshed one by one in round robin, one every nREFI / banks cycles. a
+/// same-bank refresh covers the bank of an index in every bank group, so the bank indices take
+/// turns instead
+pub struct Refresh {
+    channel_id: u64,
+    levels: usize,
+    rank_index: usize,
+    per_bank: bool,
+    /// the index and the size of each level a per-bank refresh picks, down to the bank, the
+    /// others under the rank are all refreshed
+    bank_levels: Vec<(usize, usize)>,
+    refresh_interval: u64,
+    /// the cycle when each rank should be refreshed next time
+    next_refresh: Vec<u64>,
+    /// the next bank of each rank to be refreshed in per-bank mode
+    next_bank: Vec<usize>,
+}
 impl Refresh {
-    pub fn tick(&mut self, clk: u64) {
-        tracing::error!(clk, "Refresh");
-        // TODO: implement refresh
+    pub fn new<T>(config: &Config, channel: &Dram<T>) -> Self
+    where
+        T: DramSpec,
+    {
+        // checked by `SimpleMemory::with_config`
+        let per_bank = config.per_bank_refresh;
+        assert!(
+            !per_bank || T::supports_per_bank_refresh(),
+            "per-bank refresh is not supported by this standard"
+        );
+        let spec = &channel.spec;
+        let rank_index = spec.get_level_index(spec.get_rank_level());
+        let bank_index = spec.get_level_index(Level::Bank);
+        let first = if T::refreshes_same_bank() {
+            bank_index
+        } else {
+            rank_index + 1
+        };
+        let child_size = spec.get_child_size();
+        let bank_levels = (first..=bank_index)
+            .map(|level| (level, child_size[level]))
+            .collect::<Vec<_>>();
+        let banks: usize = bank_levels.iter().map(|(_, size)| size).product();
+        let refresh_interval = if per_bank {
+            spec.get_refresh_interval() / banks as u64
+        } else {
+            spec.get_refresh_interval()
+        };
+        let ranks = channel.children.len();
+        Self {
+            channel_id: channel.id,
+            levels: spec.get_levels().len(),
+            rank_index,
+            per_bank,
+            bank_levels,
+            refresh_interval,
+            next_refresh: vec![refresh_interval; ranks],
+            next_bank: vec![0; ranks],
+        }
+    }
+    /// inject the refresh requests into `queue` for the ranks reaching their deadline
+    pub fn tick(&mut self, clk: u64, queue: &mut Queue) {
+        for (rank, next_refresh) in self.next_refresh.iter_mut().enumerate() {
+            if clk < *next_refresh {
+                continue;
+            }
+            if queue.full() {
+                // try again next cycle
+                tracing::debug!(clk, rank, "refresh delayed, queue is full");
+                continue;
+            }
+            let mut addr_vec = vec![u64::MAX; self.levels];
+            // the channel is the root of the hierarchy
+            addr_vec[0] = self.channel_id;
+            addr_vec[self.rank_index] = rank as u64;
+            let req_type = if self.per_bank {
+                // split the flat bank id into the bank group and bank addresses
+                let next_bank = &mut self.next_bank[rank];
+                let mut bank = *next_bank;
+                for &(level, size) in self.bank_levels.iter().rev() {
+                    addr_vec[level] = (bank % size) as u64;
+                    bank /= size;
+                }
+                let banks: usize = self.bank_levels.iter().map(|(_, size)| size).product();
+                *next_bank = (*next_bank + 1) % banks;
+                ReqType::RefreshBank
+            } else {
+                ReqType::Refresh
+            };
+            let mut req = Request::with_addr_vec(addr_vec, req_type);
+            req.arrival_time = clk;
+            queue.queue.push_back(req);
+            // advance from the deadline so a delayed refresh does not shift the later ones
+            *next_refresh += self.refresh_interval;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use super::Refresh;
+    use crate::{
+        command::Command,
+        config::Config,
+        controller::{Controller, Queue},
+        ddr4::DDR4,
+        dram::{Dram, DramSpec},
+        memory::Level,
+        request::{ReqType, Request},
+    };
+
+    fn get_nrfc(ddr4: &Arc<DDR4>) -> u64 {
+        ddr4.get_timming(ddr4.get_level_index(Level::Rank), &Command::REF)
+            .iter()
+            .find(|t| t.cmd == Command::ACT)
+            .unwrap()
+            .val
+    }
+
+    #[test]
+    fn test_refresh_every_nrefi() {
+        let config = Config {
+            ranks: 2,
+            ..Default::default()
+        };
+        let ddr4 = Arc::new(DDR4::new(&config));
+        let n_refi = ddr4.get_refresh_interval();
+        let channel = Dram::new(ddr4.clone(), Level::Channel, ddr4.get_child_size());
+        let mut controller = Controller::new(&config, channel);
+        let mut refreshed = vec![vec![]; 2];
+        for clk in 1..=3 * n_refi + 10 {
+            controller.tick(clk);
+            for (rank, refreshed) in refreshed.iter_mut().enumerate() {
+                let last = controller.channel.children[rank].prev[Command::REF as usize][0];
+                if last != u64::MAX && refreshed.last() != Some(&last) {
+                    refreshed.push(last);
+                }
+            }
+        }
+        for refreshed in refreshed {
+            assert_eq!(refreshed.len(), 3);
+            assert!(refreshed[0] >= n_refi);
+            for pair in refreshed.windows(2) {
+                assert_eq!(pair[1] - pair[0], n_refi);
+            }
+        }
+    }
+
+    #[test]
+    fn test_delayed_refresh() {
+        let config = Config::default();
+        let ddr4 = Arc::new(DDR4::new(&config));
+        let n_refi = ddr4.get_refresh_interval();
+        let channel = Dram::new(ddr4.clone(), Level::Channel, ddr4.get_child_size());
+        let mut refresh = Refresh::new(&config, &channel);
+        let mut queue = Queue::new(1);
+        queue
+            .queue
+            .push_back(Request::with_addr_vec(vec![0; 6], ReqType::Read));
+        // the queue is full at the deadline, the refresh goes in once it is drained
+        refresh.tick(n_refi, &mut queue);
+        assert_eq!(queue.size(), 1);
+        queue.queue.clear();
+        refresh.tick(n_refi + 100, &mut queue);
+        assert_eq!(queue.queue[0].arrival_time, n_refi + 100);
+        queue.queue.clear();
+        // the next deadline does not drift
+        refresh.tick(2 * n_refi - 1, &mut queue);
+        assert_eq!(queue.size(), 0);
+        refresh.tick(2 * n_refi, &mut queue);
+        assert_eq!(queue.size(), 1);
+    }
+
+    #[test]
+    fn test_read_stall_during_refresh() {
+        let config = Config::default();
+        let ddr4 = Arc::new(DDR4::new(&config));
+        let n_refi = ddr4.get_refresh_interval();
+        let n_rfc = get_nrfc(&ddr4);
+        let channel = Dram::new(ddr4.clone(), Level::Channel, ddr4.get_child_size());
+        let mut controller = Controller::new(&config, channel);
+        let mut clk = 0;
+        while controller.channel.children[0].prev[Command::REF as usize][0] == u64::MAX {
+            clk += 1;
+            controller.tick(clk);
+        }
+        let ref_clk = controller.channel.children[0].prev[Command::REF as usize][0];
+        assert_eq!(ref_clk, n_refi);
+
+        let mut req = Request::new(0, ReqType::Read);
+        req.addr_vec = vec![0; ddr4.get_levels().len()];
+        req.done_setup = true;
+        controller.try_enqueue(req).unwrap();
+        while controller.finished_queue.is_empty() {
+            clk += 1;
+            controller.tick(clk);
+        }
+        let bank = &controller.channel.children[0].children[0].children[0];
+        let act_clk = bank.prev[Command::ACT as usize][0];
+        assert!(act_clk >= ref_clk + n_rfc);
+        let req = controller.finished_queue.pop_front().unwrap();
+        assert!(req.finish_time >= ref_clk + n_rfc + ddr4.get_read_latency());
     }
 }
@@ -1,7 +1,24 @@
-#[derive(Debug, Clone, Copy)]
+#[cfg(feature = "functional")]
+use crate::functional::Line;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ReqType {
     Read,
     Write,
+    Refresh,
+    /// refresh only the bank targeted by addr_vec
+    RefreshBank,
+    /// a MAC of the PIM unit of the bank targeted by addr_vec
+    PimMac,
+    /// load a column into the PIM register of the bank targeted by addr_vec
+    PimLoad,
+    /// store the PIM register of the bank targeted by addr_vec into a column
+    PimStore,
+    /// a MAC of the PIM units of all the banks targeted by addr_vec, the bank and the bank group
+    /// set to `u64::MAX` target all of them in the rank
+    PimMacAllBank,
+    /// broadcast the operand to the PIM units of all the banks of the rank
+    PimBroadcast,
 }
 #[derive(Debug)]
 pub struct Request {
@@ -11,6 +28,11 @@ pub struct Request {
     pub req_type: ReqType,
     pub arrival_time: u64,
     pub finish_time: u64,
+    /// no command of the request has been issued yet
+    pub is_first_command: bool,
+    /// the payload of a write, or the line a read returns once finished
+    #[cfg(feature = "functional")]
+    pub data: Option<Box<Line>>,
 }
 impl Request {
     pub fn new(addr: u64, req_type: ReqType) -> Self {
@@ -21,6 +43,31 @@ impl Request {
             req_type,
             arrival_time: 0,
             finish_time: 0,
+            is_first_command: true,
+            #[cfg(feature = "functional")]
+            data: None,
+        }
+    }
+    /// build a request that targets the `addr_vec` directly, levels set to `u64::MAX` are not targeted
+    pub fn with_addr_vec(addr_vec: Vec<u64>, req_type: ReqType) -> Self {
+        Self {
+            addr: 0,
+            addr_vec,
+            done_setup: true,
+            req_type,
+            arrival_time: 0,
+            finish_time: 0,
+            is_first_command: true,
+            #[cfg(feature = "functional")]
+            data: None,
+        }
+    }
+    /// build a write carrying `data` to the line at `addr`
+    #[cfg(feature = "functional")]
+    pub fn with_data(addr: u64, data: Line) -> Self {
+        Self {
+            data: Some(Box::new(data)),
+            ..Self::new(addr, ReqType::Write)
         }
     }
 }
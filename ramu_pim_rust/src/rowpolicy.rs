@@ -1,2 +1,139 @@
-#[derive(Default)]
-pub struct RowPolicy {}
+use std::str::FromStr;
+
+use crate::{
+    command::Command,
+    config::Config,
+    dram::{Dram, DramSpec},
+    rowtable::RowTable,
+};
+
+/// Closed: precharge a row as soon as there are no pending requests to schedule
+///
+/// ClosedAP: like Closed, but accesses to the last pending request of a row are upgraded to
+/// RDA/WRA so the row is closed by auto precharge
+///
+/// Opened: precharge a row only if there are pending references to other rows
+///
+/// Timeout: precharge a row after it is not accessed for `timeout` cycles
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RowPolicyType {
+    Closed,
+    ClosedAP,
+    #[default]
+    Opened,
+    Timeout,
+}
+impl FromStr for RowPolicyType {
+    type Err = ();
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "Closed" => RowPolicyType::Closed,
+            "ClosedAP" => RowPolicyType::ClosedAP,
+            "Opened" => RowPolicyType::Opened,
+            "Timeout" => RowPolicyType::Timeout,
+            _ => return Err(()),
+        })
+    }
+}
+pub struct RowPolicy {
+    pub policy_type: RowPolicyType,
+    pub timeout: u64,
+}
+impl Default for RowPolicy {
+    fn default() -> Self {
+        Self {
+            policy_type: Default::default(),
+            timeout: 50,
+        }
+    }
+}
+impl RowPolicy {
+    pub fn new(config: &Config) -> Self {
+        Self {
+            policy_type: config.row_policy,
+            timeout: config.row_policy_timeout,
+        }
+    }
+    /// return the addr_vec of a bank that should be precharged now
+    pub fn get_victim<T>(&self, dram: &Dram<T>, row_table: &RowTable, clk: u64) -> Option<Vec<u64>>
+    where
+        T: DramSpec,
+    {
+        let cmd = Command::PRE;
+        row_table
+            .table
+            .iter()
+            .filter(|(_, entry)| match self.policy_type {
+                RowPolicyType::Closed | RowPolicyType::ClosedAP => true,
+                RowPolicyType::Opened => false,
+                RowPolicyType::Timeout => clk - entry.timestamp >= self.timeout,
+            })
+            .map(|(bank, _)| {
+                let mut addr_vec = bank.clone();
+                addr_vec.resize(dram.spec.get_levels().len(), u64::MAX);
+                addr_vec
+            })
+            .find(|addr_vec| dram.check(&cmd, addr_vec, clk))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use super::RowPolicyType;
+    use crate::{
+        command::Command,
+        config::Config,
+        controller::Controller,
+        ddr4::DDR4,
+        dram::{Dram, DramSpec},
+        memory::Level,
+        request::{ReqType, Request},
+    };
+
+    /// stream `count` reads to the columns of a single row, one request every `interval` cycles,
+    /// return the number of activations
+    fn stream_row(policy: RowPolicyType, interval: u64, count: u64) -> u64 {
+        let config = Config {
+            row_policy: policy,
+            ..Default::default()
+        };
+        let ddr4 = Arc::new(DDR4::new(&config));
+        let channel = Dram::new(ddr4.clone(), Level::Channel, ddr4.get_child_size());
+        let mut controller = Controller::new(&config, channel);
+        let mut acts = 0;
+        let mut finished = 0;
+        let mut clk = 0;
+        while finished < count {
+            clk += 1;
+            if clk % interval == 0 && clk / interval <= count {
+                let req =
+                    Request::with_addr_vec(vec![0, 0, 0, 0, 0, clk / interval], ReqType::Read);
+                controller.try_enqueue(req).unwrap();
+            }
+            controller.tick(clk);
+            let bank = &controller.channel.children[0].children[0].children[0];
+            if bank.prev[Command::ACT as usize][0] == clk {
+                acts += 1;
+            }
+            finished += controller.finished_queue.drain(..).count() as u64;
+        }
+        assert!(clk < ddr4.get_refresh_interval());
+        acts
+    }
+
+    #[test]
+    fn test_row_policy_hit_rate() {
+        // every access hits the opened row except the first one
+        assert_eq!(stream_row(RowPolicyType::Opened, 100, 32), 1);
+        // the row is closed between two accesses, every access misses
+        assert_eq!(stream_row(RowPolicyType::Closed, 100, 32), 32);
+        assert_eq!(stream_row(RowPolicyType::ClosedAP, 100, 32), 32);
+        // the row is closed only when the accesses are sparser than the timeout
+        assert_eq!(stream_row(RowPolicyType::Timeout, 20, 32), 1);
+        assert_eq!(stream_row(RowPolicyType::Timeout, 100, 32), 32);
+        // back-to-back accesses to the same row are hits even with the closed policy
+        assert_eq!(stream_row(RowPolicyType::Closed, 1, 32), 1);
+    }
+}
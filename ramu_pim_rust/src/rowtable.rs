@@ -1,2 +1,224 @@
-#[derive(Default)]
-pub struct RowTable {}
+use std::collections::BTreeMap;
+
+use crate::{command::Command, dram::DramSpec, memory::Level};
+
+pub struct Entry {
+    pub row: u64,
+    /// the number of column accesses since the row is opened
+    pub hits: u64,
+    /// the cycle the row is opened
+    pub opened: u64,
+    /// the cycle the row is opened or accessed last time
+    pub timestamp: u64,
+}
+/// the opened rows of all banks, keyed by the bank-level prefix of addr_vec
+pub struct RowTable {
+    pub table: BTreeMap<Vec<u64>, Entry>,
+    /// the index of the row in addr_vec
+    row_index: usize,
+    /// the number of nodes of each level, to expand the levels an all-bank command leaves unset
+    child_size: Vec<usize>,
+}
+impl RowTable {
+    pub fn new<T>(spec: &T) -> Self
+    where
+        T: DramSpec,
+    {
+        Self {
+            table: Default::default(),
+            row_index: spec.get_level_index(Level::Row),
+            child_size: spec.get_child_size().to_vec(),
+        }
+    }
+    pub fn update<T>(&mut self, spec: &T, cmd: &Command, addr_vec: &[u64], clk: u64)
+    where
+        T: DramSpec,
+    {
+        let row = addr_vec[self.row_index];
+        match cmd {
+            Command::ACT | Command::ACTAB => {
+                for bank in self.get_banks(addr_vec) {
+                    self.table.insert(
+                        bank,
+                        Entry {
+                            row,
+                            hits: 0,
+                            opened: clk,
+                            timestamp: clk,
+                        },
+                    );
+                }
+            }
+            Command::RD
+            | Command::WR
+            | Command::RDA
+            | Command::WRA
+            | Command::MAC
+            | Command::LDR
+            | Command::STR
+            | Command::MACAB => {
+                for bank in self.get_banks(addr_vec) {
+                    let entry = self
+                        .table
+                        .get_mut(&bank)
+                        .expect("the accessed row should be opened");
+                    assert_eq!(entry.row, row);
+                    entry.hits += 1;
+                    entry.timestamp = clk;
+                }
+            }
+            _ => {}
+        }
+        // we are closing one or more rows, remove their entries
+        let scope = match cmd {
+            Command::RDA | Command::WRA => Level::Bank,
+            Command::PRE | Command::PREA | Command::REF | Command::PREAB => spec.get_scope(cmd),
+            _ => return,
+        };
+        let scope = spec.get_level_index(scope);
+        let prefix = &addr_vec[..=scope];
+        self.table
+            .retain(|bank, _| !is_targeted(&bank[..=scope], prefix));
+    }
+    /// return the hits of the row targeted by `addr_vec`, or of the opened row of its bank when
+    /// `to_opened_row` is set
+    pub fn get_hits(&self, addr_vec: &[u64], to_opened_row: bool) -> u64 {
+        match self.get_entry(addr_vec) {
+            Some(entry) if to_opened_row || entry.row == addr_vec[self.row_index] => entry.hits,
+            _ => 0,
+        }
+    }
+    pub fn get_open_row(&self, addr_vec: &[u64]) -> Option<u64> {
+        self.get_entry(addr_vec).map(|entry| entry.row)
+    }
+    /// the entry of the bank targeted by `addr_vec`, or of the first bank targeted by an all-bank
+    /// command, they are opened and accessed together
+    fn get_entry(&self, addr_vec: &[u64]) -> Option<&Entry> {
+        let bank = self.get_bank(addr_vec);
+        if bank.contains(&u64::MAX) {
+            self.table.get(&self.get_banks(addr_vec)[0])
+        } else {
+            self.table.get(bank)
+        }
+    }
+    /// the prefix of `addr_vec` that identifies a bank
+    fn get_bank<'b>(&self, addr_vec: &'b [u64]) -> &'b [u64] {
+        &addr_vec[..self.row_index]
+    }
+    /// the banks targeted by `addr_vec`, every node of a level set to `u64::MAX` is targeted
+    fn get_banks(&self, addr_vec: &[u64]) -> Vec<Vec<u64>> {
+        let mut banks = vec![vec![]];
+        for (level, &addr) in self.get_bank(addr_vec).iter().enumerate() {
+            let addrs = if addr == u64::MAX {
+                (0..self.child_size[level] as u64).collect()
+            } else {
+                vec![addr]
+            };
+            banks = banks
+                .into_iter()
+                .flat_map(|bank| {
+                    addrs.iter().map(move |&addr| {
+                        let mut bank = bank.clone();
+                        bank.push(addr);
+                        bank
+                    })
+                })
+                .collect();
+        }
+        banks
+    }
+}
+/// return if the `node` prefix of a bank is targeted by the `prefix` of an addr_vec
+fn is_targeted(node: &[u64], prefix: &[u64]) -> bool {
+    node.iter()
+        .zip(prefix)
+        .all(|(&node, &addr)| addr == u64::MAX || node == addr)
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use super::RowTable;
+    use crate::{
+        command::Command,
+        config::Config,
+        controller::Controller,
+        ddr4::DDR4,
+        dram::{Dram, DramSpec},
+        memory::Level,
+        request::{ReqType, Request},
+    };
+
+    #[test]
+    fn test_row_table() {
+        let ddr4 = DDR4::new(&Config::default());
+        let mut row_table = RowTable::new(&ddr4);
+        let bank0 = [0, 0, 0, 0, 3, 0];
+        let bank1 = [0, 0, 1, 2, 5, 0];
+        row_table.update(&ddr4, &Command::ACT, &bank0, 10);
+        row_table.update(&ddr4, &Command::ACT, &bank1, 20);
+        row_table.update(&ddr4, &Command::RD, &bank0, 30);
+        row_table.update(&ddr4, &Command::WR, &bank0, 40);
+        let entry = &row_table.table[&bank0[..4]];
+        assert_eq!(
+            (entry.row, entry.hits, entry.opened, entry.timestamp),
+            (3, 2, 10, 40)
+        );
+        assert_eq!(row_table.get_hits(&bank0, false), 2);
+        assert_eq!(row_table.get_hits(&[0, 0, 0, 0, 4, 0], false), 0);
+        assert_eq!(row_table.get_hits(&[0, 0, 0, 0, 4, 0], true), 2);
+        assert_eq!(row_table.get_open_row(&bank1), Some(5));
+
+        // auto precharge closes the row after the access
+        row_table.update(&ddr4, &Command::RDA, &bank0, 50);
+        assert_eq!(row_table.get_open_row(&bank0), None);
+        assert_eq!(row_table.get_open_row(&bank1), Some(5));
+        row_table.update(&ddr4, &Command::ACT, &bank0, 60);
+        row_table.update(&ddr4, &Command::PRE, &bank0, 70);
+        assert_eq!(row_table.get_open_row(&bank0), None);
+        // PREA closes all the banks of the rank
+        row_table.update(&ddr4, &Command::ACT, &bank0, 80);
+        let rank = [0, 0, u64::MAX, u64::MAX, u64::MAX, u64::MAX];
+        row_table.update(&ddr4, &Command::PREA, &rank, 90);
+        assert!(row_table.table.is_empty());
+
+        // the all-bank commands open and close every bank of the bank group
+        let bank_group = [0, 0, 1, u64::MAX, 7, 0];
+        row_table.update(&ddr4, &Command::ACTAB, &bank_group, 100);
+        assert_eq!(row_table.table.len(), 4);
+        row_table.update(&ddr4, &Command::MACAB, &bank_group, 110);
+        assert_eq!(row_table.get_hits(&[0, 0, 1, 3, 7, 0], false), 1);
+        row_table.update(&ddr4, &Command::ACT, &bank0, 120);
+        row_table.update(&ddr4, &Command::PREAB, &bank_group, 130);
+        assert_eq!(row_table.table.len(), 1);
+        assert_eq!(row_table.get_open_row(&bank0), Some(3));
+    }
+
+    #[test]
+    fn test_row_hit_query() {
+        let config = Config::default();
+        let ddr4 = Arc::new(DDR4::new(&config));
+        let channel = Dram::new(ddr4.clone(), Level::Channel, ddr4.get_child_size());
+        let mut controller = Controller::new(&config, channel);
+        let hit = Request::with_addr_vec(vec![0, 0, 0, 0, 0, 1], ReqType::Read);
+        let miss = Request::with_addr_vec(vec![0, 0, 0, 1, 0, 0], ReqType::Read);
+        let conflict = Request::with_addr_vec(vec![0, 0, 0, 0, 1, 0], ReqType::Write);
+        controller
+            .try_enqueue(Request::with_addr_vec(vec![0; 6], ReqType::Read))
+            .unwrap();
+        let mut clk = 0;
+        while controller.finished_queue.is_empty() {
+            clk += 1;
+            controller.tick(clk);
+        }
+        assert!(controller.is_row_hit_req(&hit));
+        assert!(controller.is_row_open_req(&hit));
+        assert!(!controller.is_row_hit_req(&miss));
+        assert!(!controller.is_row_open_req(&miss));
+        assert!(!controller.is_row_hit_req(&conflict));
+        assert!(controller.is_row_open_req(&conflict));
+        assert!(controller.is_row_hit_cmd(&Command::WR, &hit.addr_vec));
+        assert_eq!(controller.row_table.get_hits(&hit.addr_vec, false), 1);
+    }
+}
@@ -0,0 +1,102 @@
+use crate::{
+    config::{Config, ConfigError},
+    factory::create_memory,
+    memory::MemoryTrait,
+    request::{ReqType, Request},
+};
+
+/// the memory handed to the C++ side, it owns the memory of any standard behind one type, like
+/// the `ramulator_wrapper` of ramulator
+pub struct RustMemory {
+    memory: Box<dyn MemoryTrait + Send>,
+}
+impl RustMemory {
+    pub fn new(config: &Config) -> Result<Self, ConfigError> {
+        Ok(Self {
+            memory: create_memory(config)?,
+        })
+    }
+    /// return if the memory accepts the request, the C++ side sends it again later otherwise
+    pub fn try_send(&mut self, addr: u64, is_write: bool) -> bool {
+        let req_type = if is_write {
+            ReqType::Write
+        } else {
+            ReqType::Read
+        };
+        self.memory.try_send(Request::new(addr, req_type)).is_ok()
+    }
+    pub fn tick(&mut self) {
+        self.memory.tick();
+    }
+    /// pop the next finished read into `addr`, return false if there is none, the finished
+    /// writes are not returned
+    pub fn try_recv(&mut self, addr: &mut u64) -> bool {
+        while let Some(req) = self.memory.try_recv() {
+            if req.req_type == ReqType::Read {
+                *addr = req.addr;
+                return true;
+            }
+        }
+        false
+    }
+    pub fn pending_requests(&self) -> usize {
+        self.memory.pending_requests()
+    }
+    pub fn get_channel_num(&self) -> usize {
+        self.memory.get_channel_num()
+    }
+    pub fn get_channel_id(&self, addr: u64) -> usize {
+        self.memory.get_channel_id(addr)
+    }
+    /// drain the requests in flight and return the stats as text
+    pub fn finish(&mut self) -> String {
+        self.memory.finish().to_text()
+    }
+}
+
+/// build a memory from a ramulator config file
+pub fn memory_from_file(path: &str) -> Result<Box<RustMemory>, ConfigError> {
+    Ok(Box::new(RustMemory::new(&Config::from_file(path)?)?))
+}
+/// build a memory from the content of a ramulator config file
+pub fn memory_from_str(content: &str) -> Result<Box<RustMemory>, ConfigError> {
+    Ok(Box::new(RustMemory::new(&Config::parse(content)?)?))
+}
+
+#[cfg(test)]
+mod test {
+    use super::{memory_from_file, memory_from_str};
+
+    #[test]
+    fn test_rust_memory() {
+        let mut memory = memory_from_str("standard = HBM\nchannels = 8").unwrap();
+        for i in 0..16 {
+            assert!(memory.try_send(i * 64, i % 4 == 0));
+        }
+        let mut addrs = vec![];
+        let mut addr = 0;
+        while memory.pending_requests() > 0 {
+            memory.tick();
+            while memory.try_recv(&mut addr) {
+                addrs.push(addr);
+            }
+        }
+        addrs.sort();
+        assert_eq!(
+            addrs,
+            (0..16)
+                .filter(|i| i % 4 != 0)
+                .map(|i| i * 64)
+                .collect::<Vec<_>>()
+        );
+        assert!(memory.finish().contains("read_requests"));
+
+        assert!(memory_from_str("standard = DDR9").is_err());
+        assert!(memory_from_file("no/such/config.cfg").is_err());
+        let path = concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/../ramulator/configs/DDR4-config.cfg"
+        );
+        assert!(memory_from_file(path).is_ok());
+    }
+}
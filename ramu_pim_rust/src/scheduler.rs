@@ -1,31 +1,303 @@
-use crate::{controller::Queue, dram::Dram, request::Request};
+use std::str::FromStr;
 
-#[derive(Default)]
+use crate::{
+    config::Config,
+    controller::Queue,
+    dram::{Dram, DramSpec},
+    memory::Level,
+    request::Request,
+    rowtable::RowTable,
+};
+
+/// FCFS: schedule the requests chronologically
+///
+/// FRFCFS: schedule the ready requests first, then the oldest one
+///
+/// FRFCFS_Cap: same as FRFCFS, but a request is not considered ready once the opened row of its
+/// bank has been hit more than `cap` times
+///
+/// FRFCFS_PriorHit: schedule the ready row hits first, and never close a row that still has
+/// pending hits
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+#[allow(clippy::upper_case_acronyms, non_camel_case_types)]
 pub enum SchedulerType {
     #[default]
     FCFS,
     FRFCFS,
+    FRFCFS_Cap,
+    FRFCFS_PriorHit,
+}
+impl FromStr for SchedulerType {
+    type Err = ();
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "FCFS" => SchedulerType::FCFS,
+            "FRFCFS" => SchedulerType::FRFCFS,
+            "FRFCFS_Cap" => SchedulerType::FRFCFS_Cap,
+            "FRFCFS_PriorHit" => SchedulerType::FRFCFS_PriorHit,
+            _ => return Err(()),
+        })
+    }
 }
-#[derive(Default)]
 pub struct Scheduler {
     pub scheduler_type: SchedulerType,
+    pub cap: u64,
+}
+impl Default for Scheduler {
+    fn default() -> Self {
+        Self {
+            scheduler_type: Default::default(),
+            cap: 16,
+        }
+    }
 }
 
 impl Scheduler {
-    pub fn get_best_req<'a, 'b, T>(
+    pub fn new(config: &Config) -> Self {
+        Self {
+            scheduler_type: config.scheduler_type,
+            cap: config.scheduler_cap,
+        }
+    }
+    pub fn get_best_req<'b, T>(
         &self,
         queue: &'b Queue,
-        dram: &Dram<'a, T>,
-    ) -> Option<(usize, &'b Request)> {
-        if queue.size() == 0 {
-            return None;
-        } else {
-            match self.scheduler_type {
-                SchedulerType::FCFS => {
-                    return Some((0, &queue.queue[0]));
-                }
-                SchedulerType::FRFCFS => todo!(),
-            }
+        dram: &Dram<T>,
+        row_table: &RowTable,
+        clk: u64,
+    ) -> Option<(usize, &'b Request)>
+    where
+        T: DramSpec,
+    {
+        let reqs = queue.queue.iter().enumerate();
+        match self.scheduler_type {
+            SchedulerType::FCFS => reqs.reduce(|req1, req2| compare(req1, false, req2, false)),
+            SchedulerType::FRFCFS => reqs.reduce(|req1, req2| {
+                let ready1 = is_ready(req1.1, dram, clk);
+                let ready2 = is_ready(req2.1, dram, clk);
+                compare(req1, ready1, req2, ready2)
+            }),
+            SchedulerType::FRFCFS_Cap => reqs.reduce(|req1, req2| {
+                let ready1 = is_ready(req1.1, dram, clk)
+                    && row_table.get_hits(&req1.1.addr_vec, false) <= self.cap;
+                let ready2 = is_ready(req2.1, dram, clk)
+                    && row_table.get_hits(&req2.1.addr_vec, false) <= self.cap;
+                compare(req1, ready1, req2, ready2)
+            }),
+            SchedulerType::FRFCFS_PriorHit => self.get_best_prior_hit(queue, dram, clk),
+        }
+    }
+    fn get_best_prior_hit<'b, T>(
+        &self,
+        queue: &'b Queue,
+        dram: &Dram<T>,
+        clk: u64,
+    ) -> Option<(usize, &'b Request)>
+    where
+        T: DramSpec,
+    {
+        let is_ready_hit =
+            |req: &Request| is_ready(req, dram, clk) && dram.check_row_hit(&req.addr_vec);
+        let head =
+            queue.queue.iter().enumerate().reduce(|req1, req2| {
+                compare(req1, is_ready_hit(req1.1), req2, is_ready_hit(req2.1))
+            })?;
+        if is_ready_hit(head.1) {
+            return Some(head);
+        }
+        // the banks that still have pending hits, they should not be closed
+        let row_index = dram.spec.get_level_index(Level::Row);
+        let hit_banks = queue
+            .queue
+            .iter()
+            .filter(|req| dram.check_row_hit(&req.addr_vec))
+            .map(|req| get_bank(&req.addr_vec, row_index))
+            .collect::<Vec<_>>();
+        queue
+            .queue
+            .iter()
+            .enumerate()
+            .filter(|(_, req)| {
+                dram.check_row_hit(&req.addr_vec)
+                    || !dram.check_row_open(&req.addr_vec)
+                    || !hit_banks.contains(&get_bank(&req.addr_vec, row_index))
+            })
+            .reduce(|req1, req2| {
+                let ready1 = is_ready(req1.1, dram, clk);
+                let ready2 = is_ready(req2.1, dram, clk);
+                compare(req1, ready1, req2, ready2)
+            })
+    }
+}
+
+/// the prefix of `addr_vec` that identifies a bank, the levels above the row
+fn get_bank(addr_vec: &[u64], row_index: usize) -> &[u64] {
+    &addr_vec[..row_index]
+}
+fn is_ready<T>(req: &Request, dram: &Dram<T>, clk: u64) -> bool
+where
+    T: DramSpec,
+{
+    let cmd = dram.decode(&dram.get_first_cmd(&req.req_type), &req.addr_vec);
+    dram.check(&cmd, &req.addr_vec, clk)
+}
+/// prefer the ready request, then the older one
+fn compare<'b>(
+    req1: (usize, &'b Request),
+    ready1: bool,
+    req2: (usize, &'b Request),
+    ready2: bool,
+) -> (usize, &'b Request) {
+    if ready1 ^ ready2 {
+        if ready1 {
+            return req1;
         }
+        return req2;
+    }
+    if req1.1.arrival_time <= req2.1.arrival_time {
+        req1
+    } else {
+        req2
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use super::{Scheduler, SchedulerType};
+    use crate::{
+        command::Command,
+        config::Config,
+        controller::Queue,
+        ddr4::DDR4,
+        dram::{Dram, DramSpec},
+        memory::Level,
+        request::{ReqType, Request},
+        rowtable::RowTable,
+    };
+
+    fn build_req(row: u64, arrival_time: u64) -> Request {
+        let mut req = Request::with_addr_vec(vec![0, 0, 0, 0, row, 0], ReqType::Read);
+        req.arrival_time = arrival_time;
+        req
+    }
+    fn get_timing(ddr4: &Arc<DDR4>, level: Level, from: Command, to: Command) -> u64 {
+        ddr4.get_timming(ddr4.get_level_index(level), &from)
+            .iter()
+            .find(|t| t.cmd == to)
+            .unwrap()
+            .val
+    }
+
+    /// row 0 of bank 0 is opened, the older request conflicts and the younger one hits
+    fn setup(ddr4: &Arc<DDR4>) -> (Dram<DDR4>, RowTable, Queue) {
+        let mut dram = Dram::new(ddr4.clone(), Level::Channel, ddr4.get_child_size());
+        dram.update(&Command::ACT, &[0, 0, 0, 0, 0, 0], 0);
+        let mut row_table = RowTable::new(&**ddr4);
+        row_table.update(&**ddr4, &Command::ACT, &[0, 0, 0, 0, 0, 0], 0);
+        let mut queue = Queue::default();
+        queue.queue.push_back(build_req(1, 1));
+        queue.queue.push_back(build_req(0, 2));
+        (dram, row_table, queue)
+    }
+
+    #[test]
+    fn test_frfcfs() {
+        let ddr4 = Arc::new(DDR4::new(&Config::default()));
+        let (dram, row_table, queue) = setup(&ddr4);
+        // the RD is ready while the PRE is not
+        let clk = get_timing(&ddr4, Level::Bank, Command::ACT, Command::RD);
+        let n_ras = get_timing(&ddr4, Level::Bank, Command::ACT, Command::PRE);
+        assert!(clk < n_ras);
+
+        let mut scheduler = Scheduler::default();
+        assert_eq!(
+            scheduler
+                .get_best_req(&queue, &dram, &row_table, clk)
+                .unwrap()
+                .0,
+            0
+        );
+        scheduler.scheduler_type = SchedulerType::FRFCFS;
+        assert_eq!(
+            scheduler
+                .get_best_req(&queue, &dram, &row_table, clk)
+                .unwrap()
+                .0,
+            1
+        );
+        // both are ready, the older one goes first
+        assert_eq!(
+            scheduler
+                .get_best_req(&queue, &dram, &row_table, n_ras)
+                .unwrap()
+                .0,
+            0
+        );
+    }
+
+    #[test]
+    fn test_frfcfs_cap() {
+        let ddr4 = Arc::new(DDR4::new(&Config::default()));
+        let (dram, mut row_table, queue) = setup(&ddr4);
+        let clk = get_timing(&ddr4, Level::Bank, Command::ACT, Command::RD);
+        let scheduler = Scheduler {
+            scheduler_type: SchedulerType::FRFCFS_Cap,
+            cap: 1,
+        };
+        row_table.update(&*ddr4, &Command::RD, &[0, 0, 0, 0, 0, 0], 0);
+        assert_eq!(
+            scheduler
+                .get_best_req(&queue, &dram, &row_table, clk)
+                .unwrap()
+                .0,
+            1
+        );
+        // the cap is reached, fall back to the oldest
+        row_table.update(&*ddr4, &Command::RD, &[0, 0, 0, 0, 0, 0], 0);
+        assert_eq!(
+            scheduler
+                .get_best_req(&queue, &dram, &row_table, clk)
+                .unwrap()
+                .0,
+            0
+        );
+        // a new activation resets the hits
+        row_table.update(&*ddr4, &Command::ACT, &[0, 0, 0, 0, 0, 0], 0);
+        assert_eq!(
+            scheduler
+                .get_best_req(&queue, &dram, &row_table, clk)
+                .unwrap()
+                .0,
+            1
+        );
+    }
+
+    #[test]
+    fn test_frfcfs_prior_hit() {
+        let ddr4 = Arc::new(DDR4::new(&Config::default()));
+        let (dram, row_table, queue) = setup(&ddr4);
+        let n_ras = get_timing(&ddr4, Level::Bank, Command::ACT, Command::PRE);
+        let scheduler = Scheduler {
+            scheduler_type: SchedulerType::FRFCFS_PriorHit,
+            ..Default::default()
+        };
+        // both are ready, but the hit goes first
+        assert_eq!(
+            scheduler
+                .get_best_req(&queue, &dram, &row_table, n_ras)
+                .unwrap()
+                .0,
+            1
+        );
+        // neither is ready, but the older conflict must not close the row
+        assert_eq!(
+            scheduler
+                .get_best_req(&queue, &dram, &row_table, 0)
+                .unwrap()
+                .0,
+            1
+        );
     }
 }
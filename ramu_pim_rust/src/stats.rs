@@ -0,0 +1,228 @@
+use std::fmt::Write;
+
+/// a histogram of samples with fixed-size buckets, the samples beyond the last bucket are
+/// counted as overflows
+#[derive(Debug, Clone, PartialEq)]
+pub struct Histogram {
+    pub bucket_size: u64,
+    pub buckets: Vec<u64>,
+    pub overflows: u64,
+    pub samples: u64,
+}
+/// 10-cycle buckets up to 500 cycles, wide enough for the latencies of a memory request
+impl Default for Histogram {
+    fn default() -> Self {
+        Self::new(10, 50)
+    }
+}
+impl Histogram {
+    pub fn new(bucket_size: u64, num_buckets: usize) -> Self {
+        Self {
+            bucket_size,
+            buckets: vec![0; num_buckets],
+            overflows: 0,
+            samples: 0,
+        }
+    }
+    pub fn add(&mut self, value: u64) {
+        self.samples += 1;
+        match self.buckets.get_mut((value / self.bucket_size) as usize) {
+            Some(bucket) => *bucket += 1,
+            None => self.overflows += 1,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum StatValue {
+    Scalar(f64),
+    /// the total is printed first, then each element
+    Vector(Vec<f64>),
+    Histogram(Histogram),
+}
+#[derive(Debug, Clone, PartialEq)]
+pub struct Stat {
+    pub name: String,
+    pub desc: String,
+    /// the digits after the decimal point in the text output
+    pub precision: usize,
+    pub value: StatValue,
+}
+
+/// the registry the components dump their counters into at the end of a run
+#[derive(Debug, Default)]
+pub struct StatList {
+    pub stats: Vec<Stat>,
+}
+impl StatList {
+    pub fn scalar(&mut self, name: impl Into<String>, desc: &str, precision: usize, value: f64) {
+        self.push(name, desc, precision, StatValue::Scalar(value));
+    }
+    pub fn vector(&mut self, name: impl Into<String>, desc: &str, values: Vec<f64>) {
+        self.push(name, desc, 0, StatValue::Vector(values));
+    }
+    pub fn histogram(&mut self, name: impl Into<String>, desc: &str, histogram: Histogram) {
+        self.push(name, desc, 0, StatValue::Histogram(histogram));
+    }
+    fn push(&mut self, name: impl Into<String>, desc: &str, precision: usize, value: StatValue) {
+        self.stats.push(Stat {
+            name: name.into(),
+            desc: desc.to_string(),
+            precision,
+            value,
+        });
+    }
+    pub fn get(&self, name: &str) -> Option<&StatValue> {
+        self.stats
+            .iter()
+            .find(|stat| stat.name == name)
+            .map(|stat| &stat.value)
+    }
+    /// return the value of a scalar stat
+    pub fn get_scalar(&self, name: &str) -> Option<f64> {
+        match self.get(name)? {
+            StatValue::Scalar(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    /// dump the stats in the format of ramulator's stats file
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+        for stat in &self.stats {
+            match &stat.value {
+                StatValue::Scalar(value) => {
+                    write_line(&mut text, &stat.name, *value, stat.precision, &stat.desc);
+                }
+                StatValue::Vector(values) => {
+                    let total = values.iter().sum();
+                    write_line(&mut text, &stat.name, total, stat.precision, &stat.desc);
+                    for (i, value) in values.iter().enumerate() {
+                        write_line(&mut text, &format!("[{i}]"), *value, stat.precision, "");
+                    }
+                }
+                StatValue::Histogram(histogram) => {
+                    let samples = histogram.samples as f64;
+                    write_line(&mut text, &stat.name, samples, 0, &stat.desc);
+                    for (i, count) in histogram.buckets.iter().enumerate() {
+                        let low = i as u64 * histogram.bucket_size;
+                        let high = low + histogram.bucket_size - 1;
+                        let name = format!("[{low}-{high}]");
+                        write_line(&mut text, &name, *count as f64, 0, "");
+                    }
+                    let overflows = histogram.overflows as f64;
+                    write_line(&mut text, "[overflows]", overflows, 0, "");
+                }
+            }
+        }
+        text
+    }
+
+    /// dump the stats as a JSON object keyed by the stat names
+    pub fn to_json(&self) -> String {
+        let mut json = String::from("{\n");
+        for (i, stat) in self.stats.iter().enumerate() {
+            let value = match &stat.value {
+                StatValue::Scalar(value) => json_number(*value),
+                StatValue::Vector(values) => json_array(values.iter().copied()),
+                StatValue::Histogram(histogram) => format!(
+                    "{{\"bucket_size\": {}, \"samples\": {}, \"overflows\": {}, \"buckets\": {}}}",
+                    histogram.bucket_size,
+                    histogram.samples,
+                    histogram.overflows,
+                    json_array(histogram.buckets.iter().map(|&count| count as f64))
+                ),
+            };
+            let separator = if i + 1 == self.stats.len() { "" } else { "," };
+            writeln!(
+                json,
+                "  {}: {}{}",
+                json_string(&stat.name),
+                value,
+                separator
+            )
+            .unwrap();
+        }
+        json.push('}');
+        json
+    }
+}
+
+fn write_line(text: &mut String, name: &str, value: f64, precision: usize, desc: &str) {
+    writeln!(text, "{name:>40}{value:>20.precision$}{:>40}{desc}", "# ").unwrap();
+}
+fn json_number(value: f64) -> String {
+    if value.is_finite() {
+        format!("{value}")
+    } else {
+        "null".to_string()
+    }
+}
+fn json_array(values: impl Iterator<Item = f64>) -> String {
+    let values = values.map(json_number).collect::<Vec<_>>();
+    format!("[{}]", values.join(", "))
+}
+fn json_string(s: &str) -> String {
+    let mut escaped = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            c if c.is_control() => write!(escaped, "\\u{:04x}", c as u32).unwrap(),
+            c => escaped.push(c),
+        }
+    }
+    escaped.push('"');
+    escaped
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Histogram, StatList};
+
+    #[test]
+    fn test_stat_output() {
+        let mut stats = StatList::default();
+        stats.scalar("read_latency_avg_0", "The average latency", 6, 12.5);
+        stats.vector("read_requests", "Number of reads", vec![1.0, 2.0]);
+        let mut histogram = Histogram::new(10, 2);
+        histogram.add(3);
+        histogram.add(15);
+        histogram.add(100);
+        stats.histogram("read_latency_hist_0", "Read latency", histogram);
+
+        let text = stats.to_text();
+        let lines = text.lines().collect::<Vec<_>>();
+        assert_eq!(
+            lines[0],
+            format!(
+                "{:>40}{:>20}{:>40}The average latency",
+                "read_latency_avg_0", "12.500000", "# "
+            )
+        );
+        assert_eq!(
+            lines[1],
+            format!(
+                "{:>40}{:>20}{:>40}Number of reads",
+                "read_requests", "3", "# "
+            )
+        );
+        assert_eq!(lines[3], format!("{:>40}{:>20}{:>40}", "[1]", "2", "# "));
+        assert_eq!(
+            lines[6],
+            format!("{:>40}{:>20}{:>40}", "[10-19]", "1", "# ")
+        );
+        assert_eq!(
+            lines[7],
+            format!("{:>40}{:>20}{:>40}", "[overflows]", "1", "# ")
+        );
+
+        let json = stats.to_json();
+        assert!(json.contains("\"read_latency_avg_0\": 12.5,"));
+        assert!(json.contains("\"read_requests\": [1, 2],"));
+        assert!(json.contains(
+            "\"read_latency_hist_0\": {\"bucket_size\": 10, \"samples\": 3, \"overflows\": 1, \"buckets\": [1, 1]}\n"
+        ));
+        assert_eq!(stats.get_scalar("read_latency_avg_0"), Some(12.5));
+    }
+}